@@ -48,6 +48,10 @@ axios.interceptors.response.use(
 
 export * from "./favorite_directory";
 export * from "./fs";
+export * from "./key_store";
+export * from "./known_host";
 export * from "./sftp";
+export * from "./ssh_connection";
 export * from "./target";
+export * from "./target_group";
 export * from "./transfer";
@@ -0,0 +1,54 @@
+import axios from "axios";
+
+export interface IStoredKey {
+    id: number;
+    name: string;
+    algorithm: string;
+    public_key: string;
+    fingerprint: string;
+    comment: string;
+    created_at: number;
+}
+
+export async function getKeyStoreList() {
+    const response = await axios.get<IStoredKey[]>("/api/key_store/list");
+    return response.data;
+}
+
+export async function postKeyStoreGenerate(payload: {
+    name: string;
+    algorithm: "ed25519" | "rsa";
+    bits?: number;
+    comment?: string;
+}) {
+    const response = await axios.post<IStoredKey>(
+        "/api/key_store/generate",
+        payload,
+    );
+    return response.data;
+}
+
+export async function postKeyStoreImport(payload: {
+    name: string;
+    private_key: string;
+    passphrase?: string;
+    comment?: string;
+}) {
+    const response = await axios.post<IStoredKey>(
+        "/api/key_store/import",
+        payload,
+    );
+    return response.data;
+}
+
+export async function postKeyStoreRemove(id: number) {
+    await axios.post("/api/key_store/remove", { id });
+}
+
+export async function postKeyStoreInstall(payload: {
+    key_id: number;
+    target_id: number;
+    password?: string;
+}) {
+    await axios.post("/api/key_store/install", payload);
+}
@@ -0,0 +1,57 @@
+import axios from "axios";
+
+export interface IKnownHost {
+    id: number;
+    host: string;
+    port: number;
+    key_algorithm: string;
+    public_key: string;
+    fingerprint: string;
+    marker?: "revoked" | null;
+}
+
+export interface IKnownHostImportResult {
+    imported: number;
+    skipped: { line: number; reason: string }[];
+}
+
+export async function getKnownHostList(params?: {
+    host?: string;
+    port?: number;
+}) {
+    const response = await axios.get<IKnownHost[]>("/api/known_host/list", {
+        params,
+    });
+    return response.data;
+}
+
+export async function postKnownHostRemove(id: number) {
+    await axios.post("/api/known_host/remove", { id });
+}
+
+export async function postKnownHostPin(payload: {
+    host: string;
+    port: number;
+    public_key: string;
+}) {
+    const response = await axios.post<IKnownHost>(
+        "/api/known_host/pin",
+        payload,
+    );
+    return response.data;
+}
+
+export async function postKnownHostImport(content: string) {
+    const response = await axios.post<IKnownHostImportResult>(
+        "/api/known_host/import",
+        { content },
+    );
+    return response.data;
+}
+
+export async function getKnownHostExport() {
+    const response = await axios.get<string>("/api/known_host/export", {
+        responseType: "text",
+    });
+    return response.data;
+}
@@ -0,0 +1,93 @@
+import axios from "axios";
+import { io } from "socket.io-client";
+
+export interface ISshConnectionAlgorithms {
+    kex: string;
+    host_key: string;
+    cipher: string;
+    client_mac: string;
+    server_mac: string;
+    client_compression: string;
+    server_compression: string;
+}
+
+export interface ISshConnection {
+    id: string;
+    expired: boolean;
+    closed: boolean;
+    type_name: string;
+    target_id: number;
+    active_channels: number;
+    /** Unix 时间戳，毫秒 */
+    created_at: number;
+    /** Unix 时间戳，毫秒 */
+    last_activity: number;
+    bytes_in: number;
+    bytes_out: number;
+    server_version?: string;
+    agent_identity?: string;
+    algorithms?: ISshConnectionAlgorithms;
+}
+
+export type ISshConnectionEventType =
+    | "connecting"
+    | "connect_failed"
+    | "authenticated"
+    | "channel_opened"
+    | "channel_closed"
+    | "expiring"
+    | "closed";
+
+export interface ISshConnectionEvent {
+    type: ISshConnectionEventType;
+    target_id: number;
+    connection_id?: string;
+    active_channels: number;
+    reason?: string;
+    /** Unix 时间戳，毫秒 */
+    at: number;
+}
+
+export async function getSshConnectionList(targetId?: number) {
+    const response = await axios.get<ISshConnection[]>(
+        "/api/ssh_connection/list",
+        { params: { target_id: targetId } },
+    );
+    return response.data;
+}
+
+export async function postSshConnectionExpire(
+    targetId: number,
+    connectionId: string,
+) {
+    await axios.post("/api/ssh_connection/expire", null, {
+        params: { target_id: targetId, connection_id: connectionId },
+    });
+}
+
+/** 预热目标的连接，使随后打开终端时无需等待握手 */
+export async function postSshConnectionWarm(targetId: number) {
+    await axios.post("/api/ssh_connection/warm", null, {
+        params: { target_id: targetId },
+    });
+}
+
+/**
+ * 订阅连接池事件，不传 targetId 时接收所有目标的事件
+ * @returns 取消订阅的函数
+ */
+export function subscribeSshConnectionEvents(
+    onEvent: (event: ISshConnectionEvent) => void,
+    targetId?: number,
+) {
+    const socket = io(location.origin, {
+        path: "/api/ssh_connection/events/socket.io",
+        query: targetId === undefined ? {} : { target_id: targetId },
+        transports:
+            "WebSocket" in window ? ["websocket"] : ["polling", "websocket"],
+    });
+    socket.on("connection_event", onEvent);
+    return () => {
+        socket.disconnect();
+    };
+}
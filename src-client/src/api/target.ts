@@ -1,5 +1,24 @@
 import axios from "axios";
 
+export interface ITargetCertificateInfo {
+    key_id: string;
+    principals: string[];
+    /** Unix 时间戳，秒 */
+    valid_after: number;
+    /** Unix 时间戳，秒 */
+    valid_before: number;
+    ca_fingerprint: string;
+}
+
+/** 按优先级排列的 SSH 算法，为空时使用默认值 */
+export interface ITargetAlgorithms {
+    kex?: string[];
+    host_key?: string[];
+    cipher?: string[];
+    mac?: string[];
+    compression?: string[];
+}
+
 export interface ITarget {
     id: number;
     host: string;
@@ -7,12 +26,125 @@ export interface ITarget {
     method: number;
     user: string;
     key?: string;
+    key_id?: number | null;
+    certificate?: string;
+    certificate_info?: ITargetCertificateInfo;
     password?: string;
     system?: string;
+    jump_target_id?: number;
+    proxy?: string;
+    proxy_command?: string;
+    algorithms?: ITargetAlgorithms;
+    connect_timeout?: number;
+    connect_retries?: number;
+    min_idle_connections?: number;
+    host_key_policy?: "accept-new" | "strict" | "ask" | "disabled" | null;
+    group_id?: number | null;
+    tags?: string[] | null;
+}
+
+export interface ISshConfigImportEntry {
+    alias: string;
+    host: string;
+    port?: number | null;
+    user?: string | null;
+    proxy_jump?: string | null;
+    action: "create" | "update" | "skip";
+    target_id?: number | null;
+    messages: string[];
+}
+
+export interface ISshConfigImportResult {
+    entries: ISshConfigImportEntry[];
+    warnings: string[];
+}
+
+export type TargetBundleFormat = "json" | "csv" | "encrypted";
+
+export interface ITargetImportResult {
+    entries: {
+        host: string;
+        port?: number | null;
+        user: string;
+        action: "create" | "update" | "skip";
+        conflict: boolean;
+        target_id?: number | null;
+        messages: string[];
+    }[];
+    created_groups: string[];
+    known_hosts: number;
+    skipped_known_hosts: string[];
+}
+
+export interface IAnsibleInventoryImportResult {
+    entries: {
+        name: string;
+        host: string;
+        port?: number | null;
+        user?: string | null;
+        group?: string | null;
+        action: "create" | "update" | "skip";
+        target_id?: number | null;
+        messages: string[];
+    }[];
+    created_groups: string[];
+    warnings: string[];
+}
+
+export type TargetTestStage =
+    | "dns"
+    | "connect"
+    | "banner"
+    | "key_exchange"
+    | "host_key"
+    | "auth_methods"
+    | "authentication"
+    | "sftp";
+
+export interface ITargetTestReport {
+    success: boolean;
+    stages: {
+        stage: TargetTestStage;
+        status: "passed" | "failed" | "skipped";
+        elapsed_ms?: number | null;
+        detail?: string | null;
+    }[];
+    addresses: string[];
+    server_version?: string | null;
+    host_key?: {
+        algorithm: string;
+        fingerprint: string;
+        verdict:
+            | "trusted"
+            | "new"
+            | "unknown"
+            | "changed"
+            | "revoked"
+            | "unchecked";
+    } | null;
+    auth_methods: string[];
 }
 
-export async function getTargetList() {
-    const response = await axios.get<ITarget[]>("/api/target/list");
+export interface ITargetCapabilities {
+    target_id: number;
+    os: string;
+    os_version?: string | null;
+    shell?: string | null;
+    home_dir?: string | null;
+    sftp_version?: number | null;
+    sftp_extensions: string[];
+    tools: string[];
+    detected_at: number;
+}
+
+export async function getTargetList(params?: {
+    group_id?: number;
+    tag?: string;
+    search?: string;
+}) {
+    const response = await axios.get<ITarget[]>("/api/target/list", {
+        params,
+    });
     return response.data;
 }
 
@@ -30,3 +162,89 @@ export async function postTargetRemove(id: number) {
     const response = await axios.post<ITarget[]>("/api/target/remove", { id });
     return response.data;
 }
+
+export async function postTargetTest(payload: {
+    target_id?: number;
+    target?: Omit<ITarget, "id">;
+}) {
+    const response = await axios.post<ITargetTestReport>(
+        "/api/target/test",
+        payload,
+    );
+    return response.data;
+}
+
+export async function getTargetCapabilities(target_id: number) {
+    const response = await axios.get<ITargetCapabilities | null>(
+        "/api/target/capabilities",
+        { params: { target_id } },
+    );
+    return response.data;
+}
+
+export async function postTargetCapabilitiesDetect(target_id: number) {
+    const response = await axios.post<ITargetCapabilities>(
+        "/api/target/capabilities/detect",
+        null,
+        { params: { target_id } },
+    );
+    return response.data;
+}
+
+export async function postTargetSshConfigImport(payload: {
+    content: string;
+    files?: { path: string; content: string }[];
+    dry_run?: boolean;
+}) {
+    const response = await axios.post<ISshConfigImportResult>(
+        "/api/target/ssh_config/import",
+        payload,
+    );
+    return response.data;
+}
+
+export async function postTargetExport(payload: {
+    format: TargetBundleFormat;
+    passphrase?: string;
+}) {
+    const response = await axios.post<string>("/api/target/export", payload, {
+        responseType: "text",
+    });
+    return response.data;
+}
+
+export async function postTargetImport(payload: {
+    format: TargetBundleFormat;
+    content: string;
+    passphrase?: string;
+    mode?: "merge" | "overwrite";
+    dry_run?: boolean;
+}) {
+    const response = await axios.post<ITargetImportResult>(
+        "/api/target/import",
+        payload,
+    );
+    return response.data;
+}
+
+export async function postTargetAnsibleImport(payload: {
+    source: string;
+    content: string;
+    format?: "ini" | "yaml";
+    default_user?: string;
+    files?: { path: string; content: string }[];
+    dry_run?: boolean;
+}) {
+    const response = await axios.post<IAnsibleInventoryImportResult>(
+        "/api/target/ansible/import",
+        payload,
+    );
+    return response.data;
+}
+
+export async function getTargetSshConfigExport() {
+    const response = await axios.get<string>("/api/target/ssh_config/export", {
+        responseType: "text",
+    });
+    return response.data;
+}
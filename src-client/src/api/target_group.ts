@@ -0,0 +1,35 @@
+import axios from "axios";
+
+export interface ITargetGroup {
+    id: number;
+    name: string;
+    parent_id?: number | null;
+}
+
+export async function getTargetGroupList() {
+    const response = await axios.get<ITargetGroup[]>("/api/target_group/list");
+    return response.data;
+}
+
+export async function postTargetGroupAdd(payload: {
+    name: string;
+    parent_id?: number | null;
+}) {
+    const response = await axios.post<ITargetGroup>(
+        "/api/target_group/add",
+        payload,
+    );
+    return response.data;
+}
+
+export async function postTargetGroupUpdate(payload: ITargetGroup) {
+    const response = await axios.post<ITargetGroup>(
+        "/api/target_group/update",
+        payload,
+    );
+    return response.data;
+}
+
+export async function postTargetGroupRemove(id: number) {
+    await axios.post("/api/target_group/remove", { id });
+}
@@ -26,6 +26,7 @@ export interface ITransferTask {
     estimated_time?: number;
     ranges: ITransferRange[];
     fail_reason?: string;
+    attempts: number;
     created_at: number;
     updated_at: number;
     ended_at?: number;
@@ -1,5 +1,5 @@
 import { useMemoizedFn, useRequest } from "ahooks";
-import { useState } from "react";
+import { useEffect, useState } from "react";
 
 import "./index.css";
 
@@ -9,6 +9,7 @@ import getSftpLsMapFiles from "@/helpers/getSftpLsMapFiles";
 import useAppStore from "@/store";
 
 import { isSearchUri } from "../Pathbar/search";
+import connectAuthPrompt from "./authPrompt";
 import FilesviewBase from "./Base";
 import { handleDelete, handleRename } from "./remoteActions";
 import handleContextmenu from "./remoteHandleContextmenu";
@@ -26,6 +27,8 @@ export default function FilesviewRemote({
     const [cwd, setCwd] = useState("");
     const [pathHistory, setPathHistory] = useState<string[]>([]);
 
+    useEffect(() => connectAuthPrompt(targetId), [targetId]);
+
     const pushPathHistory = useMemoizedFn((newPath: string) => {
         setPathHistory((history) => {
             const index = history.indexOf(newPath);
@@ -0,0 +1,135 @@
+import { Input, Modal } from "antd";
+import axios from "axios";
+import { io } from "socket.io-client";
+
+import { parseSftpUri } from "@/helpers/file_uri";
+
+interface IAuthPromptRequest {
+    target_id: number;
+    name: string;
+    instructions: string;
+    prompts: { prompt: string; echo: boolean }[];
+}
+
+interface IHostKeyPromptRequest {
+    target_id: number;
+    host: string;
+    port: number;
+    key_algorithm: string;
+    fingerprint: string;
+}
+
+function hostKeyPromptConfirm(request: IHostKeyPromptRequest) {
+    return new Promise<boolean>((resolve) => {
+        Modal.confirm({
+            title: "Unknown host key",
+            content: (
+                <>
+                    <p>
+                        {`The authenticity of host '${request.host}:${request.port}' can't be established.`}
+                    </p>
+                    <p>
+                        {`${request.key_algorithm} key fingerprint is ${request.fingerprint}.`}
+                    </p>
+                    <p>Are you sure you want to continue connecting?</p>
+                </>
+            ),
+            onOk() {
+                resolve(true);
+            },
+            onCancel() {
+                resolve(false);
+            },
+        });
+    });
+}
+
+function authPromptConfirm(request: IAuthPromptRequest) {
+    return new Promise<string[]>((resolve) => {
+        const answers = request.prompts.map(() => "");
+        Modal.confirm({
+            title: request.name || "Keyboard-interactive authentication",
+            content: (
+                <>
+                    {request.instructions && <p>{request.instructions}</p>}
+                    {request.prompts.map((item, index) => {
+                        const InputComponent = item.echo
+                            ? Input
+                            : Input.Password;
+                        return (
+                            <div key={`${index}-${item.prompt}`}>
+                                <div>{item.prompt}</div>
+                                <InputComponent
+                                    autoFocus={index === 0}
+                                    onChange={(evt) => {
+                                        answers[index] = evt.target.value;
+                                    }}
+                                />
+                            </div>
+                        );
+                    })}
+                </>
+            ),
+            onOk() {
+                resolve(answers);
+            },
+            onCancel() {
+                resolve([]);
+            },
+        });
+    });
+}
+
+/** 各目标的提示通道令牌，服务端只把带上该令牌的请求触发的认证提示发给它 */
+const promptTokens = new Map<number, string>();
+
+axios.interceptors.request.use((config) => {
+    if (!config.url?.startsWith("/api/sftp/")) {
+        return config;
+    }
+    const targetId =
+        config.params?.target_id ??
+        parseSftpUri(config.params?.uri ?? "")?.targetId;
+    const token = promptTokens.get(targetId);
+    if (token) {
+        config.headers.set("X-Auth-Prompt-Token", token);
+    }
+    return config;
+});
+
+/**
+ * 建立键盘交互认证提示通道，在文件视图打开期间为目标应答服务端的提示，
+ * 包括确认未知的主机密钥
+ * @returns 关闭通道的函数
+ */
+export default function connectAuthPrompt(targetId: number) {
+    const socket = io(location.origin, {
+        path: "/api/ssh/auth_prompt/socket.io",
+        query: { target_id: targetId },
+        transports:
+            "WebSocket" in window ? ["websocket"] : ["polling", "websocket"],
+    });
+    let token: string | undefined;
+    socket.on("auth_prompt_token", (issued: string) => {
+        token = issued;
+        promptTokens.set(targetId, issued);
+    });
+    socket.on(
+        "auth_prompt",
+        (request: IAuthPromptRequest, ack: (answers: string[]) => void) => {
+            authPromptConfirm(request).then(ack);
+        },
+    );
+    socket.on(
+        "host_key_prompt",
+        (request: IHostKeyPromptRequest, ack: (trusted: boolean) => void) => {
+            hostKeyPromptConfirm(request).then(ack);
+        },
+    );
+    return () => {
+        if (token && promptTokens.get(targetId) === token) {
+            promptTokens.delete(targetId);
+        }
+        socket.disconnect();
+    };
+}
@@ -1,9 +1,13 @@
 import { useMount } from "ahooks";
-import { Button, Modal, Space, Table } from "antd";
-import { useMemo, useState } from "react";
+import { Button, Input, Modal, Space, Table } from "antd";
+import { useMemo, useRef, useState } from "react";
 import { useTranslation } from "react-i18next";
 
-import { getTargetList, postTargetRemove } from "@/api";
+import {
+    getTargetList,
+    postSshConnectionWarm,
+    postTargetRemove,
+} from "@/api";
 import useAppStore from "@/store";
 
 import "./List.css";
@@ -19,12 +23,26 @@ export default function TargetList({ tab }: { tab: ITab }) {
     const { setTabPath } = useAppStore();
     const [editorOpen, setEditorOpen] = useState(false);
     const [editorData, setEditorData] = useState<ITarget>();
+    const warmedTargets = useRef(new Set<number>());
+    const search = useRef("");
 
     async function refresh() {
-        const res = await getTargetList();
+        const res = await getTargetList(
+            search.current ? { search: search.current } : undefined,
+        );
         setDataSource(res);
     }
 
+    function warm(targetId: number) {
+        if (warmedTargets.current.has(targetId)) {
+            return;
+        }
+        warmedTargets.current.add(targetId);
+        postSshConnectionWarm(targetId).catch(() => {
+            warmedTargets.current.delete(targetId);
+        });
+    }
+
     // biome-ignore lint/correctness/useExhaustiveDependencies: 没用到可能变化的状态
     const columns: ColumnsType<ITarget> = useMemo(
         () => [
@@ -58,6 +76,23 @@ export default function TargetList({ tab }: { tab: ITab }) {
                         <div className="targetListTablePlaceholder">Linux</div>
                     ),
             },
+            {
+                title: t("target_certificate_expiry"),
+                key: "certificate_expiry",
+                width: 160,
+                render: (_, record) =>
+                    record.certificate_info && (
+                        <span
+                            title={record.certificate_info.principals.join(
+                                ", ",
+                            )}
+                        >
+                            {new Date(
+                                record.certificate_info.valid_before * 1000,
+                            ).toLocaleString()}
+                        </span>
+                    ),
+            },
             {
                 title: t("app_common_action"),
                 key: "action",
@@ -129,6 +164,14 @@ export default function TargetList({ tab }: { tab: ITab }) {
                     {t("target_new")}
                 </Button>
                 <Button onClick={refresh}>{t("app_btn_refresh")}</Button>
+                <Input.Search
+                    allowClear
+                    style={{ width: 240 }}
+                    onSearch={(value) => {
+                        search.current = value.trim();
+                        refresh();
+                    }}
+                />
             </div>
             <Table
                 className="targetListTable"
@@ -138,6 +181,9 @@ export default function TargetList({ tab }: { tab: ITab }) {
                 size="small"
                 scroll={{ y: 390 }}
                 pagination={false}
+                onRow={(record) => ({
+                    onMouseEnter: () => warm(record.id),
+                })}
             />
             <TargetEditor
                 open={editorOpen}
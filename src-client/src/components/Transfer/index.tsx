@@ -319,6 +319,16 @@ const TransferTable = ({ type }: { type: "ALL" | ITransferTask["type"] }) => {
                                             ),
                                         })}
                                     </Text>
+                                    {record.attempts > 1 && (
+                                        <Text
+                                            className="WebSSH-TransferProgressMetaItem WebSSH-TransferProgressMetaItem--attempt"
+                                            type="secondary"
+                                        >
+                                            {t("transfer_attempt", {
+                                                count: record.attempts,
+                                            })}
+                                        </Text>
+                                    )}
                                 </>
                             ) : (
                                 <>
@@ -105,6 +105,7 @@ function createTask(
         estimated_time: undefined,
         ranges: [[0, 9]],
         fail_reason: undefined,
+        attempts: 0,
         created_at: Date.now(),
         updated_at: Date.now(),
         ended_at: undefined,
@@ -124,6 +124,10 @@ function createTerminal(socket) {
     });
 
     term.onData((data) => {
+        if (authPromptPlugin.onInput) {
+            authPromptPlugin.onInput(data);
+            return;
+        }
         if (socket.connected) {
             socket.emit("input", data);
         }
@@ -131,6 +135,7 @@ function createTerminal(socket) {
 
     enhanceMouseCopyPaste(term);
     manuallyRetryPlugin.apply(socket, term);
+    authPromptPlugin.apply(socket, term);
 
     term.focus();
 
@@ -264,6 +269,90 @@ var manuallyRetryPlugin = {
     },
 };
 
+var authPromptPlugin = {
+    /** @type {((data: string) => void) | null} */
+    onInput: null,
+    /**
+     * 键盘交互认证：服务端通过 auth_prompt 事件下发提示，
+     * 在终端中逐行读取用户输入，通过 ack 回传全部答案。
+     * 未知主机密钥：服务端通过 host_key_prompt 事件下发指纹，
+     * 用户输入 yes 时通过 ack 回传 true。
+     * @param {import('socket.io-client').Socket} socket
+     * @param {import('@xterm/xterm').Terminal} term
+     */
+    apply(socket, term) {
+        socket.on("auth_prompt", async (request, ack) => {
+            if (request.name) {
+                term.writeln(request.name);
+            }
+            if (request.instructions) {
+                term.writeln(request.instructions);
+            }
+            var answers = [];
+            for (var item of request.prompts) {
+                answers.push(
+                    await authPromptPlugin.readLine(
+                        term,
+                        item.prompt,
+                        item.echo,
+                    ),
+                );
+            }
+            ack(answers);
+        });
+        socket.on("host_key_prompt", async (request, ack) => {
+            term.writeln(
+                `The authenticity of host '${request.host}:${request.port}' can't be established.`,
+            );
+            term.writeln(
+                `${request.key_algorithm} key fingerprint is ${request.fingerprint}.`,
+            );
+            var answer = await authPromptPlugin.readLine(
+                term,
+                "Are you sure you want to continue connecting (yes/no)? ",
+                true,
+            );
+            ack(answer.trim().toLowerCase() === "yes");
+        });
+    },
+
+    /**
+     * @param {import('@xterm/xterm').Terminal} term
+     * @param {string} prompt
+     * @param {boolean} echo 为 false 时不回显输入（如密码、验证码）
+     * @returns {Promise<string>}
+     */
+    readLine(term, prompt, echo) {
+        term.write(prompt);
+        return new Promise((resolve) => {
+            var line = "";
+            authPromptPlugin.onInput = (data) => {
+                for (var char of data) {
+                    if (char === "\r") {
+                        authPromptPlugin.onInput = null;
+                        term.writeln("");
+                        resolve(line);
+                        return;
+                    }
+                    if (char === "\x7f") {
+                        if (line !== "") {
+                            line = line.slice(0, -1);
+                            if (echo) {
+                                term.write("\b \b");
+                            }
+                        }
+                        continue;
+                    }
+                    line += char;
+                    if (echo) {
+                        term.write(char);
+                    }
+                }
+            };
+        });
+    },
+};
+
 /**
  * 增强鼠标复制粘贴功能
  * - 实现自动复制选中文本
@@ -7,11 +7,34 @@ use utoipa::OpenApi;
         crate::apis::target::handlers::target_add,
         crate::apis::target::handlers::target_update,
         crate::apis::target::handlers::target_remove,
+        crate::apis::target::handlers::target_test,
+        crate::apis::target::handlers::target_capabilities,
+        crate::apis::target::handlers::target_capabilities_detect,
+        crate::apis::target::handlers::target_export,
+        crate::apis::target::handlers::target_import,
+        crate::apis::target::handlers::target_ssh_config_import,
+        crate::apis::target::handlers::target_ssh_config_export,
+        crate::apis::target::handlers::target_ansible_import,
+        crate::apis::target_group::handlers::target_group_list,
+        crate::apis::target_group::handlers::target_group_add,
+        crate::apis::target_group::handlers::target_group_update,
+        crate::apis::target_group::handlers::target_group_remove,
+        crate::apis::known_host::handlers::known_host_list,
+        crate::apis::known_host::handlers::known_host_remove,
+        crate::apis::known_host::handlers::known_host_pin,
+        crate::apis::known_host::handlers::known_host_import,
+        crate::apis::known_host::handlers::known_host_export,
+        crate::apis::key_store::handlers::key_store_list,
+        crate::apis::key_store::handlers::key_store_generate,
+        crate::apis::key_store::handlers::key_store_import,
+        crate::apis::key_store::handlers::key_store_remove,
+        crate::apis::key_store::handlers::key_store_install,
         crate::apis::favorite_directory::handlers::favorite_directory_list,
         crate::apis::favorite_directory::handlers::favorite_directory_add,
         crate::apis::favorite_directory::handlers::favorite_directory_remove,
         crate::apis::ssh_connection::handlers::list,
         crate::apis::ssh_connection::handlers::expire,
+        crate::apis::ssh_connection::handlers::warm,
         crate::apis::ssh::handlers::exec_handler,
         crate::apis::sftp::handlers::ls,
         crate::apis::sftp::handlers::mkdir,
@@ -48,12 +71,16 @@ use utoipa::OpenApi;
         schemas(
             crate::apis::ApiErr,
             crate::entities::favorite_directory::Model,
+            crate::entities::ssh_known_host::Model,
+            crate::entities::ssh_known_host::KnownHostMarker,
+            crate::entities::key_store::Model,
             crate::apis::fs::FsFile,
             crate::apis::fs::FsUserDir,
             crate::apis::sftp::SftpUserDir,
             crate::apis::transfer::CreateUploadTaskPayload,
             crate::apis::transfer::CreateDownloadTaskPayload,
             crate::apis::transfer::TransferTaskResponse,
+            crate::apis::ssh_connection::dto::ConnectionEventInfo,
             crate::entities::transfer_task::TransferTaskType,
             crate::entities::transfer_task::TransferTaskStatus,
         ),
@@ -63,6 +90,9 @@ use utoipa::OpenApi;
     ),
     tags(
         (name = "target", description = "SSH 目标管理 API"),
+        (name = "target_group", description = "SSH 目标分组 API"),
+        (name = "known_host", description = "主机密钥管理 API"),
+        (name = "key_store", description = "密钥库 API"),
         (name = "favorite_directory", description = "收藏目录 API"),
         (name = "ssh_connection", description = "SSH 连接管理 API"),
         (name = "ssh", description = "SSH 命令执行 API"),
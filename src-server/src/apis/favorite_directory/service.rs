@@ -76,7 +76,7 @@ async fn discover_default_directories(
             .collect());
     }
 
-    Ok(sftp::discover_user_dirs(connection_pool, target_id)
+    Ok(sftp::discover_user_dirs(connection_pool, target_id, None)
         .await?
         .into_iter()
         .map(|dir| (dir.name, dir.path))
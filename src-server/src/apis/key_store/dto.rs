@@ -0,0 +1,49 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// 生成密钥的算法
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum KeyStoreAlgorithm {
+    #[serde(rename = "ed25519")]
+    Ed25519,
+    #[serde(rename = "rsa")]
+    Rsa,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct KeyStoreGeneratePayload {
+    /// 显示名称
+    pub name: String,
+    pub algorithm: KeyStoreAlgorithm,
+    /// RSA 密钥长度（2048、3072 或 4096），默认 3072
+    pub bits: Option<usize>,
+    /// 密钥注释，如 user@host
+    pub comment: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct KeyStoreImportPayload {
+    /// 显示名称
+    pub name: String,
+    /// 私钥内容（OpenSSH、PEM 或 PuTTY 格式）
+    pub private_key: String,
+    /// 私钥口令，私钥已加密时必填；导入后以未加密的形式保存
+    pub passphrase: Option<String>,
+    /// 密钥注释，为空时保留私钥中的注释
+    pub comment: Option<String>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct KeyStoreRemovePayload {
+    pub id: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct KeyStoreInstallPayload {
+    /// 要安装的密钥 ID
+    pub key_id: i32,
+    /// 安装到该目标用户的 ~/.ssh/authorized_keys
+    pub target_id: i32,
+    /// 登录目标使用的密码，为空时使用目标自身的认证方式
+    pub password: Option<String>,
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State};
+
+use crate::{
+    AppState,
+    apis::{
+        ApiErr, InternalErrorResponse, ValidJson,
+        key_store::{
+            dto::{
+                KeyStoreGeneratePayload, KeyStoreImportPayload, KeyStoreInstallPayload,
+                KeyStoreRemovePayload,
+            },
+            service,
+        },
+    },
+    entities::key_store,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/key_store/list",
+    tag = "key_store",
+    summary = "获取密钥库中的密钥列表",
+    operation_id = "key_store_list",
+    responses(
+        (status = 200, description = "成功获取密钥列表", body = [key_store::Model]),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn key_store_list(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<key_store::Model>>, ApiErr> {
+    Ok(Json(service::list(&state.db, &state.secrets).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/key_store/generate",
+    tag = "key_store",
+    summary = "生成新的密钥",
+    operation_id = "key_store_generate",
+    request_body = KeyStoreGeneratePayload,
+    responses(
+        (status = 200, description = "成功生成密钥", body = key_store::Model),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn key_store_generate(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<KeyStoreGeneratePayload>,
+) -> Result<Json<key_store::Model>, ApiErr> {
+    Ok(Json(
+        service::generate(&state.db, &state.secrets, payload).await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/key_store/import",
+    tag = "key_store",
+    summary = "导入已有的私钥",
+    operation_id = "key_store_import",
+    request_body = KeyStoreImportPayload,
+    responses(
+        (status = 200, description = "成功导入密钥", body = key_store::Model),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn key_store_import(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<KeyStoreImportPayload>,
+) -> Result<Json<key_store::Model>, ApiErr> {
+    Ok(Json(
+        service::import(&state.db, &state.secrets, payload).await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/key_store/remove",
+    tag = "key_store",
+    summary = "删除密钥",
+    description = "仍被目标使用的密钥不能删除",
+    operation_id = "key_store_remove",
+    request_body = KeyStoreRemovePayload,
+    responses(
+        (status = 200, description = "成功删除密钥"),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn key_store_remove(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<KeyStoreRemovePayload>,
+) -> Result<(), ApiErr> {
+    service::remove(&state.db, payload.id).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/key_store/install",
+    tag = "key_store",
+    summary = "安装公钥到目标",
+    description = "类似 ssh-copy-id，将公钥追加到目标用户的 ~/.ssh/authorized_keys，已存在时不重复添加",
+    operation_id = "key_store_install",
+    request_body = KeyStoreInstallPayload,
+    responses(
+        (status = 200, description = "成功安装公钥"),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn key_store_install(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<KeyStoreInstallPayload>,
+) -> Result<(), ApiErr> {
+    service::install(&state.db, &state.secrets, &state.connection_pool, payload).await
+}
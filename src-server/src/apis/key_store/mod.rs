@@ -0,0 +1,23 @@
+pub mod dto;
+pub mod handlers;
+mod service;
+
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::AppState;
+
+pub(crate) fn router_builder(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/list", get(handlers::key_store_list))
+        .route("/generate", post(handlers::key_store_generate))
+        .route("/import", post(handlers::key_store_import))
+        .route("/remove", post(handlers::key_store_remove))
+        .route("/install", post(handlers::key_store_install))
+        .fallback(|| async { "not supported" })
+        .with_state(app_state)
+}
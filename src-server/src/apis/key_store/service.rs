@@ -0,0 +1,334 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use russh::keys::{
+    Algorithm, HashAlg, PrivateKey,
+    ssh_key::{
+        LineEnding,
+        private::{KeypairData, RsaKeypair},
+    },
+};
+use sea_orm::{ActiveValue::NotSet, ActiveValue::Set, DatabaseConnection};
+
+use crate::{
+    apis::{
+        ApiErr,
+        key_store::dto::{
+            KeyStoreAlgorithm, KeyStoreGeneratePayload, KeyStoreImportPayload,
+            KeyStoreInstallPayload,
+        },
+        ssh::{exec, shell_quote},
+        target,
+    },
+    consts::services_err_code::*,
+    entities::key_store,
+    map_db_err, map_ssh_err,
+    repositories::{key_store as key_store_repository, target as target_repository},
+    secrets::Secrets,
+    ssh_connection_pool::{ChannelMode, ChannelPriority, SshConnectionPool},
+};
+
+const DEFAULT_RSA_BITS: usize = 3072;
+const RSA_BITS: [usize; 3] = [2048, 3072, 4096];
+
+pub async fn list(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+) -> Result<Vec<key_store::Model>, ApiErr> {
+    Ok(map_db_err!(key_store_repository::list(db, secrets).await)?)
+}
+
+pub async fn generate(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    payload: KeyStoreGeneratePayload,
+) -> Result<key_store::Model, ApiErr> {
+    validate_name(&payload.name)?;
+    let bits = payload.bits.unwrap_or(DEFAULT_RSA_BITS);
+    if payload.algorithm == KeyStoreAlgorithm::Rsa && !RSA_BITS.contains(&bits) {
+        return Err(invalid_request(&format!(
+            "RSA key size must be one of {RSA_BITS:?}"
+        )));
+    }
+    let algorithm = payload.algorithm;
+    // RSA key generation takes long enough to stall the runtime.
+    let mut private_key = tokio::task::spawn_blocking(move || match algorithm {
+        KeyStoreAlgorithm::Ed25519 => PrivateKey::random(&mut rand::rng(), Algorithm::Ed25519),
+        KeyStoreAlgorithm::Rsa => RsaKeypair::random(&mut rand::rng(), bits)
+            .and_then(|keypair| PrivateKey::new(KeypairData::from(keypair), "")),
+    })
+    .await
+    .map_err(|err| internal_error(&format!("failed to generate key: {err}")))?
+    .map_err(|err| internal_error(&format!("failed to generate key: {err}")))?;
+    private_key.set_comment(payload.comment.unwrap_or_default());
+    store(db, secrets, &payload.name, &private_key).await
+}
+
+pub async fn import(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    payload: KeyStoreImportPayload,
+) -> Result<key_store::Model, ApiErr> {
+    validate_name(&payload.name)?;
+    let mut private_key =
+        russh::keys::decode_secret_key(payload.private_key.trim(), payload.passphrase.as_deref())
+            .map_err(|err| invalid_request(&format!("invalid private key: {err}")))?;
+    if let Some(comment) = payload.comment.as_deref() {
+        private_key.set_comment(comment);
+    }
+    store(db, secrets, &payload.name, &private_key).await
+}
+
+async fn store(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    name: &str,
+    private_key: &PrivateKey,
+) -> Result<key_store::Model, ApiErr> {
+    let encoded = private_key
+        .to_openssh(LineEnding::LF)
+        .map_err(|err| internal_error(&format!("failed to encode private key: {err}")))?;
+    let public_key = private_key
+        .public_key()
+        .to_openssh()
+        .map_err(|err| internal_error(&format!("failed to encode public key: {err}")))?;
+    let active_model = key_store::ActiveModel {
+        id: NotSet,
+        name: Set(name.to_string()),
+        algorithm: Set(private_key.algorithm().as_str().to_string()),
+        public_key: Set(public_key),
+        private_key: Set(encoded.to_string()),
+        fingerprint: Set(private_key.fingerprint(HashAlg::Sha256).to_string()),
+        comment: Set(private_key.comment().to_string()),
+        created_at: Set(now_ms()),
+    };
+    Ok(map_db_err!(
+        key_store_repository::insert(db, secrets, active_model).await
+    )?)
+}
+
+pub async fn remove(db: &DatabaseConnection, id: i32) -> Result<(), ApiErr> {
+    let used_by = map_db_err!(key_store_repository::delete_if_unused(db, id).await)?;
+    if used_by > 0 {
+        return Err(invalid_request(&format!(
+            "the key is used by {used_by} target(s)"
+        )));
+    }
+    Ok(())
+}
+
+/// Appends the public key to `~/.ssh/authorized_keys` of the target user,
+/// like ssh-copy-id.
+pub async fn install(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    connection_pool: &SshConnectionPool,
+    payload: KeyStoreInstallPayload,
+) -> Result<(), ApiErr> {
+    let stored_key =
+        map_db_err!(key_store_repository::find_by_id(db, secrets, payload.key_id).await)?
+            .ok_or_else(|| invalid_request(&format!("key {} not found", payload.key_id)))?;
+    let target = map_db_err!(target_repository::find_by_id(db, secrets, payload.target_id).await)?
+        .ok_or_else(|| invalid_request(&format!("target {} not found", payload.target_id)))?;
+    if map_db_err!(target::is_windows(db, &target).await)? {
+        return Err(invalid_request(
+            "installing keys on Windows targets is not supported",
+        ));
+    }
+
+    let channel = match payload.password {
+        Some(password) => map_ssh_err!(
+            connection_pool
+                .password_channel(payload.target_id, password)
+                .await
+        )?,
+        None => map_ssh_err!(
+            connection_pool
+                .channel(
+                    payload.target_id,
+                    ChannelMode::Dedicated,
+                    ChannelPriority::Exec
+                )
+                .await
+        )?,
+    };
+    exec(channel, &authorized_keys_command(&stored_key.public_key)).await?;
+    Ok(())
+}
+
+/// The key counts as installed when a line already holds the same key type
+/// and data, whatever its options or comment.
+fn authorized_keys_command(public_key: &str) -> String {
+    let public_key = public_key.trim();
+    let mut fields = public_key.split_whitespace();
+    let key_type = shell_quote(fields.next().unwrap_or_default());
+    let key_data = shell_quote(fields.next().unwrap_or_default());
+    let line = shell_quote(public_key);
+    format!(
+        "umask 077; mkdir -p ~/.ssh && touch ~/.ssh/authorized_keys && \
+         {{ awk -v type={key_type} -v data={key_data} \
+         '{{ for (i = 1; i < NF; i++) if ($i == type && $(i + 1) == data) found = 1 }} \
+         END {{ exit !found }}' ~/.ssh/authorized_keys || \
+         printf '%s\\n' {line} >> ~/.ssh/authorized_keys; }}"
+    )
+}
+
+fn validate_name(name: &str) -> Result<(), ApiErr> {
+    if name.trim().is_empty() {
+        return Err(invalid_request("name must not be empty"));
+    }
+    Ok(())
+}
+
+fn invalid_request(message: &str) -> ApiErr {
+    ApiErr {
+        code: ERR_CODE_KEY_STORE_INVALID_REQUEST,
+        message: message.to_string(),
+    }
+}
+
+fn internal_error(message: &str) -> ApiErr {
+    ApiErr {
+        code: ERR_CODE_KEY_STORE_ERR,
+        message: message.to_string(),
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Database;
+    use sea_orm_migration::MigratorTrait;
+
+    use crate::{
+        entities::target::{self, TargetAuthMethod},
+        migrations::Migrator,
+    };
+
+    use super::*;
+
+    fn generate_payload(comment: Option<&str>) -> KeyStoreGeneratePayload {
+        KeyStoreGeneratePayload {
+            name: "deploy".to_string(),
+            algorithm: KeyStoreAlgorithm::Ed25519,
+            bits: None,
+            comment: comment.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn keys_are_stored_without_passphrase_and_listed_without_private_key() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let generated = generate(
+            &db,
+            &Secrets::default(),
+            generate_payload(Some("deploy@webssh")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(generated.algorithm, "ssh-ed25519");
+        assert_eq!(generated.comment, "deploy@webssh");
+        assert!(generated.public_key.ends_with(" deploy@webssh"));
+        assert!(
+            serde_json::to_value(&generated)
+                .unwrap()
+                .get("private_key")
+                .is_none()
+        );
+
+        let original = PrivateKey::from_openssh(&generated.private_key).unwrap();
+        let encrypted = original
+            .encrypt(&mut rand::rng(), "secret")
+            .unwrap()
+            .to_openssh(LineEnding::LF)
+            .unwrap();
+        let import_payload = |passphrase: Option<&str>| KeyStoreImportPayload {
+            name: "imported".to_string(),
+            private_key: encrypted.to_string(),
+            passphrase: passphrase.map(str::to_string),
+            comment: None,
+        };
+        let err = match import(&db, &Secrets::default(), import_payload(None)).await {
+            Ok(_) => panic!("an encrypted key needs its passphrase"),
+            Err(err) => err,
+        };
+        assert_eq!(err.code, ERR_CODE_KEY_STORE_INVALID_REQUEST);
+
+        let imported = import(&db, &Secrets::default(), import_payload(Some("secret")))
+            .await
+            .unwrap();
+        assert_eq!(imported.fingerprint, generated.fingerprint);
+        assert!(
+            !PrivateKey::from_openssh(&imported.private_key)
+                .unwrap()
+                .is_encrypted()
+        );
+        assert_eq!(list(&db, &Secrets::default()).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn keys_used_by_a_target_cannot_be_removed() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let stored_key = generate(&db, &Secrets::default(), generate_payload(None))
+            .await
+            .unwrap();
+        let target = target_repository::insert(
+            &db,
+            &Secrets::default(),
+            target::Model {
+                id: 0,
+                host: "127.0.0.1".to_string(),
+                port: Some(22),
+                method: TargetAuthMethod::PrivateKey,
+                user: "deploy".to_string(),
+                key: None,
+                key_id: Some(stored_key.id),
+                certificate: None,
+                password: None,
+                system: None,
+                jump_target_id: None,
+                proxy: None,
+                proxy_command: None,
+                algorithms: None,
+                connect_timeout: None,
+                connect_retries: None,
+                min_idle_connections: None,
+                host_key_policy: None,
+                group_id: None,
+                tags: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let err = remove(&db, stored_key.id).await.unwrap_err();
+        assert_eq!(err.code, ERR_CODE_KEY_STORE_INVALID_REQUEST);
+        assert_eq!(list(&db, &Secrets::default()).await.unwrap().len(), 1);
+
+        target_repository::delete_with_favorite_directories(&db, target.id)
+            .await
+            .unwrap();
+        remove(&db, stored_key.id).await.unwrap();
+        assert!(list(&db, &Secrets::default()).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn public_keys_are_quoted_for_the_remote_shell() {
+        assert_eq!(
+            authorized_keys_command("ssh-ed25519 AAAA it's me"),
+            "umask 077; mkdir -p ~/.ssh && touch ~/.ssh/authorized_keys && \
+             { awk -v type='ssh-ed25519' -v data='AAAA' \
+             '{ for (i = 1; i < NF; i++) if ($i == type && $(i + 1) == data) found = 1 } \
+             END { exit !found }' ~/.ssh/authorized_keys || \
+             printf '%s\\n' 'ssh-ed25519 AAAA it'\\''s me' >> ~/.ssh/authorized_keys; }"
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct KnownHostListQuery {
+    /// 只列出该主机的密钥
+    pub host: Option<String>,
+    /// 只列出该端口的密钥
+    pub port: Option<u16>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct KnownHostRemovePayload {
+    pub id: i32,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct KnownHostPinPayload {
+    pub host: String,
+    pub port: u16,
+    /// OpenSSH 格式的主机公钥，如 `ssh-ed25519 AAAA...`，会替换该主机端口已保存的密钥
+    pub public_key: String,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct KnownHostImportPayload {
+    /// OpenSSH known_hosts 文件内容
+    pub content: String,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct KnownHostImportResult {
+    /// 导入的主机密钥数量，已存在的密钥会更新标记
+    pub imported: usize,
+    /// 无法解析而跳过的行
+    pub skipped: Vec<KnownHostSkippedLine>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq, ToSchema)]
+pub struct KnownHostSkippedLine {
+    /// 从 1 开始的行号
+    pub line: usize,
+    pub reason: String,
+}
@@ -0,0 +1,193 @@
+use russh::keys::ssh_key;
+
+use crate::{
+    apis::known_host::dto::KnownHostSkippedLine,
+    entities::ssh_known_host::{self, KnownHostMarker},
+    ssh_connection_pool::{PATTERN_PORT, ServerPublicKey},
+};
+
+/// known_hosts 文件中的一个主机密钥，一行可以对应多个主机
+pub struct KnownHostEntry {
+    pub host: String,
+    pub port: u16,
+    pub marker: Option<KnownHostMarker>,
+    pub key: ServerPublicKey,
+}
+
+/// 解析 OpenSSH known_hosts 文件内容，无法解析的行记录在第二个返回值中。
+/// 主机字段全部是普通主机名（可带 `[host]:port`）时拆分为每个主机一条记录，
+/// 否则整个主机模式列表保存为一条端口为 0 的记录
+pub fn parse(content: &str) -> (Vec<KnownHostEntry>, Vec<KnownHostSkippedLine>) {
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_line(line) {
+            Ok(parsed) => entries.extend(parsed),
+            Err(reason) => skipped.push(KnownHostSkippedLine {
+                line: index + 1,
+                reason,
+            }),
+        }
+    }
+    (entries, skipped)
+}
+
+fn parse_line(line: &str) -> Result<Vec<KnownHostEntry>, String> {
+    let mut fields = line.split_whitespace();
+    let mut hosts = fields.next();
+    let marker = match hosts {
+        // russh 只协商普通主机密钥算法，服务器无法出示主机证书，CA 条目不会生效
+        Some("@cert-authority") => {
+            return Err(
+                "@cert-authority is not supported: host certificates are not negotiated"
+                    .to_string(),
+            );
+        }
+        Some("@revoked") => Some(KnownHostMarker::Revoked),
+        Some(marker) if marker.starts_with('@') => {
+            return Err(format!("unknown marker {marker}"));
+        }
+        _ => None,
+    };
+    if marker.is_some() {
+        hosts = fields.next();
+    }
+    let (Some(hosts), Some(key_type), Some(key_data)) = (hosts, fields.next(), fields.next())
+    else {
+        return Err("expected host patterns, key type and key".to_string());
+    };
+    let public_key = ssh_key::PublicKey::from_openssh(&format!("{key_type} {key_data}"))
+        .map_err(|err| format!("invalid public key: {err}"))?;
+    let key = ServerPublicKey::from(&public_key);
+
+    let endpoints = hosts
+        .split(',')
+        .map(literal_endpoint)
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_else(|| vec![(hosts.to_string(), PATTERN_PORT)]);
+    Ok(endpoints
+        .into_iter()
+        .map(|(host, port)| KnownHostEntry {
+            host,
+            port,
+            marker,
+            key: key.clone(),
+        })
+        .collect())
+}
+
+/// `host` 或 `[host]:port` 形式的普通主机名，含通配符、取反或哈希时返回 None
+fn literal_endpoint(pattern: &str) -> Option<(String, u16)> {
+    if pattern.is_empty() || pattern.starts_with('|') || pattern.contains(['*', '?', '!']) {
+        return None;
+    }
+    match pattern.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, port) = bracketed.split_once("]:")?;
+            let port = port.parse().ok().filter(|port| *port != PATTERN_PORT)?;
+            Some((host.to_lowercase(), port))
+        }
+        None => Some((pattern.to_lowercase(), 22)),
+    }
+}
+
+/// 按 OpenSSH known_hosts 格式输出，每个主机密钥一行
+pub fn format(known_hosts: &[ssh_known_host::Model]) -> String {
+    known_hosts
+        .iter()
+        .map(|known_host| {
+            let marker = match known_host.marker {
+                Some(KnownHostMarker::Revoked) => "@revoked ",
+                None => "",
+            };
+            let host = match known_host.port {
+                PATTERN_PORT | 22 => known_host.host.clone(),
+                port => format!("[{}]:{port}", known_host.host),
+            };
+            format!(
+                "{marker}{host} {} {}\n",
+                known_host.key_algorithm, known_host.public_key
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use russh::keys::PrivateKey;
+
+    fn public_key() -> String {
+        PrivateKey::random(&mut rand::rng(), ssh_key::Algorithm::Ed25519)
+            .unwrap()
+            .public_key()
+            .to_openssh()
+            .unwrap()
+    }
+
+    #[test]
+    fn lines_are_split_into_hosts_or_kept_as_patterns() {
+        let key = public_key();
+        let content = format!(
+            "# comment\n\
+             \n\
+             Web.example.com,[db.example.com]:2222 {key} comment\n\
+             @cert-authority *.example.com {key}\n\
+             @revoked * {key}\n\
+             |1|c2FsdA==|aGFzaA== {key}\n\
+             @unknown host {key}\n\
+             host ssh-ed25519\n"
+        );
+        let (entries, skipped) = parse(&content);
+
+        let endpoints: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.host.as_str(), entry.port, entry.marker))
+            .collect();
+        assert_eq!(
+            endpoints,
+            vec![
+                ("web.example.com", 22, None),
+                ("db.example.com", 2222, None),
+                ("*", PATTERN_PORT, Some(KnownHostMarker::Revoked)),
+                ("|1|c2FsdA==|aGFzaA==", PATTERN_PORT, None),
+            ]
+        );
+        assert_eq!(
+            skipped.iter().map(|line| line.line).collect::<Vec<_>>(),
+            vec![4, 7, 8]
+        );
+    }
+
+    #[test]
+    fn formatted_entries_parse_back() {
+        let key = public_key();
+        let (entries, _) = parse(&format!(
+            "host,[other]:2222 {key}\n@revoked *.example.com {key}\n"
+        ));
+        let models: Vec<_> = entries
+            .into_iter()
+            .enumerate()
+            .map(|(id, entry)| ssh_known_host::Model {
+                id: id as i32,
+                host: entry.host,
+                port: entry.port,
+                key_algorithm: entry.key.key_algorithm,
+                public_key: entry.key.public_key,
+                fingerprint: entry.key.fingerprint,
+                marker: entry.marker,
+            })
+            .collect();
+        let key_fields = key.split_whitespace().take(2).collect::<Vec<_>>().join(" ");
+        assert_eq!(
+            format(&models),
+            format!(
+                "host {key_fields}\n[other]:2222 {key_fields}\n@revoked *.example.com {key_fields}\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::Query, extract::State};
+
+use crate::{
+    AppState,
+    apis::{
+        ApiErr, InternalErrorResponse, ValidJson,
+        known_host::{
+            dto::{
+                KnownHostImportPayload, KnownHostImportResult, KnownHostListQuery,
+                KnownHostPinPayload, KnownHostRemovePayload,
+            },
+            service,
+        },
+    },
+    entities::ssh_known_host,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/known_host/list",
+    tag = "known_host",
+    summary = "获取已信任的主机密钥列表",
+    operation_id = "known_host_list",
+    params(KnownHostListQuery),
+    responses(
+        (status = 200, description = "成功获取主机密钥列表", body = [ssh_known_host::Model]),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn known_host_list(
+    State(state): State<Arc<AppState>>,
+    Query(payload): Query<KnownHostListQuery>,
+) -> Result<Json<Vec<ssh_known_host::Model>>, ApiErr> {
+    Ok(Json(service::list(&state.db, payload).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/known_host/remove",
+    tag = "known_host",
+    summary = "删除主机密钥",
+    operation_id = "known_host_remove",
+    request_body = KnownHostRemovePayload,
+    responses(
+        (status = 200, description = "成功删除主机密钥"),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn known_host_remove(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<KnownHostRemovePayload>,
+) -> Result<(), ApiErr> {
+    service::remove(&state.db, payload.id).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/known_host/pin",
+    tag = "known_host",
+    summary = "手动信任主机密钥",
+    operation_id = "known_host_pin",
+    request_body = KnownHostPinPayload,
+    responses(
+        (status = 200, description = "成功信任主机密钥", body = ssh_known_host::Model),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn known_host_pin(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<KnownHostPinPayload>,
+) -> Result<Json<ssh_known_host::Model>, ApiErr> {
+    Ok(Json(service::pin(&state.db, payload).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/known_host/import",
+    tag = "known_host",
+    summary = "导入 OpenSSH known_hosts 文件",
+    operation_id = "known_host_import",
+    request_body = KnownHostImportPayload,
+    responses(
+        (status = 200, description = "成功导入主机密钥", body = KnownHostImportResult),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn known_host_import(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<KnownHostImportPayload>,
+) -> Result<Json<KnownHostImportResult>, ApiErr> {
+    Ok(Json(service::import(&state.db, &payload.content).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/known_host/export",
+    tag = "known_host",
+    summary = "导出为 OpenSSH known_hosts 文件",
+    operation_id = "known_host_export",
+    responses(
+        (status = 200, description = "known_hosts 文件内容", body = String, content_type = "text/plain"),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn known_host_export(State(state): State<Arc<AppState>>) -> Result<String, ApiErr> {
+    service::export(&state.db).await
+}
@@ -0,0 +1,24 @@
+pub mod dto;
+mod file;
+pub mod handlers;
+mod service;
+
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::AppState;
+
+pub(crate) fn router_builder(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/list", get(handlers::known_host_list))
+        .route("/remove", post(handlers::known_host_remove))
+        .route("/pin", post(handlers::known_host_pin))
+        .route("/import", post(handlers::known_host_import))
+        .route("/export", get(handlers::known_host_export))
+        .fallback(|| async { "not supported" })
+        .with_state(app_state)
+}
@@ -0,0 +1,222 @@
+use russh::keys::ssh_key;
+use sea_orm::{ActiveValue::NotSet, ActiveValue::Set, DatabaseConnection};
+
+use crate::{
+    apis::{
+        ApiErr,
+        known_host::{
+            dto::{KnownHostImportResult, KnownHostListQuery, KnownHostPinPayload},
+            file,
+        },
+    },
+    consts::services_err_code::{ERR_CODE_DB_ERR, ERR_CODE_KNOWN_HOST_INVALID_REQUEST},
+    entities::ssh_known_host,
+    map_db_err,
+    repositories::ssh_known_host as ssh_known_host_repository,
+    ssh_connection_pool::ServerPublicKey,
+};
+
+pub async fn list(
+    db: &DatabaseConnection,
+    query: KnownHostListQuery,
+) -> Result<Vec<ssh_known_host::Model>, ApiErr> {
+    Ok(map_db_err!(
+        ssh_known_host_repository::list(db, query.host.as_deref(), query.port).await
+    )?)
+}
+
+pub async fn remove(db: &DatabaseConnection, id: i32) -> Result<(), ApiErr> {
+    map_db_err!(ssh_known_host_repository::delete_by_id(db, id).await)?;
+    Ok(())
+}
+
+pub async fn pin(
+    db: &DatabaseConnection,
+    payload: KnownHostPinPayload,
+) -> Result<ssh_known_host::Model, ApiErr> {
+    if payload.host.is_empty() {
+        return Err(invalid_request("host must not be empty"));
+    }
+    if payload.port == 0 {
+        return Err(invalid_request("port must be greater than 0"));
+    }
+    let public_key = ssh_key::PublicKey::from_openssh(payload.public_key.trim())
+        .map_err(|err| invalid_request(&format!("invalid public key: {err}")))?;
+    let key = ServerPublicKey::from(&public_key);
+
+    let host = payload.host.to_lowercase();
+    let known_host = ssh_known_host::ActiveModel {
+        id: NotSet,
+        host: Set(host.clone()),
+        port: Set(payload.port),
+        key_algorithm: Set(key.key_algorithm),
+        public_key: Set(key.public_key),
+        fingerprint: Set(key.fingerprint),
+        marker: Set(None),
+    };
+    Ok(map_db_err!(
+        ssh_known_host_repository::replace(db, &host, payload.port, known_host).await
+    )?)
+}
+
+pub async fn import(
+    db: &DatabaseConnection,
+    content: &str,
+) -> Result<KnownHostImportResult, ApiErr> {
+    let (entries, skipped) = file::parse(content);
+    let imported = entries.len();
+    let known_hosts = entries
+        .into_iter()
+        .map(|entry| ssh_known_host::ActiveModel {
+            id: NotSet,
+            host: Set(entry.host),
+            port: Set(entry.port),
+            key_algorithm: Set(entry.key.key_algorithm),
+            public_key: Set(entry.key.public_key),
+            fingerprint: Set(entry.key.fingerprint),
+            marker: Set(entry.marker),
+        })
+        .collect();
+    map_db_err!(ssh_known_host_repository::upsert_many(db, known_hosts).await)?;
+    Ok(KnownHostImportResult { imported, skipped })
+}
+
+pub async fn export(db: &DatabaseConnection) -> Result<String, ApiErr> {
+    let known_hosts = map_db_err!(ssh_known_host_repository::list(db, None, None).await)?;
+    Ok(file::format(&known_hosts))
+}
+
+fn invalid_request(message: &str) -> ApiErr {
+    ApiErr {
+        code: ERR_CODE_KNOWN_HOST_INVALID_REQUEST,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::Migrator;
+    use russh::keys::PrivateKey;
+    use sea_orm::Database;
+    use sea_orm_migration::MigratorTrait;
+
+    fn public_key() -> String {
+        PrivateKey::random(&mut rand::rng(), ssh_key::Algorithm::Ed25519)
+            .unwrap()
+            .public_key()
+            .to_openssh()
+            .unwrap()
+    }
+
+    fn pin_payload(host: &str, public_key: String) -> KnownHostPinPayload {
+        KnownHostPinPayload {
+            host: host.to_string(),
+            port: 22,
+            public_key,
+        }
+    }
+
+    #[tokio::test]
+    async fn pinning_replaces_the_keys_of_a_host() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let first = pin(&db, pin_payload("host", public_key())).await.unwrap();
+        let other = pin(&db, pin_payload("other", public_key())).await.unwrap();
+        let second = pin(&db, pin_payload("host", public_key())).await.unwrap();
+        assert_eq!(second.key_algorithm, "ssh-ed25519");
+        assert_ne!(first.fingerprint, second.fingerprint);
+
+        let query = KnownHostListQuery {
+            host: Some("host".to_string()),
+            port: None,
+        };
+        assert_eq!(list(&db, query).await.unwrap(), vec![second.clone()]);
+
+        remove(&db, second.id).await.unwrap();
+        let query = KnownHostListQuery {
+            host: None,
+            port: Some(22),
+        };
+        assert_eq!(list(&db, query).await.unwrap(), vec![other]);
+    }
+
+    #[tokio::test]
+    async fn import_updates_markers_of_stored_keys() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let key = public_key();
+        let pinned = pin(&db, pin_payload("host", key.clone())).await.unwrap();
+
+        let result = import(&db, &format!("@revoked host {key}\nbroken\n"))
+            .await
+            .unwrap();
+        assert_eq!(result.imported, 1);
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].line, 2);
+
+        let stored = list(
+            &db,
+            KnownHostListQuery {
+                host: None,
+                port: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].id, pinned.id);
+        assert_eq!(
+            stored[0].marker,
+            Some(ssh_known_host::KnownHostMarker::Revoked)
+        );
+        assert!(
+            export(&db)
+                .await
+                .unwrap()
+                .starts_with("@revoked host ssh-ed25519 ")
+        );
+    }
+
+    #[tokio::test]
+    async fn hosts_match_in_any_letter_case() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let pinned = pin(&db, pin_payload("Server.Example.com", public_key()))
+            .await
+            .unwrap();
+        assert_eq!(pinned.host, "server.example.com");
+        let result = import(&db, &format!("SERVER.example.com {}", public_key()))
+            .await
+            .unwrap();
+        assert_eq!(result.imported, 1);
+        let result = import(
+            &db,
+            &format!("@cert-authority SERVER.example.com {}", public_key()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.imported, 0);
+        assert_eq!(result.skipped.len(), 1);
+
+        let query = KnownHostListQuery {
+            host: Some("SERVER.EXAMPLE.COM".to_string()),
+            port: None,
+        };
+        assert_eq!(list(&db, query).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn invalid_public_key_is_rejected() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let err = pin(&db, pin_payload("host", "ssh-ed25519 invalid".to_string()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ERR_CODE_KNOWN_HOST_INVALID_REQUEST);
+        let err = pin(&db, pin_payload("", public_key())).await.unwrap_err();
+        assert_eq!(err.code, ERR_CODE_KNOWN_HOST_INVALID_REQUEST);
+    }
+}
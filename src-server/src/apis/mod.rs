@@ -1,9 +1,12 @@
 pub mod favorite_directory;
 pub mod fs;
+pub mod key_store;
+pub mod known_host;
 pub mod sftp;
 pub mod ssh;
 pub mod ssh_connection;
 pub mod target;
+pub mod target_group;
 pub mod transfer;
 
 use axum::{
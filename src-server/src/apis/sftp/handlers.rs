@@ -24,16 +24,18 @@ use crate::{
             ContentRange, QueryTargetId, Range, SftpFile, SftpFileUriPayload, SftpLsPayload,
             SftpRenamePayload, SftpUploadResponse, SftpUserDir,
         },
+        target,
     },
     consts::services_err_code::*,
     map_db_err, map_ssh_err,
     sftp_client::{SftpAttrs, SftpFileType, SftpOpenOptions},
-    ssh_connection_pool::ChannelMode,
+    ssh_connection_pool::{ChannelMode, ChannelPriority},
 };
 
-use super::service::{discover_user_dirs, get_file_name, parse_file_uri, resolve_user_dir_home};
+use super::service::{
+    discover_user_dirs, get_file_name, parse_file_uri, request_prompter, resolve_user_dir_home,
+};
 
-const WINDOWS: &str = "windows";
 const CHUNK_SIZE: usize = 8192;
 
 #[utoipa::path(
@@ -54,6 +56,7 @@ const CHUNK_SIZE: usize = 8192;
 pub async fn ls(
     State(state): State<Arc<AppState>>,
     Query(payload): Query<SftpLsPayload>,
+    headers: HeaderMap,
 ) -> Result<Json<Vec<SftpFile>>, ApiErr> {
     info!("@sftp_ls {:?}", payload);
 
@@ -61,7 +64,12 @@ pub async fn ls(
     let sftp = map_ssh_err!(
         state
             .connection_pool
-            .sftp(uri.target_id, ChannelMode::Shared)
+            .prompted_sftp(
+                uri.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Browse,
+                request_prompter(&state.connection_pool, uri.target_id, &headers)
+            )
             .await
     )?;
     let read_dir = map_ssh_err!(sftp.read_dir(uri.path).await)?;
@@ -103,6 +111,7 @@ pub async fn ls(
 pub async fn mkdir(
     State(state): State<Arc<AppState>>,
     Query(payload): Query<SftpFileUriPayload>,
+    headers: HeaderMap,
 ) -> Result<(), ApiErr> {
     info!("@sftp_mkdir {:?}", payload);
 
@@ -110,7 +119,12 @@ pub async fn mkdir(
     let sftp = map_ssh_err!(
         state
             .connection_pool
-            .sftp(uri.target_id, ChannelMode::Shared)
+            .prompted_sftp(
+                uri.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Browse,
+                request_prompter(&state.connection_pool, uri.target_id, &headers)
+            )
             .await
     )?;
     let _ = map_ssh_err!(sftp.create_dir(uri.path).await)?;
@@ -138,6 +152,7 @@ pub async fn mkdir(
 pub async fn stat(
     State(state): State<Arc<AppState>>,
     Query(payload): Query<SftpFileUriPayload>,
+    headers: HeaderMap,
 ) -> Result<Json<SftpFile>, ApiErr> {
     info!("@sftp_stat {:?}", payload);
 
@@ -145,7 +160,12 @@ pub async fn stat(
     let sftp = map_ssh_err!(
         state
             .connection_pool
-            .sftp(uri.target_id, ChannelMode::Shared)
+            .prompted_sftp(
+                uri.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Browse,
+                request_prompter(&state.connection_pool, uri.target_id, &headers)
+            )
             .await
     )?;
     let attr = map_ssh_err!(sftp.metadata(uri.path).await)?;
@@ -171,13 +191,19 @@ pub async fn stat(
 pub async fn user_dir_home(
     State(state): State<Arc<AppState>>,
     Query(payload): Query<QueryTargetId>,
+    headers: HeaderMap,
 ) -> Result<String, ApiErr> {
     info!("@sftp_user_dir_home {:?}", payload);
 
     let sftp = map_ssh_err!(
         state
             .connection_pool
-            .sftp(payload.target_id, ChannelMode::Shared)
+            .prompted_sftp(
+                payload.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Browse,
+                request_prompter(&state.connection_pool, payload.target_id, &headers)
+            )
             .await
     )?;
     resolve_user_dir_home(&sftp).await
@@ -201,11 +227,17 @@ pub async fn user_dir_home(
 pub async fn user_dirs(
     State(state): State<Arc<AppState>>,
     Query(payload): Query<QueryTargetId>,
+    headers: HeaderMap,
 ) -> Result<Json<Vec<SftpUserDir>>, ApiErr> {
     info!("@sftp_user_dirs {:?}", payload);
 
     Ok(Json(
-        discover_user_dirs(&state.connection_pool, payload.target_id).await?,
+        discover_user_dirs(
+            &state.connection_pool,
+            payload.target_id,
+            request_prompter(&state.connection_pool, payload.target_id, &headers),
+        )
+        .await?,
     ))
 }
 
@@ -227,13 +259,20 @@ pub async fn user_dirs(
 pub async fn cp(
     State(state): State<Arc<AppState>>,
     Query(payload): Query<SftpRenamePayload>,
+    headers: HeaderMap,
 ) -> Result<(), ApiErr> {
     info!("@sftp_cp {:?}", payload);
 
     let uri = parse_file_uri(payload.uri.as_str())?;
-    let context = map_db_err!(state.connection_pool.context(uri.target_id).await)?;
-    let is_windows = context.target().system.as_deref() == Some(WINDOWS);
-    let channel = map_ssh_err!(context.channel(ChannelMode::Shared).await)?;
+    let context = map_db_err!(state.connection_pool.context(uri.target_id).await)?.with_prompter(
+        request_prompter(&state.connection_pool, uri.target_id, &headers),
+    );
+    let is_windows = map_db_err!(target::is_windows(&state.db, context.target()).await)?;
+    let channel = map_ssh_err!(
+        context
+            .channel(ChannelMode::Shared, ChannelPriority::Exec)
+            .await
+    )?;
 
     if is_windows {
         todo!();
@@ -268,6 +307,7 @@ pub async fn cp(
 pub async fn rename(
     State(state): State<Arc<AppState>>,
     Query(payload): Query<SftpRenamePayload>,
+    headers: HeaderMap,
 ) -> Result<(), ApiErr> {
     info!("@sftp_rename {:?}", payload);
 
@@ -275,7 +315,12 @@ pub async fn rename(
     let sftp = map_ssh_err!(
         state
             .connection_pool
-            .sftp(uri.target_id, ChannelMode::Shared)
+            .prompted_sftp(
+                uri.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Browse,
+                request_prompter(&state.connection_pool, uri.target_id, &headers)
+            )
             .await
     )?;
     let _ = map_ssh_err!(sftp.rename(uri.path, payload.target_path.as_str()).await)?;
@@ -303,6 +348,7 @@ pub async fn rename(
 pub async fn rm(
     State(state): State<Arc<AppState>>,
     Query(payload): Query<SftpFileUriPayload>,
+    headers: HeaderMap,
 ) -> Result<(), ApiErr> {
     info!("@sftp_rm {:?}", payload);
 
@@ -310,7 +356,12 @@ pub async fn rm(
     let sftp = map_ssh_err!(
         state
             .connection_pool
-            .sftp(uri.target_id, ChannelMode::Shared)
+            .prompted_sftp(
+                uri.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Browse,
+                request_prompter(&state.connection_pool, uri.target_id, &headers)
+            )
             .await
     )?;
     let _ = map_ssh_err!(sftp.remove_file(uri.path).await)?;
@@ -339,13 +390,20 @@ pub async fn rm(
 pub async fn rm_rf(
     State(state): State<Arc<AppState>>,
     Query(payload): Query<SftpFileUriPayload>,
+    headers: HeaderMap,
 ) -> Result<(), ApiErr> {
     info!("@sftp_rm_rf {:?}", payload);
 
     let uri = parse_file_uri(payload.uri.as_str())?;
-    let context = map_db_err!(state.connection_pool.context(uri.target_id).await)?;
-    let is_windows = context.target().system.as_deref() == Some(WINDOWS);
-    let channel = map_ssh_err!(context.channel(ChannelMode::Shared).await)?;
+    let context = map_db_err!(state.connection_pool.context(uri.target_id).await)?.with_prompter(
+        request_prompter(&state.connection_pool, uri.target_id, &headers),
+    );
+    let is_windows = map_db_err!(target::is_windows(&state.db, context.target()).await)?;
+    let channel = map_ssh_err!(
+        context
+            .channel(ChannelMode::Shared, ChannelPriority::Exec)
+            .await
+    )?;
 
     if is_windows {
         let file_path = uri.path[1..].replace("/", "\\");
@@ -422,7 +480,12 @@ pub async fn upload(
     let sftp = map_ssh_err!(
         state
             .connection_pool
-            .sftp(uri.target_id, ChannelMode::Shared)
+            .prompted_sftp(
+                uri.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Transfer,
+                request_prompter(&state.connection_pool, uri.target_id, &headers)
+            )
             .await
     )?;
 
@@ -520,7 +583,12 @@ pub async fn download(
     let sftp = map_ssh_err!(
         state
             .connection_pool
-            .sftp(uri.target_id, ChannelMode::Shared)
+            .prompted_sftp(
+                uri.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Transfer,
+                request_prompter(&state.connection_pool, uri.target_id, &headers)
+            )
             .await
     )?;
 
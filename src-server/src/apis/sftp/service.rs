@@ -1,5 +1,6 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, sync::Arc};
 
+use axum::http::HeaderMap;
 use tracing::debug;
 
 use crate::{
@@ -7,19 +8,42 @@ use crate::{
     consts::services_err_code::*,
     map_ssh_err,
     sftp_client::{FastSftpClient, SftpFileType},
-    ssh_connection_pool::{ChannelMode, SshConnectionPool},
+    ssh_connection_pool::{AuthPrompter, ChannelMode, ChannelPriority, SshConnectionPool},
 };
 
 use super::dto::SftpUserDir;
 
 const URI_SEP: &str = ":";
 const PATH_SEP: &str = "/";
+/// 文件视图在该请求头中带上其键盘交互提示连接下发的令牌
+const AUTH_PROMPT_TOKEN_HEADER: &str = "x-auth-prompt-token";
+
+/// 发起请求的文件视图为目标注册的提示应答方，请求触发的连接由它回答认证提示，
+/// 不会转给其它浏览器
+pub(crate) fn request_prompter(
+    connection_pool: &SshConnectionPool,
+    target_id: i32,
+    headers: &HeaderMap,
+) -> Option<Arc<dyn AuthPrompter>> {
+    let token = headers.get(AUTH_PROMPT_TOKEN_HEADER)?.to_str().ok()?;
+    connection_pool.auth_prompter(target_id, token)
+}
 
 pub(crate) async fn discover_user_dirs(
     connection_pool: &SshConnectionPool,
     target_id: i32,
+    prompter: Option<Arc<dyn AuthPrompter>>,
 ) -> Result<Vec<SftpUserDir>, ApiErr> {
-    let sftp = map_ssh_err!(connection_pool.sftp(target_id, ChannelMode::Shared).await)?;
+    let sftp = map_ssh_err!(
+        connection_pool
+            .prompted_sftp(
+                target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Browse,
+                prompter
+            )
+            .await
+    )?;
     let home = resolve_user_dir_home(&sftp).await?;
     let entries = map_ssh_err!(sftp.read_dir(&home).await)?;
     let available_dirs = entries
@@ -10,6 +10,11 @@ pub(crate) struct TerminalQueryParams {
     pub(crate) target_id: i32,
 }
 
+#[derive(Deserialize, Debug)]
+pub(crate) struct AuthPromptQueryParams {
+    pub(crate) target_id: i32,
+}
+
 #[derive(Deserialize, Debug)]
 #[allow(dead_code)]
 pub(crate) struct Resize {
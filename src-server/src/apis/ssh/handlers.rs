@@ -16,13 +16,13 @@ use crate::{
     apis::{
         ApiErr, InternalErrorResponse,
         ssh::{
-            dto::{QueryTargetId, Resize, TerminalQueryParams},
-            service::exec,
+            dto::{AuthPromptQueryParams, QueryTargetId, Resize, TerminalQueryParams},
+            service::{SocketAuthPrompter, exec},
         },
     },
     consts::services_err_code::*,
     map_ssh_err,
-    ssh_connection_pool::{ChannelMode, SshChannelGuard, SshConnectionPool},
+    ssh_connection_pool::{ChannelMode, ChannelPriority, SshChannelGuard, SshConnectionPool},
 };
 
 #[utoipa::path(
@@ -54,7 +54,11 @@ pub(crate) async fn exec_handler(
 
     let channel = map_ssh_err!(
         connection_pool
-            .channel(payload.target_id, ChannelMode::Shared)
+            .channel(
+                payload.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Exec
+            )
             .await
     )?;
     let result = exec(channel, body.as_str()).await?;
@@ -83,6 +87,41 @@ pub(crate) fn terminal_router_builder(
         .with_state(connection_pool_clone)
 }
 
+/// 文件视图等非终端客户端通过该 Socket.IO 连接接收目标的键盘交互提示，
+/// 连接后通过 `auth_prompt_token` 事件下发随机令牌，只应答在
+/// `X-Auth-Prompt-Token` 请求头中带上该令牌的请求，断开连接后不再为该目标应答
+pub(crate) fn auth_prompt_router_builder(
+    connection_pool: Arc<SshConnectionPool>,
+) -> Router<Arc<SshConnectionPool>> {
+    let connection_pool_clone = connection_pool.clone();
+    let (svc, io) = SocketIo::builder().build_svc();
+    io.ns("/", async move |socket: SocketRef| {
+        let query = socket.req_parts().uri.query().unwrap_or_default();
+        let params: AuthPromptQueryParams = match serde_qs::from_str(query) {
+            Ok(params) => params,
+            Err(err) => {
+                error!("sid={} invalid auth prompt query. {:?}", socket.id, err);
+                let _ = socket.disconnect();
+                return;
+            }
+        };
+        info!("sid={} auth prompt target {}", socket.id, params.target_id);
+        let registration = connection_pool.register_auth_prompter(
+            params.target_id,
+            Arc::new(SocketAuthPrompter::new(socket.clone())),
+        );
+        let _ = socket.emit("auth_prompt_token", registration.token());
+        let registration = Arc::new(std::sync::Mutex::new(Some(registration)));
+        socket.on_disconnect(async move |socket: SocketRef| {
+            debug!("sid={} auth prompt disconnect", socket.id);
+            registration.lock().unwrap().take();
+        });
+    });
+    Router::new()
+        .fallback_service(svc)
+        .with_state(connection_pool_clone)
+}
+
 struct SshTerminalSession {
     socket: SocketRef,
 }
@@ -96,7 +135,12 @@ impl SshTerminalSession {
         }
         let params = result.unwrap();
         let result = connection_pool
-            .channel(params.target_id, ChannelMode::Shared)
+            .prompted_channel(
+                params.target_id,
+                ChannelMode::Shared,
+                ChannelPriority::Interactive,
+                Some(Arc::new(SocketAuthPrompter::new(socket.clone()))),
+            )
             .await;
         if let Err(err) = result {
             anyhow::bail!("Failed to get channel: {:?}", err);
@@ -10,6 +10,7 @@ use crate::ssh_connection_pool::SshConnectionPool;
 
 pub(crate) use handlers::exec_handler;
 pub use service::exec;
+pub(crate) use service::shell_quote;
 
 pub(crate) fn router_builder(connection_pool: Arc<SshConnectionPool>) -> Router {
     Router::new()
@@ -17,6 +18,10 @@ pub(crate) fn router_builder(connection_pool: Arc<SshConnectionPool>) -> Router
             "/terminal",
             handlers::terminal_router_builder(connection_pool.clone()),
         )
+        .nest(
+            "/auth_prompt",
+            handlers::auth_prompt_router_builder(connection_pool.clone()),
+        )
         .route("/exec", post(exec_handler))
         .fallback(|| async { "not supported" })
         .with_state(connection_pool)
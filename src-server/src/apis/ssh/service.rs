@@ -1,11 +1,75 @@
+use std::time::Duration;
+
 use anyhow::Result;
+use futures_util::future::BoxFuture;
 use russh::ChannelMsg;
+use socketioxide::extract::SocketRef;
 use tracing::debug;
 
 use crate::{
-    apis::ApiErr, consts::services_err_code::*, map_ssh_err, ssh_connection_pool::SshChannelGuard,
+    apis::ApiErr,
+    consts::services_err_code::*,
+    map_ssh_err,
+    ssh_connection_pool::{
+        AuthPromptRequest, AuthPrompter, HostKeyPromptRequest, SshChannelGuard, SshPoolError,
+        SshPoolResult,
+    },
 };
 
+/// 等待浏览器回答键盘交互提示的最长时间，实际仍受 SSH 连接超时限制
+const AUTH_PROMPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 通过 Socket.IO 的 `auth_prompt` 事件把键盘交互提示转发给浏览器，
+/// 并以 ack 回传的字符串数组作为答案；
+/// 未知主机密钥通过 `host_key_prompt` 事件请求浏览器确认，ack 回传是否信任
+pub(crate) struct SocketAuthPrompter {
+    socket: SocketRef,
+}
+
+impl SocketAuthPrompter {
+    pub(crate) fn new(socket: SocketRef) -> Self {
+        Self { socket }
+    }
+}
+
+impl AuthPrompter for SocketAuthPrompter {
+    fn prompt(&self, request: AuthPromptRequest) -> BoxFuture<'_, SshPoolResult<Vec<String>>> {
+        Box::pin(async move {
+            let target_id = request.target_id;
+            let prompt_failed =
+                |reason: String| SshPoolError::AuthPromptFailed { target_id, reason };
+            let ack = self
+                .socket
+                .timeout(AUTH_PROMPT_TIMEOUT)
+                .emit_with_ack::<_, Vec<String>>("auth_prompt", &request)
+                .map_err(|err| prompt_failed(err.to_string()))?;
+            ack.await.map_err(|err| prompt_failed(err.to_string()))
+        })
+    }
+
+    fn confirm_host_key(
+        &self,
+        request: HostKeyPromptRequest,
+    ) -> BoxFuture<'_, SshPoolResult<bool>> {
+        Box::pin(async move {
+            let target_id = request.target_id;
+            let prompt_failed =
+                |reason: String| SshPoolError::AuthPromptFailed { target_id, reason };
+            let ack = self
+                .socket
+                .timeout(AUTH_PROMPT_TIMEOUT)
+                .emit_with_ack::<_, bool>("host_key_prompt", &request)
+                .map_err(|err| prompt_failed(err.to_string()))?;
+            ack.await.map_err(|err| prompt_failed(err.to_string()))
+        })
+    }
+}
+
+/// Quotes `value` as a single word for a POSIX shell.
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
 pub async fn exec(mut channel: SshChannelGuard, command: &str) -> Result<String, ApiErr> {
     debug!("@exec start {:?}", command);
     map_ssh_err!(channel.exec(true, command).await)?;
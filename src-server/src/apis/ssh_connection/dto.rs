@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::ssh_connection_pool::{ConnectionSnapshot, ConnectionState};
+use crate::ssh_connection_pool::{
+    ConnectionEvent, ConnectionEventKind, ConnectionSnapshot, ConnectionState, NegotiatedAlgorithms,
+};
 
 #[derive(Debug, Deserialize, utoipa::IntoParams)]
 pub struct SshConnectionExpirePayload {
@@ -10,6 +12,12 @@ pub struct SshConnectionExpirePayload {
     pub connection_id: String,
 }
 
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct SshConnectionWarmPayload {
+    /// 要预热的 SSH 目标 ID
+    pub target_id: i32,
+}
+
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct ConnectionInfo {
     /// SSH 连接 ID
@@ -22,6 +30,55 @@ pub struct ConnectionInfo {
     pub type_name: String,
     /// 关联的 SSH 目标 ID
     pub target_id: i32,
+    /// 正在使用的通道数
+    pub active_channels: usize,
+    /// 创建时间（Unix 时间戳，毫秒）
+    pub created_at: u64,
+    /// 最后一次收发数据的时间（Unix 时间戳，毫秒）
+    pub last_activity: u64,
+    /// 已接收字节数
+    pub bytes_in: u64,
+    /// 已发送字节数
+    pub bytes_out: u64,
+    /// 服务端版本标识（如 SSH-2.0-OpenSSH_9.6）
+    pub server_version: Option<String>,
+    /// 通过 ssh-agent 认证成功的身份（指纹与备注）
+    pub agent_identity: Option<String>,
+    /// 密钥交换协商出的算法
+    pub algorithms: Option<ConnectionAlgorithms>,
+}
+
+/// SSH 连接协商出的算法
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ConnectionAlgorithms {
+    /// 密钥交换算法
+    pub kex: String,
+    /// 主机密钥算法
+    pub host_key: String,
+    /// 加密算法
+    pub cipher: String,
+    /// 客户端到服务端的 MAC 算法
+    pub client_mac: String,
+    /// 服务端到客户端的 MAC 算法
+    pub server_mac: String,
+    /// 客户端到服务端的压缩算法
+    pub client_compression: String,
+    /// 服务端到客户端的压缩算法
+    pub server_compression: String,
+}
+
+impl From<NegotiatedAlgorithms> for ConnectionAlgorithms {
+    fn from(algorithms: NegotiatedAlgorithms) -> Self {
+        Self {
+            kex: algorithms.kex,
+            host_key: algorithms.host_key,
+            cipher: algorithms.cipher,
+            client_mac: algorithms.client_mac,
+            server_mac: algorithms.server_mac,
+            client_compression: algorithms.client_compression,
+            server_compression: algorithms.server_compression,
+        }
+    }
 }
 
 impl From<ConnectionSnapshot> for ConnectionInfo {
@@ -32,6 +89,82 @@ impl From<ConnectionSnapshot> for ConnectionInfo {
             closed: snapshot.state == ConnectionState::Closed,
             type_name: "SSH".to_string(),
             target_id: snapshot.target_id,
+            active_channels: snapshot.active_channels,
+            created_at: snapshot.created_at,
+            last_activity: snapshot.last_activity,
+            bytes_in: snapshot.bytes_in,
+            bytes_out: snapshot.bytes_out,
+            server_version: snapshot.server_version,
+            agent_identity: snapshot.agent_identity,
+            algorithms: snapshot.algorithms.map(ConnectionAlgorithms::from),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ConnectionEventQueryParams {
+    pub(crate) target_id: Option<i32>,
+}
+
+/// SSH 连接生命周期事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionEventType {
+    /// 正在建立连接
+    Connecting,
+    /// 建立连接失败
+    ConnectFailed,
+    /// 认证成功并加入连接池
+    Authenticated,
+    /// 打开通道
+    ChannelOpened,
+    /// 关闭通道
+    ChannelClosed,
+    /// 连接过期，空闲后关闭
+    Expiring,
+    /// 连接已关闭
+    Closed,
+}
+
+/// SSH 连接生命周期事件，通过 /api/ssh_connection/events 的 Socket.IO
+/// connection_event 事件推送
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ConnectionEventInfo {
+    /// 事件类型
+    #[serde(rename = "type")]
+    pub event_type: ConnectionEventType,
+    /// 关联的 SSH 目标 ID
+    pub target_id: i32,
+    /// SSH 连接 ID，认证成功前为空
+    pub connection_id: Option<String>,
+    /// 事件发生后正在使用的通道数
+    pub active_channels: usize,
+    /// 失败或关闭原因
+    pub reason: Option<String>,
+    /// 事件时间（Unix 时间戳，毫秒）
+    pub at: u64,
+}
+
+impl From<ConnectionEvent> for ConnectionEventInfo {
+    fn from(event: ConnectionEvent) -> Self {
+        let (event_type, reason) = match event.kind {
+            ConnectionEventKind::Connecting => (ConnectionEventType::Connecting, None),
+            ConnectionEventKind::ConnectFailed { reason } => {
+                (ConnectionEventType::ConnectFailed, Some(reason))
+            }
+            ConnectionEventKind::Authenticated => (ConnectionEventType::Authenticated, None),
+            ConnectionEventKind::ChannelOpened => (ConnectionEventType::ChannelOpened, None),
+            ConnectionEventKind::ChannelClosed => (ConnectionEventType::ChannelClosed, None),
+            ConnectionEventKind::Expiring => (ConnectionEventType::Expiring, None),
+            ConnectionEventKind::Closed { reason } => (ConnectionEventType::Closed, Some(reason)),
+        };
+        Self {
+            event_type,
+            target_id: event.target_id,
+            connection_id: event.connection_id,
+            active_channels: event.active_channels,
+            reason,
+            at: event.at,
         }
     }
 }
@@ -1,16 +1,21 @@
 use std::{collections::HashMap, sync::Arc};
 
 use axum::{
-    Json,
+    Json, Router,
     extract::{Query, State},
 };
-use tracing::{debug, info};
+use socketioxide::{SocketIo, extract::SocketRef};
+use tokio::sync::{Notify, broadcast::error::RecvError};
+use tracing::{debug, error, info, warn};
 
 use crate::{
     apis::{
         ApiErr, InternalErrorResponse,
         ssh_connection::{
-            dto::{ConnectionInfo, SshConnectionExpirePayload},
+            dto::{
+                ConnectionEventInfo, ConnectionEventQueryParams, ConnectionInfo,
+                SshConnectionExpirePayload, SshConnectionWarmPayload,
+            },
             service,
         },
     },
@@ -76,3 +81,88 @@ pub(crate) async fn expire(
     debug!("@ssh_connection done {:?}", payload);
     Ok(())
 }
+
+#[utoipa::path(
+    post,
+    path = "/api/ssh_connection/warm",
+    tag = "ssh_connection",
+    summary = "预热 SSH 连接",
+    description = "提前建立并认证目标的连接（数量为目标的最小空闲连接数，至少一个），使随后打开终端或 SFTP 时无需等待握手",
+    operation_id = "ssh_connection_warm",
+    params(
+        SshConnectionWarmPayload
+    ),
+    responses(
+        (status = 200, description = "连接已就绪"),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub(crate) async fn warm(
+    State(connection_pool): State<Arc<SshConnectionPool>>,
+    Query(payload): Query<SshConnectionWarmPayload>,
+) -> Result<(), ApiErr> {
+    info!("@ssh_connection {:?}", payload);
+
+    service::warm(&connection_pool, payload.target_id).await?;
+
+    debug!("@ssh_connection done {:?}", payload);
+    Ok(())
+}
+
+/// 通过 Socket.IO 推送连接池事件（connection_event），可按 target_id 过滤
+pub(crate) fn events_router_builder(
+    connection_pool: Arc<SshConnectionPool>,
+) -> Router<Arc<SshConnectionPool>> {
+    let connection_pool_clone = connection_pool.clone();
+    let (svc, io) = SocketIo::builder().build_svc();
+    io.ns("/", async move |socket: SocketRef| {
+        let query = socket.req_parts().uri.query().unwrap_or_default();
+        let params: ConnectionEventQueryParams = match serde_qs::from_str(query) {
+            Ok(params) => params,
+            Err(err) => {
+                error!(
+                    "sid={} invalid connection event query. {:?}",
+                    socket.id, err
+                );
+                let _ = socket.disconnect();
+                return;
+            }
+        };
+        info!("sid={} connection events {:?}", socket.id, params);
+        let mut events = connection_pool.subscribe_connection_events();
+        let disconnected = Arc::new(Notify::new());
+        socket.on_disconnect({
+            let disconnected = disconnected.clone();
+            async move |socket: SocketRef| {
+                debug!("sid={} connection events disconnect", socket.id);
+                disconnected.notify_one();
+            }
+        });
+        tokio::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    _ = disconnected.notified() => return,
+                    event = events.recv() => event,
+                };
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("sid={} skipped {} connection events", socket.id, skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                if params
+                    .target_id
+                    .is_some_and(|target_id| target_id != event.target_id)
+                {
+                    continue;
+                }
+                let _ = socket.emit("connection_event", &ConnectionEventInfo::from(event));
+            }
+        });
+    });
+    Router::new()
+        .fallback_service(svc)
+        .with_state(connection_pool_clone)
+}
@@ -11,12 +11,17 @@ use axum::{
 
 use crate::ssh_connection_pool::SshConnectionPool;
 
-pub(crate) use handlers::{expire, list};
+pub(crate) use handlers::{expire, list, warm};
 
 pub(crate) fn router_builder(connection_pool: Arc<SshConnectionPool>) -> Router {
     Router::new()
+        .nest(
+            "/events",
+            handlers::events_router_builder(connection_pool.clone()),
+        )
         .route("/list", get(list))
         .route("/expire", post(expire))
+        .route("/warm", post(warm))
         .fallback(|| async { "not supported" })
         .with_state(connection_pool)
 }
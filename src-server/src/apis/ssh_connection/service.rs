@@ -1,4 +1,9 @@
-use crate::{apis::ssh_connection::dto::ConnectionInfo, ssh_connection_pool::SshConnectionPool};
+use crate::{
+    apis::{ApiErr, ssh_connection::dto::ConnectionInfo},
+    consts::services_err_code::*,
+    map_ssh_err,
+    ssh_connection_pool::SshConnectionPool,
+};
 
 pub async fn list(
     connection_pool: &SshConnectionPool,
@@ -17,3 +22,7 @@ pub async fn expire(connection_pool: &SshConnectionPool, target_id: i32, connect
         .expire_connection(target_id, connection_id)
         .await;
 }
+
+pub async fn warm(connection_pool: &SshConnectionPool, target_id: i32) -> Result<(), ApiErr> {
+    map_ssh_err!(connection_pool.warm(target_id).await)
+}
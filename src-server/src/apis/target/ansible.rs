@@ -0,0 +1,835 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_yaml_ng::{Mapping, Value};
+
+use crate::apis::target::dto::AnsibleInventoryFormat;
+
+/// 所有主机都属于的分组
+const ALL: &str = "all";
+/// INI 中没有列在任何分组下的主机所属的分组
+const UNGROUPED: &str = "ungrouped";
+/// 展开主机范围（如 `web[01:50]`）时最多生成的主机数
+const MAX_RANGE_HOSTS: usize = 10_000;
+
+/// inventory 中的分组
+#[derive(Debug, PartialEq, Eq)]
+pub struct InventoryGroup {
+    pub name: String,
+    /// 第一个把该分组列为子分组的分组，顶层分组为空
+    pub parent: Option<String>,
+}
+
+/// inventory 中的主机，变量按 Ansible 的优先级（all、父分组、子分组、
+/// 主机变量）合并
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct InventoryHost {
+    /// inventory 中的主机名
+    pub name: String,
+    /// ansible_host
+    pub host: Option<String>,
+    /// ansible_port
+    pub port: Option<u16>,
+    /// ansible_user
+    pub user: Option<String>,
+    /// ansible_ssh_private_key_file
+    pub private_key_file: Option<String>,
+    /// 直接列出该主机的分组（不含 all 和 ungrouped）
+    pub groups: Vec<String>,
+}
+
+impl InventoryHost {
+    /// 实际连接的主机，未设置 ansible_host 时为主机名
+    pub fn host(&self) -> &str {
+        self.host.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Inventory {
+    pub groups: Vec<InventoryGroup>,
+    pub hosts: Vec<InventoryHost>,
+}
+
+impl Inventory {
+    /// 从顶层分组到 `group` 的名称路径
+    pub fn group_path(&self, group: &str) -> Vec<String> {
+        let mut path = vec![group.to_string()];
+        let mut current = group;
+        // 长度限制用于在分组循环时停止
+        while path.len() <= self.groups.len()
+            && let Some(parent) = self
+                .groups
+                .iter()
+                .find(|group| group.name == current)
+                .and_then(|group| group.parent.as_deref())
+        {
+            path.insert(0, parent.to_string());
+            current = parent;
+        }
+        path
+    }
+}
+
+/// 根据内容判断 inventory 格式：第一行有效内容为 `---` 或 `名称:` 时为 YAML
+pub fn detect_format(content: &str) -> AnsibleInventoryFormat {
+    let first = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with(['#', ';']));
+    match first {
+        Some(line)
+            if line == "---"
+                || (!line.starts_with('[')
+                    && split_tokens(line)
+                        .first()
+                        .is_some_and(|token| token.ends_with(':'))) =>
+        {
+            AnsibleInventoryFormat::Yaml
+        }
+        _ => AnsibleInventoryFormat::Ini,
+    }
+}
+
+/// 解析 INI 或 YAML 格式的 inventory，返回分组、主机以及无法处理的内容的提示
+pub fn parse(
+    content: &str,
+    format: AnsibleInventoryFormat,
+) -> Result<(Inventory, Vec<String>), String> {
+    let mut builder = Builder::default();
+    match format {
+        AnsibleInventoryFormat::Ini => parse_ini(content, &mut builder),
+        AnsibleInventoryFormat::Yaml => parse_yaml(content, &mut builder)?,
+    }
+    Ok(builder.finish())
+}
+
+#[derive(Default)]
+struct GroupDef {
+    name: String,
+    parents: Vec<usize>,
+    hosts: Vec<usize>,
+    vars: Vec<(String, String)>,
+}
+
+struct HostDef {
+    name: String,
+    vars: Vec<(String, String)>,
+}
+
+/// 按出现顺序收集分组和主机，`finish` 时合并变量
+#[derive(Default)]
+struct Builder {
+    groups: Vec<GroupDef>,
+    group_index: HashMap<String, usize>,
+    hosts: Vec<HostDef>,
+    host_index: HashMap<String, usize>,
+    memberships: HashSet<(usize, usize)>,
+    warnings: Vec<String>,
+}
+
+impl Builder {
+    fn group(&mut self, name: &str) -> usize {
+        if let Some(index) = self.group_index.get(name) {
+            return *index;
+        }
+        self.groups.push(GroupDef {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        self.group_index
+            .insert(name.to_string(), self.groups.len() - 1);
+        self.groups.len() - 1
+    }
+
+    fn add_child(&mut self, parent: &str, child: &str) {
+        let parent = self.group(parent);
+        let child = self.group(child);
+        if parent != child && !self.groups[child].parents.contains(&parent) {
+            self.groups[child].parents.push(parent);
+        }
+    }
+
+    fn add_var(&mut self, group: &str, key: &str, value: &str) {
+        let group = self.group(group);
+        self.groups[group]
+            .vars
+            .push((key.to_string(), value.to_string()));
+    }
+
+    /// 添加主机模式（可带端口和范围）展开后的主机
+    fn add_hosts(
+        &mut self,
+        source: &str,
+        group: &str,
+        pattern: &str,
+        mut vars: Vec<(String, String)>,
+    ) {
+        let (pattern, port) = split_port(pattern);
+        let names = match expand_range(pattern) {
+            Ok(names) => names,
+            Err(err) => {
+                self.warnings.push(format!("{source}: {err}"));
+                return;
+            }
+        };
+        if let Some(port) = port {
+            vars.insert(0, ("ansible_port".to_string(), port.to_string()));
+        }
+        let group = self.group(group);
+        for name in names {
+            let name = match name
+                .strip_prefix('[')
+                .and_then(|name| name.strip_suffix(']'))
+            {
+                Some(address) => address.to_string(),
+                None => name,
+            };
+            let host = match self.host_index.get(&name) {
+                Some(host) => *host,
+                None => {
+                    self.hosts.push(HostDef {
+                        name: name.clone(),
+                        vars: Vec::new(),
+                    });
+                    self.host_index.insert(name, self.hosts.len() - 1);
+                    self.hosts.len() - 1
+                }
+            };
+            if self.memberships.insert((group, host)) {
+                self.groups[group].hosts.push(host);
+            }
+            self.hosts[host].vars.extend(vars.iter().cloned());
+        }
+    }
+
+    fn finish(mut self) -> (Inventory, Vec<String>) {
+        let all = self.group(ALL);
+        let ungrouped = self.group(UNGROUPED);
+        let implicit = |group: usize| group == all || group == ungrouped;
+        // 第一个父分组决定分组路径和变量的优先级
+        let parents: Vec<Option<usize>> = self
+            .groups
+            .iter()
+            .map(|group| {
+                group
+                    .parents
+                    .iter()
+                    .copied()
+                    .find(|&parent| !implicit(parent))
+            })
+            .collect();
+        let depths: Vec<usize> = (0..self.groups.len())
+            .map(|group| {
+                if group == all {
+                    return 0;
+                }
+                let mut depth = 1;
+                let mut current = group;
+                while depth <= self.groups.len()
+                    && let Some(parent) = parents[current]
+                {
+                    depth += 1;
+                    current = parent;
+                }
+                depth
+            })
+            .collect();
+
+        let mut direct_groups = vec![Vec::new(); self.hosts.len()];
+        for (index, group) in self.groups.iter().enumerate() {
+            for &host in &group.hosts {
+                direct_groups[host].push(index);
+            }
+        }
+        let mut hosts = Vec::with_capacity(self.hosts.len());
+        for (host, direct) in self.hosts.iter().zip(direct_groups) {
+            let mut applicable = vec![all];
+            let mut pending = direct.clone();
+            while let Some(group) = pending.pop() {
+                if !applicable.contains(&group) {
+                    applicable.push(group);
+                    pending.extend(&self.groups[group].parents);
+                }
+            }
+            applicable.sort_by(|&a, &b| {
+                depths[a]
+                    .cmp(&depths[b])
+                    .then_with(|| self.groups[a].name.cmp(&self.groups[b].name))
+            });
+            let mut vars = HashMap::new();
+            let group_vars = applicable
+                .iter()
+                .flat_map(|&group| &self.groups[group].vars);
+            for (key, value) in group_vars.chain(&host.vars) {
+                vars.insert(key.as_str(), value.as_str());
+            }
+            let var = |names: &[&str]| {
+                names
+                    .iter()
+                    .find_map(|name| vars.get(name))
+                    .map(|value| value.to_string())
+            };
+            let port = var(&["ansible_port", "ansible_ssh_port"]).and_then(|port| {
+                let parsed = port.parse().ok();
+                if parsed.is_none() {
+                    self.warnings
+                        .push(format!("host {}: invalid ansible_port {port}", host.name));
+                }
+                parsed
+            });
+            hosts.push(InventoryHost {
+                name: host.name.clone(),
+                host: var(&["ansible_host", "ansible_ssh_host"]),
+                port,
+                user: var(&["ansible_user", "ansible_ssh_user"]),
+                private_key_file: var(&[
+                    "ansible_ssh_private_key_file",
+                    "ansible_private_key_file",
+                ]),
+                groups: direct
+                    .into_iter()
+                    .filter(|&group| !implicit(group))
+                    .map(|group| self.groups[group].name.clone())
+                    .collect(),
+            });
+        }
+        let groups = self
+            .groups
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !implicit(*index))
+            .map(|(index, group)| InventoryGroup {
+                name: group.name.clone(),
+                parent: parents[index].map(|parent| self.groups[parent].name.clone()),
+            })
+            .collect();
+        (Inventory { groups, hosts }, self.warnings)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Hosts,
+    Vars,
+    Children,
+    Unsupported,
+}
+
+fn parse_ini(content: &str, builder: &mut Builder) {
+    let mut group = UNGROUPED.to_string();
+    let mut section = Section::Hosts;
+    for (index, raw) in content.lines().enumerate() {
+        let source = format!("line {}", index + 1);
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(header) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            let header = header.trim();
+            let (name, kind) = match header.split_once(':') {
+                None => (header, Section::Hosts),
+                Some((name, "vars")) => (name, Section::Vars),
+                Some((name, "children")) => (name, Section::Children),
+                Some((name, _)) => {
+                    builder
+                        .warnings
+                        .push(format!("{source}: unsupported section [{header}]"));
+                    (name, Section::Unsupported)
+                }
+            };
+            group = name.trim().to_string();
+            section = kind;
+            builder.group(&group);
+            continue;
+        }
+        match section {
+            Section::Hosts => {
+                let tokens = split_tokens(line);
+                let Some((pattern, args)) = tokens.split_first() else {
+                    continue;
+                };
+                let mut vars = Vec::new();
+                for arg in args {
+                    match arg.split_once('=') {
+                        Some((key, value)) => vars.push((key.to_string(), value.to_string())),
+                        None => builder
+                            .warnings
+                            .push(format!("{source}: expected key=value, got {arg}")),
+                    }
+                }
+                builder.add_hosts(&source, &group, pattern, vars);
+            }
+            Section::Vars => match line.split_once('=') {
+                Some((key, value)) => builder.add_var(&group, key.trim(), unquote(value.trim())),
+                None => builder
+                    .warnings
+                    .push(format!("{source}: expected key=value")),
+            },
+            Section::Children => {
+                if let Some(child) = split_tokens(line).first() {
+                    builder.add_child(&group, child);
+                }
+            }
+            Section::Unsupported => {}
+        }
+    }
+}
+
+/// 按空白拆分 INI 主机行，去掉引号，不在引号内的 `#` 开始注释
+fn split_tokens(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut in_token = false;
+    let mut quote = None;
+    for c in line.chars() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => token.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_token = true;
+            }
+            None if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut token));
+                    in_token = false;
+                }
+            }
+            None if c == '#' && !in_token => break,
+            None => {
+                token.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+/// 拆出 `host:port` 中的端口，方括号内（范围、IPv6 地址）的冒号不计
+fn split_port(pattern: &str) -> (&str, Option<&str>) {
+    let mut depth = 0usize;
+    let mut colons = Vec::new();
+    for (index, c) in pattern.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 => colons.push(index),
+            _ => {}
+        }
+    }
+    match colons[..] {
+        [index]
+            if index + 1 < pattern.len()
+                && pattern[index + 1..].bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            (&pattern[..index], Some(&pattern[index + 1..]))
+        }
+        _ => (pattern, None),
+    }
+}
+
+/// 展开 `[01:50]`、`[a:f]`、`[1:9:2]` 形式的主机范围，其他方括号原样保留
+fn expand_range(pattern: &str) -> Result<Vec<String>, String> {
+    let Some((prefix, rest)) = pattern.split_once('[') else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    let Some((range, suffix)) = rest.split_once(']') else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    let (begin, end, step) = match range.split(':').collect::<Vec<_>>()[..] {
+        [begin, end] => (begin, end, "1"),
+        [begin, end, step] => (begin, end, step),
+        _ => return Ok(vec![pattern.to_string()]),
+    };
+    let is_number = |value: &str| !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit());
+    let is_letter =
+        |value: &str| value.len() == 1 && value.bytes().all(|b| b.is_ascii_alphabetic());
+    let invalid = || format!("invalid host range {pattern}");
+    let too_many = || format!("host range {pattern} expands to more than {MAX_RANGE_HOSTS} hosts");
+    let values: Vec<String> = if is_number(begin) && is_number(end) {
+        let step: usize = step
+            .parse()
+            .ok()
+            .filter(|step| *step > 0)
+            .ok_or_else(invalid)?;
+        let first: u64 = begin.parse().map_err(|_| invalid())?;
+        let last: u64 = end.parse().map_err(|_| invalid())?;
+        let width = if begin.len() > 1 && begin.starts_with('0') {
+            if begin.len() != end.len() {
+                return Err(format!(
+                    "host range {pattern} must have begin and end of the same length"
+                ));
+            }
+            begin.len()
+        } else {
+            0
+        };
+        if first > last {
+            return Err(invalid());
+        }
+        if (last - first) / step as u64 >= MAX_RANGE_HOSTS as u64 {
+            return Err(too_many());
+        }
+        (first..=last)
+            .step_by(step)
+            .map(|value| format!("{value:0width$}"))
+            .collect()
+    } else if is_letter(begin) && is_letter(end) {
+        let step: usize = step
+            .parse()
+            .ok()
+            .filter(|step| *step > 0)
+            .ok_or_else(invalid)?;
+        let (first, last) = (begin.as_bytes()[0], end.as_bytes()[0]);
+        if first > last {
+            return Err(invalid());
+        }
+        (first..=last)
+            .step_by(step)
+            .map(|value| char::from(value).to_string())
+            .collect()
+    } else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    let suffixes = expand_range(suffix)?;
+    if values.len() * suffixes.len() > MAX_RANGE_HOSTS {
+        return Err(too_many());
+    }
+    Ok(values
+        .iter()
+        .flat_map(|value| {
+            suffixes
+                .iter()
+                .map(move |suffix| format!("{prefix}{value}{suffix}"))
+        })
+        .collect())
+}
+
+fn parse_yaml(content: &str, builder: &mut Builder) -> Result<(), String> {
+    let mut root: Value = serde_yaml_ng::from_str(content).map_err(|err| err.to_string())?;
+    root.apply_merge().map_err(|err| err.to_string())?;
+    let groups = match root {
+        Value::Null => return Ok(()),
+        Value::Mapping(groups) => groups,
+        _ => return Err("the inventory must be a mapping of groups".to_string()),
+    };
+    for (name, node) in groups {
+        match scalar(&name) {
+            Some(name) => load_group(builder, &name, node),
+            None => builder
+                .warnings
+                .push("a group name must be a string".to_string()),
+        }
+    }
+    Ok(())
+}
+
+/// 加载 YAML 中的分组：`hosts`、`vars` 和 `children`
+fn load_group(builder: &mut Builder, name: &str, node: Value) {
+    builder.group(name);
+    let entries = match node {
+        Value::Null => return,
+        Value::Mapping(entries) => entries,
+        _ => {
+            builder
+                .warnings
+                .push(format!("group {name}: expected a mapping"));
+            return;
+        }
+    };
+    let source = format!("group {name}");
+    for (key, value) in entries {
+        let key = scalar(&key).unwrap_or_default();
+        match (key.as_str(), value) {
+            (_, Value::Null) => {}
+            ("hosts", Value::Mapping(hosts)) => {
+                for (pattern, vars) in hosts {
+                    let Some(pattern) = scalar(&pattern) else {
+                        builder
+                            .warnings
+                            .push(format!("{source}: a host name must be a string"));
+                        continue;
+                    };
+                    let vars = match vars {
+                        Value::Null => Vec::new(),
+                        Value::Mapping(vars) => scalar_vars(vars),
+                        _ => {
+                            builder
+                                .warnings
+                                .push(format!("host {pattern}: expected a mapping"));
+                            Vec::new()
+                        }
+                    };
+                    builder.add_hosts(&source, name, &pattern, vars);
+                }
+            }
+            ("vars", Value::Mapping(vars)) => {
+                for (key, value) in scalar_vars(vars) {
+                    builder.add_var(name, &key, &value);
+                }
+            }
+            ("children", Value::Mapping(children)) => {
+                for (child, node) in children {
+                    let Some(child) = scalar(&child) else {
+                        builder
+                            .warnings
+                            .push(format!("{source}: a group name must be a string"));
+                        continue;
+                    };
+                    builder.add_child(name, &child);
+                    load_group(builder, &child, node);
+                }
+            }
+            ("hosts" | "vars" | "children", _) => builder
+                .warnings
+                .push(format!("{source}: {key} must be a mapping")),
+            _ => builder
+                .warnings
+                .push(format!("{source}: unsupported key {key}")),
+        }
+    }
+}
+
+/// 字符串、数字和布尔值的文本，其余的值返回 `None`
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// 只保留标量变量，列表、映射等变量与目标配置无关
+fn scalar_vars(vars: Mapping) -> Vec<(String, String)> {
+    vars.into_iter()
+        .filter_map(|(key, value)| Some((scalar(&key)?, scalar(&value)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(name: &str, groups: &[&str]) -> InventoryHost {
+        InventoryHost {
+            name: name.to_string(),
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ini_inventory_merges_group_and_host_vars() {
+        let content = "\
+# comment
+bastion.example.com ansible_user=jump
+
+[web]
+web[01:02].example.com
+web3 ansible_host=10.0.0.3 ansible_port=2222 note=\"a b\" # comment
+
+[db]
+db1:2200 ansible_ssh_private_key_file=~/.ssh/db
+
+[prod:children]
+web
+db
+
+[prod:vars]
+ansible_user = deploy
+
+[web:vars]
+ansible_user=www
+
+[all:vars]
+ansible_port=22
+
+[web:extra]
+";
+        assert_eq!(detect_format(content), AnsibleInventoryFormat::Ini);
+        let (inventory, warnings) = parse(content, AnsibleInventoryFormat::Ini).unwrap();
+
+        assert_eq!(warnings, vec!["line 24: unsupported section [web:extra]"]);
+        assert_eq!(
+            inventory.groups,
+            vec![
+                InventoryGroup {
+                    name: "web".to_string(),
+                    parent: Some("prod".to_string()),
+                },
+                InventoryGroup {
+                    name: "db".to_string(),
+                    parent: Some("prod".to_string()),
+                },
+                InventoryGroup {
+                    name: "prod".to_string(),
+                    parent: None,
+                },
+            ]
+        );
+        assert_eq!(
+            inventory.hosts,
+            vec![
+                InventoryHost {
+                    user: Some("jump".to_string()),
+                    port: Some(22),
+                    ..host("bastion.example.com", &[])
+                },
+                InventoryHost {
+                    user: Some("www".to_string()),
+                    port: Some(22),
+                    ..host("web01.example.com", &["web"])
+                },
+                InventoryHost {
+                    user: Some("www".to_string()),
+                    port: Some(22),
+                    ..host("web02.example.com", &["web"])
+                },
+                InventoryHost {
+                    host: Some("10.0.0.3".to_string()),
+                    user: Some("www".to_string()),
+                    port: Some(2222),
+                    ..host("web3", &["web"])
+                },
+                InventoryHost {
+                    user: Some("deploy".to_string()),
+                    port: Some(2200),
+                    private_key_file: Some("~/.ssh/db".to_string()),
+                    ..host("db1", &["db"])
+                },
+            ]
+        );
+        assert_eq!(inventory.group_path("db"), vec!["prod", "db"]);
+    }
+
+    #[test]
+    fn yaml_inventory_is_parsed() {
+        let content = "\
+---
+all:
+  vars:
+    ansible_user: admin
+    ntp_servers:
+      - 10.0.0.1
+    motd: |
+      welcome
+  hosts:
+    bastion:
+      ansible_host: \"203.0.113.10\" # public address
+  children:
+    prod:
+      vars: {ansible_user: deploy}
+      children:
+        web:
+          hosts:
+            web[a:b].example.com:
+            '[2001:db8::1]:2022':
+        db:
+          hosts:
+            db1:
+              ansible_private_key_file: keys/db
+              ansible_port: 5022
+    staging: {}
+";
+        assert_eq!(detect_format(content), AnsibleInventoryFormat::Yaml);
+        let (inventory, warnings) = parse(content, AnsibleInventoryFormat::Yaml).unwrap();
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(
+            inventory
+                .groups
+                .iter()
+                .map(|group| (group.name.as_str(), group.parent.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("prod", None),
+                ("web", Some("prod")),
+                ("db", Some("prod")),
+                ("staging", None),
+            ]
+        );
+        assert_eq!(
+            inventory.hosts,
+            vec![
+                InventoryHost {
+                    host: Some("203.0.113.10".to_string()),
+                    user: Some("admin".to_string()),
+                    ..host("bastion", &[])
+                },
+                InventoryHost {
+                    user: Some("deploy".to_string()),
+                    ..host("weba.example.com", &["web"])
+                },
+                InventoryHost {
+                    user: Some("deploy".to_string()),
+                    ..host("webb.example.com", &["web"])
+                },
+                InventoryHost {
+                    user: Some("deploy".to_string()),
+                    port: Some(2022),
+                    ..host("2001:db8::1", &["web"])
+                },
+                InventoryHost {
+                    user: Some("deploy".to_string()),
+                    port: Some(5022),
+                    private_key_file: Some("keys/db".to_string()),
+                    ..host("db1", &["db"])
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_input_is_reported() {
+        let (inventory, warnings) = parse(
+            "[web]\nweb[1:20000]\nweb[3:1]\nweb[01:100]\nweb1 ansible_port=http\n",
+            AnsibleInventoryFormat::Ini,
+        )
+        .unwrap();
+        assert_eq!(inventory.hosts.len(), 1);
+        assert_eq!(
+            warnings,
+            vec![
+                "line 2: host range web[1:20000] expands to more than 10000 hosts",
+                "line 3: invalid host range web[3:1]",
+                "line 4: host range web[01:100] must have begin and end of the same length",
+                "host web1: invalid ansible_port http",
+            ]
+        );
+
+        assert_eq!(
+            parse(
+                "all:\n  hosts:\n      web1:\n    web2:\n",
+                AnsibleInventoryFormat::Yaml
+            )
+            .err(),
+            Some(
+                "did not find expected key at line 4 column 5, while parsing a block mapping at line 2 column 3"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse("- all\n", AnsibleInventoryFormat::Yaml).err(),
+            Some("the inventory must be a mapping of groups".to_string())
+        );
+    }
+}
@@ -0,0 +1,494 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::{
+    entities::{
+        ssh_known_host::KnownHostMarker,
+        target::{TargetAlgorithms, TargetAuthMethod, TargetHostKeyPolicy},
+    },
+    secrets::{self, PASSPHRASE_ITERATIONS, PassphraseSealed},
+};
+
+const FORMAT: &str = "webssh-rs-targets";
+const ENCRYPTED_FORMAT: &str = "webssh-rs-targets-encrypted";
+const VERSION: u32 = 1;
+const KDF: &str = "pbkdf2-hmac-sha256";
+/// 导入加密文件时允许的最大迭代次数，避免构造的文件长时间占用 CPU
+const MAX_ITERATIONS: u32 = 10_000_000;
+const CSV_COLUMNS: [&str; 13] = [
+    "host",
+    "port",
+    "user",
+    "method",
+    "system",
+    "group",
+    "tags",
+    "jump",
+    "proxy",
+    "proxy_command",
+    "host_key_policy",
+    "connect_timeout",
+    "connect_retries",
+];
+
+/// 批量导出的目标、分组与已知主机密钥
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct TargetBundle {
+    pub format: String,
+    pub version: u32,
+    /// 所有分组，以从顶级分组开始的名称路径表示
+    #[serde(default)]
+    pub groups: Vec<Vec<String>>,
+    #[serde(default)]
+    pub targets: Vec<BundleTarget>,
+    #[serde(default)]
+    pub known_hosts: Vec<BundleKnownHost>,
+}
+
+impl TargetBundle {
+    pub fn new() -> Self {
+        Self {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            groups: Vec::new(),
+            targets: Vec::new(),
+            known_hosts: Vec::new(),
+        }
+    }
+}
+
+/// 导出的目标，不含数据库 ID，跳板机与分组以地址和名称路径引用
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct BundleTarget {
+    pub host: String,
+    pub port: Option<u16>,
+    pub user: String,
+    pub method: TargetAuthMethod,
+    /// 只在加密文件中导出
+    pub password: Option<String>,
+    /// 私钥内容（密钥库中的私钥同样导出到这里），只在加密文件中导出
+    pub key: Option<String>,
+    pub certificate: Option<String>,
+    pub system: Option<String>,
+    /// 跳板机地址 `user@host:port`
+    pub jump: Option<String>,
+    pub proxy: Option<String>,
+    pub proxy_command: Option<String>,
+    pub algorithms: Option<TargetAlgorithms>,
+    pub connect_timeout: Option<u32>,
+    pub connect_retries: Option<u32>,
+    pub min_idle_connections: Option<u32>,
+    pub host_key_policy: Option<TargetHostKeyPolicy>,
+    /// 所属分组的名称路径，为空表示未分组
+    #[serde(default)]
+    pub group: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// SFTP 收藏目录
+    #[serde(default)]
+    pub favorites: Vec<BundleFavorite>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct BundleFavorite {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct BundleKnownHost {
+    pub host: String,
+    pub port: u16,
+    pub key_algorithm: String,
+    pub public_key: String,
+    pub fingerprint: String,
+    pub marker: Option<KnownHostMarker>,
+}
+
+/// 用口令加密的 TargetBundle
+#[derive(Serialize, Deserialize)]
+struct EncryptedBundle {
+    format: String,
+    version: u32,
+    kdf: String,
+    iterations: u32,
+    /// Base64 编码的盐
+    salt: String,
+    /// Base64 编码的 nonce、密文与认证标签
+    data: String,
+}
+
+pub fn to_json(bundle: &TargetBundle) -> String {
+    serde_json::to_string_pretty(bundle).expect("target bundles serialize to JSON")
+}
+
+pub fn from_json(content: &str) -> Result<TargetBundle, String> {
+    let bundle: TargetBundle =
+        serde_json::from_str(content).map_err(|err| format!("invalid target file: {err}"))?;
+    check_format(&bundle.format, bundle.version, FORMAT)?;
+    Ok(bundle)
+}
+
+pub fn encrypt(bundle: &TargetBundle, passphrase: &str) -> Result<String, String> {
+    encrypt_with_iterations(bundle, passphrase, PASSPHRASE_ITERATIONS)
+}
+
+fn encrypt_with_iterations(
+    bundle: &TargetBundle,
+    passphrase: &str,
+    iterations: u32,
+) -> Result<String, String> {
+    let plaintext =
+        Zeroizing::new(serde_json::to_vec(bundle).expect("target bundles serialize to JSON"));
+    let sealed = secrets::seal_with_passphrase(passphrase, iterations, &plaintext)
+        .map_err(|err| err.to_string())?;
+    let encrypted = EncryptedBundle {
+        format: ENCRYPTED_FORMAT.to_string(),
+        version: VERSION,
+        kdf: KDF.to_string(),
+        iterations: sealed.iterations,
+        salt: STANDARD.encode(&sealed.salt),
+        data: STANDARD.encode(&sealed.data),
+    };
+    Ok(serde_json::to_string_pretty(&encrypted).expect("encrypted bundles serialize to JSON"))
+}
+
+pub fn decrypt(content: &str, passphrase: &str) -> Result<TargetBundle, String> {
+    let encrypted: EncryptedBundle = serde_json::from_str(content)
+        .map_err(|err| format!("invalid encrypted target file: {err}"))?;
+    check_format(&encrypted.format, encrypted.version, ENCRYPTED_FORMAT)?;
+    if encrypted.kdf != KDF {
+        return Err(format!("unsupported key derivation {}", encrypted.kdf));
+    }
+    if encrypted.iterations == 0 || encrypted.iterations > MAX_ITERATIONS {
+        return Err(format!(
+            "key derivation iterations must be between 1 and {MAX_ITERATIONS}"
+        ));
+    }
+    let decode = |value: &str| {
+        STANDARD
+            .decode(value)
+            .map_err(|err| format!("invalid encrypted target file: {err}"))
+    };
+    let sealed = PassphraseSealed {
+        salt: decode(&encrypted.salt)?,
+        iterations: encrypted.iterations,
+        data: decode(&encrypted.data)?,
+    };
+    let plaintext =
+        secrets::open_with_passphrase(passphrase, &sealed).map_err(|err| err.to_string())?;
+    let bundle: TargetBundle = serde_json::from_slice(&plaintext)
+        .map_err(|err| format!("invalid encrypted target file: {err}"))?;
+    check_format(&bundle.format, bundle.version, FORMAT)?;
+    Ok(bundle)
+}
+
+fn check_format(format: &str, version: u32, expected: &str) -> Result<(), String> {
+    if format != expected {
+        return Err(format!("expected a {expected} file, got {format}"));
+    }
+    if version == 0 || version > VERSION {
+        return Err(format!("unsupported {format} version {version}"));
+    }
+    Ok(())
+}
+
+/// 每行一个目标。分组路径以 `/` 连接，标签以 `;` 分隔；
+/// 不含密码、私钥、收藏目录与已知主机密钥
+pub fn to_csv(bundle: &TargetBundle) -> String {
+    let mut output = String::new();
+    write_csv_row(&mut output, CSV_COLUMNS.map(str::to_string));
+    let number = |value: Option<u32>| value.map(|value| value.to_string()).unwrap_or_default();
+    for target in &bundle.targets {
+        write_csv_row(
+            &mut output,
+            [
+                target.host.clone(),
+                target.port.map(|port| port.to_string()).unwrap_or_default(),
+                target.user.clone(),
+                method_name(&target.method).to_string(),
+                target.system.clone().unwrap_or_default(),
+                target.group.join("/"),
+                target.tags.join(";"),
+                target.jump.clone().unwrap_or_default(),
+                target.proxy.clone().unwrap_or_default(),
+                target.proxy_command.clone().unwrap_or_default(),
+                target.host_key_policy.map(policy_name).unwrap_or_default(),
+                number(target.connect_timeout),
+                number(target.connect_retries),
+            ],
+        );
+    }
+    output
+}
+
+/// 按表头中的列名读取目标，只有 host 与 user 列是必需的。
+/// 未填写 method 的目标在连接时询问密码
+pub fn from_csv(content: &str) -> Result<TargetBundle, String> {
+    let mut rows = parse_csv(content)?.into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .ok_or_else(|| "the CSV file is empty".to_string())?
+        .iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|column| column == name);
+    for required in ["host", "user"] {
+        if column(required).is_none() {
+            return Err(format!("the CSV header has no {required} column"));
+        }
+    }
+
+    let mut bundle = TargetBundle::new();
+    // 表头是第 1 行
+    for (row_number, row) in (2..).zip(rows) {
+        if row.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let field = |name: &str| {
+            column(name)
+                .and_then(|index| row.get(index))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let invalid = |name: &str, value: &str| format!("row {row_number}: invalid {name} {value}");
+        let number = |name: &str| {
+            field(name)
+                .map(|value| value.parse::<u32>().map_err(|_| invalid(name, value)))
+                .transpose()
+        };
+        let group: Vec<String> = field("group")
+            .into_iter()
+            .flat_map(|group| group.split('/'))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !group.is_empty() && !bundle.groups.contains(&group) {
+            bundle.groups.push(group.clone());
+        }
+        bundle.targets.push(BundleTarget {
+            host: field("host")
+                .ok_or_else(|| format!("row {row_number}: host is empty"))?
+                .to_string(),
+            port: field("port")
+                .map(|port| port.parse().map_err(|_| invalid("port", port)))
+                .transpose()?,
+            user: field("user")
+                .ok_or_else(|| format!("row {row_number}: user is empty"))?
+                .to_string(),
+            method: match field("method") {
+                Some(method) => parse_method(method).ok_or_else(|| invalid("method", method))?,
+                None => TargetAuthMethod::Prompt,
+            },
+            password: None,
+            key: None,
+            certificate: None,
+            system: field("system").map(str::to_string),
+            jump: field("jump").map(str::to_string),
+            proxy: field("proxy").map(str::to_string),
+            proxy_command: field("proxy_command").map(str::to_string),
+            algorithms: None,
+            connect_timeout: number("connect_timeout")?,
+            connect_retries: number("connect_retries")?,
+            min_idle_connections: None,
+            host_key_policy: field("host_key_policy")
+                .map(|policy| {
+                    parse_policy(policy).ok_or_else(|| invalid("host_key_policy", policy))
+                })
+                .transpose()?,
+            group,
+            tags: field("tags")
+                .into_iter()
+                .flat_map(|tags| tags.split(';'))
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect(),
+            favorites: Vec::new(),
+        });
+    }
+    Ok(bundle)
+}
+
+fn method_name(method: &TargetAuthMethod) -> &'static str {
+    match method {
+        TargetAuthMethod::Password => "password",
+        TargetAuthMethod::PrivateKey => "private_key",
+        TargetAuthMethod::None => "none",
+        TargetAuthMethod::Agent => "agent",
+        TargetAuthMethod::KeyboardInteractive => "keyboard_interactive",
+        TargetAuthMethod::Prompt => "prompt",
+    }
+}
+
+/// 接受 method_name 输出的名称或数据库中的数值
+fn parse_method(value: &str) -> Option<TargetAuthMethod> {
+    if let Ok(value) = value.parse::<i32>() {
+        return TargetAuthMethod::try_from(value).ok();
+    }
+    [
+        TargetAuthMethod::Password,
+        TargetAuthMethod::PrivateKey,
+        TargetAuthMethod::None,
+        TargetAuthMethod::Agent,
+        TargetAuthMethod::KeyboardInteractive,
+        TargetAuthMethod::Prompt,
+    ]
+    .into_iter()
+    .find(|method| method_name(method).eq_ignore_ascii_case(value))
+}
+
+fn policy_name(policy: TargetHostKeyPolicy) -> String {
+    serde_json::to_value(policy)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn parse_policy(value: &str) -> Option<TargetHostKeyPolicy> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase())).ok()
+}
+
+fn write_csv_row(output: &mut String, fields: impl IntoIterator<Item = String>) {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) || field.trim() != field {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    output.push_str(&fields.join(","));
+    output.push('\n');
+}
+
+/// RFC 4180：双引号包围的字段可以包含逗号、换行和成对的双引号
+fn parse_csv(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' | '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("the CSV file ends inside a quoted field".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(host: &str, user: &str) -> BundleTarget {
+        BundleTarget {
+            host: host.to_string(),
+            port: None,
+            user: user.to_string(),
+            method: TargetAuthMethod::Prompt,
+            password: None,
+            key: None,
+            certificate: None,
+            system: None,
+            jump: None,
+            proxy: None,
+            proxy_command: None,
+            algorithms: None,
+            connect_timeout: None,
+            connect_retries: None,
+            min_idle_connections: None,
+            host_key_policy: None,
+            group: Vec::new(),
+            tags: Vec::new(),
+            favorites: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn csv_round_trips_quoted_fields() {
+        let mut bundle = TargetBundle::new();
+        let mut db = target("db.internal", "postgres");
+        db.port = Some(2200);
+        db.method = TargetAuthMethod::Agent;
+        db.group = vec!["prod".to_string(), "eu, west".to_string()];
+        db.tags = vec!["db".to_string(), "critical".to_string()];
+        db.jump = Some("ops@bastion:22".to_string());
+        db.proxy_command = Some("ssh -W \"%h:%p\" gw\nexit".to_string());
+        db.host_key_policy = Some(TargetHostKeyPolicy::AcceptNew);
+        db.connect_timeout = Some(15);
+        bundle.groups.push(db.group.clone());
+        bundle.targets.push(db);
+        bundle.targets.push(target("bastion", "ops"));
+
+        let csv = to_csv(&bundle);
+        assert!(csv.starts_with("host,port,user,method,"));
+        assert!(from_csv(&csv).unwrap() == bundle);
+    }
+
+    #[test]
+    fn csv_columns_are_matched_by_name() {
+        let bundle =
+            from_csv("\u{feff}User,Host,Method\r\nroot,10.0.0.1,2\r\n,,\r\nadmin,10.0.0.2,\r\n")
+                .unwrap();
+        assert_eq!(bundle.targets.len(), 2);
+        assert_eq!(bundle.targets[0].host, "10.0.0.1");
+        assert_eq!(bundle.targets[0].method, TargetAuthMethod::PrivateKey);
+        assert_eq!(bundle.targets[1].method, TargetAuthMethod::Prompt);
+
+        assert_eq!(
+            from_csv("host,user,port\nweb,root,http\n").err().as_deref(),
+            Some("row 2: invalid port http")
+        );
+        assert!(from_csv("host,port\nweb,22\n").is_err());
+        assert!(from_csv("host,user\n\"web,root\n").is_err());
+    }
+
+    #[test]
+    fn encrypted_bundles_need_the_passphrase() {
+        let mut bundle = TargetBundle::new();
+        let mut web = target("web", "root");
+        web.password = Some("hunter2".to_string());
+        bundle.targets.push(web);
+
+        let encrypted = encrypt_with_iterations(&bundle, "team secret", 1_000).unwrap();
+        assert!(!encrypted.contains("hunter2"));
+        assert!(decrypt(&encrypted, "team secret").unwrap() == bundle);
+        assert!(decrypt(&encrypted, "wrong").is_err());
+        // An encrypted file is not a plain target file and vice versa.
+        assert!(from_json(&encrypted).is_err());
+        assert!(decrypt(&to_json(&bundle), "team secret").is_err());
+    }
+}
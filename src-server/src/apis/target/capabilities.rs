@@ -0,0 +1,335 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sea_orm::{DatabaseConnection, DbErr};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
+
+use crate::{
+    apis::{ApiErr, ssh::exec},
+    consts::services_err_code::*,
+    entities::{
+        target,
+        target_capabilities::{self, CapabilityNames, WINDOWS},
+    },
+    map_db_err, map_ssh_err,
+    repositories::target_capabilities as target_capabilities_repository,
+    ssh_connection_pool::{ChannelMode, ChannelPriority, ConnectionEventKind, SshConnectionPool},
+};
+
+/// Tools looked up with `command -v` on POSIX targets.
+const POSIX_TOOLS: &[&str] = &[
+    "tar",
+    "gzip",
+    "unzip",
+    "sha256sum",
+    "shasum",
+    "md5sum",
+    "rsync",
+    "python3",
+];
+
+/// Tools looked up with `where` on Windows targets.
+const WINDOWS_TOOLS: &[&str] = &["tar", "certutil", "robocopy", "powershell", "pwsh"];
+
+/// Environment reported by the probe command.
+#[derive(Debug, PartialEq, Eq)]
+struct RemoteEnvironment {
+    os: String,
+    os_version: Option<String>,
+    shell: Option<String>,
+    tools: Vec<String>,
+}
+
+/// Whether `target` runs Windows, as detected or, until its first
+/// detection, as entered in `system`.
+pub(crate) async fn is_windows(
+    db: &DatabaseConnection,
+    target: &target::Model,
+) -> Result<bool, DbErr> {
+    Ok(
+        match target_capabilities_repository::find_by_target_id(db, target.id).await? {
+            Some(capabilities) => capabilities.is_windows(),
+            None => target.system.as_deref() == Some(WINDOWS),
+        },
+    )
+}
+
+/// Whether `tool` was found on `target_id`; false until its first
+/// detection.
+pub(crate) async fn has_tool(
+    db: &DatabaseConnection,
+    target_id: i32,
+    tool: &str,
+) -> Result<bool, DbErr> {
+    Ok(
+        target_capabilities_repository::find_by_target_id(db, target_id)
+            .await?
+            .is_some_and(|capabilities| capabilities.has_tool(tool)),
+    )
+}
+
+/// Detects the capabilities of a target once its first connection is
+/// authenticated, unless they are known already. Stops with the pool.
+pub(crate) fn spawn_detection(db: DatabaseConnection, connection_pool: &Arc<SshConnectionPool>) {
+    let mut events = connection_pool.subscribe_connection_events();
+    let connection_pool = Arc::downgrade(connection_pool);
+    let detecting = Arc::new(Mutex::new(HashSet::new()));
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "capability detection skipped connection events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            if event.kind != ConnectionEventKind::Authenticated {
+                continue;
+            }
+            let target_id = event.target_id;
+            let Some(connection_pool) = connection_pool.upgrade() else {
+                return;
+            };
+            if !detecting
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .insert(target_id)
+            {
+                continue;
+            }
+            let db = db.clone();
+            let detecting = detecting.clone();
+            tokio::spawn(async move {
+                match target_capabilities_repository::find_by_target_id(&db, target_id).await {
+                    Ok(Some(_)) => {}
+                    Ok(None) => match detect(&db, &connection_pool, target_id).await {
+                        Ok(capabilities) => info!(
+                            target_id,
+                            os = capabilities.os,
+                            "detected target capabilities"
+                        ),
+                        Err(err) => warn!(target_id, %err, "failed to detect target capabilities"),
+                    },
+                    Err(err) => warn!(target_id, %err, "failed to query target capabilities"),
+                }
+                detecting
+                    .lock()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .remove(&target_id);
+            });
+        }
+    });
+}
+
+/// Probes the OS, shell, home directory, SFTP server and tools of
+/// `target_id` and stores them as its capabilities.
+pub(crate) async fn detect(
+    db: &DatabaseConnection,
+    connection_pool: &SshConnectionPool,
+    target_id: i32,
+) -> Result<target_capabilities::Model, ApiErr> {
+    let sftp = map_ssh_err!(
+        connection_pool
+            .sftp(target_id, ChannelMode::Shared, ChannelPriority::Browse)
+            .await
+    )?;
+    let sftp_version = sftp.server_version();
+    let sftp_extensions = sftp.server_extensions().to_vec();
+    let home_dir = sftp.realpath(".").await.ok();
+    sftp.shutdown().await;
+
+    let channel = map_ssh_err!(
+        connection_pool
+            .channel(target_id, ChannelMode::Shared, ChannelPriority::Exec)
+            .await
+    )?;
+    let environment = match exec(channel, &posix_probe_command()).await {
+        Ok(output) => parse_posix_probe(&output),
+        Err(err) => {
+            debug!(target_id, %err, "POSIX probe failed, trying Windows");
+            None
+        }
+    };
+    let environment = match environment {
+        Some(environment) => environment,
+        None => {
+            let channel = map_ssh_err!(
+                connection_pool
+                    .channel(target_id, ChannelMode::Shared, ChannelPriority::Exec)
+                    .await
+            )?;
+            let output = exec(channel, &windows_probe_command()).await?;
+            parse_windows_probe(&output).ok_or_else(|| ApiErr {
+                code: ERR_CODE_SSH_EXEC,
+                message: "could not detect the remote operating system".to_string(),
+            })?
+        }
+    };
+
+    let capabilities = target_capabilities::Model {
+        target_id,
+        os: environment.os,
+        os_version: environment.os_version,
+        shell: environment.shell,
+        home_dir,
+        sftp_version: Some(sftp_version),
+        sftp_extensions: CapabilityNames(sftp_extensions),
+        tools: CapabilityNames(environment.tools),
+        detected_at: now_ms(),
+    };
+    Ok(map_db_err!(
+        target_capabilities_repository::upsert(db, capabilities).await
+    )?)
+}
+
+fn posix_probe_command() -> String {
+    format!(
+        "printf 'os=%s\\nos_version=%s\\nshell=%s\\n' \"$(uname -s)\" \"$(uname -r)\" \"$SHELL\"; \
+         for tool in {}; do command -v \"$tool\" >/dev/null 2>&1 && printf 'tool=%s\\n' \"$tool\"; done; \
+         exit 0",
+        POSIX_TOOLS.join(" ")
+    )
+}
+
+/// Runs through `cmd` so that it works whichever shell OpenSSH for Windows
+/// is configured with. Without a `DefaultShell` value the shell is cmd.exe.
+fn windows_probe_command() -> String {
+    format!(
+        r#"cmd /c "ver & (reg query HKLM\SOFTWARE\OpenSSH /v DefaultShell 2>nul) & (for %t in ({}) do @where %t 2>nul) & exit /b 0""#,
+        WINDOWS_TOOLS.join(" ")
+    )
+}
+
+fn parse_posix_probe(output: &str) -> Option<RemoteEnvironment> {
+    let mut os = None;
+    let mut os_version = None;
+    let mut shell = None;
+    let mut tools = Vec::new();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "os" if !value.is_empty() => os = Some(posix_os_name(value)),
+            "os_version" if !value.is_empty() => os_version = Some(value.to_string()),
+            "shell" if !value.is_empty() => shell = Some(value.to_string()),
+            "tool" if POSIX_TOOLS.contains(&value) => tools.push(value.to_string()),
+            _ => {}
+        }
+    }
+    Some(RemoteEnvironment {
+        os: os?,
+        os_version,
+        shell,
+        tools,
+    })
+}
+
+/// Lowercases `uname -s`, reporting the POSIX layers of Windows (MSYS2,
+/// Git Bash, Cygwin) as Windows since their SFTP paths are Windows paths.
+fn posix_os_name(uname: &str) -> String {
+    let os = uname.to_lowercase();
+    if ["mingw", "msys", "cygwin"]
+        .iter()
+        .any(|prefix| os.starts_with(prefix))
+    {
+        WINDOWS.to_string()
+    } else {
+        os
+    }
+}
+
+fn parse_windows_probe(output: &str) -> Option<RemoteEnvironment> {
+    let mut os_version = None;
+    let mut shell = None;
+    let mut tools = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if line.starts_with("Microsoft Windows") {
+            os_version = Some(line.to_string());
+        } else if let Some((_, value)) = line
+            .strip_prefix("DefaultShell")
+            .and_then(|rest| rest.split_once("REG_SZ"))
+        {
+            shell = Some(value.trim().to_string());
+        } else if let Some(file_name) = line.rsplit('\\').next().filter(|_| line.contains('\\')) {
+            let tool = file_name
+                .to_lowercase()
+                .trim_end_matches(".exe")
+                .to_string();
+            if WINDOWS_TOOLS.contains(&tool.as_str()) && !tools.contains(&tool) {
+                tools.push(tool);
+            }
+        }
+    }
+    Some(RemoteEnvironment {
+        os: WINDOWS.to_string(),
+        os_version: Some(os_version?),
+        shell: shell.or_else(|| Some("cmd.exe".to_string())),
+        tools,
+    })
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posix_probe_output_is_parsed() {
+        let output = "os=Linux\nos_version=6.8.0-45-generic\nshell=/bin/bash\n\
+                      tool=tar\ntool=sha256sum\ntool=unexpected\n";
+
+        assert_eq!(
+            parse_posix_probe(output),
+            Some(RemoteEnvironment {
+                os: "linux".to_string(),
+                os_version: Some("6.8.0-45-generic".to_string()),
+                shell: Some("/bin/bash".to_string()),
+                tools: vec!["tar".to_string(), "sha256sum".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_posix_probe("os=MINGW64_NT-10.0-19045\n").map(|environment| environment.os),
+            Some(WINDOWS.to_string())
+        );
+        assert_eq!(parse_posix_probe("os=\nshell=/bin/sh\n"), None);
+        assert_eq!(parse_posix_probe("'printf' is not recognized"), None);
+    }
+
+    #[test]
+    fn windows_probe_output_is_parsed() {
+        let output = "\r\nMicrosoft Windows [Version 10.0.19045.3570]\r\n\r\n\
+                      HKEY_LOCAL_MACHINE\\SOFTWARE\\OpenSSH\r\n    \
+                      DefaultShell    REG_SZ    C:\\Program Files\\PowerShell\\7\\pwsh.exe\r\n\r\n\
+                      C:\\Windows\\System32\\tar.exe\r\n\
+                      C:\\Windows\\System32\\certutil.exe\r\n";
+
+        assert_eq!(
+            parse_windows_probe(output),
+            Some(RemoteEnvironment {
+                os: WINDOWS.to_string(),
+                os_version: Some("Microsoft Windows [Version 10.0.19045.3570]".to_string()),
+                shell: Some("C:\\Program Files\\PowerShell\\7\\pwsh.exe".to_string()),
+                tools: vec!["tar".to_string(), "certutil".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_windows_probe("Microsoft Windows [Version 10.0.17763.1]")
+                .and_then(|environment| environment.shell),
+            Some("cmd.exe".to_string())
+        );
+        assert_eq!(parse_windows_probe("exec cmd /c ver done"), None);
+    }
+}
@@ -0,0 +1,520 @@
+use russh::keys::{HashAlg, ssh_key};
+use sea_orm::ActiveValue::Set;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use crate::{
+    apis::ssh_connection::dto::ConnectionAlgorithms,
+    entities::target::{self, TargetAlgorithms, TargetAuthMethod, TargetHostKeyPolicy, TargetTags},
+    ssh_connection_pool::{
+        ConnectionDiagnostics, DiagnosticStage, HostKeyVerdict, StageReport, StageStatus,
+    },
+};
+
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct TargetListQuery {
+    /// 只列出该分组（含子分组）中的目标
+    pub group_id: Option<i32>,
+    /// 只列出带有该标签的目标
+    pub tag: Option<String>,
+    /// 按主机、用户名或操作系统搜索
+    pub search: Option<String>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct TargetCapabilitiesQuery {
+    /// 目标 ID
+    pub target_id: i32,
+}
+
+#[derive(Deserialize, Debug, utoipa::ToSchema)]
+pub struct TargetUpdatePayload {
+    /// 目标 ID
+    pub id: i32,
+    /// SSH 目标主机地址
+    pub host: String,
+    /// SSH 端口号
+    pub port: Option<u16>,
+    /// 认证方式
+    pub method: TargetAuthMethod,
+    /// SSH 用户名
+    pub user: String,
+    /// 私钥内容
+    pub key: Option<String>,
+    /// 密钥库中的私钥 ID
+    pub key_id: Option<i32>,
+    /// OpenSSH 用户证书内容
+    pub certificate: Option<String>,
+    /// 密码
+    pub password: Option<String>,
+    /// 操作系统类型
+    pub system: Option<String>,
+    /// 跳板机目标 ID
+    pub jump_target_id: Option<i32>,
+    /// 出站代理，为空时使用全局默认代理，direct 表示不使用代理
+    pub proxy: Option<String>,
+    /// 代理命令（同 OpenSSH ProxyCommand）
+    pub proxy_command: Option<String>,
+    /// SSH 算法偏好
+    pub algorithms: Option<TargetAlgorithms>,
+    /// 建立连接的超时时间（秒）
+    pub connect_timeout: Option<u32>,
+    /// 建立连接失败后的重试次数
+    pub connect_retries: Option<u32>,
+    /// 保持就绪的空闲连接数
+    pub min_idle_connections: Option<u32>,
+    /// 主机密钥检查策略，为空时使用全局设置
+    pub host_key_policy: Option<TargetHostKeyPolicy>,
+    /// 所属分组 ID
+    pub group_id: Option<i32>,
+    /// 标签
+    pub tags: Option<TargetTags>,
+}
+
+impl From<TargetUpdatePayload> for target::ActiveModel {
+    fn from(p: TargetUpdatePayload) -> Self {
+        target::ActiveModel {
+            id: Set(p.id),
+            host: Set(p.host),
+            port: Set(p.port),
+            method: Set(p.method),
+            user: Set(p.user),
+            key: Set(p.key),
+            key_id: Set(p.key_id),
+            certificate: Set(p.certificate),
+            password: Set(p.password),
+            system: Set(p.system),
+            jump_target_id: Set(p.jump_target_id),
+            proxy: Set(p.proxy),
+            proxy_command: Set(p.proxy_command),
+            algorithms: Set(p.algorithms),
+            connect_timeout: Set(p.connect_timeout),
+            connect_retries: Set(p.connect_retries),
+            min_idle_connections: Set(p.min_idle_connections),
+            host_key_policy: Set(p.host_key_policy),
+            group_id: Set(p.group_id),
+            tags: Set(p.tags),
+        }
+    }
+}
+
+/// OpenSSH 用户证书信息
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, utoipa::ToSchema)]
+pub struct TargetCertificateInfo {
+    /// 证书 key ID
+    pub key_id: String,
+    /// 证书允许登录的用户（principals），为空表示不限制
+    pub principals: Vec<String>,
+    /// 生效时间（Unix 时间戳，秒）
+    pub valid_after: u64,
+    /// 过期时间（Unix 时间戳，秒）
+    pub valid_before: u64,
+    /// 签发 CA 公钥的 SHA256 指纹
+    pub ca_fingerprint: String,
+}
+
+impl TargetCertificateInfo {
+    pub(crate) fn parse(certificate: &str) -> Result<Self, ssh_key::Error> {
+        let certificate = ssh_key::Certificate::from_openssh(certificate.trim())?;
+        Ok(Self {
+            key_id: certificate.key_id().to_string(),
+            principals: certificate.valid_principals().to_vec(),
+            valid_after: certificate.valid_after(),
+            valid_before: certificate.valid_before(),
+            ca_fingerprint: certificate
+                .signature_key()
+                .fingerprint(HashAlg::Sha256)
+                .to_string(),
+        })
+    }
+}
+
+/// SSH 目标及其用户证书信息
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct TargetInfo {
+    #[serde(flatten)]
+    pub target: target::Model,
+    /// 用户证书信息（当目标配置了证书时）
+    pub certificate_info: Option<TargetCertificateInfo>,
+}
+
+impl From<target::Model> for TargetInfo {
+    fn from(target: target::Model) -> Self {
+        let certificate_info = target
+            .certificate
+            .as_deref()
+            .and_then(|certificate| TargetCertificateInfo::parse(certificate).ok());
+        Self {
+            target,
+            certificate_info,
+        }
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct TargetRemovePayload {
+    /// 要删除的目标 ID
+    pub id: i32,
+}
+
+/// 导入时上传的文件（Include 引用的配置文件或 IdentityFile 私钥）
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SshConfigFile {
+    /// 文件路径，相对路径视为 `~/.ssh` 下的文件
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SshConfigImportPayload {
+    /// OpenSSH 客户端配置（`~/.ssh/config`）内容
+    pub content: String,
+    /// Include 和 IdentityFile 引用的文件
+    #[serde(default)]
+    pub files: Vec<SshConfigFile>,
+    /// 只预览变更，不写入数据库
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 导入时对一个 Host 执行的操作
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SshConfigImportAction {
+    /// 新建目标
+    Create,
+    /// 更新已存在的目标
+    Update,
+    /// 跳过（目标已存在且没有变化，或配置不完整）
+    Skip,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub struct SshConfigImportEntry {
+    /// ssh_config 中的 Host 名称
+    pub alias: String,
+    /// 实际连接的主机（HostName）
+    pub host: String,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    /// 跳板机，多级 ProxyJump 时为最后一级
+    pub proxy_jump: Option<String>,
+    pub action: SshConfigImportAction,
+    /// 新建或匹配到的目标 ID，预览时新建的目标为空
+    pub target_id: Option<i32>,
+    /// 导入过程中的提示
+    pub messages: Vec<String>,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub struct SshConfigImportResult {
+    pub entries: Vec<SshConfigImportEntry>,
+    /// 无法处理的指令
+    pub warnings: Vec<String>,
+}
+
+/// 批量导出、导入目标的文件格式
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetBundleFormat {
+    /// JSON，包含分组、收藏目录与已知主机密钥，不含密码与私钥
+    Json,
+    /// CSV，每行一个目标，不含密码、私钥、收藏目录与已知主机密钥
+    Csv,
+    /// 用口令加密的 JSON，包含密码与私钥
+    Encrypted,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct TargetExportPayload {
+    pub format: TargetBundleFormat,
+    /// 加密口令，format 为 encrypted 时必填
+    pub passphrase: Option<String>,
+}
+
+/// 导入的目标与已有目标（主机、端口、用户名相同）冲突时的处理方式
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetImportMode {
+    /// 只填写已有目标中为空的配置，合并标签与收藏目录
+    #[default]
+    Merge,
+    /// 用导入的配置覆盖已有目标，导入文件中没有的密码与私钥保持不变
+    Overwrite,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct TargetImportPayload {
+    pub format: TargetBundleFormat,
+    /// 文件内容
+    pub content: String,
+    /// 解密口令，format 为 encrypted 时必填
+    pub passphrase: Option<String>,
+    #[serde(default)]
+    pub mode: TargetImportMode,
+    /// 只预览变更，不写入数据库
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 导入时对一个目标执行的操作
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetImportAction {
+    /// 新建目标
+    Create,
+    /// 合并到或覆盖已有目标
+    Update,
+    /// 跳过（已有目标没有变化，或配置无效）
+    Skip,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub struct TargetImportEntry {
+    pub host: String,
+    pub port: Option<u16>,
+    pub user: String,
+    pub action: TargetImportAction,
+    /// 是否与已有目标冲突（主机、端口、用户名相同）
+    pub conflict: bool,
+    /// 新建或冲突的已有目标 ID，预览时新建的目标为空
+    pub target_id: Option<i32>,
+    /// 导入过程中的提示
+    pub messages: Vec<String>,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub struct TargetImportResult {
+    pub entries: Vec<TargetImportEntry>,
+    /// 新建的分组，名称路径以 / 连接
+    pub created_groups: Vec<String>,
+    /// 导入的已知主机密钥数量
+    pub known_hosts: usize,
+    /// 公钥无效或与指纹不符而跳过的已知主机密钥
+    pub skipped_known_hosts: Vec<String>,
+}
+
+/// Ansible inventory 文件格式
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnsibleInventoryFormat {
+    Ini,
+    Yaml,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct AnsibleInventoryImportPayload {
+    /// inventory 名称（如文件路径），再次导入同名 inventory 时更新之前导入的目标
+    pub source: String,
+    /// inventory 文件内容
+    pub content: String,
+    /// 为空时根据内容判断
+    pub format: Option<AnsibleInventoryFormat>,
+    /// 未设置 ansible_user 的主机使用的用户名
+    pub default_user: Option<String>,
+    /// ansible_ssh_private_key_file 引用的私钥文件
+    #[serde(default)]
+    pub files: Vec<SshConfigFile>,
+    /// 只预览变更，不写入数据库
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub struct AnsibleInventoryImportEntry {
+    /// inventory 中的主机名
+    pub name: String,
+    /// 实际连接的主机（ansible_host）
+    pub host: String,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    /// 目标所在分组的名称路径，以 / 连接
+    pub group: Option<String>,
+    pub action: TargetImportAction,
+    /// 新建或匹配到的目标 ID，预览时新建的目标为空
+    pub target_id: Option<i32>,
+    /// 导入过程中的提示
+    pub messages: Vec<String>,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub struct AnsibleInventoryImportResult {
+    pub entries: Vec<AnsibleInventoryImportEntry>,
+    /// 新建的分组，名称路径以 / 连接
+    pub created_groups: Vec<String>,
+    /// 无法处理的内容
+    pub warnings: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, utoipa::ToSchema)]
+pub struct TargetTestPayload {
+    /// 已保存目标的 ID，未提供 target 时测试该目标的已保存配置
+    pub target_id: Option<i32>,
+    /// 要测试的目标配置，可以尚未保存
+    pub target: Option<target::Model>,
+}
+
+/// 连接测试的阶段，按执行顺序排列
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetTestStage {
+    /// 解析主机名
+    Dns,
+    /// 建立 TCP 连接（或经代理、跳板机打开通道）
+    Connect,
+    /// 读取服务端版本标识
+    Banner,
+    /// 密钥交换
+    KeyExchange,
+    /// 校验主机密钥
+    HostKey,
+    /// 查询服务端支持的认证方式
+    AuthMethods,
+    /// 认证
+    Authentication,
+    /// 启动 SFTP 子系统
+    Sftp,
+}
+
+impl From<DiagnosticStage> for TargetTestStage {
+    fn from(stage: DiagnosticStage) -> Self {
+        match stage {
+            DiagnosticStage::Dns => Self::Dns,
+            DiagnosticStage::Connect => Self::Connect,
+            DiagnosticStage::Banner => Self::Banner,
+            DiagnosticStage::KeyExchange => Self::KeyExchange,
+            DiagnosticStage::HostKey => Self::HostKey,
+            DiagnosticStage::AuthMethods => Self::AuthMethods,
+            DiagnosticStage::Authentication => Self::Authentication,
+            DiagnosticStage::Sftp => Self::Sftp,
+        }
+    }
+}
+
+/// 连接测试阶段的结果
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetTestStageStatus {
+    /// 通过
+    Passed,
+    /// 失败
+    Failed,
+    /// 未执行（不适用于当前连接方式，或前面的阶段已失败）
+    Skipped,
+}
+
+impl From<StageStatus> for TargetTestStageStatus {
+    fn from(status: StageStatus) -> Self {
+        match status {
+            StageStatus::Passed => Self::Passed,
+            StageStatus::Failed => Self::Failed,
+            StageStatus::Skipped => Self::Skipped,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, utoipa::ToSchema)]
+pub struct TargetTestStageResult {
+    pub stage: TargetTestStage,
+    pub status: TargetTestStageStatus,
+    /// 耗时（毫秒），未执行时为空
+    pub elapsed_ms: Option<u64>,
+    /// 阶段详情或失败原因
+    pub detail: Option<String>,
+}
+
+impl From<StageReport> for TargetTestStageResult {
+    fn from(report: StageReport) -> Self {
+        Self {
+            stage: report.stage.into(),
+            status: report.status.into(),
+            elapsed_ms: report
+                .elapsed
+                .map(|elapsed| elapsed.as_millis().try_into().unwrap_or(u64::MAX)),
+            detail: report.detail,
+        }
+    }
+}
+
+/// 主机密钥与已保存主机密钥的比对结果
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetHostKeyVerdict {
+    /// 与已保存的主机密钥一致
+    Trusted,
+    /// 尚未保存，按策略会在首次连接时接受并保存
+    New,
+    /// 尚未保存，按策略需要先保存或确认
+    Unknown,
+    /// 与已保存的主机密钥不一致
+    Changed,
+    /// 主机密钥已被吊销
+    Revoked,
+    /// 未检查主机密钥
+    Unchecked,
+}
+
+impl From<HostKeyVerdict> for TargetHostKeyVerdict {
+    fn from(verdict: HostKeyVerdict) -> Self {
+        match verdict {
+            HostKeyVerdict::Trusted => Self::Trusted,
+            HostKeyVerdict::New => Self::New,
+            HostKeyVerdict::Unknown => Self::Unknown,
+            HostKeyVerdict::Changed => Self::Changed,
+            HostKeyVerdict::Revoked => Self::Revoked,
+            HostKeyVerdict::Unchecked => Self::Unchecked,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, utoipa::ToSchema)]
+pub struct TargetTestHostKey {
+    /// 主机密钥算法
+    pub algorithm: String,
+    /// 主机密钥的 SHA256 指纹
+    pub fingerprint: String,
+    pub verdict: TargetHostKeyVerdict,
+}
+
+/// 连接测试报告
+#[derive(Serialize, Debug, Clone, utoipa::ToSchema)]
+pub struct TargetTestReport {
+    /// 所有阶段均未失败
+    pub success: bool,
+    pub stages: Vec<TargetTestStageResult>,
+    /// 主机名解析出的地址，经代理或跳板机连接时为空
+    pub addresses: Vec<String>,
+    /// 服务端版本标识（如 SSH-2.0-OpenSSH_9.6）
+    pub server_version: Option<String>,
+    /// 密钥交换协商出的算法
+    pub algorithms: Option<ConnectionAlgorithms>,
+    pub host_key: Option<TargetTestHostKey>,
+    /// 服务端提供的认证方式（如 publickey、password）
+    pub auth_methods: Vec<String>,
+}
+
+impl From<ConnectionDiagnostics> for TargetTestReport {
+    fn from(diagnostics: ConnectionDiagnostics) -> Self {
+        Self {
+            success: diagnostics.passed(),
+            stages: diagnostics
+                .stages
+                .into_iter()
+                .map(TargetTestStageResult::from)
+                .collect(),
+            addresses: diagnostics
+                .addresses
+                .iter()
+                .map(|address| address.ip().to_string())
+                .collect(),
+            server_version: diagnostics.server_version,
+            algorithms: diagnostics.algorithms.map(ConnectionAlgorithms::from),
+            host_key: diagnostics.host_key.map(|host_key| TargetTestHostKey {
+                algorithm: host_key.algorithm,
+                fingerprint: host_key.fingerprint,
+                verdict: host_key.verdict.into(),
+            }),
+            auth_methods: diagnostics.auth_methods,
+        }
+    }
+}
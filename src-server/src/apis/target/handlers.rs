@@ -0,0 +1,275 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+
+use crate::{
+    AppState,
+    apis::{
+        ApiErr, InternalErrorResponse, ValidJson,
+        target::{
+            dto::{
+                AnsibleInventoryImportPayload, AnsibleInventoryImportResult,
+                SshConfigImportPayload, SshConfigImportResult, TargetCapabilitiesQuery,
+                TargetExportPayload, TargetImportPayload, TargetImportResult, TargetInfo,
+                TargetListQuery, TargetRemovePayload, TargetTestPayload, TargetTestReport,
+                TargetUpdatePayload,
+            },
+            service,
+        },
+    },
+    entities::{target, target_capabilities},
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/target/list",
+    tag = "target",
+    summary = "获取 SSH 目标列表",
+    operation_id = "target_list",
+    params(TargetListQuery),
+    responses(
+        (status = 200, description = "成功获取 SSH 目标列表", body = [TargetInfo]),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_list(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TargetListQuery>,
+) -> Result<Json<Vec<TargetInfo>>, ApiErr> {
+    let targets = service::list(&state.db, &state.secrets, query).await?;
+    Ok(Json(targets))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target/add",
+    tag = "target",
+    summary = "添加新的 SSH 目标",
+    operation_id = "target_add",
+    request_body = target::Model,
+    responses(
+        (status = 200, description = "成功添加 SSH 目标", body = TargetInfo),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_add(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<target::Model>,
+) -> Result<Json<TargetInfo>, ApiErr> {
+    let target = service::add(&state.db, &state.secrets, &state.connection_pool, payload).await?;
+    Ok(Json(target))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target/update",
+    tag = "target",
+    summary = "更新 SSH 目标",
+    operation_id = "target_update",
+    request_body = TargetUpdatePayload,
+    responses(
+        (status = 200, description = "成功更新 SSH 目标", body = TargetInfo),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_update(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<TargetUpdatePayload>,
+) -> Result<Json<TargetInfo>, ApiErr> {
+    let target =
+        service::update(&state.db, &state.secrets, &state.connection_pool, payload).await?;
+    Ok(Json(target))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target/remove",
+    tag = "target",
+    summary = "删除 SSH 目标",
+    operation_id = "target_remove",
+    request_body = TargetRemovePayload,
+    responses(
+        (status = 200, description = "成功删除 SSH 目标"),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_remove(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<TargetRemovePayload>,
+) -> Result<(), ApiErr> {
+    service::remove(&state.db, &state.connection_pool, payload.id).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target/ssh_config/import",
+    tag = "target",
+    summary = "从 OpenSSH 客户端配置导入 SSH 目标",
+    operation_id = "target_ssh_config_import",
+    request_body = SshConfigImportPayload,
+    responses(
+        (status = 200, description = "导入结果，dry_run 时为预览", body = SshConfigImportResult),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_ssh_config_import(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<SshConfigImportPayload>,
+) -> Result<Json<SshConfigImportResult>, ApiErr> {
+    let result =
+        service::import_ssh_config(&state.db, &state.secrets, &state.connection_pool, payload)
+            .await?;
+    Ok(Json(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target/ansible/import",
+    tag = "target",
+    summary = "从 Ansible inventory 导入 SSH 目标",
+    operation_id = "target_ansible_import",
+    request_body = AnsibleInventoryImportPayload,
+    responses(
+        (status = 200, description = "导入结果，dry_run 时为预览", body = AnsibleInventoryImportResult),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_ansible_import(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<AnsibleInventoryImportPayload>,
+) -> Result<Json<AnsibleInventoryImportResult>, ApiErr> {
+    let result = service::import_ansible_inventory(
+        &state.db,
+        &state.secrets,
+        &state.connection_pool,
+        payload,
+    )
+    .await?;
+    Ok(Json(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/target/ssh_config/export",
+    tag = "target",
+    summary = "导出为 OpenSSH 客户端配置",
+    operation_id = "target_ssh_config_export",
+    responses(
+        (status = 200, description = "ssh_config 文件内容", body = String, content_type = "text/plain"),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_ssh_config_export(
+    State(state): State<Arc<AppState>>,
+) -> Result<String, ApiErr> {
+    service::export_ssh_config(&state.db, &state.secrets).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target/export",
+    tag = "target",
+    summary = "批量导出目标",
+    operation_id = "target_export",
+    request_body = TargetExportPayload,
+    responses(
+        (status = 200, description = "JSON、CSV 或加密文件的内容", body = String, content_type = "text/plain"),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_export(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<TargetExportPayload>,
+) -> Result<String, ApiErr> {
+    service::export_targets(&state.db, &state.secrets, payload).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target/import",
+    tag = "target",
+    summary = "批量导入目标",
+    operation_id = "target_import",
+    request_body = TargetImportPayload,
+    responses(
+        (status = 200, description = "导入结果，dry_run 时为预览", body = TargetImportResult),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_import(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<TargetImportPayload>,
+) -> Result<Json<TargetImportResult>, ApiErr> {
+    let result =
+        service::import_targets(&state.db, &state.secrets, &state.connection_pool, payload).await?;
+    Ok(Json(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target/test",
+    tag = "target",
+    summary = "分阶段测试 SSH 目标的连接",
+    operation_id = "target_test",
+    request_body = TargetTestPayload,
+    responses(
+        (status = 200, description = "连接测试报告，某一阶段失败时后续阶段为 skipped", body = TargetTestReport),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_test(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<TargetTestPayload>,
+) -> Result<Json<TargetTestReport>, ApiErr> {
+    let report = service::test(&state.db, &state.secrets, &state.connection_pool, payload).await?;
+    Ok(Json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/target/capabilities",
+    tag = "target",
+    summary = "获取目标的远程环境信息",
+    operation_id = "target_capabilities",
+    params(TargetCapabilitiesQuery),
+    responses(
+        (status = 200, description = "首次连接后检测到的操作系统、Shell、SFTP 版本与可用工具，尚未检测时为 null", body = Option<target_capabilities::Model>),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_capabilities(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TargetCapabilitiesQuery>,
+) -> Result<Json<Option<target_capabilities::Model>>, ApiErr> {
+    let capabilities = service::capabilities(&state.db, query.target_id).await?;
+    Ok(Json(capabilities))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target/capabilities/detect",
+    tag = "target",
+    summary = "重新检测目标的远程环境",
+    operation_id = "target_capabilities_detect",
+    params(TargetCapabilitiesQuery),
+    responses(
+        (status = 200, description = "检测结果", body = target_capabilities::Model),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_capabilities_detect(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TargetCapabilitiesQuery>,
+) -> Result<Json<target_capabilities::Model>, ApiErr> {
+    let capabilities = service::detect_capabilities(
+        &state.db,
+        &state.secrets,
+        &state.connection_pool,
+        query.target_id,
+    )
+    .await?;
+    Ok(Json(capabilities))
+}
@@ -0,0 +1,48 @@
+mod ansible;
+mod bundle;
+mod capabilities;
+pub mod dto;
+pub mod handlers;
+mod service;
+mod ssh_config;
+
+#[cfg(test)]
+pub(crate) use capabilities::detect as detect_capabilities_for_test;
+#[cfg(test)]
+pub(crate) use service::{remove as remove_for_test, update as update_for_test};
+
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::AppState;
+
+pub(crate) use capabilities::{
+    has_tool, is_windows, spawn_detection as spawn_capability_detection,
+};
+pub use dto::{TargetCertificateInfo, TargetInfo, TargetRemovePayload, TargetUpdatePayload};
+pub use handlers::{
+    target_add, target_ansible_import, target_capabilities, target_capabilities_detect,
+    target_export, target_import, target_list, target_remove, target_ssh_config_export,
+    target_ssh_config_import, target_test, target_update,
+};
+pub(crate) fn router_builder(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/list", get(target_list))
+        .route("/add", post(target_add))
+        .route("/update", post(target_update))
+        .route("/remove", post(target_remove))
+        .route("/test", post(target_test))
+        .route("/capabilities", get(target_capabilities))
+        .route("/capabilities/detect", post(target_capabilities_detect))
+        .route("/export", post(target_export))
+        .route("/import", post(target_import))
+        .route("/ssh_config/import", post(target_ssh_config_import))
+        .route("/ssh_config/export", get(target_ssh_config_export))
+        .route("/ansible/import", post(target_ansible_import))
+        .fallback(|| async { "not supported" })
+        .with_state(app_state)
+}
@@ -0,0 +1,2743 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use russh::keys::{HashAlg, PrivateKey, ssh_key};
+use sea_orm::{
+    ActiveModelTrait,
+    ActiveValue::{NotSet, Set},
+    ConnectionTrait, DatabaseConnection, DbErr, TransactionTrait,
+};
+
+use crate::{
+    apis::{
+        ApiErr,
+        target::{
+            ansible,
+            bundle::{self, BundleFavorite, BundleKnownHost, BundleTarget, TargetBundle},
+            capabilities::detect,
+            dto::{
+                AnsibleInventoryImportEntry, AnsibleInventoryImportPayload,
+                AnsibleInventoryImportResult, SshConfigFile, SshConfigImportAction,
+                SshConfigImportEntry, SshConfigImportPayload, SshConfigImportResult,
+                TargetBundleFormat, TargetCertificateInfo, TargetExportPayload, TargetImportAction,
+                TargetImportEntry, TargetImportMode, TargetImportPayload, TargetImportResult,
+                TargetInfo, TargetListQuery, TargetTestPayload, TargetTestReport,
+                TargetUpdatePayload,
+            },
+            ssh_config,
+        },
+    },
+    consts::services_err_code::*,
+    entities::{
+        favorite_directory, ssh_known_host,
+        target::{self, TargetAlgorithms, TargetAuthMethod, TargetTags},
+        target_capabilities, target_group, target_source,
+    },
+    map_db_err, map_ssh_err,
+    repositories::{
+        favorite_directory as favorite_directory_repository, key_store as key_store_repository,
+        ssh_known_host as ssh_known_host_repository, target as target_repository,
+        target_capabilities as target_capabilities_repository,
+        target_group as target_group_repository, target_source as target_source_repository,
+    },
+    secrets::Secrets,
+    ssh_connection_pool::{
+        ServerPublicKey, SshConnectionPool, SshPoolError, TargetProxy, expand_proxy_command,
+        validate_algorithms,
+    },
+};
+
+const MAX_CONNECT_TIMEOUT_SECS: u32 = 600;
+const MAX_CONNECT_RETRIES: u32 = 10;
+
+pub async fn list(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    query: TargetListQuery,
+) -> Result<Vec<TargetInfo>, ApiErr> {
+    let group_ids = match query.group_id {
+        Some(group_id) => Some(map_db_err!(
+            target_group_repository::with_descendants(db, group_id).await
+        )?),
+        None => None,
+    };
+    let non_empty = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let (tag, search) = (non_empty(&query.tag), non_empty(&query.search));
+    let targets = map_db_err!(
+        target_repository::list_filtered(
+            db,
+            secrets,
+            group_ids.as_deref(),
+            tag.as_deref(),
+            search.as_deref()
+        )
+        .await
+    )?;
+    Ok(targets.into_iter().map(TargetInfo::from).collect())
+}
+
+pub async fn add(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    connection_pool: &SshConnectionPool,
+    mut payload: target::Model,
+) -> Result<TargetInfo, ApiErr> {
+    validate_group_id(db, payload.group_id).await?;
+    payload.tags = normalize_tags(payload.tags);
+    validate_key_id(db, secrets, payload.key_id).await?;
+    validate_certificate(payload.certificate.as_deref())?;
+    validate_proxy(payload.proxy.as_deref())?;
+    validate_proxy_command(
+        connection_pool.allows_proxy_command(),
+        payload.proxy_command.as_deref(),
+        payload.jump_target_id,
+        &payload.host,
+        &payload.user,
+    )?;
+    validate_target_algorithms(payload.algorithms.as_ref())?;
+    validate_connect_settings(
+        payload.connect_timeout,
+        payload.connect_retries,
+        payload.min_idle_connections,
+        connection_pool.max_connections_per_target(),
+    )?;
+    let target = map_db_err!(target_repository::insert(db, secrets, payload).await)?;
+    Ok(TargetInfo::from(target))
+}
+
+pub async fn update(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    connection_pool: &SshConnectionPool,
+    mut payload: TargetUpdatePayload,
+) -> Result<TargetInfo, ApiErr> {
+    validate_group_id(db, payload.group_id).await?;
+    payload.tags = normalize_tags(payload.tags);
+    validate_key_id(db, secrets, payload.key_id).await?;
+    validate_certificate(payload.certificate.as_deref())?;
+    validate_proxy(payload.proxy.as_deref())?;
+    validate_proxy_command(
+        connection_pool.allows_proxy_command(),
+        payload.proxy_command.as_deref(),
+        payload.jump_target_id,
+        &payload.host,
+        &payload.user,
+    )?;
+    validate_target_algorithms(payload.algorithms.as_ref())?;
+    validate_connect_settings(
+        payload.connect_timeout,
+        payload.connect_retries,
+        payload.min_idle_connections,
+        connection_pool.max_connections_per_target(),
+    )?;
+    let target_id = payload.id;
+    let active_model = target::ActiveModel::from(payload);
+    let target = map_db_err!(
+        connection_pool
+            .with_target_mutation(target_id, move || async move {
+                let previous = target_repository::find_by_id(db, secrets, target_id).await?;
+                let target = target_repository::update(db, secrets, active_model).await?;
+                // Detected capabilities describe the old machine once the
+                // target points somewhere else.
+                if previous.is_some_and(|previous| !same_endpoint(&previous, &target)) {
+                    target_capabilities_repository::delete(db, target_id).await?;
+                }
+                Ok::<_, DbErr>(target)
+            })
+            .await
+    )?;
+    Ok(TargetInfo::from(target))
+}
+
+fn same_endpoint(previous: &target::Model, target: &target::Model) -> bool {
+    previous.host == target.host
+        && previous.port == target.port
+        && previous.user == target.user
+        && previous.jump_target_id == target.jump_target_id
+        && previous.proxy == target.proxy
+        && previous.proxy_command == target.proxy_command
+}
+
+pub async fn capabilities(
+    db: &DatabaseConnection,
+    target_id: i32,
+) -> Result<Option<target_capabilities::Model>, ApiErr> {
+    Ok(map_db_err!(
+        target_capabilities_repository::find_by_target_id(db, target_id).await
+    )?)
+}
+
+pub async fn detect_capabilities(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    connection_pool: &SshConnectionPool,
+    target_id: i32,
+) -> Result<target_capabilities::Model, ApiErr> {
+    map_db_err!(target_repository::find_by_id(db, secrets, target_id).await)?.ok_or_else(|| {
+        ApiErr {
+            code: ERR_CODE_TARGET_INVALID_REQUEST,
+            message: format!("target {target_id} not found"),
+        }
+    })?;
+    detect(db, connection_pool, target_id).await
+}
+
+async fn validate_key_id(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    key_id: Option<i32>,
+) -> Result<(), ApiErr> {
+    let Some(key_id) = key_id else {
+        return Ok(());
+    };
+    match map_db_err!(key_store_repository::find_by_id(db, secrets, key_id).await)? {
+        Some(_) => Ok(()),
+        None => Err(ApiErr {
+            code: ERR_CODE_TARGET_INVALID_REQUEST,
+            message: format!("stored key {key_id} not found"),
+        }),
+    }
+}
+
+async fn validate_group_id(db: &DatabaseConnection, group_id: Option<i32>) -> Result<(), ApiErr> {
+    let Some(group_id) = group_id else {
+        return Ok(());
+    };
+    match map_db_err!(target_group_repository::find_by_id(db, group_id).await)? {
+        Some(_) => Ok(()),
+        None => Err(ApiErr {
+            code: ERR_CODE_TARGET_INVALID_REQUEST,
+            message: format!("group {group_id} not found"),
+        }),
+    }
+}
+
+/// Trims tags and drops empty and repeated ones; no tags are stored as NULL.
+fn normalize_tags(tags: Option<TargetTags>) -> Option<TargetTags> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.into_iter().flat_map(|tags| tags.0) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|existing| existing == tag) {
+            normalized.push(tag.to_string());
+        }
+    }
+    (!normalized.is_empty()).then_some(TargetTags(normalized))
+}
+
+fn validate_certificate(certificate: Option<&str>) -> Result<(), ApiErr> {
+    match certificate {
+        Some(certificate) => TargetCertificateInfo::parse(certificate)
+            .map(|_| ())
+            .map_err(|err| ApiErr {
+                code: ERR_CODE_TARGET_INVALID_REQUEST,
+                message: format!("invalid OpenSSH user certificate: {err}"),
+            }),
+        None => Ok(()),
+    }
+}
+
+fn validate_proxy(proxy: Option<&str>) -> Result<(), ApiErr> {
+    TargetProxy::parse(proxy).map(|_| ()).map_err(|err| ApiErr {
+        code: ERR_CODE_TARGET_INVALID_REQUEST,
+        message: err.to_string(),
+    })
+}
+
+fn validate_proxy_command(
+    allowed: bool,
+    proxy_command: Option<&str>,
+    jump_target_id: Option<i32>,
+    host: &str,
+    user: &str,
+) -> Result<(), ApiErr> {
+    let Some(command) = proxy_command.filter(|command| !command.trim().is_empty()) else {
+        return Ok(());
+    };
+    let invalid = |message: String| ApiErr {
+        code: ERR_CODE_TARGET_INVALID_REQUEST,
+        message,
+    };
+    if !allowed {
+        return Err(invalid(SshPoolError::ProxyCommandDisabled.to_string()));
+    }
+    if jump_target_id.is_some() {
+        return Err(invalid(
+            "ProxyCommand cannot be combined with a jump target".to_string(),
+        ));
+    }
+    expand_proxy_command(command, host, 22, user)
+        .map(|_| ())
+        .map_err(|err| invalid(err.to_string()))
+}
+
+fn validate_target_algorithms(algorithms: Option<&TargetAlgorithms>) -> Result<(), ApiErr> {
+    match algorithms {
+        Some(algorithms) => validate_algorithms(algorithms).map_err(|err| ApiErr {
+            code: ERR_CODE_TARGET_INVALID_REQUEST,
+            message: err.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn validate_connect_settings(
+    connect_timeout: Option<u32>,
+    connect_retries: Option<u32>,
+    min_idle_connections: Option<u32>,
+    max_connections_per_target: usize,
+) -> Result<(), ApiErr> {
+    if connect_timeout.is_some_and(|timeout| !(1..=MAX_CONNECT_TIMEOUT_SECS).contains(&timeout)) {
+        return Err(ApiErr {
+            code: ERR_CODE_TARGET_INVALID_REQUEST,
+            message: format!(
+                "connect timeout must be between 1 and {MAX_CONNECT_TIMEOUT_SECS} seconds"
+            ),
+        });
+    }
+    if connect_retries.is_some_and(|retries| retries > MAX_CONNECT_RETRIES) {
+        return Err(ApiErr {
+            code: ERR_CODE_TARGET_INVALID_REQUEST,
+            message: format!("connect retries must not exceed {MAX_CONNECT_RETRIES}"),
+        });
+    }
+    if min_idle_connections.is_some_and(|min_idle| min_idle as usize > max_connections_per_target) {
+        return Err(ApiErr {
+            code: ERR_CODE_TARGET_INVALID_REQUEST,
+            message: format!(
+                "minimum idle connections must not exceed the {max_connections_per_target} connections allowed per target"
+            ),
+        });
+    }
+    Ok(())
+}
+
+pub async fn remove(
+    db: &DatabaseConnection,
+    connection_pool: &SshConnectionPool,
+    id: i32,
+) -> Result<(), ApiErr> {
+    map_db_err!(
+        connection_pool
+            .with_target_mutation(id, || {
+                target_repository::delete_with_favorite_directories(db, id)
+            })
+            .await
+    )?;
+    Ok(())
+}
+
+/// Tests the saved configuration of `target_id`, or `target` when given,
+/// which may not be saved yet. With both, `target` is tested as the new
+/// configuration of `target_id`.
+pub async fn test(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    connection_pool: &SshConnectionPool,
+    payload: TargetTestPayload,
+) -> Result<TargetTestReport, ApiErr> {
+    let target = match (payload.target, payload.target_id) {
+        (Some(mut target), target_id) => {
+            validate_key_id(db, secrets, target.key_id).await?;
+            validate_certificate(target.certificate.as_deref())?;
+            validate_proxy(target.proxy.as_deref())?;
+            validate_proxy_command(
+                connection_pool.allows_proxy_command(),
+                target.proxy_command.as_deref(),
+                target.jump_target_id,
+                &target.host,
+                &target.user,
+            )?;
+            validate_target_algorithms(target.algorithms.as_ref())?;
+            validate_connect_settings(
+                target.connect_timeout,
+                target.connect_retries,
+                target.min_idle_connections,
+                connection_pool.max_connections_per_target(),
+            )?;
+            target.id = target_id.unwrap_or_default();
+            target
+        }
+        (None, Some(target_id)) => map_db_err!(
+            target_repository::find_by_id(db, secrets, target_id).await
+        )?
+        .ok_or_else(|| ApiErr {
+            code: ERR_CODE_TARGET_INVALID_REQUEST,
+            message: format!("target {target_id} not found"),
+        })?,
+        (None, None) => {
+            return Err(ApiErr {
+                code: ERR_CODE_TARGET_INVALID_REQUEST,
+                message: "target_id or target is required".to_string(),
+            });
+        }
+    };
+    let diagnostics = map_ssh_err!(connection_pool.diagnose(&target).await)?;
+    Ok(TargetTestReport::from(diagnostics))
+}
+
+/// ProxyJump target of an imported host.
+enum ImportJump {
+    /// Another host of the same config, by index.
+    Host(usize),
+    Target(i32),
+}
+
+struct ImportPlan {
+    entry: SshConfigImportEntry,
+    key: Option<String>,
+    existing: Option<target::Model>,
+    jump: Option<ImportJump>,
+}
+
+/// Host, port and user of the entries an import has planned, so that an
+/// entry for the same target as an earlier one is skipped.
+#[derive(Default)]
+struct PlannedAddresses(HashSet<(String, u16, String)>);
+
+impl PlannedAddresses {
+    /// Records the address, false when an earlier entry has it.
+    fn insert(&mut self, host: &str, port: Option<u16>, user: &str) -> bool {
+        self.0
+            .insert((host.to_lowercase(), port.unwrap_or(22), user.to_string()))
+    }
+}
+
+/// First of `targets` at `host` and `port`, logging in as `user` if given.
+fn find_by_address<'a>(
+    targets: impl IntoIterator<Item = &'a target::Model>,
+    host: &str,
+    port: Option<u16>,
+    user: Option<&str>,
+) -> Option<&'a target::Model> {
+    targets.into_iter().find(|target| {
+        target.host.eq_ignore_ascii_case(host)
+            && target.port.unwrap_or(22) == port.unwrap_or(22)
+            && user.is_none_or(|user| target.user == user)
+    })
+}
+
+/// Content of the `kind` an imported host names at `path`, noting in
+/// `messages` when the file was not uploaded with the import.
+fn imported_key(
+    files: &[SshConfigFile],
+    kind: &str,
+    path: Option<&str>,
+    messages: &mut Vec<String>,
+) -> Option<String> {
+    let path = path?;
+    let file = ssh_config::find_file(files, path);
+    if file.is_none() {
+        messages.push(format!(
+            "{kind} {path} was not provided, the target uses ssh-agent"
+        ));
+    }
+    file.map(|file| file.content.clone())
+}
+
+/// New target for an imported host, authenticating with `key` or, without
+/// one, with ssh-agent.
+fn imported_target(
+    host: String,
+    port: Option<u16>,
+    user: String,
+    key: Option<String>,
+) -> target::Model {
+    target::Model {
+        id: 0,
+        host,
+        port,
+        method: match key {
+            Some(_) => TargetAuthMethod::PrivateKey,
+            None => TargetAuthMethod::Agent,
+        },
+        user,
+        key,
+        key_id: None,
+        certificate: None,
+        password: None,
+        system: None,
+        jump_target_id: None,
+        proxy: None,
+        proxy_command: None,
+        algorithms: None,
+        connect_timeout: None,
+        connect_retries: None,
+        min_idle_connections: None,
+        host_key_policy: None,
+        group_id: None,
+        tags: None,
+    }
+}
+
+/// Target ID of the jump host `name` an entry takes from the same import,
+/// whose entries are applied in [`import_order`]: `Some(None)` when the
+/// entry goes without it and `None` when the jump host is only created by
+/// this import (dry run). `looped` is set when the jump host is not applied
+/// before the entry because it leads back to it.
+fn planned_jump_target_id(
+    name: &str,
+    looped: bool,
+    skipped: bool,
+    target_id: Option<i32>,
+    messages: &mut Vec<String>,
+) -> Option<Option<i32>> {
+    if looped {
+        messages.push(format!("jump host {name} forms a loop and is ignored"));
+        Some(None)
+    } else if skipped && target_id.is_none() {
+        messages.push(format!("jump host {name} is skipped"));
+        Some(None)
+    } else {
+        target_id.map(Some)
+    }
+}
+
+/// Creates targets for the hosts of an OpenSSH client config and updates the
+/// jump host and identity of targets that already exist with the same host,
+/// port and user. Jump hosts are imported before the hosts that use them.
+pub async fn import_ssh_config(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    connection_pool: &SshConnectionPool,
+    payload: SshConfigImportPayload,
+) -> Result<SshConfigImportResult, ApiErr> {
+    let (hosts, warnings) = ssh_config::parse(&payload.content, &payload.files);
+    let transaction = map_db_err!(db.begin().await)?;
+    let db = &transaction;
+    let targets = map_db_err!(target_repository::list(db, secrets).await)?;
+
+    let mut seen = PlannedAddresses::default();
+    let mut plans = Vec::with_capacity(hosts.len());
+    for host in &hosts {
+        let mut entry = SshConfigImportEntry {
+            alias: host.alias.clone(),
+            host: host.host(),
+            port: host.port,
+            user: host.user.clone(),
+            proxy_jump: None,
+            action: SshConfigImportAction::Create,
+            target_id: None,
+            messages: Vec::new(),
+        };
+        let key = imported_key(
+            &payload.files,
+            "identity file",
+            host.identity_file.as_deref(),
+            &mut entry.messages,
+        );
+        let existing =
+            find_by_address(&targets, &entry.host, entry.port, entry.user.as_deref()).cloned();
+        if entry.user.is_none() {
+            entry.action = SshConfigImportAction::Skip;
+            entry.messages.push("User is not set".to_string());
+        } else if !seen.insert(
+            &entry.host,
+            entry.port,
+            entry.user.as_deref().unwrap_or_default(),
+        ) {
+            entry.action = SshConfigImportAction::Skip;
+            entry
+                .messages
+                .push("duplicate of an earlier Host with the same host, port and user".to_string());
+        }
+
+        let jump = match host.proxy_jump.as_deref() {
+            None | Some("none") => None,
+            Some(proxy_jump) => {
+                let hops: Vec<&str> = proxy_jump.split(',').collect();
+                let hop = hops[hops.len() - 1];
+                if hops.len() > 1 {
+                    entry.messages.push(format!(
+                        "only the last ProxyJump hop {hop} is used, it reaches the earlier hops through its own jump host"
+                    ));
+                }
+                entry.proxy_jump = Some(hop.to_string());
+                match hosts.iter().position(|host| host.alias == hop) {
+                    Some(index) => Some(ImportJump::Host(index)),
+                    None => {
+                        let (user, jump_host, port) = ssh_config::parse_jump(hop);
+                        let jump_target = find_by_address(&targets, jump_host, port, user);
+                        if jump_target.is_none() {
+                            entry
+                                .messages
+                                .push(format!("jump host {hop} is not a stored target"));
+                        }
+                        jump_target.map(|target| ImportJump::Target(target.id))
+                    }
+                }
+            }
+        };
+        plans.push(ImportPlan {
+            entry,
+            key,
+            existing,
+            jump,
+        });
+    }
+
+    let jumps: Vec<Option<usize>> = plans
+        .iter()
+        .map(|plan| match plan.jump {
+            Some(ImportJump::Host(jump)) => Some(jump),
+            _ => None,
+        })
+        .collect();
+    let mut applied = vec![false; plans.len()];
+    for index in import_order(&jumps) {
+        applied[index] = true;
+        let jump_target_id = match plans[index].jump {
+            None => Some(None),
+            Some(ImportJump::Target(id)) => Some(Some(id)),
+            Some(ImportJump::Host(jump)) => {
+                let looped = jump == index || !applied[jump];
+                let (alias, skipped, target_id) = {
+                    let jump = &plans[jump].entry;
+                    (
+                        jump.alias.clone(),
+                        jump.action == SshConfigImportAction::Skip,
+                        jump.target_id,
+                    )
+                };
+                planned_jump_target_id(
+                    &alias,
+                    looped,
+                    skipped,
+                    target_id,
+                    &mut plans[index].entry.messages,
+                )
+            }
+        };
+        let plan = &mut plans[index];
+        if plan.entry.action == SshConfigImportAction::Skip {
+            continue;
+        }
+
+        let Some(existing) = plan.existing.clone() else {
+            if !payload.dry_run {
+                let target = target::Model {
+                    jump_target_id: jump_target_id.flatten(),
+                    ..imported_target(
+                        plan.entry.host.clone(),
+                        plan.entry.port,
+                        plan.entry.user.clone().unwrap_or_default(),
+                        plan.key.clone(),
+                    )
+                };
+                let target = map_db_err!(target_repository::insert(db, secrets, target).await)?;
+                plan.entry.target_id = Some(target.id);
+            }
+            continue;
+        };
+
+        plan.entry.target_id = Some(existing.id);
+        let mut active_model = target::ActiveModel::from(existing.clone());
+        if jump_target_id != Some(existing.jump_target_id) {
+            if existing
+                .proxy_command
+                .as_deref()
+                .is_some_and(|command| !command.trim().is_empty())
+            {
+                plan.entry.messages.push(
+                    "the target uses a ProxyCommand, its jump host is not changed".to_string(),
+                );
+            } else {
+                active_model.jump_target_id = Set(jump_target_id.flatten());
+            }
+        }
+        if let Some(key) = &plan.key {
+            let same_key = existing.key.as_ref() == Some(key)
+                || is_stored_key(db, secrets, existing.key_id, key).await?;
+            if !same_key {
+                active_model.key = Set(Some(key.clone()));
+                active_model.key_id = Set(None);
+            }
+            if !same_key || existing.method != TargetAuthMethod::PrivateKey {
+                active_model.method = Set(TargetAuthMethod::PrivateKey);
+            }
+        }
+        if !active_model.is_changed() {
+            plan.entry.action = SshConfigImportAction::Skip;
+            plan.entry
+                .messages
+                .push("the target already exists".to_string());
+            continue;
+        }
+        plan.entry.action = SshConfigImportAction::Update;
+        if !payload.dry_run {
+            map_db_err!(
+                connection_pool
+                    .with_target_mutation(existing.id, move || {
+                        target_repository::update(db, secrets, active_model)
+                    })
+                    .await
+            )?;
+        }
+    }
+    if !payload.dry_run {
+        map_db_err!(transaction.commit().await)?;
+    }
+
+    Ok(SshConfigImportResult {
+        entries: plans.into_iter().map(|plan| plan.entry).collect(),
+        warnings,
+    })
+}
+
+/// Whether `key`, the content of an imported identity file, is the key
+/// store key `key_id`, which the target then keeps using.
+async fn is_stored_key<C>(
+    db: &C,
+    secrets: &Secrets,
+    key_id: Option<i32>,
+    key: &str,
+) -> Result<bool, ApiErr>
+where
+    C: ConnectionTrait,
+{
+    let Some(key_id) = key_id else {
+        return Ok(false);
+    };
+    let Some(stored_key) =
+        map_db_err!(key_store_repository::find_by_id(db, secrets, key_id).await)?
+    else {
+        return Ok(false);
+    };
+    // The public half of an OpenSSH key is readable without its passphrase.
+    let fingerprint = PrivateKey::from_openssh(key.trim())
+        .ok()
+        .or_else(|| russh::keys::decode_secret_key(key, None).ok())
+        .map(|key| key.fingerprint(HashAlg::Sha256).to_string());
+    Ok(fingerprint.is_some_and(|fingerprint| fingerprint == stored_key.fingerprint))
+}
+
+/// Orders imported hosts so that jump hosts from the same file come first.
+/// `jumps` holds the index of each host's jump host within the file.
+fn import_order(jumps: &[Option<usize>]) -> Vec<usize> {
+    fn visit(index: usize, jumps: &[Option<usize>], visited: &mut [bool], order: &mut Vec<usize>) {
+        if visited[index] {
+            return;
+        }
+        visited[index] = true;
+        if let Some(jump) = jumps[index] {
+            visit(jump, jumps, visited, order);
+        }
+        order.push(index);
+    }
+
+    let mut visited = vec![false; jumps.len()];
+    let mut order = Vec::with_capacity(jumps.len());
+    for index in 0..jumps.len() {
+        visit(index, jumps, &mut visited, &mut order);
+    }
+    order
+}
+
+/// Exports the targets as an OpenSSH client config. Targets are named after
+/// their host, with the target ID appended when several share a host.
+pub async fn export_ssh_config(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+) -> Result<String, ApiErr> {
+    let targets = map_db_err!(target_repository::list(db, secrets).await)?;
+    let key_names: HashMap<i32, String> =
+        map_db_err!(key_store_repository::list(db, secrets).await)?
+            .into_iter()
+            .map(|stored_key| (stored_key.id, stored_key.name))
+            .collect();
+    let mut hosts: HashMap<&str, usize> = HashMap::new();
+    for target in &targets {
+        *hosts.entry(target.host.as_str()).or_default() += 1;
+    }
+    let aliases: HashMap<i32, String> = targets
+        .iter()
+        .map(|target| {
+            let alias = match hosts[target.host.as_str()] {
+                1 => target.host.clone(),
+                _ => format!("{}-{}", target.host, target.id),
+            };
+            (target.id, alias)
+        })
+        .collect();
+    Ok(ssh_config::format(
+        &targets,
+        |id| {
+            aliases
+                .get(&id)
+                .cloned()
+                .unwrap_or_else(|| format!("target-{id}"))
+        },
+        |key_id| key_names.get(&key_id).cloned(),
+    ))
+}
+
+/// Creates or updates targets for the hosts of an Ansible inventory. A host
+/// goes into the target group of the first inventory group listing it, and
+/// is remembered by its inventory name so that importing the same `source`
+/// again updates its target instead of adding another one.
+pub async fn import_ansible_inventory(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    connection_pool: &SshConnectionPool,
+    payload: AnsibleInventoryImportPayload,
+) -> Result<AnsibleInventoryImportResult, ApiErr> {
+    let source = payload.source.trim();
+    if source.is_empty() {
+        return Err(invalid_request("source is required".to_string()));
+    }
+    let format = payload
+        .format
+        .unwrap_or_else(|| ansible::detect_format(&payload.content));
+    let (inventory, warnings) =
+        ansible::parse(&payload.content, format).map_err(invalid_request)?;
+    let dry_run = payload.dry_run;
+    let default_user = payload
+        .default_user
+        .as_deref()
+        .map(str::trim)
+        .filter(|user| !user.is_empty());
+    let transaction = map_db_err!(db.begin().await)?;
+    let db = &transaction;
+    let targets = map_db_err!(target_repository::list(db, secrets).await)?;
+    let sources = map_db_err!(
+        target_source_repository::list_by_source(db, target_source::ANSIBLE, source).await
+    )?;
+    // Targets imported for another host of the inventory are not matched by
+    // address, the host they belong to updates them.
+    let claimed: HashSet<i32> = sources
+        .iter()
+        .filter(|source| {
+            inventory
+                .hosts
+                .iter()
+                .any(|host| host.name == source.reference)
+        })
+        .map(|source| source.target_id)
+        .collect();
+    let mut groups = map_db_err!(target_group_repository::list(db).await)?;
+    let mut created_groups = Vec::new();
+    let mut seen = PlannedAddresses::default();
+    let mut entries = Vec::with_capacity(inventory.hosts.len());
+    for host in &inventory.hosts {
+        let group_path = host
+            .groups
+            .first()
+            .map(|group| inventory.group_path(group))
+            .unwrap_or_default();
+        let mut entry = AnsibleInventoryImportEntry {
+            name: host.name.clone(),
+            host: host.host().to_string(),
+            port: host.port,
+            user: host.user.as_deref().or(default_user).map(str::to_string),
+            group: (!group_path.is_empty()).then(|| group_path.join("/")),
+            action: TargetImportAction::Create,
+            target_id: None,
+            messages: Vec::new(),
+        };
+        if host.groups.len() > 1 {
+            entry.messages.push(format!(
+                "the host is also in {}, only the first group is used",
+                host.groups[1..].join(", ")
+            ));
+        }
+        let key = imported_key(
+            &payload.files,
+            "private key file",
+            host.private_key_file.as_deref(),
+            &mut entry.messages,
+        );
+        let Some(user) = entry.user.clone() else {
+            entry.action = TargetImportAction::Skip;
+            entry.messages.push("ansible_user is not set".to_string());
+            entries.push(entry);
+            continue;
+        };
+        if !seen.insert(&entry.host, entry.port, &user) {
+            entry.action = TargetImportAction::Skip;
+            entry
+                .messages
+                .push("duplicate of an earlier host with the same host, port and user".to_string());
+            entries.push(entry);
+            continue;
+        }
+
+        let imported = sources
+            .iter()
+            .find(|source| source.reference == host.name)
+            .and_then(|source| targets.iter().find(|target| target.id == source.target_id));
+        let existing = imported
+            .or_else(|| {
+                find_by_address(
+                    targets
+                        .iter()
+                        .filter(|target| !claimed.contains(&target.id)),
+                    &entry.host,
+                    entry.port,
+                    Some(&user),
+                )
+            })
+            .cloned();
+        let group_id =
+            resolve_group_path(db, &mut groups, &group_path, dry_run, &mut created_groups).await?;
+
+        let Some(existing) = existing else {
+            if !dry_run {
+                let target = target::Model {
+                    group_id,
+                    ..imported_target(entry.host.clone(), entry.port, user, key)
+                };
+                let target = map_db_err!(target_repository::insert(db, secrets, target).await)?;
+                record_ansible_source(db, target.id, source, &host.name).await?;
+                entry.target_id = Some(target.id);
+            }
+            entries.push(entry);
+            continue;
+        };
+
+        entry.target_id = Some(existing.id);
+        // A target imported from this inventory follows it, other targets
+        // only get a group if they have none.
+        let from_source = imported.is_some();
+        let mut updated = existing.clone();
+        if from_source {
+            updated.host = entry.host.clone();
+            updated.port = entry.port;
+            updated.user = user;
+        }
+        // Only a dry run leaves a group of the path unresolved.
+        let mut moves_to_new_group = false;
+        if !group_path.is_empty() && (from_source || existing.group_id.is_none()) {
+            match group_id {
+                Some(group_id) => updated.group_id = Some(group_id),
+                None => moves_to_new_group = true,
+            }
+        }
+        if let Some(key) = key {
+            updated.method = TargetAuthMethod::PrivateKey;
+            if existing.key.as_ref() != Some(&key)
+                && !is_stored_key(db, secrets, existing.key_id, &key).await?
+            {
+                updated.key = Some(key);
+                updated.key_id = None;
+            }
+        }
+        if !from_source {
+            entry
+                .messages
+                .push("matched an existing target with the same host, port and user".to_string());
+        }
+        if updated == existing && !moves_to_new_group {
+            entry.action = TargetImportAction::Skip;
+            entry.messages.push("the target is unchanged".to_string());
+        } else {
+            entry.action = TargetImportAction::Update;
+        }
+        if dry_run {
+            entries.push(entry);
+            continue;
+        }
+        if updated != existing {
+            let target_id = existing.id;
+            let endpoint_changed = !same_endpoint(&existing, &updated);
+            let active_model = target::ActiveModel::from(updated).reset_all();
+            map_db_err!(
+                connection_pool
+                    .with_target_mutation(target_id, move || async move {
+                        let target = target_repository::update(db, secrets, active_model).await?;
+                        if endpoint_changed {
+                            target_capabilities_repository::delete(db, target_id).await?;
+                        }
+                        Ok::<_, DbErr>(target)
+                    })
+                    .await
+            )?;
+        }
+        record_ansible_source(db, existing.id, source, &host.name).await?;
+        entries.push(entry);
+    }
+    if !dry_run {
+        map_db_err!(transaction.commit().await)?;
+    }
+
+    Ok(AnsibleInventoryImportResult {
+        entries,
+        created_groups,
+        warnings,
+    })
+}
+
+async fn record_ansible_source<C>(
+    db: &C,
+    target_id: i32,
+    source: &str,
+    reference: &str,
+) -> Result<(), ApiErr>
+where
+    C: ConnectionTrait,
+{
+    map_db_err!(
+        target_source_repository::upsert(
+            db,
+            target_source::Model {
+                target_id,
+                kind: target_source::ANSIBLE.to_string(),
+                source: source.to_string(),
+                reference: reference.to_string(),
+                imported_at: now_ms(),
+            },
+        )
+        .await
+    )
+}
+
+/// Exports every target with its group and favorite directories, plus all
+/// groups and known host keys. Only encrypted exports contain passwords and
+/// private keys; keys from the key store are written into the targets.
+pub async fn export_targets(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    payload: TargetExportPayload,
+) -> Result<String, ApiErr> {
+    let passphrase = match payload.format {
+        TargetBundleFormat::Encrypted => Some(required_passphrase(payload.passphrase)?),
+        TargetBundleFormat::Json | TargetBundleFormat::Csv => None,
+    };
+    let include_secrets = passphrase.is_some();
+    let targets = map_db_err!(target_repository::list(db, secrets).await)?;
+    let groups = map_db_err!(target_group_repository::list(db).await)?;
+    let stored_keys: HashMap<i32, String> = if include_secrets {
+        map_db_err!(key_store_repository::list(db, secrets).await)?
+            .into_iter()
+            .map(|stored_key| (stored_key.id, stored_key.private_key))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let group_paths = group_paths(&groups);
+
+    let mut bundle = TargetBundle::new();
+    bundle.groups = groups
+        .iter()
+        .filter_map(|group| group_paths.get(&group.id).cloned())
+        .collect();
+    bundle.groups.sort();
+    for target in &targets {
+        let favorites =
+            map_db_err!(favorite_directory_repository::list_by_target(db, target.id).await)?;
+        let key = target
+            .key_id
+            .and_then(|key_id| stored_keys.get(&key_id).cloned())
+            .or_else(|| target.key.clone());
+        bundle.targets.push(BundleTarget {
+            host: target.host.clone(),
+            port: target.port,
+            user: target.user.clone(),
+            method: target.method.clone(),
+            password: target.password.clone().filter(|_| include_secrets),
+            key: key.filter(|_| include_secrets),
+            certificate: target.certificate.clone(),
+            system: target.system.clone(),
+            jump: target
+                .jump_target_id
+                .and_then(|jump_target_id| targets.iter().find(|jump| jump.id == jump_target_id))
+                .map(jump_address),
+            proxy: if include_secrets {
+                target.proxy.clone()
+            } else {
+                proxy_without_credentials(target.proxy.as_deref())
+            },
+            proxy_command: target.proxy_command.clone(),
+            algorithms: target.algorithms.clone(),
+            connect_timeout: target.connect_timeout,
+            connect_retries: target.connect_retries,
+            min_idle_connections: target.min_idle_connections,
+            host_key_policy: target.host_key_policy,
+            group: target
+                .group_id
+                .and_then(|group_id| group_paths.get(&group_id).cloned())
+                .unwrap_or_default(),
+            tags: target.tags.clone().map(|tags| tags.0).unwrap_or_default(),
+            favorites: favorites
+                .into_iter()
+                .map(|favorite| BundleFavorite {
+                    name: favorite.name,
+                    path: favorite.path,
+                    is_default: favorite.is_default,
+                })
+                .collect(),
+        });
+    }
+    bundle.known_hosts = map_db_err!(ssh_known_host_repository::list(db, None, None).await)?
+        .into_iter()
+        .map(|known_host| BundleKnownHost {
+            host: known_host.host,
+            port: known_host.port,
+            key_algorithm: known_host.key_algorithm,
+            public_key: known_host.public_key,
+            fingerprint: known_host.fingerprint,
+            marker: known_host.marker,
+        })
+        .collect();
+
+    match passphrase {
+        Some(passphrase) => {
+            tokio::task::spawn_blocking(move || bundle::encrypt(&bundle, &passphrase))
+                .await
+                .map_err(|err| internal_error(format!("failed to encrypt targets: {err}")))?
+                .map_err(invalid_request)
+        }
+        None if payload.format == TargetBundleFormat::Csv => Ok(bundle::to_csv(&bundle)),
+        None => Ok(bundle::to_json(&bundle)),
+    }
+}
+
+/// Imports targets written by [`export_targets`] or a hand-made CSV file.
+/// A target with the same host, port and user as a stored target is merged
+/// into it or overwrites it, depending on the mode. Missing groups are
+/// created and jump hosts from the same file are imported first.
+pub async fn import_targets(
+    db: &DatabaseConnection,
+    secrets: &Secrets,
+    connection_pool: &SshConnectionPool,
+    payload: TargetImportPayload,
+) -> Result<TargetImportResult, ApiErr> {
+    let bundle = match payload.format {
+        TargetBundleFormat::Json => bundle::from_json(&payload.content),
+        TargetBundleFormat::Csv => bundle::from_csv(&payload.content),
+        TargetBundleFormat::Encrypted => {
+            let passphrase = required_passphrase(payload.passphrase)?;
+            let content = payload.content;
+            tokio::task::spawn_blocking(move || bundle::decrypt(&content, &passphrase))
+                .await
+                .map_err(|err| internal_error(format!("failed to decrypt targets: {err}")))?
+        }
+    }
+    .map_err(invalid_request)?;
+    let dry_run = payload.dry_run;
+    // A failing import leaves nothing behind; a dry run never commits.
+    let transaction = map_db_err!(db.begin().await)?;
+    let db = &transaction;
+    let existing = map_db_err!(target_repository::list(db, secrets).await)?;
+    let mut groups = map_db_err!(target_group_repository::list(db).await)?;
+    let mut created_groups = Vec::new();
+    for path in &bundle.groups {
+        resolve_group_path(db, &mut groups, path, dry_run, &mut created_groups).await?;
+    }
+
+    let targets = &bundle.targets;
+    let jumps: Vec<Option<usize>> = targets
+        .iter()
+        .map(|target| {
+            let (user, host, port) = ssh_config::parse_jump(target.jump.as_deref()?);
+            targets.iter().position(|jump| {
+                jump.host.eq_ignore_ascii_case(host)
+                    && jump.port.unwrap_or(22) == port.unwrap_or(22)
+                    && user.is_none_or(|user| jump.user == user)
+            })
+        })
+        .collect();
+    let mut entries: Vec<TargetImportEntry> = targets
+        .iter()
+        .map(|target| TargetImportEntry {
+            host: target.host.trim().to_string(),
+            port: target.port,
+            user: target.user.trim().to_string(),
+            action: TargetImportAction::Create,
+            conflict: false,
+            target_id: None,
+            messages: Vec::new(),
+        })
+        .collect();
+    let mut seen = PlannedAddresses::default();
+    for entry in &mut entries {
+        if entry.host.is_empty() || entry.user.is_empty() {
+            entry.action = TargetImportAction::Skip;
+            entry
+                .messages
+                .push("host and user are required".to_string());
+        } else if !seen.insert(&entry.host, entry.port, &entry.user) {
+            entry.action = TargetImportAction::Skip;
+            entry.messages.push(
+                "duplicate of an earlier target with the same host, port and user".to_string(),
+            );
+        }
+    }
+
+    let mut applied = vec![false; targets.len()];
+    for index in import_order(&jumps) {
+        applied[index] = true;
+        if entries[index].action == TargetImportAction::Skip {
+            continue;
+        }
+        let target = &targets[index];
+        let jump_target_id = match (target.jump.as_deref(), jumps[index]) {
+            (None, _) => None,
+            (Some(jump), Some(jump_index)) => {
+                let looped = jump_index == index || !applied[jump_index];
+                let skipped = entries[jump_index].action == TargetImportAction::Skip;
+                let target_id = entries[jump_index].target_id;
+                planned_jump_target_id(
+                    jump,
+                    looped,
+                    skipped,
+                    target_id,
+                    &mut entries[index].messages,
+                )
+                .flatten()
+            }
+            (Some(jump), None) => {
+                let (user, host, port) = ssh_config::parse_jump(jump);
+                let jump_target = find_by_address(&existing, host, port, user);
+                if jump_target.is_none() {
+                    let message = format!("jump host {jump} is not a stored target");
+                    entries[index].messages.push(message);
+                }
+                jump_target.map(|jump_target| jump_target.id)
+            }
+        };
+        // A ProxyCommand runs on this server, so a file only brings one in
+        // where the server allows them.
+        let mut proxy_command = target
+            .proxy_command
+            .clone()
+            .filter(|command| !command.trim().is_empty());
+        if proxy_command.is_some() && !connection_pool.allows_proxy_command() {
+            proxy_command = None;
+            entries[index].messages.push(
+                "ProxyCommand is dropped, set WEBSSH_RS_ALLOW_PROXY_COMMAND=true to import it"
+                    .to_string(),
+            );
+        }
+        let mut imported = target::Model {
+            method: target.method.clone(),
+            certificate: target.certificate.clone(),
+            password: target.password.clone(),
+            system: target.system.clone(),
+            jump_target_id,
+            proxy: target.proxy.clone(),
+            proxy_command,
+            algorithms: target.algorithms.clone(),
+            connect_timeout: target.connect_timeout,
+            connect_retries: target.connect_retries,
+            min_idle_connections: target.min_idle_connections,
+            host_key_policy: target.host_key_policy,
+            tags: normalize_tags(Some(TargetTags(target.tags.clone()))),
+            ..imported_target(
+                entries[index].host.clone(),
+                target.port,
+                entries[index].user.clone(),
+                target.key.clone(),
+            )
+        };
+        let conflict = find_by_address(
+            &existing,
+            &imported.host,
+            imported.port,
+            Some(&imported.user),
+        )
+        .cloned();
+        let merged = match (&conflict, payload.mode) {
+            (None, _) => None,
+            (Some(existing), TargetImportMode::Merge) => {
+                Some(merge_target(existing, imported.clone()))
+            }
+            (Some(existing), TargetImportMode::Overwrite) => {
+                Some(overwrite_target(existing, imported.clone()))
+            }
+        };
+        if let Err(err) =
+            validate_imported_target(merged.as_ref().unwrap_or(&imported), connection_pool)
+        {
+            entries[index].action = TargetImportAction::Skip;
+            entries[index].messages.push(err.message);
+            continue;
+        }
+        let group_id =
+            resolve_group_path(db, &mut groups, &target.group, dry_run, &mut created_groups)
+                .await?;
+
+        let entry = &mut entries[index];
+        let Some((existing, mut merged)) = conflict.zip(merged) else {
+            imported.group_id = group_id;
+            if !dry_run {
+                let created = map_db_err!(target_repository::insert(db, secrets, imported).await)?;
+                import_favorites(db, created.id, &target.favorites).await?;
+                entry.target_id = Some(created.id);
+            }
+            continue;
+        };
+
+        entry.conflict = true;
+        entry.target_id = Some(existing.id);
+        if payload.mode == TargetImportMode::Overwrite || merged.group_id.is_none() {
+            merged.group_id = group_id.or(merged.group_id);
+        }
+        let stored_favorites =
+            map_db_err!(favorite_directory_repository::list_by_target(db, existing.id).await)?;
+        let new_favorites: Vec<&BundleFavorite> = target
+            .favorites
+            .iter()
+            .filter(|favorite| {
+                !stored_favorites
+                    .iter()
+                    .any(|stored| stored.path == favorite.path)
+            })
+            .collect();
+        if merged == existing && new_favorites.is_empty() {
+            entry.action = TargetImportAction::Skip;
+            entry
+                .messages
+                .push("the target already exists and is unchanged".to_string());
+            continue;
+        }
+        entry.action = TargetImportAction::Update;
+        if !new_favorites.is_empty() {
+            entry.messages.push(format!(
+                "{} favorite directories are added",
+                new_favorites.len()
+            ));
+        }
+        if dry_run {
+            continue;
+        }
+        if merged != existing {
+            let active_model = target::ActiveModel::from(merged).reset_all();
+            map_db_err!(
+                connection_pool
+                    .with_target_mutation(existing.id, move || {
+                        target_repository::update(db, secrets, active_model)
+                    })
+                    .await
+            )?;
+        }
+        let new_favorites: Vec<BundleFavorite> = new_favorites
+            .into_iter()
+            .map(|favorite| BundleFavorite {
+                name: favorite.name.clone(),
+                path: favorite.path.clone(),
+                is_default: favorite.is_default,
+            })
+            .collect();
+        import_favorites(db, existing.id, &new_favorites).await?;
+    }
+
+    let mut skipped_known_hosts = Vec::new();
+    let active_models: Vec<_> = bundle
+        .known_hosts
+        .into_iter()
+        .filter_map(|known_host| match known_host_key(&known_host) {
+            Ok(key) => Some(ssh_known_host::ActiveModel {
+                id: NotSet,
+                host: Set(known_host.host),
+                port: Set(known_host.port),
+                key_algorithm: Set(key.key_algorithm),
+                public_key: Set(key.public_key),
+                fingerprint: Set(key.fingerprint),
+                marker: Set(known_host.marker),
+            }),
+            Err(reason) => {
+                skipped_known_hosts.push(format!(
+                    "known host {}:{} is skipped: {reason}",
+                    known_host.host, known_host.port
+                ));
+                None
+            }
+        })
+        .collect();
+    let known_hosts = active_models.len();
+    if !dry_run {
+        map_db_err!(ssh_known_host_repository::upsert_many(db, active_models).await)?;
+        map_db_err!(transaction.commit().await)?;
+    }
+
+    Ok(TargetImportResult {
+        entries,
+        created_groups,
+        known_hosts,
+        skipped_known_hosts,
+    })
+}
+
+/// Parses the public key of a bundle known host. The algorithm and
+/// fingerprint stored with it are derived from the key, not taken from the
+/// file, which must agree with them.
+fn known_host_key(known_host: &BundleKnownHost) -> Result<ServerPublicKey, String> {
+    let public_key = ssh_key::PublicKey::from_openssh(&format!(
+        "{} {}",
+        known_host.key_algorithm, known_host.public_key
+    ))
+    .map_err(|err| format!("invalid public key: {err}"))?;
+    let key = ServerPublicKey::from(&public_key);
+    if key.key_algorithm != known_host.key_algorithm {
+        return Err(format!(
+            "the key is {}, not {}",
+            key.key_algorithm, known_host.key_algorithm
+        ));
+    }
+    if key.fingerprint != known_host.fingerprint {
+        return Err(format!(
+            "fingerprint {} does not match the key ({})",
+            known_host.fingerprint, key.fingerprint
+        ));
+    }
+    Ok(key)
+}
+
+fn required_passphrase(passphrase: Option<String>) -> Result<String, ApiErr> {
+    passphrase
+        .filter(|passphrase| !passphrase.is_empty())
+        .ok_or_else(|| invalid_request("a passphrase is required for encrypted files".to_string()))
+}
+
+fn invalid_request(message: String) -> ApiErr {
+    ApiErr {
+        code: ERR_CODE_TARGET_INVALID_REQUEST,
+        message,
+    }
+}
+
+fn internal_error(message: String) -> ApiErr {
+    ApiErr {
+        code: ERR_CODE_TARGET_ERR,
+        message,
+    }
+}
+
+/// Name paths of the groups, starting at a top-level group.
+fn group_paths(groups: &[target_group::Model]) -> HashMap<i32, Vec<String>> {
+    groups
+        .iter()
+        .map(|group| {
+            let mut path = vec![group.name.clone()];
+            let mut parent_id = group.parent_id;
+            // The length bound stops at a parent loop.
+            while let Some(id) = parent_id
+                && path.len() <= groups.len()
+                && let Some(parent) = groups.iter().find(|parent| parent.id == id)
+            {
+                path.insert(0, parent.name.clone());
+                parent_id = parent.parent_id;
+            }
+            (group.id, path)
+        })
+        .collect()
+}
+
+/// Finds the group at `path`, creating the missing groups unless `dry_run`.
+/// Returns `None` for an empty path and for groups only a dry run creates.
+async fn resolve_group_path<C>(
+    db: &C,
+    groups: &mut Vec<target_group::Model>,
+    path: &[String],
+    dry_run: bool,
+    created_groups: &mut Vec<String>,
+) -> Result<Option<i32>, ApiErr>
+where
+    C: ConnectionTrait,
+{
+    let mut parent_id = None;
+    // Set once a dry run reaches a group that does not exist yet.
+    let mut missing = false;
+    let mut names = Vec::new();
+    for name in path
+        .iter()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+    {
+        names.push(name);
+        if !missing
+            && let Some(group) = groups
+                .iter()
+                .find(|group| group.parent_id == parent_id && group.name == name)
+        {
+            parent_id = Some(group.id);
+            continue;
+        }
+        let created = names.join("/");
+        if !created_groups.contains(&created) {
+            created_groups.push(created);
+        }
+        if dry_run {
+            missing = true;
+            continue;
+        }
+        let group = map_db_err!(
+            target_group_repository::insert(
+                db,
+                target_group::ActiveModel {
+                    id: NotSet,
+                    name: Set(name.to_string()),
+                    parent_id: Set(parent_id),
+                },
+            )
+            .await
+        )?;
+        parent_id = Some(group.id);
+        groups.push(group);
+    }
+    Ok(parent_id.filter(|_| !missing))
+}
+
+fn validate_imported_target(
+    target: &target::Model,
+    connection_pool: &SshConnectionPool,
+) -> Result<(), ApiErr> {
+    validate_certificate(target.certificate.as_deref())?;
+    validate_proxy(target.proxy.as_deref())?;
+    validate_proxy_command(
+        connection_pool.allows_proxy_command(),
+        target.proxy_command.as_deref(),
+        target.jump_target_id,
+        &target.host,
+        &target.user,
+    )?;
+    validate_target_algorithms(target.algorithms.as_ref())?;
+    validate_connect_settings(
+        target.connect_timeout,
+        target.connect_retries,
+        target.min_idle_connections,
+        connection_pool.max_connections_per_target(),
+    )
+}
+
+/// Fills the settings `existing` leaves empty and adds the imported tags.
+fn merge_target(existing: &target::Model, imported: target::Model) -> target::Model {
+    let mut merged = existing.clone();
+    if merged.key.is_none() && merged.key_id.is_none() {
+        merged.key = imported.key;
+    }
+    merged.certificate = merged.certificate.or(imported.certificate);
+    merged.password = merged.password.or(imported.password);
+    merged.system = merged.system.or(imported.system);
+    merged.proxy = merged.proxy.or(imported.proxy);
+    merged.algorithms = merged.algorithms.or(imported.algorithms);
+    merged.connect_timeout = merged.connect_timeout.or(imported.connect_timeout);
+    merged.connect_retries = merged.connect_retries.or(imported.connect_retries);
+    merged.min_idle_connections = merged
+        .min_idle_connections
+        .or(imported.min_idle_connections);
+    merged.host_key_policy = merged.host_key_policy.or(imported.host_key_policy);
+    // A jump host and a ProxyCommand exclude each other.
+    if merged.jump_target_id.is_none() && merged.proxy_command.is_none() {
+        merged.jump_target_id = imported.jump_target_id;
+        merged.proxy_command = imported
+            .proxy_command
+            .filter(|_| merged.jump_target_id.is_none());
+    }
+    let tags = existing
+        .tags
+        .iter()
+        .chain(&imported.tags)
+        .flat_map(|tags| tags.0.iter().cloned())
+        .collect();
+    merged.tags = normalize_tags(Some(TargetTags(tags)));
+    merged
+}
+
+/// Replaces the settings of `existing`, keeping its secrets when the import
+/// has none.
+fn overwrite_target(existing: &target::Model, imported: target::Model) -> target::Model {
+    let (key, key_id) = match imported.key {
+        Some(key) => (Some(key), None),
+        None => (existing.key.clone(), existing.key_id),
+    };
+    target::Model {
+        id: existing.id,
+        key,
+        key_id,
+        password: imported.password.or_else(|| existing.password.clone()),
+        ..imported
+    }
+}
+
+async fn import_favorites<C>(
+    db: &C,
+    target_id: i32,
+    favorites: &[BundleFavorite],
+) -> Result<(), ApiErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    for favorite in favorites {
+        map_db_err!(
+            favorite_directory_repository::insert_if_absent(
+                db,
+                target_id,
+                &favorite.path,
+                favorite_directory::ActiveModel {
+                    id: NotSet,
+                    target_id: Set(target_id),
+                    name: Set(favorite.name.clone()),
+                    path: Set(favorite.path.clone()),
+                    is_default: Set(favorite.is_default),
+                    created_at: Set(now_ms()),
+                },
+            )
+            .await
+        )?;
+    }
+    Ok(())
+}
+
+/// `user@host:port` as understood by [`ssh_config::parse_jump`].
+fn jump_address(target: &target::Model) -> String {
+    let port = target.port.unwrap_or(22);
+    if target.host.contains(':') {
+        format!("{}@[{}]:{port}", target.user, target.host)
+    } else {
+        format!("{}@{}:{port}", target.user, target.host)
+    }
+}
+
+fn proxy_without_credentials(proxy: Option<&str>) -> Option<String> {
+    match TargetProxy::parse(proxy) {
+        Ok(TargetProxy::Default) => None,
+        Ok(TargetProxy::Direct) => Some("direct".to_string()),
+        Ok(TargetProxy::Proxy(proxy)) => Some(proxy.to_string()),
+        Err(_) => None,
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ActiveValue::NotSet, ActiveValue::Set, Database};
+    use sea_orm_migration::MigratorTrait;
+
+    use russh::keys::ssh_key::LineEnding;
+
+    use crate::{
+        config::CheckServerKey,
+        entities::{favorite_directory, key_store, target::TargetAuthMethod},
+        migrations::Migrator,
+        repositories::favorite_directory as favorite_directory_repository,
+        tests::sftp_server,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn add_validates_certificates_and_reports_their_validity() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let private_key =
+            russh::keys::PrivateKey::random(&mut rand::rng(), russh::keys::Algorithm::Ed25519)
+                .unwrap();
+        let mut payload = target::Model {
+            id: 0,
+            host: "127.0.0.1".to_string(),
+            port: Some(22),
+            method: TargetAuthMethod::PrivateKey,
+            user: "deploy".to_string(),
+            key: None,
+            key_id: None,
+            certificate: Some("not a certificate".to_string()),
+            password: None,
+            system: None,
+            jump_target_id: None,
+            proxy: None,
+            proxy_command: None,
+            algorithms: None,
+            connect_timeout: None,
+            connect_retries: None,
+            min_idle_connections: None,
+            host_key_policy: None,
+            group_id: None,
+            tags: None,
+        };
+
+        let err = match add(&db, &Secrets::default(), &connection_pool, payload.clone()).await {
+            Ok(_) => panic!("an invalid certificate should be rejected"),
+            Err(err) => err,
+        };
+        assert_eq!(err.code, ERR_CODE_TARGET_INVALID_REQUEST);
+        assert!(
+            target_repository::list(&db, &Secrets::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        payload.certificate = Some(sftp_server::sign_user_certificate(
+            &private_key,
+            &["deploy", "admin"],
+            1_700_000_000,
+            1_800_000_000,
+        ));
+        let target = add(&db, &Secrets::default(), &connection_pool, payload)
+            .await
+            .unwrap();
+        let certificate_info = target.certificate_info.unwrap();
+        assert_eq!(certificate_info.key_id, "webssh-rs-test");
+        assert_eq!(certificate_info.principals, vec!["deploy", "admin"]);
+        assert_eq!(certificate_info.valid_after, 1_700_000_000);
+        assert_eq!(certificate_info.valid_before, 1_800_000_000);
+        assert!(certificate_info.ca_fingerprint.starts_with("SHA256:"));
+    }
+
+    #[tokio::test]
+    async fn add_rejects_proxy_commands_unless_the_server_allows_them() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let mut payload = target_model("10.0.0.7", "deploy");
+        payload.proxy_command = Some("ssh -W %h:%p bastion".to_string());
+
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let err = match add(&db, &Secrets::default(), &connection_pool, payload.clone()).await {
+            Ok(_) => panic!("a ProxyCommand should be rejected while they are disabled"),
+            Err(err) => err,
+        };
+        assert_eq!(err.code, ERR_CODE_TARGET_INVALID_REQUEST);
+        assert!(err.message.contains("WEBSSH_RS_ALLOW_PROXY_COMMAND"));
+
+        let connection_pool = connection_pool.with_proxy_command_allowed(true);
+        let mut unsafe_host = payload.clone();
+        unsafe_host.host = "10.0.0.7;reboot".to_string();
+        assert!(
+            add(&db, &Secrets::default(), &connection_pool, unsafe_host)
+                .await
+                .is_err()
+        );
+        assert!(
+            target_repository::list(&db, &Secrets::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let target = add(&db, &Secrets::default(), &connection_pool, payload)
+            .await
+            .unwrap();
+        assert_eq!(
+            target.target.proxy_command.as_deref(),
+            Some("ssh -W %h:%p bastion")
+        );
+    }
+
+    #[tokio::test]
+    async fn add_rejects_more_idle_connections_than_a_target_may_open() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 2, 1);
+        let mut payload = target_model("10.0.0.8", "deploy");
+        payload.min_idle_connections = Some(3);
+
+        let err = match add(&db, &Secrets::default(), &connection_pool, payload.clone()).await {
+            Ok(_) => panic!("min_idle_connections above the pool limit should be rejected"),
+            Err(err) => err,
+        };
+        assert_eq!(err.code, ERR_CODE_TARGET_INVALID_REQUEST);
+
+        payload.min_idle_connections = Some(2);
+        add(&db, &Secrets::default(), &connection_pool, payload)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn remove_cleans_remote_favorites_without_touching_local_favorites() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let target = add(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            target::Model {
+                id: 0,
+                host: "127.0.0.1".to_string(),
+                port: Some(22),
+                method: TargetAuthMethod::Password,
+                user: "test".to_string(),
+                key: None,
+                key_id: None,
+                certificate: None,
+                password: Some("password".to_string()),
+                system: None,
+                jump_target_id: None,
+                proxy: None,
+                proxy_command: None,
+                algorithms: None,
+                connect_timeout: None,
+                connect_retries: None,
+                min_idle_connections: None,
+                host_key_policy: None,
+                group_id: None,
+                tags: None,
+            },
+        )
+        .await
+        .unwrap()
+        .target;
+        let local = favorite_directory_repository::insert_if_absent(
+            &db,
+            0,
+            "/local",
+            favorite_directory::ActiveModel {
+                id: NotSet,
+                target_id: Set(0),
+                name: Set("Local".to_string()),
+                path: Set("/local".to_string()),
+                is_default: Set(false),
+                created_at: Set(1),
+            },
+        )
+        .await
+        .unwrap();
+        favorite_directory_repository::initialize_defaults(&db, 0, 1, Vec::new())
+            .await
+            .unwrap();
+        favorite_directory_repository::insert_if_absent(
+            &db,
+            target.id,
+            "/remote",
+            favorite_directory::ActiveModel {
+                id: NotSet,
+                target_id: Set(target.id),
+                name: Set("Remote".to_string()),
+                path: Set("/remote".to_string()),
+                is_default: Set(false),
+                created_at: Set(1),
+            },
+        )
+        .await
+        .unwrap();
+        favorite_directory_repository::initialize_defaults(&db, target.id, 1, Vec::new())
+            .await
+            .unwrap();
+
+        remove(&db, &connection_pool, target.id).await.unwrap();
+
+        assert!(
+            target_repository::find_by_id(&db, &Secrets::default(), target.id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            favorite_directory_repository::list_by_target(&db, target.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            !favorite_directory_repository::is_initialized(&db, target.id)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            favorite_directory_repository::list_by_target(&db, 0)
+                .await
+                .unwrap(),
+            vec![local.clone()]
+        );
+        assert!(
+            favorite_directory_repository::is_initialized(&db, 0)
+                .await
+                .unwrap()
+        );
+
+        remove(&db, &connection_pool, 0).await.unwrap();
+        assert_eq!(
+            favorite_directory_repository::list_by_target(&db, 0)
+                .await
+                .unwrap(),
+            vec![local]
+        );
+        assert!(
+            favorite_directory_repository::is_initialized(&db, 0)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn ssh_config_import_previews_creates_updates_and_skips_duplicates() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let existing = target_repository::insert(
+            &db,
+            &Secrets::default(),
+            target::Model {
+                id: 0,
+                host: "10.0.0.5".to_string(),
+                port: Some(2222),
+                method: TargetAuthMethod::Password,
+                user: "deploy".to_string(),
+                key: None,
+                key_id: None,
+                certificate: None,
+                password: Some("password".to_string()),
+                system: None,
+                jump_target_id: None,
+                proxy: None,
+                proxy_command: None,
+                algorithms: None,
+                connect_timeout: None,
+                connect_retries: None,
+                min_idle_connections: None,
+                host_key_policy: None,
+                group_id: None,
+                tags: None,
+            },
+        )
+        .await
+        .unwrap();
+        let payload = |dry_run| SshConfigImportPayload {
+            content: "\
+Host bastion
+    HostName bastion.example.com
+    User ops
+Host db
+    HostName 10.0.0.5
+    Port 2222
+    ProxyJump bastion
+    IdentityFile ~/.ssh/id_db
+Host app
+    HostName app.example.com
+    ProxyJump db
+Host *
+    User deploy
+"
+            .to_string(),
+            files: vec![crate::apis::target::dto::SshConfigFile {
+                path: "id_db".to_string(),
+                content: "private key".to_string(),
+            }],
+            dry_run,
+        };
+        let actions = |result: &SshConfigImportResult| {
+            result
+                .entries
+                .iter()
+                .map(|entry| (entry.alias.clone(), entry.action, entry.target_id))
+                .collect::<Vec<_>>()
+        };
+
+        let preview = import_ssh_config(&db, &Secrets::default(), &connection_pool, payload(true))
+            .await
+            .unwrap();
+        assert_eq!(
+            actions(&preview),
+            vec![
+                ("bastion".to_string(), SshConfigImportAction::Create, None),
+                (
+                    "db".to_string(),
+                    SshConfigImportAction::Update,
+                    Some(existing.id)
+                ),
+                ("app".to_string(), SshConfigImportAction::Create, None),
+            ]
+        );
+        assert_eq!(
+            target_repository::list(&db, &Secrets::default())
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let imported =
+            import_ssh_config(&db, &Secrets::default(), &connection_pool, payload(false))
+                .await
+                .unwrap();
+        let ids: Vec<i32> = imported
+            .entries
+            .iter()
+            .map(|entry| entry.target_id.unwrap())
+            .collect();
+        let db_target = target_repository::find_by_id(&db, &Secrets::default(), existing.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(db_target.jump_target_id, Some(ids[0]));
+        assert_eq!(db_target.method, TargetAuthMethod::PrivateKey);
+        assert_eq!(db_target.key.as_deref(), Some("private key"));
+        let app = target_repository::find_by_id(&db, &Secrets::default(), ids[2])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(app.host, "app.example.com");
+        assert_eq!(app.user, "deploy");
+        assert_eq!(app.method, TargetAuthMethod::Agent);
+        assert_eq!(app.jump_target_id, Some(existing.id));
+
+        let reimported =
+            import_ssh_config(&db, &Secrets::default(), &connection_pool, payload(false))
+                .await
+                .unwrap();
+        assert!(
+            reimported
+                .entries
+                .iter()
+                .all(|entry| entry.action == SshConfigImportAction::Skip)
+        );
+        assert_eq!(
+            target_repository::list(&db, &Secrets::default())
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+
+        let exported = export_ssh_config(&db, &Secrets::default()).await.unwrap();
+        assert!(
+            exported.contains(
+                "Host 10.0.0.5\n    HostName 10.0.0.5\n    Port 2222\n    User deploy\n    ProxyJump bastion.example.com\n"
+            ),
+            "{exported}"
+        );
+    }
+
+    #[tokio::test]
+    async fn ssh_config_import_keeps_the_stored_key_of_a_target() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let private_key =
+            PrivateKey::random(&mut rand::rng(), russh::keys::Algorithm::Ed25519).unwrap();
+        let stored_key = key_store_repository::insert(
+            &db,
+            &Secrets::default(),
+            key_store::ActiveModel {
+                id: NotSet,
+                name: Set("deploy".to_string()),
+                algorithm: Set(private_key.algorithm().as_str().to_string()),
+                public_key: Set(private_key.public_key().to_openssh().unwrap()),
+                private_key: Set(private_key.to_openssh(LineEnding::LF).unwrap().to_string()),
+                fingerprint: Set(private_key.fingerprint(HashAlg::Sha256).to_string()),
+                comment: Set(String::new()),
+                created_at: Set(0),
+            },
+        )
+        .await
+        .unwrap();
+        let existing = target_repository::insert(
+            &db,
+            &Secrets::default(),
+            target::Model {
+                id: 0,
+                host: "10.0.0.5".to_string(),
+                port: None,
+                method: TargetAuthMethod::PrivateKey,
+                user: "deploy".to_string(),
+                key: None,
+                key_id: Some(stored_key.id),
+                certificate: None,
+                password: None,
+                system: None,
+                jump_target_id: None,
+                proxy: None,
+                proxy_command: None,
+                algorithms: None,
+                connect_timeout: None,
+                connect_retries: None,
+                min_idle_connections: None,
+                host_key_policy: None,
+                group_id: None,
+                tags: None,
+            },
+        )
+        .await
+        .unwrap();
+        let payload = |key: String| SshConfigImportPayload {
+            content:
+                "Host db\n    HostName 10.0.0.5\n    User deploy\n    IdentityFile ~/.ssh/id_db\n"
+                    .to_string(),
+            files: vec![crate::apis::target::dto::SshConfigFile {
+                path: "id_db".to_string(),
+                content: key,
+            }],
+            dry_run: false,
+        };
+
+        let result = import_ssh_config(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(stored_key.private_key.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.entries[0].action, SshConfigImportAction::Skip);
+        let target = target_repository::find_by_id(&db, &Secrets::default(), existing.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.key_id, Some(stored_key.id));
+        assert_eq!(target.key, None);
+        let exported = export_ssh_config(&db, &Secrets::default()).await.unwrap();
+        assert!(
+            exported.contains("    # IdentityFile: key store key deploy is not exported\n"),
+            "{exported}"
+        );
+
+        let result = import_ssh_config(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload("another key".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.entries[0].action, SshConfigImportAction::Update);
+        let target = target_repository::find_by_id(&db, &Secrets::default(), existing.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.key_id, None);
+        assert_eq!(target.key.as_deref(), Some("another key"));
+    }
+
+    #[tokio::test]
+    async fn list_filters_by_nested_group_tag_and_search() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let group = |name: &str, parent_id| {
+            target_group_repository::insert(
+                &db,
+                crate::entities::target_group::ActiveModel {
+                    id: NotSet,
+                    name: Set(name.to_string()),
+                    parent_id: Set(parent_id),
+                },
+            )
+        };
+        let prod = group("prod", None).await.unwrap();
+        let web = group("web", Some(prod.id)).await.unwrap();
+        let target = |host: &str, group_id, tags: &[&str]| target::Model {
+            id: 0,
+            host: host.to_string(),
+            port: None,
+            method: TargetAuthMethod::Agent,
+            user: "deploy".to_string(),
+            key: None,
+            key_id: None,
+            certificate: None,
+            password: None,
+            system: None,
+            jump_target_id: None,
+            proxy: None,
+            proxy_command: None,
+            algorithms: None,
+            connect_timeout: None,
+            connect_retries: None,
+            min_idle_connections: None,
+            host_key_policy: None,
+            group_id,
+            tags: Some(TargetTags(tags.iter().map(|tag| tag.to_string()).collect())),
+        };
+        add(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            target("db1", Some(prod.id), &["db", " db ", ""]),
+        )
+        .await
+        .unwrap();
+        add(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            target("web1", Some(web.id), &["nginx"]),
+        )
+        .await
+        .unwrap();
+        add(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            target("lab1", None, &[]),
+        )
+        .await
+        .unwrap();
+        let err = match add(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            target("lost", Some(1000), &[]),
+        )
+        .await
+        {
+            Ok(_) => panic!("an unknown group should be rejected"),
+            Err(err) => err,
+        };
+        assert_eq!(err.code, ERR_CODE_TARGET_INVALID_REQUEST);
+
+        let hosts = |group_id, tag: Option<&str>, search: Option<&str>| {
+            let db = db.clone();
+            let query = TargetListQuery {
+                group_id,
+                tag: tag.map(str::to_string),
+                search: search.map(str::to_string),
+            };
+            async move {
+                list(&db, &Secrets::default(), query)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|info| info.target.host)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(hosts(None, None, None).await, vec!["db1", "web1", "lab1"]);
+        assert_eq!(hosts(Some(prod.id), None, None).await, vec!["db1", "web1"]);
+        assert_eq!(hosts(Some(web.id), None, None).await, vec!["web1"]);
+        assert_eq!(hosts(None, Some("db"), None).await, vec!["db1"]);
+        assert_eq!(hosts(None, None, Some("WEB")).await, vec!["web1"]);
+        assert_eq!(
+            target_repository::list(&db, &Secrets::default())
+                .await
+                .unwrap()[0]
+                .tags,
+            Some(TargetTags(vec!["db".to_string()]))
+        );
+        assert_eq!(
+            target_repository::list(&db, &Secrets::default())
+                .await
+                .unwrap()[2]
+                .tags,
+            None
+        );
+    }
+
+    fn target_model(host: &str, user: &str) -> target::Model {
+        target::Model {
+            id: 0,
+            host: host.to_string(),
+            port: None,
+            method: TargetAuthMethod::Password,
+            user: user.to_string(),
+            key: None,
+            key_id: None,
+            certificate: None,
+            password: None,
+            system: None,
+            jump_target_id: None,
+            proxy: None,
+            proxy_command: None,
+            algorithms: None,
+            connect_timeout: None,
+            connect_retries: None,
+            min_idle_connections: None,
+            host_key_policy: None,
+            group_id: None,
+            tags: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn exported_targets_import_into_another_database() {
+        let source = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&source, None).await.unwrap();
+        let source_pool = SshConnectionPool::new(source.clone(), CheckServerKey::Disabled, 1, 1);
+        let prod = target_group_repository::insert(
+            &source,
+            target_group::ActiveModel {
+                id: NotSet,
+                name: Set("prod".to_string()),
+                parent_id: Set(None),
+            },
+        )
+        .await
+        .unwrap();
+        let databases = target_group_repository::insert(
+            &source,
+            target_group::ActiveModel {
+                id: NotSet,
+                name: Set("databases".to_string()),
+                parent_id: Set(Some(prod.id)),
+            },
+        )
+        .await
+        .unwrap();
+        let mut postgres = target_model("10.0.0.5", "postgres");
+        postgres.password = Some("hunter2".to_string());
+        postgres.group_id = Some(databases.id);
+        postgres.tags = Some(TargetTags(vec!["db".to_string()]));
+        let postgres = add(&source, &Secrets::default(), &source_pool, postgres)
+            .await
+            .unwrap()
+            .target;
+        // Exported before its jump host to check the import order.
+        let bastion = add(
+            &source,
+            &Secrets::default(),
+            &source_pool,
+            target_model("bastion", "ops"),
+        )
+        .await
+        .unwrap()
+        .target;
+        let mut active_model = target::ActiveModel::from(postgres.clone());
+        active_model.jump_target_id = Set(Some(bastion.id));
+        target_repository::update(&source, &Secrets::default(), active_model)
+            .await
+            .unwrap();
+        favorite_directory_repository::insert_if_absent(
+            &source,
+            postgres.id,
+            "/var/lib/postgresql",
+            favorite_directory::ActiveModel {
+                id: NotSet,
+                target_id: Set(postgres.id),
+                name: Set("data".to_string()),
+                path: Set("/var/lib/postgresql".to_string()),
+                is_default: Set(false),
+                created_at: Set(1),
+            },
+        )
+        .await
+        .unwrap();
+        let host_key = ServerPublicKey::from(
+            russh::keys::PrivateKey::random(&mut rand::rng(), russh::keys::Algorithm::Ed25519)
+                .unwrap()
+                .public_key(),
+        );
+        ssh_known_host_repository::upsert_many(
+            &source,
+            vec![ssh_known_host::ActiveModel {
+                id: NotSet,
+                host: Set("bastion".to_string()),
+                port: Set(22),
+                key_algorithm: Set(host_key.key_algorithm),
+                public_key: Set(host_key.public_key),
+                fingerprint: Set(host_key.fingerprint),
+                marker: Set(None),
+            }],
+        )
+        .await
+        .unwrap();
+
+        let exported = export_targets(
+            &source,
+            &Secrets::default(),
+            TargetExportPayload {
+                format: TargetBundleFormat::Json,
+                passphrase: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!exported.contains("hunter2"));
+
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let payload = |format, content: &str, mode, dry_run| TargetImportPayload {
+            format,
+            content: content.to_string(),
+            passphrase: None,
+            mode,
+            dry_run,
+        };
+
+        let preview = import_targets(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(
+                TargetBundleFormat::Json,
+                &exported,
+                TargetImportMode::Merge,
+                true,
+            ),
+        )
+        .await
+        .unwrap();
+        assert!(
+            preview
+                .entries
+                .iter()
+                .all(|entry| entry.action == TargetImportAction::Create && !entry.conflict)
+        );
+        assert_eq!(preview.created_groups, vec!["prod", "prod/databases"]);
+        assert_eq!(preview.known_hosts, 1);
+        assert!(
+            target_repository::list(&db, &Secrets::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        import_targets(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(
+                TargetBundleFormat::Json,
+                &exported,
+                TargetImportMode::Merge,
+                false,
+            ),
+        )
+        .await
+        .unwrap();
+        let targets = target_repository::list(&db, &Secrets::default())
+            .await
+            .unwrap();
+        let imported_bastion = targets.iter().find(|target| target.user == "ops").unwrap();
+        let imported_postgres = targets
+            .iter()
+            .find(|target| target.user == "postgres")
+            .unwrap();
+        assert_eq!(imported_postgres.jump_target_id, Some(imported_bastion.id));
+        assert_eq!(imported_postgres.password, None);
+        assert_eq!(
+            imported_postgres.tags,
+            Some(TargetTags(vec!["db".to_string()]))
+        );
+        let groups = target_group_repository::list(&db).await.unwrap();
+        assert_eq!(
+            group_paths(&groups).get(&imported_postgres.group_id.unwrap()),
+            Some(&vec!["prod".to_string(), "databases".to_string()])
+        );
+        assert_eq!(
+            favorite_directory_repository::list_by_target(&db, imported_postgres.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            ssh_known_host_repository::list(&db, None, None)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let again = import_targets(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(
+                TargetBundleFormat::Json,
+                &exported,
+                TargetImportMode::Merge,
+                false,
+            ),
+        )
+        .await
+        .unwrap();
+        assert!(
+            again
+                .entries
+                .iter()
+                .all(|entry| entry.action == TargetImportAction::Skip && entry.conflict)
+        );
+        assert!(again.created_groups.is_empty());
+
+        let mut active_model = target::ActiveModel::from(imported_postgres.clone());
+        active_model.password = Set(Some("kept".to_string()));
+        active_model.system = Set(Some("linux".to_string()));
+        target_repository::update(&db, &Secrets::default(), active_model)
+            .await
+            .unwrap();
+        let csv = "host,user,system,tags\n10.0.0.5,postgres,freebsd,backup\n";
+        let merged = import_targets(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(TargetBundleFormat::Csv, csv, TargetImportMode::Merge, false),
+        )
+        .await
+        .unwrap();
+        assert_eq!(merged.entries[0].action, TargetImportAction::Update);
+        let target = target_repository::find_by_id(&db, &Secrets::default(), imported_postgres.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.system.as_deref(), Some("linux"));
+        assert_eq!(
+            target.tags,
+            Some(TargetTags(vec!["db".to_string(), "backup".to_string()]))
+        );
+
+        import_targets(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(
+                TargetBundleFormat::Csv,
+                csv,
+                TargetImportMode::Overwrite,
+                false,
+            ),
+        )
+        .await
+        .unwrap();
+        let target = target_repository::find_by_id(&db, &Secrets::default(), imported_postgres.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.system.as_deref(), Some("freebsd"));
+        assert_eq!(target.method, TargetAuthMethod::Prompt);
+        assert_eq!(target.password.as_deref(), Some("kept"));
+        assert_eq!(target.jump_target_id, None);
+        assert_eq!(target.group_id, None);
+    }
+
+    #[tokio::test]
+    async fn bundle_known_hosts_are_checked_against_their_keys() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let random_key = || {
+            ServerPublicKey::from(
+                russh::keys::PrivateKey::random(&mut rand::rng(), russh::keys::Algorithm::Ed25519)
+                    .unwrap()
+                    .public_key(),
+            )
+        };
+        let known_host = |host: &str, key: ServerPublicKey| BundleKnownHost {
+            host: host.to_string(),
+            port: 22,
+            key_algorithm: key.key_algorithm,
+            public_key: key.public_key,
+            fingerprint: key.fingerprint,
+            marker: None,
+        };
+        let mut bundle = TargetBundle::new();
+        bundle.known_hosts = vec![
+            known_host("valid", random_key()),
+            BundleKnownHost {
+                fingerprint: random_key().fingerprint,
+                ..known_host("tampered", random_key())
+            },
+            BundleKnownHost {
+                public_key: "AAAAC3NzaC1lZDI1NTE5AAAAIA".to_string(),
+                ..known_host("corrupt", random_key())
+            },
+        ];
+
+        let result = import_targets(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            TargetImportPayload {
+                format: TargetBundleFormat::Json,
+                content: bundle::to_json(&bundle),
+                passphrase: None,
+                mode: TargetImportMode::Merge,
+                dry_run: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.known_hosts, 1);
+        assert_eq!(result.skipped_known_hosts.len(), 2);
+        assert!(result.skipped_known_hosts[0].starts_with("known host tampered:22"));
+        assert!(result.skipped_known_hosts[1].starts_with("known host corrupt:22"));
+        let stored = ssh_known_host_repository::list(&db, None, None)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].host, "valid");
+    }
+
+    #[tokio::test]
+    async fn imported_proxy_commands_are_dropped_unless_the_server_allows_them() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let csv = "host,user,proxy_command,group\n10.0.0.5,deploy,ssh -W %h:%p gw,prod\n";
+        let payload = |dry_run| TargetImportPayload {
+            format: TargetBundleFormat::Csv,
+            content: csv.to_string(),
+            passphrase: None,
+            mode: TargetImportMode::Merge,
+            dry_run,
+        };
+
+        let preview = import_targets(&db, &Secrets::default(), &connection_pool, payload(true))
+            .await
+            .unwrap();
+        assert_eq!(preview.entries[0].action, TargetImportAction::Create);
+        assert!(
+            preview.entries[0]
+                .messages
+                .iter()
+                .any(|message| message.contains("ProxyCommand is dropped"))
+        );
+        assert!(target_group_repository::list(&db).await.unwrap().is_empty());
+
+        import_targets(&db, &Secrets::default(), &connection_pool, payload(false))
+            .await
+            .unwrap();
+        let targets = target_repository::list(&db, &Secrets::default())
+            .await
+            .unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].proxy_command, None);
+        assert!(targets[0].group_id.is_some());
+
+        let connection_pool = connection_pool.with_proxy_command_allowed(true);
+        import_targets(&db, &Secrets::default(), &connection_pool, payload(false))
+            .await
+            .unwrap();
+        let target = target_repository::find_by_id(&db, &Secrets::default(), targets[0].id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.proxy_command.as_deref(), Some("ssh -W %h:%p gw"));
+    }
+
+    #[tokio::test]
+    async fn ansible_reimport_updates_the_targets_it_created() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let connection_pool = SshConnectionPool::new(db.clone(), CheckServerKey::Disabled, 1, 1);
+        let manual = add(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            target_model("10.0.0.9", "deploy"),
+        )
+        .await
+        .unwrap()
+        .target;
+        let payload = |content: &str, dry_run| AnsibleInventoryImportPayload {
+            source: "inventories/prod.ini".to_string(),
+            content: content.to_string(),
+            format: None,
+            default_user: None,
+            files: vec![crate::apis::target::dto::SshConfigFile {
+                path: "~/.ssh/web".to_string(),
+                content: "PRIVATE KEY".to_string(),
+            }],
+            dry_run,
+        };
+        let actions = |result: &AnsibleInventoryImportResult| {
+            result
+                .entries
+                .iter()
+                .map(|entry| (entry.name.clone(), entry.action, entry.target_id))
+                .collect::<Vec<_>>()
+        };
+        let inventory = "\
+[web]
+web1 ansible_host=10.0.0.1
+web2 ansible_host=10.0.0.9 ansible_ssh_private_key_file=~/.ssh/web
+[prod:children]
+web
+[all:vars]
+ansible_user=deploy
+";
+
+        let preview = import_ansible_inventory(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(inventory, true),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            actions(&preview),
+            vec![
+                ("web1".to_string(), TargetImportAction::Create, None),
+                (
+                    "web2".to_string(),
+                    TargetImportAction::Update,
+                    Some(manual.id)
+                ),
+            ]
+        );
+        assert_eq!(preview.created_groups, vec!["prod", "prod/web"]);
+        assert_eq!(
+            target_repository::list(&db, &Secrets::default())
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let imported = import_ansible_inventory(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(inventory, false),
+        )
+        .await
+        .unwrap();
+        let web1_id = imported.entries[0].target_id.unwrap();
+        let web1 = target_repository::find_by_id(&db, &Secrets::default(), web1_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(web1.method, TargetAuthMethod::Agent);
+        let groups = target_group_repository::list(&db).await.unwrap();
+        assert_eq!(
+            group_paths(&groups).get(&web1.group_id.unwrap()),
+            Some(&vec!["prod".to_string(), "web".to_string()])
+        );
+        let web2 = target_repository::find_by_id(&db, &Secrets::default(), manual.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(web2.method, TargetAuthMethod::PrivateKey);
+        assert_eq!(web2.key.as_deref(), Some("PRIVATE KEY"));
+        assert_eq!(web2.group_id, web1.group_id);
+
+        // The inventory moved web1, re-importing updates its target.
+        let moved = inventory.replace("10.0.0.1", "10.0.0.2 ansible_port=2222");
+        let reimported = import_ansible_inventory(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(&moved, false),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            actions(&reimported),
+            vec![
+                (
+                    "web1".to_string(),
+                    TargetImportAction::Update,
+                    Some(web1_id)
+                ),
+                (
+                    "web2".to_string(),
+                    TargetImportAction::Skip,
+                    Some(manual.id)
+                ),
+            ]
+        );
+        assert!(reimported.created_groups.is_empty());
+        let targets = target_repository::list(&db, &Secrets::default())
+            .await
+            .unwrap();
+        assert_eq!(targets.len(), 2);
+        let web1 = targets.iter().find(|target| target.id == web1_id).unwrap();
+        assert_eq!((web1.host.as_str(), web1.port), ("10.0.0.2", Some(2222)));
+
+        remove(&db, &connection_pool, web1_id).await.unwrap();
+        let recreated = import_ansible_inventory(
+            &db,
+            &Secrets::default(),
+            &connection_pool,
+            payload(&moved, false),
+        )
+        .await
+        .unwrap();
+        assert_eq!(recreated.entries[0].action, TargetImportAction::Create);
+    }
+}
@@ -0,0 +1,439 @@
+use crate::{
+    apis::target::dto::SshConfigFile, entities::target, ssh_connection_pool::wildcard_match,
+};
+
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// ssh_config 中一个具体的 Host 名称按 OpenSSH 规则（先出现的值优先，
+/// 通配符 Host 块同样生效）解析出的配置
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SshConfigHost {
+    pub alias: String,
+    pub host_name: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+impl SshConfigHost {
+    /// 实际连接的主机，未设置 HostName 时为 Host 名称
+    pub fn host(&self) -> String {
+        match &self.host_name {
+            Some(host_name) => host_name.replace("%h", &self.alias).replace("%%", "%"),
+            None => self.alias.clone(),
+        }
+    }
+}
+
+struct Line {
+    /// `文件:行号`，用于提示信息
+    source: String,
+    keyword: String,
+    args: Vec<String>,
+}
+
+/// 解析 ssh_config 内容，`Include` 在 `files` 中查找被引用的文件。
+/// 返回所有不含通配符的 Host 名称对应的配置，以及无法处理的指令的提示
+pub fn parse(content: &str, files: &[SshConfigFile]) -> (Vec<SshConfigHost>, Vec<String>) {
+    let mut lines = Vec::new();
+    let mut warnings = Vec::new();
+    collect_lines("config", content, files, 0, &mut lines, &mut warnings);
+
+    // 第一个 Host 行之前的指令对所有主机生效
+    let mut blocks: Vec<(Option<Vec<String>>, Vec<&Line>)> = vec![(Some(Vec::new()), Vec::new())];
+    let mut aliases = Vec::new();
+    for line in &lines {
+        match line.keyword.as_str() {
+            "host" => {
+                for pattern in &line.args {
+                    if !pattern.starts_with('!')
+                        && !pattern.contains(['*', '?'])
+                        && !aliases.contains(pattern)
+                    {
+                        aliases.push(pattern.clone());
+                    }
+                }
+                blocks.push((Some(line.args.clone()), Vec::new()));
+            }
+            "match" => {
+                warnings.push(format!("{}: Match blocks are not supported", line.source));
+                blocks.push((None, Vec::new()));
+            }
+            _ => blocks.last_mut().unwrap().1.push(line),
+        }
+    }
+
+    let hosts = aliases
+        .into_iter()
+        .map(|alias| {
+            let mut host = SshConfigHost {
+                alias,
+                ..Default::default()
+            };
+            let applicable = blocks.iter().filter(|(patterns, _)| match patterns {
+                Some(patterns) => patterns.is_empty() || host_matches(patterns, &host.alias),
+                None => false,
+            });
+            for line in applicable.flat_map(|(_, lines)| lines) {
+                let Some(value) = line.args.first() else {
+                    continue;
+                };
+                match line.keyword.as_str() {
+                    "hostname" => {
+                        host.host_name.get_or_insert_with(|| value.clone());
+                    }
+                    "user" => {
+                        host.user.get_or_insert_with(|| value.clone());
+                    }
+                    "identityfile" => {
+                        host.identity_file.get_or_insert_with(|| value.clone());
+                    }
+                    "proxyjump" => {
+                        host.proxy_jump.get_or_insert_with(|| value.clone());
+                    }
+                    "port" if host.port.is_none() => match value.parse() {
+                        Ok(port) => host.port = Some(port),
+                        Err(_) => warnings.push(format!("{}: invalid port {value}", line.source)),
+                    },
+                    _ => {}
+                }
+            }
+            host
+        })
+        .collect();
+    (hosts, warnings)
+}
+
+fn collect_lines(
+    name: &str,
+    content: &str,
+    files: &[SshConfigFile],
+    depth: usize,
+    lines: &mut Vec<Line>,
+    warnings: &mut Vec<String>,
+) {
+    for (index, raw) in content.lines().enumerate() {
+        let source = format!("{name}:{}", index + 1);
+        let Some((keyword, args)) = split_line(raw) else {
+            continue;
+        };
+        if keyword != "include" {
+            lines.push(Line {
+                source,
+                keyword,
+                args,
+            });
+            continue;
+        }
+        if depth >= MAX_INCLUDE_DEPTH {
+            warnings.push(format!("{source}: Include nested too deeply"));
+            continue;
+        }
+        for pattern in &args {
+            let mut included: Vec<_> = files
+                .iter()
+                .filter(|file| path_matches(pattern, &file.path))
+                .collect();
+            if included.is_empty() {
+                warnings.push(format!(
+                    "{source}: included file {pattern} was not provided"
+                ));
+            }
+            included.sort_by(|a, b| a.path.cmp(&b.path));
+            for file in included {
+                collect_lines(&file.path, &file.content, files, depth + 1, lines, warnings);
+            }
+        }
+    }
+}
+
+/// 拆分为小写的关键字和参数，支持 `Keyword=value` 和双引号参数
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let split = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let (keyword, rest) = line.split_at(split);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut in_arg = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Some((keyword.to_lowercase(), args))
+}
+
+/// OpenSSH Host 模式列表：任一模式匹配且没有取反模式匹配
+fn host_matches(patterns: &[String], host: &str) -> bool {
+    let host = host.to_lowercase();
+    let mut matched = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(&negated.to_lowercase(), &host) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(&pattern.to_lowercase(), &host),
+        }
+    }
+    matched
+}
+
+/// 相对路径和 `~/.ssh/` 开头的路径都视为 `~/.ssh` 下的文件
+fn path_matches(pattern: &str, path: &str) -> bool {
+    fn normalize(path: &str) -> &str {
+        path.strip_prefix("~/.ssh/").unwrap_or(path)
+    }
+    wildcard_match(normalize(pattern), normalize(path))
+}
+
+pub(crate) fn find_file<'a>(files: &'a [SshConfigFile], path: &str) -> Option<&'a SshConfigFile> {
+    files.iter().find(|file| path_matches(path, &file.path))
+}
+
+/// `[user@]host[:port]` 形式的 ProxyJump 跳板
+pub fn parse_jump(jump: &str) -> (Option<&str>, &str, Option<u16>) {
+    let (user, host) = match jump.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, jump),
+    };
+    if let Some(bracketed) = host.strip_prefix('[')
+        && let Some((host, port)) = bracketed.split_once(']')
+    {
+        return (
+            user,
+            host,
+            port.strip_prefix(':').and_then(|port| port.parse().ok()),
+        );
+    }
+    match host.split_once(':') {
+        Some((host, port)) if !port.contains(':') => (user, host, port.parse().ok()),
+        _ => (user, host, None),
+    }
+}
+
+/// 按 ssh_config 格式输出目标，`alias` 返回目标 ID 对应的 Host 名称，
+/// `key_name` 返回密钥库中密钥的名称。私钥不会导出，只以注释注明
+pub fn format(
+    targets: &[target::Model],
+    alias: impl Fn(i32) -> String,
+    key_name: impl Fn(i32) -> Option<String>,
+) -> String {
+    let mut output = String::new();
+    for target in targets {
+        output.push_str(&format!(
+            "Host {}\n    HostName {}\n",
+            alias(target.id),
+            target.host
+        ));
+        if let Some(port) = target.port.filter(|port| *port != 22) {
+            output.push_str(&format!("    Port {port}\n"));
+        }
+        output.push_str(&format!("    User {}\n", target.user));
+        let proxy_command = target
+            .proxy_command
+            .as_deref()
+            .map(str::trim)
+            .filter(|command| !command.is_empty());
+        if let Some(jump_target_id) = target.jump_target_id {
+            output.push_str(&format!("    ProxyJump {}\n", alias(jump_target_id)));
+        } else if let Some(proxy_command) = proxy_command {
+            output.push_str(&format!("    ProxyCommand {proxy_command}\n"));
+        }
+        if let Some(key_id) = target.key_id {
+            let name = key_name(key_id).unwrap_or_else(|| format!("#{key_id}"));
+            output.push_str(&format!(
+                "    # IdentityFile: key store key {name} is not exported\n"
+            ));
+        } else if target.key.is_some() {
+            output.push_str("    # IdentityFile: the private key of the target is not exported\n");
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str) -> SshConfigFile {
+        SshConfigFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn wildcard_blocks_fill_in_values_not_set_earlier() {
+        let content = "\
+User fallback
+Host web1 web2 !web3
+    HostName %h.example.com
+Host db
+    HostName=10.0.0.5
+    Port 2222
+    ProxyJump bastion
+    IdentityFile \"~/.ssh/id db\"
+Include conf.d/*
+Host *
+    User admin
+    Port 22
+    IdentityFile ~/.ssh/id_ed25519
+Match host db
+    User ignored
+";
+        let files = [
+            file(
+                "~/.ssh/conf.d/b",
+                "Host bastion\n  HostName bastion.example.com\n",
+            ),
+            file("conf.d/a", "Host web*\n  User deploy\n"),
+        ];
+        let (hosts, warnings) = parse(content, &files);
+
+        assert_eq!(
+            hosts,
+            vec![
+                SshConfigHost {
+                    alias: "web1".to_string(),
+                    host_name: Some("%h.example.com".to_string()),
+                    port: Some(22),
+                    user: Some("fallback".to_string()),
+                    identity_file: Some("~/.ssh/id_ed25519".to_string()),
+                    proxy_jump: None,
+                },
+                SshConfigHost {
+                    alias: "web2".to_string(),
+                    host_name: Some("%h.example.com".to_string()),
+                    port: Some(22),
+                    user: Some("fallback".to_string()),
+                    identity_file: Some("~/.ssh/id_ed25519".to_string()),
+                    proxy_jump: None,
+                },
+                SshConfigHost {
+                    alias: "db".to_string(),
+                    host_name: Some("10.0.0.5".to_string()),
+                    port: Some(2222),
+                    user: Some("fallback".to_string()),
+                    identity_file: Some("~/.ssh/id db".to_string()),
+                    proxy_jump: Some("bastion".to_string()),
+                },
+                SshConfigHost {
+                    alias: "bastion".to_string(),
+                    host_name: Some("bastion.example.com".to_string()),
+                    port: Some(22),
+                    user: Some("fallback".to_string()),
+                    identity_file: Some("~/.ssh/id_ed25519".to_string()),
+                    proxy_jump: None,
+                },
+            ]
+        );
+        assert_eq!(hosts[0].host(), "web1.example.com");
+        assert_eq!(warnings, vec!["config:14: Match blocks are not supported"]);
+    }
+
+    #[test]
+    fn negated_patterns_and_missing_includes() {
+        let (hosts, warnings) = parse(
+            "Host *.example.com !skip.example.com\n  User deploy\nHost a.example.com skip.example.com\nInclude missing\n",
+            &[],
+        );
+        assert_eq!(hosts[0].user.as_deref(), Some("deploy"));
+        assert_eq!(hosts[1].user, None);
+        assert_eq!(
+            warnings,
+            vec!["config:4: included file missing was not provided"]
+        );
+    }
+
+    #[test]
+    fn jump_hosts_are_split_into_user_host_and_port() {
+        assert_eq!(parse_jump("bastion"), (None, "bastion", None));
+        assert_eq!(
+            parse_jump("ops@bastion:2222"),
+            (Some("ops"), "bastion", Some(2222))
+        );
+        assert_eq!(parse_jump("[::1]:22"), (None, "::1", Some(22)));
+    }
+
+    #[test]
+    fn formatted_targets_parse_back() {
+        let target = |id, host: &str, port, jump_target_id| target::Model {
+            id,
+            host: host.to_string(),
+            port,
+            method: target::TargetAuthMethod::Agent,
+            user: "deploy".to_string(),
+            key: None,
+            key_id: None,
+            certificate: None,
+            password: None,
+            system: None,
+            jump_target_id,
+            proxy: None,
+            proxy_command: None,
+            algorithms: None,
+            connect_timeout: None,
+            connect_retries: None,
+            min_idle_connections: None,
+            host_key_policy: None,
+            group_id: None,
+            tags: None,
+        };
+        let mut targets = [
+            target(1, "bastion.example.com", None, None),
+            target(2, "10.0.0.5", Some(2222), Some(1)),
+            target(3, "app.example.com", None, None),
+        ];
+        targets[0].key_id = Some(7);
+        targets[2].proxy_command = Some("nc -X 5 -x proxy:1080 %h %p".to_string());
+        targets[2].key = Some("private key".to_string());
+        let content = format(
+            &targets,
+            |id| format!("target-{id}"),
+            |key_id| (key_id == 7).then(|| "deploy".to_string()),
+        );
+        assert!(
+            content.contains("    # IdentityFile: key store key deploy is not exported\n"),
+            "{content}"
+        );
+        assert!(
+            content.contains(
+                "    ProxyCommand nc -X 5 -x proxy:1080 %h %p\n    # IdentityFile: the private key of the target is not exported\n"
+            ),
+            "{content}"
+        );
+        let (hosts, warnings) = parse(&content, &[]);
+        assert!(warnings.is_empty());
+        assert_eq!(hosts[1].alias, "target-2");
+        assert_eq!(hosts[1].host(), "10.0.0.5");
+        assert_eq!(hosts[1].port, Some(2222));
+        assert_eq!(hosts[1].user.as_deref(), Some("deploy"));
+        assert_eq!(hosts[1].proxy_jump.as_deref(), Some("target-1"));
+    }
+}
@@ -0,0 +1,25 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct TargetGroupAddPayload {
+    /// 分组名称
+    pub name: String,
+    /// 上级分组 ID，为空表示顶级分组
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct TargetGroupUpdatePayload {
+    pub id: i32,
+    /// 分组名称
+    pub name: String,
+    /// 上级分组 ID，不能是该分组自身或其子分组
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct TargetGroupRemovePayload {
+    /// 要删除的分组 ID，其中的目标和子分组移动到上级分组
+    pub id: i32,
+}
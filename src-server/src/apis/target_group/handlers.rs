@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State};
+
+use crate::{
+    AppState,
+    apis::{
+        ApiErr, InternalErrorResponse, ValidJson,
+        target_group::{
+            dto::{TargetGroupAddPayload, TargetGroupRemovePayload, TargetGroupUpdatePayload},
+            service,
+        },
+    },
+    entities::target_group,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/target_group/list",
+    tag = "target_group",
+    summary = "获取目标分组列表",
+    operation_id = "target_group_list",
+    responses(
+        (status = 200, description = "成功获取分组列表", body = [target_group::Model]),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_group_list(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<target_group::Model>>, ApiErr> {
+    Ok(Json(service::list(&state.db).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target_group/add",
+    tag = "target_group",
+    summary = "添加目标分组",
+    operation_id = "target_group_add",
+    request_body = TargetGroupAddPayload,
+    responses(
+        (status = 200, description = "成功添加分组", body = target_group::Model),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_group_add(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<TargetGroupAddPayload>,
+) -> Result<Json<target_group::Model>, ApiErr> {
+    Ok(Json(service::add(&state.db, payload).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target_group/update",
+    tag = "target_group",
+    summary = "重命名或移动目标分组",
+    operation_id = "target_group_update",
+    request_body = TargetGroupUpdatePayload,
+    responses(
+        (status = 200, description = "成功更新分组", body = target_group::Model),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_group_update(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<TargetGroupUpdatePayload>,
+) -> Result<Json<target_group::Model>, ApiErr> {
+    Ok(Json(service::update(&state.db, payload).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/target_group/remove",
+    tag = "target_group",
+    summary = "删除目标分组",
+    operation_id = "target_group_remove",
+    request_body = TargetGroupRemovePayload,
+    responses(
+        (status = 200, description = "成功删除分组，其中的目标和子分组移动到上级分组"),
+        (status = 500, response = InternalErrorResponse)
+    )
+)]
+pub async fn target_group_remove(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<TargetGroupRemovePayload>,
+) -> Result<(), ApiErr> {
+    service::remove(&state.db, payload.id).await
+}
@@ -0,0 +1,22 @@
+pub mod dto;
+pub mod handlers;
+mod service;
+
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::AppState;
+
+pub(crate) fn router_builder(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/list", get(handlers::target_group_list))
+        .route("/add", post(handlers::target_group_add))
+        .route("/update", post(handlers::target_group_update))
+        .route("/remove", post(handlers::target_group_remove))
+        .fallback(|| async { "not supported" })
+        .with_state(app_state)
+}
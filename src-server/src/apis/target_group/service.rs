@@ -0,0 +1,191 @@
+use sea_orm::{
+    ActiveValue::{NotSet, Set},
+    DatabaseConnection,
+};
+
+use crate::{
+    apis::{
+        ApiErr,
+        target_group::dto::{TargetGroupAddPayload, TargetGroupUpdatePayload},
+    },
+    consts::services_err_code::{ERR_CODE_DB_ERR, ERR_CODE_TARGET_GROUP_INVALID_REQUEST},
+    entities::target_group,
+    map_db_err,
+    repositories::{target as target_repository, target_group as target_group_repository},
+};
+
+pub async fn list(db: &DatabaseConnection) -> Result<Vec<target_group::Model>, ApiErr> {
+    Ok(map_db_err!(target_group_repository::list(db).await)?)
+}
+
+pub async fn add(
+    db: &DatabaseConnection,
+    payload: TargetGroupAddPayload,
+) -> Result<target_group::Model, ApiErr> {
+    let name = validate_name(&payload.name)?;
+    validate_parent(db, None, payload.parent_id).await?;
+    Ok(map_db_err!(
+        target_group_repository::insert(
+            db,
+            target_group::ActiveModel {
+                id: NotSet,
+                name: Set(name),
+                parent_id: Set(payload.parent_id),
+            },
+        )
+        .await
+    )?)
+}
+
+pub async fn update(
+    db: &DatabaseConnection,
+    payload: TargetGroupUpdatePayload,
+) -> Result<target_group::Model, ApiErr> {
+    let name = validate_name(&payload.name)?;
+    if map_db_err!(target_group_repository::find_by_id(db, payload.id).await)?.is_none() {
+        return Err(invalid_request(format!("group {} not found", payload.id)));
+    }
+    validate_parent(db, Some(payload.id), payload.parent_id).await?;
+    Ok(map_db_err!(
+        target_group_repository::update(
+            db,
+            target_group::ActiveModel {
+                id: Set(payload.id),
+                name: Set(name),
+                parent_id: Set(payload.parent_id),
+            },
+        )
+        .await
+    )?)
+}
+
+pub async fn remove(db: &DatabaseConnection, id: i32) -> Result<(), ApiErr> {
+    map_db_err!(target_repository::delete_group(db, id).await)?;
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<String, ApiErr> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(invalid_request("group name must not be empty".to_string()));
+    }
+    Ok(name.to_string())
+}
+
+/// The parent must exist and, when moving `group_id`, must not be the group
+/// itself or one of its nested groups.
+async fn validate_parent(
+    db: &DatabaseConnection,
+    group_id: Option<i32>,
+    parent_id: Option<i32>,
+) -> Result<(), ApiErr> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+    if map_db_err!(target_group_repository::find_by_id(db, parent_id).await)?.is_none() {
+        return Err(invalid_request(format!("group {parent_id} not found")));
+    }
+    if let Some(group_id) = group_id
+        && map_db_err!(target_group_repository::with_descendants(db, group_id).await)?
+            .contains(&parent_id)
+    {
+        return Err(invalid_request(
+            "a group cannot be moved into itself or its nested groups".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn invalid_request(message: String) -> ApiErr {
+    ApiErr {
+        code: ERR_CODE_TARGET_GROUP_INVALID_REQUEST,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Database;
+    use sea_orm_migration::MigratorTrait;
+
+    use super::*;
+    use crate::{entities::target, migrations::Migrator, secrets::Secrets};
+
+    fn add_payload(name: &str, parent_id: Option<i32>) -> TargetGroupAddPayload {
+        TargetGroupAddPayload {
+            name: name.to_string(),
+            parent_id,
+        }
+    }
+
+    #[tokio::test]
+    async fn removing_a_group_moves_its_targets_and_subgroups_up() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let prod = add(&db, add_payload("prod", None)).await.unwrap();
+        let web = add(&db, add_payload(" web ", Some(prod.id))).await.unwrap();
+        let edge = add(&db, add_payload("edge", Some(web.id))).await.unwrap();
+        assert_eq!(web.name, "web");
+        assert_eq!(
+            add(&db, add_payload(" ", None)).await.unwrap_err().code,
+            ERR_CODE_TARGET_GROUP_INVALID_REQUEST
+        );
+
+        let err = update(
+            &db,
+            TargetGroupUpdatePayload {
+                id: prod.id,
+                name: "prod".to_string(),
+                parent_id: Some(edge.id),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, ERR_CODE_TARGET_GROUP_INVALID_REQUEST);
+
+        let target = target_repository::insert(
+            &db,
+            &Secrets::default(),
+            target::Model {
+                id: 0,
+                host: "web1".to_string(),
+                port: None,
+                method: target::TargetAuthMethod::Agent,
+                user: "deploy".to_string(),
+                key: None,
+                key_id: None,
+                certificate: None,
+                password: None,
+                system: None,
+                jump_target_id: None,
+                proxy: None,
+                proxy_command: None,
+                algorithms: None,
+                connect_timeout: None,
+                connect_retries: None,
+                min_idle_connections: None,
+                host_key_policy: None,
+                group_id: Some(web.id),
+                tags: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        remove(&db, web.id).await.unwrap();
+
+        let target = target_repository::find_by_id(&db, &Secrets::default(), target.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.group_id, Some(prod.id));
+        let groups = list(&db).await.unwrap();
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| (group.name.as_str(), group.parent_id))
+                .collect::<Vec<_>>(),
+            vec![("edge", Some(prod.id)), ("prod", None)]
+        );
+    }
+}
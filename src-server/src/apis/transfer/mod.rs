@@ -1,6 +1,7 @@
 pub mod dto;
 pub mod handlers;
 mod ranges;
+mod retry;
 mod runner;
 mod service;
 
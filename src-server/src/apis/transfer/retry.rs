@@ -0,0 +1,140 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    apis::ApiErr,
+    consts::services_err_code::ERR_CODE_SSH_ERR,
+    sftp_client::SftpError,
+    ssh_connection_pool::{SshPoolError, is_transient_io_error, is_transient_ssh_error},
+};
+
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+const ABORT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Failure of one transfer attempt, remembering whether the transfer may
+/// succeed when retried on a new SFTP session.
+pub(super) struct AttemptError {
+    pub(super) err: ApiErr,
+    pub(super) transient: bool,
+}
+
+impl From<ApiErr> for AttemptError {
+    fn from(err: ApiErr) -> Self {
+        Self {
+            err,
+            transient: false,
+        }
+    }
+}
+
+impl AttemptError {
+    pub(super) fn ssh(err: anyhow::Error) -> Self {
+        Self {
+            transient: is_transient(&err),
+            err: ApiErr {
+                code: ERR_CODE_SSH_ERR,
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<SftpError>() {
+            err.is_transient()
+        } else if let Some(err) = cause.downcast_ref::<SshPoolError>() {
+            err.is_transient()
+        } else if let Some(err) = cause.downcast_ref::<russh::Error>() {
+            is_transient_ssh_error(err)
+        } else if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            is_transient_io_error(err)
+        } else {
+            false
+        }
+    })
+}
+
+/// Delay before retrying after failed attempt number `attempt`, doubling
+/// from one second up to thirty seconds.
+pub(super) fn retry_delay(attempt: u32) -> Duration {
+    let factor = 1u32 << attempt.saturating_sub(1).min(16);
+    RETRY_BASE_DELAY.saturating_mul(factor).min(RETRY_MAX_DELAY)
+}
+
+/// Waits out the retry `delay`, checking `abort` every 100ms so that pausing
+/// or cancelling a task does not have to wait for the whole backoff.
+/// Returns `true` when the wait ended because the task was aborted.
+pub(super) async fn wait_retry_delay(delay: Duration, abort: &AtomicBool) -> bool {
+    let deadline = tokio::time::Instant::now() + delay;
+    loop {
+        if abort.load(Ordering::Acquire) {
+            return true;
+        }
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        tokio::time::sleep((deadline - now).min(ABORT_POLL_INTERVAL)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn connection_failures_are_transient() {
+        let lost: anyhow::Result<()> = Err(SftpError::ResponseChannelClosed.into());
+        assert!(AttemptError::ssh(lost.context("upload failed").unwrap_err()).transient);
+        let expired = anyhow::Error::new(SshPoolError::ConnectionExpired {
+            connection_id: "c1".to_string(),
+        });
+        assert!(AttemptError::ssh(expired).transient);
+        let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert!(AttemptError::ssh(SftpError::from(reset).into()).transient);
+
+        let denied = SftpError::Status {
+            code: 3,
+            message: "permission denied".to_string(),
+        };
+        assert!(!AttemptError::ssh(denied.into()).transient);
+        assert!(!AttemptError::ssh(SshPoolError::AuthenticationFailed.into()).transient);
+        assert!(!AttemptError::ssh(SftpError::Aborted.into()).transient);
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+        assert_eq!(retry_delay(3), Duration::from_secs(4));
+        assert_eq!(retry_delay(6), Duration::from_secs(30));
+        assert_eq!(retry_delay(u32::MAX), Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn abort_interrupts_the_retry_delay() {
+        let abort = Arc::new(AtomicBool::new(false));
+        assert!(!wait_retry_delay(Duration::from_millis(10), &abort).await);
+
+        let setter = abort.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            setter.store(true, Ordering::Release);
+        });
+        let aborted = tokio::time::timeout(
+            Duration::from_secs(2),
+            wait_retry_delay(RETRY_MAX_DELAY, &abort),
+        )
+        .await
+        .expect("the backoff must end soon after the abort");
+        assert!(aborted);
+    }
+}
@@ -3,12 +3,18 @@ use std::{
     sync::{Arc, atomic::AtomicBool},
 };
 
-use tokio::sync::Mutex;
+use sha2::{Digest, Sha256};
+use tokio::{io::AsyncReadExt, sync::Mutex};
 
 use crate::{
-    apis::{ApiErr, sftp::parse_file_uri},
-    consts::services_err_code::ERR_CODE_SSH_ERR,
-    map_ssh_err,
+    apis::{
+        ApiErr,
+        sftp::parse_file_uri,
+        ssh::{exec, shell_quote},
+        target::has_tool,
+    },
+    consts::services_err_code::{ERR_CODE_DB_ERR, ERR_CODE_SSH_ERR},
+    map_db_err, map_ssh_err,
     sftp_client::{
         download::{DownloadOptions, run_download},
         transfer::{
@@ -17,16 +23,21 @@ use crate::{
         },
         upload::{UploadOptions, run_upload},
     },
-    ssh_connection_pool::ChannelMode,
+    ssh_connection_pool::{ChannelMode, ChannelPriority},
 };
 
 use super::{
     ranges::{invalid_task, ranges_from_json},
-    service::TransferService,
+    retry::AttemptError,
+    service::{TransferService, map_transfer_io_err},
 };
 
 impl TransferService {
-    pub(super) async fn run_upload(&self, id: &str, abort: Arc<AtomicBool>) -> Result<(), ApiErr> {
+    pub(super) async fn run_upload(
+        &self,
+        id: &str,
+        abort: Arc<AtomicBool>,
+    ) -> Result<(), AttemptError> {
         let task = self.get_task_model(id).await?;
         let local_path = task
             .local_path
@@ -40,14 +51,18 @@ impl TransferService {
         let ranges = ranges_from_json(&task.ranges)?;
         let truncate = ranges.len() == 1 && ranges[0] == [0, task.total - 1];
 
-        let sftp = map_ssh_err!(
-            self.connection_pool
-                .sftp(uri.target_id, ChannelMode::Dedicated)
-                .await
-        )?;
+        let sftp = self
+            .connection_pool
+            .sftp(
+                uri.target_id,
+                ChannelMode::Dedicated,
+                ChannelPriority::Transfer,
+            )
+            .await
+            .map_err(AttemptError::ssh)?;
         let mut options = UploadOptions::new(
             sftp.clone(),
-            PathBuf::from(local_path),
+            PathBuf::from(&local_path),
             uri.path.to_string(),
             task.total as u64,
             abort,
@@ -64,9 +79,12 @@ impl TransferService {
         sftp.shutdown().await;
 
         if let Err(err) = upload_result {
+            // A lost connection is retried instead, so only trust the size
+            // check when it could be made.
             if self
-                .remote_file_size_matches(uri.target_id, uri.path, task.total)
-                .await?
+                .remote_file_matches(uri.target_id, uri.path, &local_path, task.total)
+                .await
+                .unwrap_or(false)
             {
                 let task = self.get_task_model(id).await?;
                 let ranges = ranges_from_json(&task.ranges)?;
@@ -75,7 +93,7 @@ impl TransferService {
                 }
                 return Ok(());
             }
-            return Err(map_transfer_err(err));
+            return Err(AttemptError::ssh(err));
         }
 
         Ok(())
@@ -85,7 +103,7 @@ impl TransferService {
         &self,
         id: &str,
         abort: Arc<AtomicBool>,
-    ) -> Result<(), ApiErr> {
+    ) -> Result<(), AttemptError> {
         let task = self.get_task_model(id).await?;
         let target_uri = task
             .target_uri
@@ -98,11 +116,15 @@ impl TransferService {
         let uri = parse_file_uri(&target_uri)?;
         let ranges = ranges_from_json(&task.ranges)?;
 
-        let sftp = map_ssh_err!(
-            self.connection_pool
-                .sftp(uri.target_id, ChannelMode::Dedicated)
-                .await
-        )?;
+        let sftp = self
+            .connection_pool
+            .sftp(
+                uri.target_id,
+                ChannelMode::Dedicated,
+                ChannelPriority::Transfer,
+            )
+            .await
+            .map_err(AttemptError::ssh)?;
         let mut options = DownloadOptions::new(
             sftp.clone(),
             uri.path.to_string(),
@@ -119,25 +141,70 @@ impl TransferService {
         let download_result = run_download(options).await;
         sftp.shutdown().await;
 
-        download_result.map_err(map_transfer_err)
+        download_result.map_err(AttemptError::ssh)
     }
 
-    async fn remote_file_size_matches(
+    /// Whether the remote file holds the whole upload. An upload sets the
+    /// size of the file before writing it, so where the target has
+    /// `sha256sum` the content is compared as well.
+    async fn remote_file_matches(
         &self,
         target_id: i32,
         remote_path: &str,
+        local_path: &str,
         total: i64,
     ) -> Result<bool, ApiErr> {
         let sftp = map_ssh_err!(
             self.connection_pool
-                .sftp(target_id, ChannelMode::Shared)
+                .sftp(target_id, ChannelMode::Shared, ChannelPriority::Transfer)
                 .await
         )?;
         let attr = map_ssh_err!(sftp.metadata(remote_path).await)?;
-        Ok(attr.size == Some(total as u64))
+        if attr.size != Some(total as u64) {
+            return Ok(false);
+        }
+        if !map_db_err!(has_tool(&self.db, target_id, "sha256sum").await)? {
+            return Ok(true);
+        }
+
+        let channel = map_ssh_err!(
+            self.connection_pool
+                .channel(target_id, ChannelMode::Shared, ChannelPriority::Exec)
+                .await
+        )?;
+        let output = exec(
+            channel,
+            &format!("sha256sum -- {}", shell_quote(remote_path)),
+        )
+        .await?;
+        let local_sha256 = file_sha256(local_path).await?;
+        Ok(sha256sum_digest(&output).is_some_and(|digest| digest == local_sha256))
     }
 }
 
+async fn file_sha256(path: &str) -> Result<String, ApiErr> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(map_transfer_io_err)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; DEFAULT_PIPELINE_CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut buffer).await.map_err(map_transfer_io_err)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Lowercase hex digest from the first line of `sha256sum` output.
+fn sha256sum_digest(output: &str) -> Option<String> {
+    let digest = output.split_whitespace().next()?.trim_start_matches('\\');
+    (digest.len() == 64 && digest.bytes().all(|byte| byte.is_ascii_hexdigit()))
+        .then(|| digest.to_ascii_lowercase())
+}
+
 fn transfer_progress(service: TransferService, id: String) -> TransferProgress {
     let progress_lock = Arc::new(Mutex::new(()));
     TransferProgress::new(move |range| {
@@ -154,9 +221,26 @@ fn transfer_progress(service: TransferService, id: String) -> TransferProgress {
     })
 }
 
-fn map_transfer_err(err: impl std::fmt::Display) -> ApiErr {
-    ApiErr {
-        code: ERR_CODE_SSH_ERR,
-        message: err.to_string(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256sum_output_is_parsed() {
+        let digest = "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855";
+        assert_eq!(
+            sha256sum_digest(&format!("{digest}  /tmp/empty\n")),
+            Some(digest.to_ascii_lowercase())
+        );
+        // GNU sha256sum escapes file names with a backslash prefix.
+        assert_eq!(
+            sha256sum_digest(&format!("\\{digest}  /tmp/a\\nb\n")),
+            Some(digest.to_ascii_lowercase())
+        );
+        assert_eq!(
+            sha256sum_digest("sha256sum: /tmp/missing: No such file\n"),
+            None
+        );
+        assert_eq!(sha256sum_digest(""), None);
     }
 }
@@ -35,7 +35,7 @@ use crate::{
     },
     map_db_err, map_ssh_err,
     sftp_client::SftpFileType,
-    ssh_connection_pool::{ChannelMode, SshConnectionPool},
+    ssh_connection_pool::{ChannelMode, ChannelPriority, SshConnectionPool},
 };
 
 use super::{
@@ -44,6 +44,7 @@ use super::{
         TransferRange, initial_ranges, ranges_from_json, ranges_size, ranges_to_json,
         subtract_range,
     },
+    retry::{retry_delay, wait_retry_delay},
 };
 
 #[derive(Clone)]
@@ -54,6 +55,7 @@ pub struct TransferService {
     scheduler_notify: Arc<Notify>,
     scheduler_started: Arc<AtomicBool>,
     max_concurrent_tasks: usize,
+    max_attempts: u32,
     pub(super) transfer_chunk_size: usize,
 }
 
@@ -80,6 +82,7 @@ impl TransferService {
             scheduler_notify: Arc::new(Notify::new()),
             scheduler_started: Arc::new(AtomicBool::new(false)),
             max_concurrent_tasks: app_state.config.transfer_task_concurrency,
+            max_attempts: app_state.config.transfer_max_attempts,
             transfer_chunk_size: app_state.config.transfer_chunk_size,
         };
         service
@@ -144,6 +147,7 @@ impl TransferService {
             estimated_time: Set(None),
             ranges: Set(ranges_to_json(&ranges)?),
             fail_reason: Set(None),
+            attempts: Set(0),
             created_at: Set(now),
             updated_at: Set(now),
             ended_at: Set(None),
@@ -192,7 +196,7 @@ impl TransferService {
 
         let sftp = map_ssh_err!(
             self.connection_pool
-                .sftp(target_id, ChannelMode::Shared)
+                .sftp(target_id, ChannelMode::Shared, ChannelPriority::Transfer)
                 .await
         )?;
         let attr = map_ssh_err!(sftp.metadata(source_path.as_str()).await)?;
@@ -222,6 +226,7 @@ impl TransferService {
             estimated_time: Set(None),
             ranges: Set(ranges_to_json(&ranges)?),
             fail_reason: Set(None),
+            attempts: Set(0),
             created_at: Set(now),
             updated_at: Set(now),
             ended_at: Set(None),
@@ -347,6 +352,7 @@ impl TransferService {
         let mut active: ActiveModel = task.into();
         active.status = Set(TransferTaskStatus::Wait);
         active.fail_reason = Set(None);
+        active.attempts = Set(0);
         active.ended_at = Set(None);
         active.updated_at = Set(now_ms());
         map_db_err!(active.update(&self.db).await)?;
@@ -513,9 +519,32 @@ impl TransferService {
             return Ok(());
         }
 
-        match task.r#type {
-            TransferTaskType::Upload => self.run_upload(id, abort).await?,
-            TransferTaskType::Download => self.run_download(id, abort).await?,
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.set_attempts(id, attempt).await?;
+            let result = match task.r#type {
+                TransferTaskType::Upload => self.run_upload(id, abort.clone()).await,
+                TransferTaskType::Download => self.run_download(id, abort.clone()).await,
+            };
+            let err = match result {
+                Ok(()) => break,
+                Err(err) => err,
+            };
+            if !err.transient || attempt >= self.max_attempts || abort.load(Ordering::Acquire) {
+                return Err(err.err);
+            }
+            let delay = retry_delay(attempt);
+            tracing::warn!(
+                task_id = id,
+                attempt,
+                ?delay,
+                "transfer attempt failed, retrying: {}",
+                err.err.message
+            );
+            if wait_retry_delay(delay, &abort).await {
+                return Ok(());
+            }
         }
 
         let task = self.get_task_model(id).await?;
@@ -536,6 +565,15 @@ impl TransferService {
         Ok(())
     }
 
+    async fn set_attempts(&self, id: &str, attempts: u32) -> Result<(), ApiErr> {
+        let task = self.get_task_model(id).await?;
+        let mut active: ActiveModel = task.into();
+        active.attempts = Set(attempts as i32);
+        active.updated_at = Set(now_ms());
+        map_db_err!(active.update(&self.db).await)?;
+        Ok(())
+    }
+
     pub(super) async fn mark_range_done(
         &self,
         id: &str,
@@ -1,11 +1,18 @@
+use std::{path::PathBuf, time::Duration};
+
 use anyhow::Result;
 
+use crate::{secrets::MasterKey, ssh_connection_pool::SshProxy};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CheckServerKey {
     /// Accept unknown host keys and remember them, but reject changed keys.
     AcceptNew,
     /// Only accept host keys that already exist in ssh_known_host.
     Strict,
+    /// Ask the client that triggered the connection to confirm unknown host
+    /// keys and remember the confirmed ones. Changed keys are rejected.
+    Ask,
     /// Skip host key checking and do not write observed keys.
     Disabled,
 }
@@ -15,6 +22,7 @@ impl CheckServerKey {
         match value.trim().to_lowercase().as_str() {
             "accept-new" | "accept_new" | "acceptnew" => Some(Self::AcceptNew),
             "strict" => Some(Self::Strict),
+            "ask" | "prompt" => Some(Self::Ask),
             "disabled" | "disable" | "off" | "false" | "no" => Some(Self::Disabled),
             _ => None,
         }
@@ -26,7 +34,29 @@ pub struct Config {
     pub max_channels_per_connection: u8,
     pub transfer_task_concurrency: usize,
     pub transfer_chunk_size: usize,
+    /// Attempts a transfer task makes before failing on connection loss.
+    pub transfer_max_attempts: u32,
     pub check_server_key: CheckServerKey,
+    /// ssh-agent socket used by targets that authenticate through the agent.
+    pub ssh_auth_sock: Option<PathBuf>,
+    /// Idle time after which a `keepalive@openssh.com` probe is sent; `None`
+    /// disables keepalives.
+    pub ssh_keepalive_interval: Option<Duration>,
+    /// Unanswered keepalive probes after which a connection is closed.
+    pub ssh_keepalive_max: usize,
+    /// Time a pooled connection may stay without channels before it is
+    /// closed; `None` keeps idle connections open.
+    pub ssh_idle_timeout: Option<Duration>,
+    /// Default HTTP CONNECT or SOCKS5 proxy for targets without their own.
+    pub ssh_proxy: Option<SshProxy>,
+    /// Keeps the last free channel of each target for terminal sessions.
+    pub ssh_reserve_interactive_channel: bool,
+    /// Lets targets use a `ProxyCommand`, which runs a shell command on
+    /// this server with the server's privileges.
+    pub ssh_allow_proxy_command: bool,
+    /// Master key that encrypts the stored target secrets; `None` keeps
+    /// them in plaintext.
+    pub master_key: Option<MasterKey>,
 }
 
 impl Default for Config {
@@ -36,7 +66,16 @@ impl Default for Config {
             max_channels_per_connection: 10,
             transfer_task_concurrency: 3,
             transfer_chunk_size: 10 * 1024 * 1024,
+            transfer_max_attempts: 5,
             check_server_key: CheckServerKey::AcceptNew,
+            ssh_auth_sock: None,
+            ssh_keepalive_interval: Some(Duration::from_secs(30)),
+            ssh_keepalive_max: 3,
+            ssh_idle_timeout: Some(Duration::from_secs(10 * 60)),
+            ssh_proxy: None,
+            ssh_reserve_interactive_channel: false,
+            ssh_allow_proxy_command: false,
+            master_key: None,
         }
     }
 }
@@ -48,7 +87,7 @@ impl Config {
             config.check_server_key =
                 CheckServerKey::from_env_value(value.as_str()).ok_or_else(|| {
                     anyhow::anyhow!(
-                        "invalid WEBSSH_RS_CHECK_SERVER_KEY value: {value}; expected accept-new, strict, or disabled"
+                        "invalid WEBSSH_RS_CHECK_SERVER_KEY value: {value}; expected accept-new, strict, ask, or disabled"
                     )
                 })?;
         }
@@ -59,6 +98,52 @@ impl Config {
         if let Ok(value) = std::env::var("WEBSSH_RS_TRANSFER_CHUNK_SIZE") {
             config.transfer_chunk_size = Config::parse_transfer_chunk_size(value.as_str())?;
         }
+        if let Ok(value) = std::env::var("WEBSSH_RS_TRANSFER_MAX_ATTEMPTS") {
+            config.transfer_max_attempts = Config::parse_transfer_max_attempts(value.as_str())?;
+        }
+        config.ssh_auth_sock = std::env::var("WEBSSH_RS_SSH_AUTH_SOCK")
+            .ok()
+            .and_then(|value| Config::parse_ssh_auth_sock(value.as_str()))
+            .or_else(|| {
+                std::env::var("SSH_AUTH_SOCK")
+                    .ok()
+                    .and_then(|value| Config::parse_ssh_auth_sock(value.as_str()))
+            });
+        if let Ok(value) = std::env::var("WEBSSH_RS_SSH_KEEPALIVE_INTERVAL") {
+            config.ssh_keepalive_interval =
+                Config::parse_optional_secs("WEBSSH_RS_SSH_KEEPALIVE_INTERVAL", value.as_str())?;
+        }
+        if let Ok(value) = std::env::var("WEBSSH_RS_SSH_KEEPALIVE_MAX") {
+            config.ssh_keepalive_max = Config::parse_ssh_keepalive_max(value.as_str())?;
+        }
+        if let Ok(value) = std::env::var("WEBSSH_RS_SSH_IDLE_TIMEOUT") {
+            config.ssh_idle_timeout =
+                Config::parse_optional_secs("WEBSSH_RS_SSH_IDLE_TIMEOUT", value.as_str())?;
+        }
+
+        if let Ok(value) = std::env::var("WEBSSH_RS_SSH_PROXY")
+            && !value.trim().is_empty()
+        {
+            config.ssh_proxy = Some(
+                SshProxy::parse(value.as_str())
+                    .map_err(|err| anyhow::anyhow!("invalid WEBSSH_RS_SSH_PROXY value: {err}"))?,
+            );
+        }
+
+        if let Ok(value) = std::env::var("WEBSSH_RS_SSH_RESERVE_INTERACTIVE_CHANNEL") {
+            config.ssh_reserve_interactive_channel =
+                Config::parse_bool("WEBSSH_RS_SSH_RESERVE_INTERACTIVE_CHANNEL", value.as_str())?;
+        }
+
+        if let Ok(value) = std::env::var("WEBSSH_RS_ALLOW_PROXY_COMMAND") {
+            config.ssh_allow_proxy_command =
+                Config::parse_bool("WEBSSH_RS_ALLOW_PROXY_COMMAND", value.as_str())?;
+        }
+
+        config.master_key = Config::parse_master_key(
+            std::env::var("WEBSSH_RS_MASTER_PASSPHRASE").ok(),
+            std::env::var("WEBSSH_RS_MASTER_KEY_FILE").ok(),
+        )?;
 
         Ok(config)
     }
@@ -86,6 +171,75 @@ impl Config {
         }
         Ok(chunk_size)
     }
+
+    fn parse_transfer_max_attempts(value: &str) -> Result<u32> {
+        let max_attempts = value.parse::<u32>().map_err(|err| {
+            anyhow::anyhow!("invalid WEBSSH_RS_TRANSFER_MAX_ATTEMPTS value: {value}: {err}")
+        })?;
+        if max_attempts == 0 {
+            return Err(anyhow::anyhow!(
+                "invalid WEBSSH_RS_TRANSFER_MAX_ATTEMPTS value: {value}; expected positive integer"
+            ));
+        }
+        Ok(max_attempts)
+    }
+
+    /// Parses a number of seconds where `0` turns the feature off.
+    fn parse_optional_secs(name: &str, value: &str) -> Result<Option<Duration>> {
+        let secs = value
+            .parse::<u64>()
+            .map_err(|err| anyhow::anyhow!("invalid {name} value: {value}: {err}"))?;
+        Ok((secs > 0).then(|| Duration::from_secs(secs)))
+    }
+
+    fn parse_bool(name: &str, value: &str) -> Result<bool> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(true),
+            "0" | "false" | "no" | "off" => Ok(false),
+            _ => Err(anyhow::anyhow!(
+                "invalid {name} value: {value}; expected true or false"
+            )),
+        }
+    }
+
+    fn parse_ssh_keepalive_max(value: &str) -> Result<usize> {
+        let max = value.parse::<usize>().map_err(|err| {
+            anyhow::anyhow!("invalid WEBSSH_RS_SSH_KEEPALIVE_MAX value: {value}: {err}")
+        })?;
+        if max == 0 {
+            return Err(anyhow::anyhow!(
+                "invalid WEBSSH_RS_SSH_KEEPALIVE_MAX value: {value}; expected positive integer"
+            ));
+        }
+        Ok(max)
+    }
+
+    fn parse_ssh_auth_sock(value: &str) -> Option<PathBuf> {
+        let value = value.trim();
+        if value.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(value))
+        }
+    }
+
+    fn parse_master_key(
+        passphrase: Option<String>,
+        key_file: Option<String>,
+    ) -> Result<Option<MasterKey>> {
+        let passphrase = passphrase.filter(|value| !value.is_empty());
+        let key_file = key_file
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map(|value| MasterKey::KeyFile(PathBuf::from(value)));
+        match (passphrase, key_file) {
+            (Some(_), Some(_)) => anyhow::bail!(
+                "set only one of WEBSSH_RS_MASTER_PASSPHRASE and WEBSSH_RS_MASTER_KEY_FILE"
+            ),
+            (Some(passphrase), None) => Ok(Some(MasterKey::Passphrase(passphrase))),
+            (None, key_file) => Ok(key_file),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -102,6 +256,10 @@ mod tests {
             CheckServerKey::from_env_value("STRICT"),
             Some(CheckServerKey::Strict)
         );
+        assert_eq!(
+            CheckServerKey::from_env_value("ask"),
+            Some(CheckServerKey::Ask)
+        );
         assert_eq!(
             CheckServerKey::from_env_value("off"),
             Some(CheckServerKey::Disabled)
@@ -127,4 +285,65 @@ mod tests {
         assert!(Config::parse_transfer_chunk_size("0").is_err());
         assert!(Config::parse_transfer_chunk_size("abc").is_err());
     }
+
+    #[test]
+    fn parse_transfer_max_attempts() {
+        assert_eq!(Config::parse_transfer_max_attempts("1").unwrap(), 1);
+        assert_eq!(Config::parse_transfer_max_attempts("5").unwrap(), 5);
+        assert!(Config::parse_transfer_max_attempts("0").is_err());
+        assert!(Config::parse_transfer_max_attempts("abc").is_err());
+    }
+
+    #[test]
+    fn parse_bool() {
+        assert!(Config::parse_bool("NAME", "true").unwrap());
+        assert!(Config::parse_bool("NAME", " ON ").unwrap());
+        assert!(!Config::parse_bool("NAME", "0").unwrap());
+        assert!(Config::parse_bool("NAME", "maybe").is_err());
+    }
+
+    #[test]
+    fn parse_master_key() {
+        assert_eq!(
+            Config::parse_master_key(None, Some(" ".into())).unwrap(),
+            None
+        );
+        assert_eq!(
+            Config::parse_master_key(Some("secret".into()), None).unwrap(),
+            Some(MasterKey::Passphrase("secret".to_string()))
+        );
+        assert_eq!(
+            Config::parse_master_key(None, Some("/etc/webssh-rs/master.key".into())).unwrap(),
+            Some(MasterKey::KeyFile(PathBuf::from(
+                "/etc/webssh-rs/master.key"
+            )))
+        );
+        assert!(Config::parse_master_key(Some("secret".into()), Some("key".into())).is_err());
+    }
+
+    #[test]
+    fn parse_ssh_auth_sock() {
+        assert_eq!(
+            Config::parse_ssh_auth_sock("/tmp/ssh-agent.sock"),
+            Some(PathBuf::from("/tmp/ssh-agent.sock"))
+        );
+        assert_eq!(Config::parse_ssh_auth_sock("  "), None);
+        assert_eq!(Config::parse_ssh_auth_sock(""), None);
+    }
+
+    #[test]
+    fn parse_ssh_keepalive_and_idle_timeout() {
+        assert_eq!(
+            Config::parse_optional_secs("WEBSSH_RS_SSH_IDLE_TIMEOUT", "600").unwrap(),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(
+            Config::parse_optional_secs("WEBSSH_RS_SSH_IDLE_TIMEOUT", "0").unwrap(),
+            None
+        );
+        assert!(Config::parse_optional_secs("WEBSSH_RS_SSH_KEEPALIVE_INTERVAL", "-1").is_err());
+        assert_eq!(Config::parse_ssh_keepalive_max("3").unwrap(), 3);
+        assert!(Config::parse_ssh_keepalive_max("0").is_err());
+        assert!(Config::parse_ssh_keepalive_max("abc").is_err());
+    }
 }
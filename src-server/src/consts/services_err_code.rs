@@ -33,3 +33,21 @@ pub const ERR_CODE_TRANSFER_ERR: u32 = 4002;
 
 /// 收藏目录请求不合法
 pub const ERR_CODE_FAVORITE_DIRECTORY_INVALID_REQUEST: u32 = 5000;
+
+/// SSH 目标请求不合法
+pub const ERR_CODE_TARGET_INVALID_REQUEST: u32 = 6000;
+
+/// SSH 目标操作失败
+pub const ERR_CODE_TARGET_ERR: u32 = 6001;
+
+/// 主机密钥请求不合法
+pub const ERR_CODE_KNOWN_HOST_INVALID_REQUEST: u32 = 7000;
+
+/// 密钥库请求不合法
+pub const ERR_CODE_KEY_STORE_INVALID_REQUEST: u32 = 8000;
+
+/// 密钥库操作失败
+pub const ERR_CODE_KEY_STORE_ERR: u32 = 8001;
+
+/// 目标分组请求不合法
+pub const ERR_CODE_TARGET_GROUP_INVALID_REQUEST: u32 = 9000;
@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, Clone, PartialEq, Eq, DeriveEntityModel, ToSchema)]
+#[sea_orm(table_name = "key_store")]
+#[schema(as = StoredKey)]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    /// 显示名称
+    pub name: String,
+    /// 密钥算法，如 ssh-ed25519、ssh-rsa
+    pub algorithm: String,
+    /// OpenSSH 格式的公钥（含注释），可直接追加到 authorized_keys
+    pub public_key: String,
+    /// 未加密的 OpenSSH 私钥，不会通过 API 返回
+    #[serde(skip_serializing)]
+    #[schema(ignore)]
+    pub private_key: String,
+    /// SHA256 指纹
+    pub fingerprint: String,
+    /// 密钥注释
+    pub comment: String,
+    pub created_at: i64,
+}
+
+impl std::fmt::Debug for Model {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Model")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("algorithm", &self.algorithm)
+            .field("public_key", &self.public_key)
+            .field("private_key", &"<secret>")
+            .field("fingerprint", &self.fingerprint)
+            .field("comment", &self.comment)
+            .field("created_at", &self.created_at)
+            .finish()
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,5 +1,10 @@
 pub mod favorite_directory;
 pub(crate) mod favorite_directory_initialization;
+pub mod key_store;
+pub(crate) mod secret_key;
 pub mod ssh_known_host;
 pub mod target;
+pub mod target_capabilities;
+pub mod target_group;
+pub mod target_source;
 pub mod transfer_task;
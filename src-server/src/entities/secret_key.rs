@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+/// 加密数据密钥（DEK）的主密钥封装信息，只有一行
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "secret_key")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: i32,
+    /// 主密钥的派生方式：passphrase（PBKDF2-HMAC-SHA256）或 key-file（HKDF-SHA256）
+    pub kdf: String,
+    /// Base64 编码的派生盐
+    pub salt: String,
+    /// PBKDF2 迭代次数，key-file 时为 0
+    pub iterations: u32,
+    /// Base64 编码、由主密钥以 AES-256-GCM 加密的数据密钥
+    pub wrapped_key: String,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
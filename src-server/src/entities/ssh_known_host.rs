@@ -1,15 +1,36 @@
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+/// known_hosts 文件中的行标记
+#[derive(
+    Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+pub enum KnownHostMarker {
+    /// 该公钥已被吊销，任何策略下都会拒绝
+    #[serde(rename = "revoked")]
+    #[sea_orm(string_value = "revoked")]
+    Revoked,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, DeriveEntityModel, ToSchema)]
 #[sea_orm(table_name = "ssh_known_host")]
+#[schema(as = KnownHost)]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
+    /// 主机名；端口为 0 时是 OpenSSH 主机模式列表（通配符、取反或 `|1|` 哈希主机名），端口包含在模式中
     pub host: String,
     pub port: u16,
+    /// 主机密钥算法，如 ssh-ed25519
     pub key_algorithm: String,
+    /// Base64 编码的主机公钥
     pub public_key: String,
+    /// SHA256 指纹
     pub fingerprint: String,
+    /// 为空时是受信任的主机密钥
+    pub marker: Option<KnownHostMarker>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
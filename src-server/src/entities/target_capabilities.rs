@@ -0,0 +1,54 @@
+use sea_orm::{FromJsonQueryResult, entity::prelude::*};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// `os` of Windows targets, and the value of `target.system` entered for them.
+pub const WINDOWS: &str = "windows";
+
+/// 名称列表（如可用的工具、SFTP 扩展）
+#[derive(
+    Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq, FromJsonQueryResult, ToSchema,
+)]
+#[serde(transparent)]
+pub struct CapabilityNames(pub Vec<String>);
+
+/// 首次连接成功后自动探测到的远程主机环境
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, DeriveEntityModel, ToSchema)]
+#[sea_orm(table_name = "target_capabilities")]
+#[schema(as = TargetCapabilities)]
+pub struct Model {
+    /// 目标 ID
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub target_id: i32,
+    /// 操作系统（小写，如 linux、darwin、freebsd、windows）
+    pub os: String,
+    /// 系统版本（uname -r 或 ver 的输出）
+    pub os_version: Option<String>,
+    /// 登录 shell
+    pub shell: Option<String>,
+    /// 用户主目录（SFTP 路径）
+    pub home_dir: Option<String>,
+    /// SFTP 协议版本
+    pub sftp_version: Option<u32>,
+    /// SFTP 服务端支持的扩展
+    pub sftp_extensions: CapabilityNames,
+    /// 远程主机上可用的工具（如 tar、sha256sum）
+    pub tools: CapabilityNames,
+    /// 探测时间（Unix 时间戳，毫秒）
+    pub detected_at: i64,
+}
+
+impl Model {
+    pub fn is_windows(&self) -> bool {
+        self.os == WINDOWS
+    }
+
+    pub fn has_tool(&self, tool: &str) -> bool {
+        self.tools.0.iter().any(|available| available == tool)
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 目标分组，可以嵌套
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, DeriveEntityModel, ToSchema)]
+#[sea_orm(table_name = "target_group")]
+#[schema(as = TargetGroup)]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    /// 分组名称
+    pub name: String,
+    /// 上级分组 ID，为空表示顶级分组
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
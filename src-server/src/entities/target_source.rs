@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// `kind` of targets imported from an Ansible inventory.
+pub const ANSIBLE: &str = "ansible";
+
+/// 目标的导入来源，重新导入时据此更新之前导入的目标
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, DeriveEntityModel, ToSchema)]
+#[sea_orm(table_name = "target_source")]
+#[schema(as = TargetSource)]
+pub struct Model {
+    /// 目标 ID
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub target_id: i32,
+    /// 来源类型，如 ansible
+    pub kind: String,
+    /// 来源名称，如 inventory 文件
+    pub source: String,
+    /// 目标在来源中的名称，如 inventory 中的主机名
+    pub reference: String,
+    /// 最近一次导入的时间（Unix 时间戳，毫秒）
+    pub imported_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -60,6 +60,7 @@ pub struct Model {
     pub estimated_time: Option<i64>,
     pub ranges: String,
     pub fail_reason: Option<String>,
+    pub attempts: i32,
     pub created_at: i64,
     pub updated_at: i64,
     pub ended_at: Option<i64>,
@@ -5,6 +5,7 @@ mod consts;
 pub mod entities;
 mod migrations;
 mod repositories;
+mod secrets;
 pub mod sftp_client;
 pub mod ssh_connection_pool;
 #[cfg(test)]
@@ -17,7 +18,10 @@ use config::Config;
 use sea_orm::{Database, DatabaseConnection};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
-use apis::{favorite_directory, fs, sftp, ssh, ssh_connection, target, transfer};
+use apis::{
+    favorite_directory, fs, key_store, known_host, sftp, ssh, ssh_connection, target, target_group,
+    transfer,
+};
 use migrations::{Migrator, MigratorTrait};
 use utoipa::OpenApi;
 
@@ -26,6 +30,7 @@ use crate::{api_doc::ApiDoc, ssh_connection_pool::SshConnectionPool};
 pub struct AppBaseState {
     db: DatabaseConnection,
     config: Config,
+    secrets: secrets::Secrets,
 }
 
 pub struct AppState {
@@ -61,17 +66,37 @@ pub async fn run_server() {
         .expect("Database connection failed");
 
     Migrator::up(&db, None).await.unwrap();
+    let secrets = secrets::unlock(&db, config.master_key.as_ref())
+        .await
+        .expect("Failed to unlock stored secrets");
 
-    let app_base_state = Arc::new(AppBaseState { db, config });
-    let connection_pool = Arc::new(SshConnectionPool::new(
-        app_base_state.db.clone(),
-        app_base_state.config.check_server_key,
-        app_base_state.config.max_connections_per_target as usize,
-        app_base_state.config.max_channels_per_connection as usize,
-    ));
+    let app_base_state = Arc::new(AppBaseState {
+        db,
+        config,
+        secrets,
+    });
+    let connection_pool = Arc::new(
+        SshConnectionPool::new(
+            app_base_state.db.clone(),
+            app_base_state.config.check_server_key,
+            app_base_state.config.max_connections_per_target as usize,
+            app_base_state.config.max_channels_per_connection as usize,
+        )
+        .with_secrets(app_base_state.secrets.clone())
+        .with_agent_socket(app_base_state.config.ssh_auth_sock.clone())
+        .with_keepalive(
+            app_base_state.config.ssh_keepalive_interval,
+            app_base_state.config.ssh_keepalive_max,
+        )
+        .with_idle_timeout(app_base_state.config.ssh_idle_timeout)
+        .with_default_proxy(app_base_state.config.ssh_proxy.clone())
+        .with_reserved_interactive_channel(app_base_state.config.ssh_reserve_interactive_channel)
+        .with_proxy_command_allowed(app_base_state.config.ssh_allow_proxy_command),
+    );
     let transfer_service =
         transfer::TransferService::new(app_base_state.clone(), connection_pool.clone());
     transfer_service.init_pending_tasks().await.unwrap();
+    target::spawn_capability_detection(app_base_state.db.clone(), &connection_pool);
 
     let app_state = Arc::new(AppState {
         base_state: app_base_state.clone(),
@@ -93,6 +118,18 @@ pub async fn run_server() {
         )
         .nest("/api/transfer", transfer::router_builder(app_state.clone()))
         .nest("/api/target", target::router_builder(app_state.clone()))
+        .nest(
+            "/api/target_group",
+            target_group::router_builder(app_state.clone()),
+        )
+        .nest(
+            "/api/known_host",
+            known_host::router_builder(app_state.clone()),
+        )
+        .nest(
+            "/api/key_store",
+            key_store::router_builder(app_state.clone()),
+        )
         .route(
             "/api-docs/openapi.json",
             axum::routing::get(|| async { axum::Json(ApiDoc::openapi()) }),
@@ -193,41 +193,42 @@ enum FavoriteDirectoryInitialization {
 #[cfg(test)]
 mod tests {
     use crate::{
-        entities::{favorite_directory, favorite_directory_initialization, ssh_known_host},
+        entities::{favorite_directory, favorite_directory_initialization},
         migrations::Migrator,
     };
-    use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database};
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, ConnectionTrait, Database, DbErr};
 
     use super::*;
 
-    fn known_host(host: &str, port: u16, key_algorithm: &str) -> ssh_known_host::ActiveModel {
-        ssh_known_host::ActiveModel {
-            id: sea_orm::ActiveValue::NotSet,
-            host: Set(host.to_string()),
-            port: Set(port),
-            key_algorithm: Set(key_algorithm.to_string()),
-            public_key: Set("public-key".to_string()),
-            fingerprint: Set("SHA256:fingerprint".to_string()),
-        }
+    async fn insert_known_host(
+        db: &sea_orm::DatabaseConnection,
+        host: &str,
+        port: u16,
+        key_algorithm: &str,
+    ) -> Result<(), DbErr> {
+        db.execute_unprepared(&format!(
+            "INSERT INTO ssh_known_host (host, port, key_algorithm, public_key, fingerprint) \
+             VALUES ('{host}', {port}, '{key_algorithm}', 'public-key', 'SHA256:fingerprint')"
+        ))
+        .await
+        .map(|_| ())
     }
 
     #[tokio::test]
     async fn init_db_enforces_one_host_key_per_endpoint() {
         let db = Database::connect("sqlite::memory:").await.unwrap();
-        Migrator::up(&db, None).await.unwrap();
+        // Later migrations allow several keys per endpoint.
+        Migrator::up(&db, Some(1)).await.unwrap();
 
-        known_host("same-host", 22, "ssh-ed25519")
-            .insert(&db)
+        insert_known_host(&db, "same-host", 22, "ssh-ed25519")
             .await
             .unwrap();
         assert!(
-            known_host("same-host", 22, "ssh-rsa")
-                .insert(&db)
+            insert_known_host(&db, "same-host", 22, "ssh-rsa")
                 .await
                 .is_err()
         );
-        known_host("same-host", 2222, "ssh-rsa")
-            .insert(&db)
+        insert_known_host(&db, "same-host", 2222, "ssh-rsa")
             .await
             .unwrap();
     }
@@ -0,0 +1,43 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per ALTER TABLE statement.
+        for column in [Target::ConnectTimeout, Target::ConnectRetries] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Target::Table)
+                        .add_column(integer_null(column))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Target::ConnectTimeout, Target::ConnectRetries] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Target::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Target {
+    Table,
+    ConnectTimeout,
+    ConnectRetries,
+}
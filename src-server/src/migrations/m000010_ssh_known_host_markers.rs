@@ -0,0 +1,187 @@
+use sea_orm_migration::{prelude::*, schema::*, sea_orm::TransactionTrait};
+
+const REBUILT_TABLE: &str = "ssh_known_host_rebuilt";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Allows several keys per host and adds the `@revoked` marker. SQLite
+/// cannot drop the unique constraints of the initial table, so the table
+/// is rebuilt.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite migrations run outside a transaction; keep the rebuild on a
+        // single connection so no other connection sees it half done.
+        let transaction = manager.get_connection().begin().await?;
+        rebuild_with_markers(&SchemaManager::new(&transaction)).await?;
+        transaction.commit().await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let transaction = manager.get_connection().begin().await?;
+        rebuild_without_markers(&SchemaManager::new(&transaction)).await?;
+        transaction.commit().await
+    }
+}
+
+async fn rebuild_with_markers(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(Alias::new(REBUILT_TABLE))
+                .col(pk_auto(SshKnownHost::Id))
+                .col(string(SshKnownHost::Host))
+                .col(small_unsigned(SshKnownHost::Port))
+                .col(string(SshKnownHost::KeyAlgorithm))
+                .col(text(SshKnownHost::PublicKey))
+                .col(string(SshKnownHost::Fingerprint))
+                .col(string_len_null(SshKnownHost::Marker, 16))
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .get_connection()
+        .execute_unprepared(&format!(
+            "INSERT INTO {REBUILT_TABLE} (id, host, port, key_algorithm, public_key, fingerprint) \
+                 SELECT id, host, port, key_algorithm, public_key, fingerprint FROM ssh_known_host"
+        ))
+        .await?;
+    manager
+        .drop_table(Table::drop().table(SshKnownHost::Table).to_owned())
+        .await?;
+    manager
+        .rename_table(
+            Table::rename()
+                .table(Alias::new(REBUILT_TABLE), SshKnownHost::Table)
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .create_index(
+            Index::create()
+                .name("idx_ssh_known_host_host_port_key")
+                .table(SshKnownHost::Table)
+                .col(SshKnownHost::Host)
+                .col(SshKnownHost::Port)
+                .col(SshKnownHost::KeyAlgorithm)
+                .col(SshKnownHost::PublicKey)
+                .unique()
+                .to_owned(),
+        )
+        .await
+}
+
+async fn rebuild_without_markers(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(Alias::new(REBUILT_TABLE))
+                .col(pk_auto(SshKnownHost::Id))
+                .col(string(SshKnownHost::Host))
+                .col(small_unsigned(SshKnownHost::Port))
+                .col(string(SshKnownHost::KeyAlgorithm))
+                .col(text(SshKnownHost::PublicKey))
+                .col(string(SshKnownHost::Fingerprint))
+                .index(
+                    Index::create()
+                        .name("idx_ssh_known_host_host_port_algorithm")
+                        .col(SshKnownHost::Host)
+                        .col(SshKnownHost::Port)
+                        .col(SshKnownHost::KeyAlgorithm)
+                        .unique(),
+                )
+                .index(
+                    Index::create()
+                        .name("idx_ssh_known_host_host_port")
+                        .col(SshKnownHost::Host)
+                        .col(SshKnownHost::Port)
+                        .unique(),
+                )
+                .to_owned(),
+        )
+        .await?;
+    // Only the first trusted key of each endpoint fits the old constraints.
+    manager
+        .get_connection()
+        .execute_unprepared(&format!(
+            "INSERT OR IGNORE INTO {REBUILT_TABLE} (id, host, port, key_algorithm, public_key, fingerprint) \
+             SELECT id, host, port, key_algorithm, public_key, fingerprint FROM ssh_known_host \
+             WHERE marker IS NULL AND port != 0 ORDER BY id"
+        ))
+        .await?;
+    manager
+        .drop_table(Table::drop().table(SshKnownHost::Table).to_owned())
+        .await?;
+    manager
+        .rename_table(
+            Table::rename()
+                .table(Alias::new(REBUILT_TABLE), SshKnownHost::Table)
+                .to_owned(),
+        )
+        .await
+}
+
+#[derive(DeriveIden)]
+enum SshKnownHost {
+    Table,
+    Id,
+    Host,
+    Port,
+    KeyAlgorithm,
+    PublicKey,
+    Fingerprint,
+    Marker,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entities::ssh_known_host::{self, KnownHostMarker},
+        migrations::Migrator,
+    };
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database};
+
+    use super::*;
+
+    fn known_host(
+        key_algorithm: &str,
+        public_key: &str,
+        marker: Option<KnownHostMarker>,
+    ) -> ssh_known_host::ActiveModel {
+        ssh_known_host::ActiveModel {
+            id: sea_orm::ActiveValue::NotSet,
+            host: Set("same-host".to_string()),
+            port: Set(22),
+            key_algorithm: Set(key_algorithm.to_string()),
+            public_key: Set(public_key.to_string()),
+            fingerprint: Set("SHA256:fingerprint".to_string()),
+            marker: Set(marker),
+        }
+    }
+
+    #[tokio::test]
+    async fn known_host_allows_several_keys_per_endpoint() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        known_host("ssh-ed25519", "first", None)
+            .insert(&db)
+            .await
+            .unwrap();
+        known_host("ssh-rsa", "second", None)
+            .insert(&db)
+            .await
+            .unwrap();
+        known_host("ssh-ed25519", "revoked", Some(KnownHostMarker::Revoked))
+            .insert(&db)
+            .await
+            .unwrap();
+        assert!(
+            known_host("ssh-ed25519", "first", Some(KnownHostMarker::Revoked))
+                .insert(&db)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(KeyStore::Table)
+                    .if_not_exists()
+                    .col(pk_auto(KeyStore::Id))
+                    .col(string(KeyStore::Name))
+                    .col(string(KeyStore::Algorithm))
+                    .col(text(KeyStore::PublicKey))
+                    .col(text(KeyStore::PrivateKey))
+                    .col(string(KeyStore::Fingerprint))
+                    .col(string(KeyStore::Comment))
+                    .col(big_integer(KeyStore::CreatedAt))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Target::Table)
+                    .add_column(integer_null(Target::KeyId))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Target::Table)
+                    .drop_column(Target::KeyId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(KeyStore::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum KeyStore {
+    Table,
+    Id,
+    Name,
+    Algorithm,
+    PublicKey,
+    PrivateKey,
+    Fingerprint,
+    Comment,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Target {
+    Table,
+    KeyId,
+}
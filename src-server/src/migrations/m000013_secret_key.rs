@@ -0,0 +1,44 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Stores the wrapped data key that encrypts target secrets. Existing
+/// plaintext secrets are encrypted when the server is first unlocked with a
+/// master key, since migrations do not know it.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SecretKey::Table)
+                    .if_not_exists()
+                    .col(integer(SecretKey::Id).primary_key())
+                    .col(string(SecretKey::Kdf))
+                    .col(string(SecretKey::Salt))
+                    .col(unsigned(SecretKey::Iterations))
+                    .col(string(SecretKey::WrappedKey))
+                    .col(big_integer(SecretKey::CreatedAt))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SecretKey::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SecretKey {
+    Table,
+    Id,
+    Kdf,
+    Salt,
+    Iterations,
+    WrappedKey,
+    CreatedAt,
+}
@@ -0,0 +1,74 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TargetGroup::Table)
+                    .if_not_exists()
+                    .col(pk_auto(TargetGroup::Id))
+                    .col(string(TargetGroup::Name))
+                    .col(integer_null(TargetGroup::ParentId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Target::Table)
+                    .add_column(integer_null(Target::GroupId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Target::Table)
+                    .add_column(json_null(Target::Tags))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Target::Table)
+                    .drop_column(Target::Tags)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Target::Table)
+                    .drop_column(Target::GroupId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TargetGroup::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TargetGroup {
+    Table,
+    Id,
+    Name,
+    ParentId,
+}
+
+#[derive(DeriveIden)]
+enum Target {
+    Table,
+    GroupId,
+    Tags,
+}
@@ -0,0 +1,47 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TargetCapabilities::Table)
+                    .if_not_exists()
+                    .col(integer(TargetCapabilities::TargetId).primary_key())
+                    .col(string(TargetCapabilities::Os))
+                    .col(string_null(TargetCapabilities::OsVersion))
+                    .col(string_null(TargetCapabilities::Shell))
+                    .col(string_null(TargetCapabilities::HomeDir))
+                    .col(integer_null(TargetCapabilities::SftpVersion))
+                    .col(json(TargetCapabilities::SftpExtensions))
+                    .col(json(TargetCapabilities::Tools))
+                    .col(big_integer(TargetCapabilities::DetectedAt))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TargetCapabilities::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TargetCapabilities {
+    Table,
+    TargetId,
+    Os,
+    OsVersion,
+    Shell,
+    HomeDir,
+    SftpVersion,
+    SftpExtensions,
+    Tools,
+    DetectedAt,
+}
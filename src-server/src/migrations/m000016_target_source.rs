@@ -0,0 +1,48 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TargetSource::Table)
+                    .if_not_exists()
+                    .col(integer(TargetSource::TargetId).primary_key())
+                    .col(string(TargetSource::Kind))
+                    .col(string(TargetSource::Source))
+                    .col(string(TargetSource::Reference))
+                    .col(big_integer(TargetSource::ImportedAt))
+                    .index(
+                        Index::create()
+                            .name("idx_target_source_kind_source_reference")
+                            .table(TargetSource::Table)
+                            .col(TargetSource::Kind)
+                            .col(TargetSource::Source)
+                            .col(TargetSource::Reference)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TargetSource::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TargetSource {
+    Table,
+    TargetId,
+    Kind,
+    Source,
+    Reference,
+    ImportedAt,
+}
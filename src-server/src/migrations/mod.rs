@@ -1,13 +1,45 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m000001_init_db;
+mod m000002_target_jump_target;
+mod m000003_target_certificate;
+mod m000004_transfer_task_attempts;
+mod m000005_target_proxy;
+mod m000006_target_proxy_command;
+mod m000007_target_algorithms;
+mod m000008_target_connect_retry;
+mod m000009_target_min_idle_connections;
+mod m000010_ssh_known_host_markers;
+mod m000011_target_host_key_policy;
+mod m000012_key_store;
+mod m000013_secret_key;
+mod m000014_target_group;
+mod m000015_target_capabilities;
+mod m000016_target_source;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m000001_init_db::Migration)]
+        vec![
+            Box::new(m000001_init_db::Migration),
+            Box::new(m000002_target_jump_target::Migration),
+            Box::new(m000003_target_certificate::Migration),
+            Box::new(m000004_transfer_task_attempts::Migration),
+            Box::new(m000005_target_proxy::Migration),
+            Box::new(m000006_target_proxy_command::Migration),
+            Box::new(m000007_target_algorithms::Migration),
+            Box::new(m000008_target_connect_retry::Migration),
+            Box::new(m000009_target_min_idle_connections::Migration),
+            Box::new(m000010_ssh_known_host_markers::Migration),
+            Box::new(m000011_target_host_key_policy::Migration),
+            Box::new(m000012_key_store::Migration),
+            Box::new(m000013_secret_key::Migration),
+            Box::new(m000014_target_group::Migration),
+            Box::new(m000015_target_capabilities::Migration),
+            Box::new(m000016_target_source::Migration),
+        ]
     }
 }
 
@@ -33,7 +65,7 @@ mod tests {
                 .await
                 .expect("Database connection failed");
 
-            Migrator::up(&db, Some(1)).await.unwrap();
+            Migrator::up(&db, None).await.unwrap();
 
             let stmt = Statement::from_string(
                 db.get_database_backend(),
@@ -43,16 +75,21 @@ mod tests {
             let stmt2 = stmt.clone();
             let rows = TableName::find_by_statement(stmt).all(&db).await.unwrap();
 
-            assert_eq!(rows.len(), 6, "Expected 6 tables, got {}", rows.len());
+            assert_eq!(rows.len(), 11, "Expected 11 tables, got {}", rows.len());
             assert_eq!(
                 Vec::from_iter(rows.iter().map(|row| row.name.as_str())),
                 vec![
                     "seaql_migrations",
                     "target",
-                    "ssh_known_host",
                     "transfer_task",
                     "favorite_directory",
-                    "favorite_directory_initialization"
+                    "favorite_directory_initialization",
+                    "ssh_known_host",
+                    "key_store",
+                    "secret_key",
+                    "target_group",
+                    "target_capabilities",
+                    "target_source"
                 ],
                 "Unexpected tables: {:?}",
                 rows
@@ -65,8 +102,20 @@ mod tests {
                 method: target::TargetAuthMethod::Password,
                 user: "root".to_string(),
                 key: None,
+                certificate: None,
                 password: Some("123456".to_string()),
                 system: Some("windows".to_string()),
+                jump_target_id: None,
+                proxy: None,
+                proxy_command: None,
+                algorithms: None,
+                connect_timeout: None,
+                connect_retries: None,
+                min_idle_connections: None,
+                host_key_policy: None,
+                group_id: None,
+                tags: None,
+                key_id: None,
             });
             let target1 = active_model.insert(&db).await.unwrap();
             assert_eq!(
@@ -76,7 +125,7 @@ mod tests {
                 target1
             );
 
-            Migrator::down(&db, Some(1)).await.unwrap();
+            Migrator::down(&db, None).await.unwrap();
             let rows = TableName::find_by_statement(stmt2).all(&db).await.unwrap();
             assert_eq!(rows.len(), 1);
             assert_eq!(
@@ -5,10 +5,13 @@ use sea_orm::{
 
 use crate::entities::{favorite_directory, favorite_directory_initialization};
 
-pub async fn list_by_target(
-    db: &DatabaseConnection,
+pub async fn list_by_target<C>(
+    db: &C,
     target_id: i32,
-) -> Result<Vec<favorite_directory::Model>, DbErr> {
+) -> Result<Vec<favorite_directory::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
     favorite_directory::Entity::find()
         .filter(favorite_directory::Column::TargetId.eq(target_id))
         .order_by_asc(favorite_directory::Column::CreatedAt)
@@ -32,12 +35,15 @@ where
         .await
 }
 
-pub async fn insert_if_absent(
-    db: &DatabaseConnection,
+pub async fn insert_if_absent<C>(
+    db: &C,
     target_id: i32,
     path: &str,
     active_model: favorite_directory::ActiveModel,
-) -> Result<favorite_directory::Model, DbErr> {
+) -> Result<favorite_directory::Model, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
     let transaction = db.begin().await?;
     favorite_directory::Entity::insert(active_model)
         .on_conflict(
@@ -0,0 +1,100 @@
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait,
+};
+
+use crate::{
+    entities::{key_store, target},
+    secrets::{SecretColumn, Secrets},
+};
+
+pub async fn list<C>(db: &C, secrets: &Secrets) -> Result<Vec<key_store::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    key_store::Entity::find()
+        .order_by_asc(key_store::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| open_private_key(secrets, model))
+        .collect()
+}
+
+pub async fn find_by_id<C>(
+    db: &C,
+    secrets: &Secrets,
+    id: i32,
+) -> Result<Option<key_store::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    key_store::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .map(|model| open_private_key(secrets, model))
+        .transpose()
+}
+
+pub async fn insert<C>(
+    db: &C,
+    secrets: &Secrets,
+    mut active_model: key_store::ActiveModel,
+) -> Result<key_store::Model, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    // The sealed private key is bound to the row id, so it is written once
+    // the row exists.
+    let private_key = active_model.private_key.take().unwrap_or_default();
+    active_model.private_key = ActiveValue::Set(String::new());
+    let transaction = db.begin().await?;
+    let inserted = active_model.insert(&transaction).await?;
+    let sealed = secrets
+        .seal(
+            SecretColumn::KeyStorePrivateKey,
+            inserted.id,
+            Some(private_key),
+        )
+        .unwrap_or_default();
+    let model = key_store::ActiveModel {
+        id: ActiveValue::Unchanged(inserted.id),
+        private_key: ActiveValue::Set(sealed),
+        ..Default::default()
+    }
+    .update(&transaction)
+    .await?;
+    transaction.commit().await?;
+    open_private_key(secrets, model)
+}
+
+/// Deletes the key unless a target still authenticates with it. Returns
+/// the number of targets that use the key, which is 0 once it is deleted.
+pub async fn delete_if_unused(db: &DatabaseConnection, id: i32) -> Result<u64, DbErr> {
+    let transaction = db.begin().await?;
+    let used_by = target::Entity::find()
+        .filter(target::Column::KeyId.eq(id))
+        .count(&transaction)
+        .await?;
+    if used_by == 0 {
+        key_store::Entity::delete_by_id(id)
+            .exec(&transaction)
+            .await?;
+    }
+    transaction.commit().await?;
+    Ok(used_by)
+}
+
+fn open_private_key(
+    secrets: &Secrets,
+    mut model: key_store::Model,
+) -> Result<key_store::Model, DbErr> {
+    model.private_key = secrets
+        .open(
+            SecretColumn::KeyStorePrivateKey,
+            model.id,
+            Some(model.private_key),
+        )?
+        .unwrap_or_default();
+    Ok(model)
+}
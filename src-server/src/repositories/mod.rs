@@ -1,2 +1,7 @@
 pub(crate) mod favorite_directory;
+pub(crate) mod key_store;
+pub(crate) mod ssh_known_host;
 pub(crate) mod target;
+pub(crate) mod target_capabilities;
+pub(crate) mod target_group;
+pub(crate) mod target_source;
@@ -0,0 +1,94 @@
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, DeleteResult, EntityTrait,
+    QueryFilter, QueryOrder, TransactionTrait,
+    sea_query::{Expr, Func, OnConflict, SimpleExpr},
+};
+
+use crate::entities::ssh_known_host;
+
+/// Matches entries stored for `host` in any letter case, as host names are
+/// case-insensitive and older entries kept the case they were typed in.
+pub fn host_eq(host: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col(ssh_known_host::Column::Host))).eq(host.to_lowercase())
+}
+
+pub async fn list(
+    db: &DatabaseConnection,
+    host: Option<&str>,
+    port: Option<u16>,
+) -> Result<Vec<ssh_known_host::Model>, DbErr> {
+    let mut query = ssh_known_host::Entity::find();
+    if let Some(host) = host {
+        query = query.filter(host_eq(host));
+    }
+    if let Some(port) = port {
+        query = query.filter(ssh_known_host::Column::Port.eq(port));
+    }
+    query
+        .order_by_asc(ssh_known_host::Column::Host)
+        .order_by_asc(ssh_known_host::Column::Port)
+        .order_by_asc(ssh_known_host::Column::Id)
+        .all(db)
+        .await
+}
+
+pub async fn delete_by_id(db: &DatabaseConnection, id: i32) -> Result<DeleteResult, DbErr> {
+    ssh_known_host::Entity::delete_by_id(id).exec(db).await
+}
+
+/// Replaces the trusted keys stored for `host:port` with `active_model`.
+/// Revoked keys are kept.
+pub async fn replace(
+    db: &DatabaseConnection,
+    host: &str,
+    port: u16,
+    active_model: ssh_known_host::ActiveModel,
+) -> Result<ssh_known_host::Model, DbErr> {
+    let transaction = db.begin().await?;
+    ssh_known_host::Entity::delete_many()
+        .filter(host_eq(host))
+        .filter(ssh_known_host::Column::Port.eq(port))
+        .filter(ssh_known_host::Column::Marker.is_null())
+        .exec(&transaction)
+        .await?;
+    let inserted = ssh_known_host::Entity::insert(active_model)
+        .exec_with_returning(&transaction)
+        .await?;
+    transaction.commit().await?;
+    Ok(inserted)
+}
+
+/// Inserts `active_models`, updating the marker of keys that are already
+/// stored for the same host.
+pub async fn upsert_many<C>(
+    db: &C,
+    active_models: Vec<ssh_known_host::ActiveModel>,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if active_models.is_empty() {
+        return Ok(());
+    }
+    let transaction = db.begin().await?;
+    // Bounded batches keep each statement below SQLite's variable limit.
+    for chunk in active_models.chunks(100) {
+        ssh_known_host::Entity::insert_many(chunk.to_vec())
+            .on_conflict(
+                OnConflict::columns([
+                    ssh_known_host::Column::Host,
+                    ssh_known_host::Column::Port,
+                    ssh_known_host::Column::KeyAlgorithm,
+                    ssh_known_host::Column::PublicKey,
+                ])
+                .update_columns([
+                    ssh_known_host::Column::Fingerprint,
+                    ssh_known_host::Column::Marker,
+                ])
+                .to_owned(),
+            )
+            .exec_without_returning(&transaction)
+            .await?;
+    }
+    transaction.commit().await
+}
@@ -0,0 +1,50 @@
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, sea_query::OnConflict};
+
+use crate::entities::target_capabilities;
+
+pub async fn find_by_target_id(
+    db: &DatabaseConnection,
+    target_id: i32,
+) -> Result<Option<target_capabilities::Model>, DbErr> {
+    target_capabilities::Entity::find_by_id(target_id)
+        .one(db)
+        .await
+}
+
+/// Stores `capabilities`, replacing an earlier detection for the target.
+pub async fn upsert(
+    db: &DatabaseConnection,
+    capabilities: target_capabilities::Model,
+) -> Result<target_capabilities::Model, DbErr> {
+    let target_id = capabilities.target_id;
+    target_capabilities::Entity::insert(target_capabilities::ActiveModel::from(capabilities))
+        .on_conflict(
+            OnConflict::column(target_capabilities::Column::TargetId)
+                .update_columns([
+                    target_capabilities::Column::Os,
+                    target_capabilities::Column::OsVersion,
+                    target_capabilities::Column::Shell,
+                    target_capabilities::Column::HomeDir,
+                    target_capabilities::Column::SftpVersion,
+                    target_capabilities::Column::SftpExtensions,
+                    target_capabilities::Column::Tools,
+                    target_capabilities::Column::DetectedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    find_by_target_id(db, target_id)
+        .await?
+        .ok_or(DbErr::RecordNotInserted)
+}
+
+pub async fn delete<C>(db: &C, target_id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    target_capabilities::Entity::delete_by_id(target_id)
+        .exec(db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryOrder,
+};
+
+use crate::entities::target_group;
+
+pub async fn list<C>(db: &C) -> Result<Vec<target_group::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    target_group::Entity::find()
+        .order_by_asc(target_group::Column::Name)
+        .all(db)
+        .await
+}
+
+pub async fn find_by_id<C>(db: &C, id: i32) -> Result<Option<target_group::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    target_group::Entity::find_by_id(id).one(db).await
+}
+
+pub async fn insert<C>(
+    db: &C,
+    active_model: target_group::ActiveModel,
+) -> Result<target_group::Model, DbErr>
+where
+    C: ConnectionTrait,
+{
+    active_model.insert(db).await
+}
+
+pub async fn update(
+    db: &DatabaseConnection,
+    active_model: target_group::ActiveModel,
+) -> Result<target_group::Model, DbErr> {
+    active_model.update(db).await
+}
+
+/// Returns `group_id` followed by the IDs of all groups nested below it.
+pub async fn with_descendants(db: &DatabaseConnection, group_id: i32) -> Result<Vec<i32>, DbErr> {
+    let groups = target_group::Entity::find().all(db).await?;
+    let mut ids = vec![group_id];
+    let mut index = 0;
+    while index < ids.len() {
+        let parent_id = ids[index];
+        ids.extend(
+            groups
+                .iter()
+                .filter(|group| group.parent_id == Some(parent_id) && !ids.contains(&group.id))
+                .map(|group| group.id)
+                .collect::<Vec<_>>(),
+        );
+        index += 1;
+    }
+    Ok(ids)
+}
@@ -0,0 +1,49 @@
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, sea_query::OnConflict,
+};
+
+use crate::entities::target_source;
+
+pub async fn list_by_source<C>(
+    db: &C,
+    kind: &str,
+    source: &str,
+) -> Result<Vec<target_source::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    target_source::Entity::find()
+        .filter(target_source::Column::Kind.eq(kind))
+        .filter(target_source::Column::Source.eq(source))
+        .all(db)
+        .await
+}
+
+/// Records where the target was imported from, replacing its earlier source
+/// and any other target recorded for the same reference.
+pub async fn upsert<C>(db: &C, source: target_source::Model) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    target_source::Entity::delete_many()
+        .filter(target_source::Column::Kind.eq(&source.kind))
+        .filter(target_source::Column::Source.eq(&source.source))
+        .filter(target_source::Column::Reference.eq(&source.reference))
+        .filter(target_source::Column::TargetId.ne(source.target_id))
+        .exec(db)
+        .await?;
+    target_source::Entity::insert(target_source::ActiveModel::from(source))
+        .on_conflict(
+            OnConflict::column(target_source::Column::TargetId)
+                .update_columns([
+                    target_source::Column::Kind,
+                    target_source::Column::Source,
+                    target_source::Column::Reference,
+                    target_source::Column::ImportedAt,
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
@@ -20,7 +20,9 @@ use tokio::{
     task::JoinHandle,
 };
 
-use crate::ssh_connection_pool::{SshChannelGuard, SshChannelTransferGuard};
+use crate::ssh_connection_pool::{
+    SshChannelGuard, SshChannelTransferGuard, is_transient_io_error, is_transient_ssh_error,
+};
 
 pub type SftpResult<T> = Result<T, SftpError>;
 
@@ -48,6 +50,20 @@ impl SftpError {
             }
         )
     }
+
+    /// Whether the failure came from the channel or connection going away,
+    /// so the operation may succeed on a new SFTP session.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Io(err) => is_transient_io_error(err),
+            Self::Ssh(err) => is_transient_ssh_error(err),
+            Self::Status { code, .. } => {
+                matches!(*code, SSH_FX_NO_CONNECTION | SSH_FX_CONNECTION_LOST)
+            }
+            Self::ResponseChannelClosed | Self::ReadStreamClosed | Self::Shutdown => true,
+            _ => false,
+        }
+    }
 }
 
 impl std::fmt::Display for SftpError {
@@ -138,6 +154,8 @@ const SSH_FXP_ATTRS: u8 = 105;
 
 const SSH_FX_OK: u32 = 0;
 const SSH_FX_EOF: u32 = 1;
+const SSH_FX_NO_CONNECTION: u32 = 6;
+const SSH_FX_CONNECTION_LOST: u32 = 7;
 const SSH_FX_OP_UNSUPPORTED: u32 = 8;
 
 const SSH_FXF_READ: u32 = 0x0000_0001;
@@ -549,6 +567,8 @@ struct FastSftpInner {
     read_stream_pending: Mutex<VecDeque<PendingReadStreamEntry>>,
     next_id: AtomicU32,
     channel_control: Mutex<Option<SftpChannelControl>>,
+    server_version: u32,
+    server_extensions: Vec<String>,
 }
 
 struct SftpChannelControl {
@@ -702,6 +722,19 @@ impl FastSftpClient {
                 actual: version_packet.packet_type,
             });
         }
+        let mut cursor = Cursor::new(version_packet.payload());
+        let server_version = match cursor.read_u32() {
+            Ok(version) => version,
+            Err(err) => {
+                close_sftp_channel(&mut channel_control).await;
+                return Err(err);
+            }
+        };
+        // Extension pairs are informational, a malformed tail is ignored.
+        let mut server_extensions = Vec::new();
+        while let (Ok(name), Ok(_data)) = (cursor.read_string(), cursor.read_string()) {
+            server_extensions.push(String::from_utf8_lossy(name).into_owned());
+        }
 
         let inner = Arc::new(FastSftpInner {
             writer: Mutex::new(writer),
@@ -709,6 +742,8 @@ impl FastSftpClient {
             read_stream_pending: Mutex::new(VecDeque::new()),
             next_id: AtomicU32::new(1),
             channel_control: Mutex::new(channel_control),
+            server_version,
+            server_extensions,
         });
 
         let read_task = tokio::spawn(read_loop(reader, inner.clone()));
@@ -718,6 +753,16 @@ impl FastSftpClient {
         })
     }
 
+    /// SFTP protocol version the server answered the init with.
+    pub fn server_version(&self) -> u32 {
+        self.inner.server_version
+    }
+
+    /// Names of the protocol extensions the server announced.
+    pub fn server_extensions(&self) -> &[String] {
+        &self.inner.server_extensions
+    }
+
     pub async fn read_dir<T: Into<String>>(&self, path: T) -> SftpResult<Vec<SftpDirEntry>> {
         let mut payload = Vec::new();
         put_string(&mut payload, path.into().as_bytes());
@@ -2009,6 +2054,8 @@ mod tests {
             read_stream_pending: Mutex::new(VecDeque::new()),
             next_id: AtomicU32::new(1),
             channel_control: Mutex::new(None),
+            server_version: 3,
+            server_extensions: Vec::new(),
         };
         let (first_tx, first_rx) = oneshot::channel();
         let (second_tx, second_rx) = oneshot::channel();
@@ -2110,6 +2157,35 @@ mod tests {
         assert_eq!(packet.payload(), payload);
     }
 
+    #[tokio::test]
+    async fn init_keeps_the_server_version_and_extensions() {
+        let (client_stream, mut server_stream) = duplex(8192);
+        let server = tokio::spawn(async move {
+            read_packet(&mut server_stream).await.expect("init packet");
+            let mut version = Vec::new();
+            put_u32(&mut version, 3);
+            put_string(&mut version, b"posix-rename@openssh.com");
+            put_string(&mut version, b"1");
+            put_string(&mut version, b"statvfs@openssh.com");
+            put_string(&mut version, b"2");
+            write_raw_packet_to(&mut server_stream, SSH_FXP_VERSION, &version)
+                .await
+                .expect("version response");
+            server_stream
+        });
+
+        let client = FastSftpClient::new_with_stream(Box::new(client_stream))
+            .await
+            .expect("sftp client");
+        let _server_stream = server.await.expect("server task");
+
+        assert_eq!(client.server_version(), 3);
+        assert_eq!(
+            client.server_extensions(),
+            ["posix-rename@openssh.com", "statvfs@openssh.com"]
+        );
+    }
+
     #[tokio::test]
     async fn realpath_returns_server_canonical_path() {
         let (client_stream, mut server_stream) = duplex(8192);
@@ -0,0 +1,171 @@
+use std::{borrow::Cow, str::FromStr};
+
+use russh::{
+    Names, Preferred, cipher, compression, kex,
+    keys::{Algorithm, key::ALL_KEY_TYPES},
+    mac,
+};
+
+use super::error::{SshPoolError, SshPoolResult};
+use crate::entities::target::TargetAlgorithms;
+
+const DEFAULT_CIPHERS: &[cipher::Name] = &[
+    cipher::AES_128_GCM,
+    cipher::AES_256_GCM,
+    cipher::AES_128_CTR,
+    cipher::AES_256_CTR,
+    cipher::CHACHA20_POLY1305,
+];
+const DEFAULT_COMPRESSION: &[compression::Name] = &[compression::NONE];
+
+/// Algorithms agreed on during the last key exchange of a connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NegotiatedAlgorithms {
+    pub kex: String,
+    pub host_key: String,
+    pub cipher: String,
+    pub client_mac: String,
+    pub server_mac: String,
+    pub client_compression: String,
+    pub server_compression: String,
+}
+
+impl From<&Names> for NegotiatedAlgorithms {
+    fn from(names: &Names) -> Self {
+        Self {
+            kex: names.kex.as_ref().to_string(),
+            host_key: names.key.to_string(),
+            cipher: names.cipher.as_ref().to_string(),
+            client_mac: names.client_mac.as_ref().to_string(),
+            server_mac: names.server_mac.as_ref().to_string(),
+            client_compression: compression_name(&names.client_compression).to_string(),
+            server_compression: compression_name(&names.server_compression).to_string(),
+        }
+    }
+}
+
+fn compression_name(compression: &compression::Compression) -> &'static str {
+    match compression {
+        compression::Compression::None => compression::NONE.as_ref(),
+        compression::Compression::Zlib => compression::ZLIB.as_ref(),
+        compression::Compression::ZlibOpenSSH => compression::ZLIB_LEGACY.as_ref(),
+    }
+}
+
+/// Builds the russh algorithm preferences for a target. Empty lists keep
+/// the defaults; unknown or insecure names are rejected.
+pub(crate) fn preferred_algorithms(algorithms: &TargetAlgorithms) -> SshPoolResult<Preferred> {
+    Ok(Preferred {
+        kex: parse_list("kex", &algorithms.kex, Preferred::DEFAULT.kex, |name| {
+            kex::Name::try_from(name)
+                .ok()
+                .filter(|name| *name != kex::NONE)
+        })?,
+        key: parse_list(
+            "host key",
+            &algorithms.host_key,
+            Preferred::DEFAULT.key,
+            |name| {
+                Algorithm::from_str(name)
+                    .ok()
+                    .filter(|algorithm| ALL_KEY_TYPES.contains(algorithm))
+            },
+        )?,
+        cipher: parse_list(
+            "cipher",
+            &algorithms.cipher,
+            Cow::Borrowed(DEFAULT_CIPHERS),
+            |name| {
+                cipher::Name::try_from(name)
+                    .ok()
+                    .filter(|name| *name != cipher::NONE && *name != cipher::CLEAR)
+            },
+        )?,
+        mac: parse_list("MAC", &algorithms.mac, Preferred::DEFAULT.mac, |name| {
+            mac::Name::try_from(name)
+                .ok()
+                .filter(|name| *name != mac::NONE)
+        })?,
+        compression: parse_list(
+            "compression",
+            &algorithms.compression,
+            Cow::Borrowed(DEFAULT_COMPRESSION),
+            |name| compression::Name::try_from(name).ok(),
+        )?,
+    })
+}
+
+/// Checks that every algorithm of a target is supported.
+pub fn validate_algorithms(algorithms: &TargetAlgorithms) -> SshPoolResult<()> {
+    preferred_algorithms(algorithms).map(|_| ())
+}
+
+fn parse_list<T: Clone>(
+    kind: &'static str,
+    names: &[String],
+    default: Cow<'static, [T]>,
+    parse: impl Fn(&str) -> Option<T>,
+) -> SshPoolResult<Cow<'static, [T]>> {
+    if names.is_empty() {
+        return Ok(default);
+    }
+    names
+        .iter()
+        .map(|name| {
+            parse(name.trim()).ok_or_else(|| SshPoolError::UnsupportedAlgorithm {
+                kind,
+                name: name.clone(),
+            })
+        })
+        .collect::<SshPoolResult<Vec<_>>>()
+        .map(Cow::Owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_algorithms_are_validated_against_russh() {
+        let preferred = preferred_algorithms(&TargetAlgorithms::default()).unwrap();
+        assert_eq!(preferred.cipher.as_ref(), DEFAULT_CIPHERS);
+        assert_eq!(preferred.compression.as_ref(), DEFAULT_COMPRESSION);
+
+        let preferred = preferred_algorithms(&TargetAlgorithms {
+            kex: vec!["diffie-hellman-group14-sha1".to_string()],
+            host_key: vec!["ssh-rsa".to_string(), "rsa-sha2-256".to_string()],
+            cipher: vec!["aes128-cbc".to_string()],
+            mac: vec!["hmac-sha1".to_string()],
+            compression: vec!["zlib@openssh.com".to_string(), "none".to_string()],
+        })
+        .unwrap();
+        assert_eq!(preferred.kex.as_ref(), [kex::DH_G14_SHA1]);
+        assert_eq!(preferred.key.len(), 2);
+        assert_eq!(preferred.cipher.as_ref(), [cipher::AES_128_CBC]);
+        assert_eq!(preferred.mac.as_ref(), [mac::HMAC_SHA1]);
+        assert_eq!(
+            preferred.compression.as_ref(),
+            [compression::ZLIB_LEGACY, compression::NONE]
+        );
+
+        for algorithms in [
+            TargetAlgorithms {
+                cipher: vec!["none".to_string()],
+                ..Default::default()
+            },
+            TargetAlgorithms {
+                kex: vec!["made-up-kex".to_string()],
+                ..Default::default()
+            },
+            TargetAlgorithms {
+                compression: vec!["lz4".to_string()],
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(
+                preferred_algorithms(&algorithms),
+                Err(SshPoolError::UnsupportedAlgorithm { .. })
+            ));
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::{
     Arc,
-    atomic::{AtomicU8, Ordering},
+    atomic::{AtomicU8, AtomicU64, Ordering},
 };
 
 use russh::{Channel, Disconnect};
@@ -8,9 +8,12 @@ use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
 use tracing::debug;
 
 use super::{
+    algorithms::NegotiatedAlgorithms,
     connector::{ConnectedSsh, SshClientHandler},
     error::{SshPoolError, SshPoolResult},
+    events::{ConnectionEventKind, ConnectionEvents, now_ms},
     target_connection_pool::TargetConnectionPool,
+    traffic::TrafficCounters,
 };
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -31,12 +34,28 @@ impl ConnectionState {
     }
 }
 
+/// Channel type requested from a pooled connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ChannelKind {
+    Session,
+    DirectTcpip { host: String, port: u16 },
+}
+
 pub(crate) struct SshConnection {
     id: String,
     handle: russh::client::Handle<SshClientHandler>,
+    agent_identity: Option<String>,
+    algorithms: Option<NegotiatedAlgorithms>,
+    target_id: i32,
+    events: ConnectionEvents,
+    traffic: Arc<TrafficCounters>,
+    created_at: u64,
     permits: Arc<Semaphore>,
     max_channels: usize,
     state: AtomicU8,
+    /// Bumped on every reservation so an idle timer can tell whether the
+    /// connection was used after it went idle.
+    reservations: AtomicU64,
     notify: Arc<Notify>,
     owner: std::sync::Weak<TargetConnectionPool>,
 }
@@ -51,25 +70,40 @@ impl SshConnection {
         let connection = Arc::new(Self {
             id: nanoid::nanoid!(),
             handle: connected.handle,
+            agent_identity: connected.agent_identity,
+            algorithms: connected.algorithms,
+            target_id: connected.target_id,
+            events: connected.events,
+            traffic: connected.traffic,
+            created_at: now_ms(),
             permits: Arc::new(Semaphore::new(max_channels)),
             max_channels,
             state: AtomicU8::new(ConnectionState::Active as u8),
+            reservations: AtomicU64::new(0),
             notify,
             owner,
         });
+        connection.emit(ConnectionEventKind::Authenticated);
         Self::start_disconnect_watcher(&connection, connected.disconnected);
         connection
     }
 
+    fn emit(&self, kind: ConnectionEventKind) {
+        self.events
+            .emit(self.target_id, Some(&self.id), kind, self.active_channels());
+    }
+
     fn start_disconnect_watcher(
         connection: &Arc<Self>,
-        disconnected: tokio::sync::oneshot::Receiver<()>,
+        disconnected: tokio::sync::oneshot::Receiver<String>,
     ) {
         let weak = Arc::downgrade(connection);
         tokio::spawn(async move {
-            let _ = disconnected.await;
+            let reason = disconnected
+                .await
+                .unwrap_or_else(|_| "SSH session ended".to_string());
             if let Some(connection) = weak.upgrade() {
-                connection.mark_closed();
+                connection.mark_closed(reason);
                 connection.remove_from_owner().await;
             }
         });
@@ -79,6 +113,22 @@ impl SshConnection {
         &self.id
     }
 
+    pub(crate) fn agent_identity(&self) -> Option<&str> {
+        self.agent_identity.as_deref()
+    }
+
+    pub(crate) fn algorithms(&self) -> Option<&NegotiatedAlgorithms> {
+        self.algorithms.as_ref()
+    }
+
+    pub(crate) fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    pub(crate) fn traffic(&self) -> &TrafficCounters {
+        &self.traffic
+    }
+
     pub(crate) fn state(&self) -> ConnectionState {
         ConnectionState::from_u8(self.state.load(Ordering::Acquire))
     }
@@ -92,11 +142,16 @@ impl SshConnection {
         self.permits.available_permits() == self.max_channels
     }
 
+    pub(crate) fn reservations(&self) -> u64 {
+        self.reservations.load(Ordering::Acquire)
+    }
+
     pub(crate) fn try_reserve(self: &Arc<Self>) -> Option<ChannelPermit> {
         if self.state() != ConnectionState::Active {
             return None;
         }
         let permit = Arc::clone(&self.permits).try_acquire_owned().ok()?;
+        self.reservations.fetch_add(1, Ordering::AcqRel);
         if self.state() != ConnectionState::Active {
             drop(permit);
             return None;
@@ -104,25 +159,43 @@ impl SshConnection {
         Some(ChannelPermit {
             permit: Some(permit),
             connection: Arc::clone(self),
+            opened: false,
         })
     }
 
-    pub(crate) async fn open_channel(&self) -> SshPoolResult<Channel<russh::client::Msg>> {
+    pub(crate) async fn open_channel(
+        &self,
+        kind: &ChannelKind,
+    ) -> SshPoolResult<Channel<russh::client::Msg>> {
         if self.state() == ConnectionState::Closed || self.handle.is_closed() {
             return Err(SshPoolError::ConnectionExpired {
                 connection_id: self.id.clone(),
             });
         }
-        Ok(self.handle.channel_open_session().await?)
+        let channel = match kind {
+            ChannelKind::Session => self.handle.channel_open_session().await?,
+            ChannelKind::DirectTcpip { host, port } => {
+                self.handle
+                    .channel_open_direct_tcpip(host.as_str(), u32::from(*port), "127.0.0.1", 0)
+                    .await?
+            }
+        };
+        Ok(channel)
     }
 
     pub(crate) fn expire(self: &Arc<Self>) {
-        let _ = self.state.compare_exchange(
-            ConnectionState::Active as u8,
-            ConnectionState::Expiring as u8,
-            Ordering::AcqRel,
-            Ordering::Acquire,
-        );
+        if self
+            .state
+            .compare_exchange(
+                ConnectionState::Active as u8,
+                ConnectionState::Expiring as u8,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            self.emit(ConnectionEventKind::Expiring);
+        }
         self.notify.notify_waiters();
         self.close_if_expired_and_idle();
     }
@@ -148,6 +221,9 @@ impl SshConnection {
             return;
         }
         debug!(connection_id = self.id, "closing SSH connection");
+        self.emit(ConnectionEventKind::Closed {
+            reason: "expired".to_string(),
+        });
         let _ = self
             .handle
             .disconnect(Disconnect::ByApplication, "", "English")
@@ -156,20 +232,32 @@ impl SshConnection {
         self.remove_from_owner().await;
     }
 
-    fn mark_closed(&self) {
+    fn mark_closed(&self, reason: String) {
         if self
             .state
             .swap(ConnectionState::Closed as u8, Ordering::AcqRel)
             != ConnectionState::Closed as u8
         {
-            debug!(connection_id = self.id, "SSH connection marked closed");
+            debug!(
+                connection_id = self.id,
+                reason, "SSH connection marked closed"
+            );
+            self.emit(ConnectionEventKind::Closed { reason });
         }
         self.notify.notify_waiters();
     }
 
     fn on_permit_released(self: &Arc<Self>) {
-        self.notify.notify_one();
+        // Every waiter re-checks, so the first one in priority order gets
+        // the channel.
+        self.notify.notify_waiters();
         self.close_if_expired_and_idle();
+        if self.state() == ConnectionState::Active
+            && self.is_idle()
+            && let Some(owner) = self.owner.upgrade()
+        {
+            owner.schedule_idle_close(self);
+        }
     }
 
     async fn remove_from_owner(&self) {
@@ -182,11 +270,24 @@ impl SshConnection {
 pub(crate) struct ChannelPermit {
     permit: Option<OwnedSemaphorePermit>,
     connection: Arc<SshConnection>,
+    opened: bool,
+}
+
+impl ChannelPermit {
+    /// Records that a channel was opened with this reservation, so that
+    /// releasing it reports the channel as closed.
+    pub(crate) fn mark_opened(&mut self) {
+        self.opened = true;
+        self.connection.emit(ConnectionEventKind::ChannelOpened);
+    }
 }
 
 impl Drop for ChannelPermit {
     fn drop(&mut self) {
         drop(self.permit.take());
+        if self.opened {
+            self.connection.emit(ConnectionEventKind::ChannelClosed);
+        }
         self.connection.on_permit_released();
     }
 }
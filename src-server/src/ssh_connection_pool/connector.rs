@@ -1,5 +1,6 @@
 use std::{
     fmt, io,
+    path::{Path, PathBuf},
     pin::Pin,
     sync::{
         Arc,
@@ -10,28 +11,50 @@ use std::{
 };
 
 use russh::{
-    Preferred, cipher,
-    client::DisconnectReason,
-    compression,
-    keys::{HashAlg, PrivateKeyWithHashAlg, PublicKeyBase64, decode_secret_key, ssh_key},
+    client::{AuthResult, DisconnectReason, KeyboardInteractiveAuthResponse},
+    keys::{
+        HashAlg, PrivateKeyWithHashAlg,
+        agent::{
+            AgentIdentity,
+            client::{AgentClient, AgentStream},
+        },
+        decode_secret_key, ssh_key,
+    },
 };
 use tokio::{
     io::{AsyncRead, AsyncWrite, ReadBuf},
-    net::TcpStream,
     sync::oneshot,
     time::{Instant, Sleep},
 };
 use tracing::{debug, warn};
+use zeroize::Zeroizing;
+
+use crate::{config::CheckServerKey, entities::target::TargetAlgorithms};
 
 use super::{
+    algorithms::{NegotiatedAlgorithms, preferred_algorithms},
+    diagnostics::{HostKeyProbe, HostKeyReport},
     error::{SshPoolError, SshPoolResult},
-    known_hosts::{KnownHosts, ServerPublicKey, verify_server_key},
+    events::{ConnectionEventKind, ConnectionEvents},
+    known_hosts::{
+        KnownHostKeys, KnownHosts, ServerPublicKey, confirm_host_key, host_key_verdict,
+        verify_server_key,
+    },
+    prompt::{AuthPrompt, AuthPromptRequest, AuthPrompter, SessionSecret},
+    proxy::SshProxy,
+    proxy_command::{ProxyCommandStream, expand_proxy_command},
+    target_connection_pool::TargetConnectionPool,
+    tcp::{connect_resolved, resolve},
+    traffic::{CountingStream, TrafficCounters},
 };
 
 const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const CONNECT_RETRY_MAX_DELAY: Duration = Duration::from_secs(10);
+const KEEPALIVE_MAX: usize = 3;
 
 #[derive(Clone)]
-struct ConnectDeadline {
+pub(super) struct ConnectDeadline {
     inner: Arc<ConnectDeadlineInner>,
 }
 
@@ -43,7 +66,7 @@ struct ConnectDeadlineInner {
 }
 
 impl ConnectDeadline {
-    fn new(timeout: Duration) -> Self {
+    pub(super) fn new(timeout: Duration) -> Self {
         Self {
             inner: Arc::new(ConnectDeadlineInner {
                 at: Instant::now() + timeout,
@@ -54,7 +77,7 @@ impl ConnectDeadline {
         }
     }
 
-    fn at(&self) -> Instant {
+    pub(super) fn at(&self) -> Instant {
         self.inner.at
     }
 
@@ -62,7 +85,7 @@ impl ConnectDeadline {
         self.inner.enabled.load(Ordering::Acquire)
     }
 
-    fn disable(&self) {
+    pub(super) fn disable(&self) {
         self.inner.enabled.store(false, Ordering::Release);
     }
 
@@ -80,7 +103,7 @@ impl ConnectDeadline {
         }
     }
 
-    fn map_result<T>(&self, result: SshPoolResult<T>) -> SshPoolResult<T> {
+    pub(super) fn map_result<T>(&self, result: SshPoolResult<T>) -> SshPoolResult<T> {
         if self.timed_out() {
             Err(self.pool_error())
         } else {
@@ -96,7 +119,10 @@ impl ConnectDeadline {
         )
     }
 
-    async fn run<T>(&self, future: impl Future<Output = SshPoolResult<T>>) -> SshPoolResult<T> {
+    pub(super) async fn run<T>(
+        &self,
+        future: impl Future<Output = SshPoolResult<T>>,
+    ) -> SshPoolResult<T> {
         match tokio::time::timeout_at(self.at(), future).await {
             Ok(result) => result,
             Err(_) => {
@@ -186,31 +212,76 @@ impl<R: AsyncWrite + Unpin> AsyncWrite for DeadlineStream<R> {
     }
 }
 
+/// Byte stream that carries an SSH session, either a TCP socket or a tunnel
+/// through a jump target.
+pub(super) trait SshTransport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> SshTransport for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
 #[derive(Clone, PartialEq, Eq)]
 pub(crate) enum SshAuth {
     Password(String),
     PrivateKey {
         key_data: String,
         passphrase: Option<String>,
+        /// OpenSSH user certificate presented instead of the bare public key.
+        certificate: Option<String>,
+    },
+    /// Try every identity held by the ssh-agent in turn.
+    Agent,
+    /// Relay the server's prompts to the client attached to the target.
+    KeyboardInteractive,
+    /// Ask the client attached to the target for the password, or for the
+    /// passphrase of `key_data`, instead of storing it.
+    Prompted {
+        key_data: Option<String>,
     },
 }
 
 impl SshAuth {
-    fn kind(&self) -> &'static str {
+    pub(super) fn kind(&self) -> &'static str {
         match self {
             Self::Password(_) => "password",
             Self::PrivateKey { .. } => "private_key",
+            Self::Agent => "agent",
+            Self::KeyboardInteractive => "keyboard_interactive",
+            Self::Prompted { .. } => "prompted",
         }
     }
+
+    /// Whether authenticating needs a client to answer prompts.
+    pub(super) fn is_interactive(&self) -> bool {
+        matches!(self, Self::KeyboardInteractive | Self::Prompted { .. })
+    }
+}
+
+type DynAgentClient = AgentClient<Box<dyn AgentStream + Send + Unpin>>;
+
+#[cfg(unix)]
+async fn connect_agent(socket: &Path) -> Result<DynAgentClient, russh::keys::Error> {
+    Ok(AgentClient::connect_uds(socket).await?.dynamic())
+}
+
+#[cfg(windows)]
+async fn connect_agent(socket: &Path) -> Result<DynAgentClient, russh::keys::Error> {
+    Ok(AgentClient::connect_named_pipe(socket).await?.dynamic())
 }
 
 #[derive(Clone, PartialEq, Eq)]
 pub(crate) struct SshConnectionSpec {
-    target_id: i32,
-    username: String,
-    host: String,
-    port: u16,
-    auth: SshAuth,
+    pub(super) target_id: i32,
+    pub(super) username: String,
+    pub(super) host: String,
+    pub(super) port: u16,
+    pub(super) auth: SshAuth,
+    jump_target_id: Option<i32>,
+    pub(super) proxy: Option<SshProxy>,
+    pub(super) proxy_command: Option<String>,
+    algorithms: TargetAlgorithms,
+    connect_timeout: Option<Duration>,
+    connect_retries: u32,
+    min_idle_connections: usize,
+    host_key_policy: Option<CheckServerKey>,
 }
 
 impl SshConnectionSpec {
@@ -227,9 +298,72 @@ impl SshConnectionSpec {
             host,
             port,
             auth,
+            jump_target_id: None,
+            proxy: None,
+            proxy_command: None,
+            algorithms: TargetAlgorithms::default(),
+            connect_timeout: None,
+            connect_retries: 0,
+            min_idle_connections: 0,
+            host_key_policy: None,
         }
     }
 
+    pub(crate) fn with_jump_target_id(mut self, jump_target_id: Option<i32>) -> Self {
+        self.jump_target_id = jump_target_id;
+        self
+    }
+
+    /// Reaches the target through `proxy` unless it is behind a jump
+    /// target.
+    pub(crate) fn with_proxy(mut self, proxy: Option<SshProxy>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Uses the stdin/stdout of `proxy_command` as the transport, like
+    /// OpenSSH's `ProxyCommand`. Takes precedence over `proxy`.
+    pub(crate) fn with_proxy_command(mut self, proxy_command: Option<String>) -> Self {
+        self.proxy_command = proxy_command;
+        self
+    }
+
+    /// Algorithm preferences offered in the key exchange.
+    pub(crate) fn with_algorithms(mut self, algorithms: TargetAlgorithms) -> Self {
+        self.algorithms = algorithms;
+        self
+    }
+
+    /// Time allowed for each connection attempt, the connector's default
+    /// when `None`.
+    pub(crate) fn with_connect_timeout(mut self, connect_timeout: Option<Duration>) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// How many more times a transient connect failure is retried, with
+    /// exponential backoff.
+    pub(crate) fn with_connect_retries(mut self, connect_retries: u32) -> Self {
+        self.connect_retries = connect_retries;
+        self
+    }
+
+    /// Authenticated connections without channels the pool keeps ready.
+    pub(crate) fn with_min_idle_connections(mut self, min_idle_connections: usize) -> Self {
+        self.min_idle_connections = min_idle_connections;
+        self
+    }
+
+    pub(crate) fn min_idle_connections(&self) -> usize {
+        self.min_idle_connections
+    }
+
+    /// Host key policy of the target, overriding the pool-wide policy.
+    pub(crate) fn with_host_key_policy(mut self, host_key_policy: Option<CheckServerKey>) -> Self {
+        self.host_key_policy = host_key_policy;
+        self
+    }
+
     pub(crate) fn target_id(&self) -> i32 {
         self.target_id
     }
@@ -243,19 +377,46 @@ impl fmt::Debug for SshConnectionSpec {
             .field("host", &self.host)
             .field("port", &self.port)
             .field("auth", &self.auth.kind())
+            .field("jump_target_id", &self.jump_target_id)
+            .field("proxy", &self.proxy)
+            .field("proxy_command", &self.proxy_command)
+            .field("algorithms", &self.algorithms)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("connect_retries", &self.connect_retries)
+            .field("min_idle_connections", &self.min_idle_connections)
+            .field("host_key_policy", &self.host_key_policy)
             .finish()
     }
 }
 
+/// Session that finished its handshake and waits for authentication.
+pub(super) struct SshSession {
+    pub(super) handle: russh::client::Handle<SshClientHandler>,
+    pub(super) disconnected: oneshot::Receiver<String>,
+    pub(super) algorithms: Option<NegotiatedAlgorithms>,
+}
+
 pub(crate) struct ConnectedSsh {
     pub(crate) handle: russh::client::Handle<SshClientHandler>,
-    pub(crate) disconnected: oneshot::Receiver<()>,
+    /// Receives why the session ended.
+    pub(crate) disconnected: oneshot::Receiver<String>,
+    pub(crate) target_id: i32,
+    pub(crate) events: ConnectionEvents,
+    pub(crate) traffic: Arc<TrafficCounters>,
+    /// ssh-agent identity that authenticated the connection, as
+    /// `fingerprint (comment)`.
+    pub(crate) agent_identity: Option<String>,
+    pub(crate) algorithms: Option<NegotiatedAlgorithms>,
 }
 
 #[derive(Clone)]
 pub struct SshConnector {
     known_hosts: KnownHosts,
     connect_timeout: Duration,
+    keepalive_interval: Option<Duration>,
+    keepalive_max: usize,
+    agent_socket: Option<PathBuf>,
+    events: ConnectionEvents,
 }
 
 impl SshConnector {
@@ -263,119 +424,584 @@ impl SshConnector {
         Self {
             known_hosts,
             connect_timeout: CONNECT_TIMEOUT,
+            keepalive_interval: None,
+            keepalive_max: KEEPALIVE_MAX,
+            agent_socket: None,
+            events: ConnectionEvents::new(),
         }
     }
 
-    pub(crate) async fn connect(&self, spec: &SshConnectionSpec) -> SshPoolResult<ConnectedSsh> {
-        let timeout = self.connect_timeout;
-        let deadline = ConnectDeadline::new(timeout);
-        self.connect_inner(spec, deadline).await
+    pub(crate) fn events(&self) -> &ConnectionEvents {
+        &self.events
+    }
+
+    pub(crate) fn with_agent_socket(mut self, agent_socket: Option<PathBuf>) -> Self {
+        self.agent_socket = agent_socket;
+        self
+    }
+
+    /// Sends `keepalive@openssh.com` after `interval` without traffic and
+    /// closes the connection once `max` probes in a row went unanswered.
+    pub(crate) fn with_keepalive(mut self, interval: Option<Duration>, max: usize) -> Self {
+        self.keepalive_interval = interval;
+        self.keepalive_max = max;
+        self
+    }
+
+    /// Connects to `spec`, tunnelling through a `direct-tcpip` channel of
+    /// `jump` when the target is only reachable through a jump target.
+    /// `session_secret` caches the answer of a prompted target, and
+    /// `prompter` is the client that answers prompts for this connect.
+    pub(crate) async fn connect(
+        &self,
+        spec: &SshConnectionSpec,
+        jump: Option<&Arc<TargetConnectionPool>>,
+        session_secret: &SessionSecret,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
+    ) -> SshPoolResult<ConnectedSsh> {
+        let timeout = self.connect_timeout(spec);
+        self.events
+            .emit(spec.target_id, None, ConnectionEventKind::Connecting, 0);
+        let mut attempt = 0;
+        let result = loop {
+            let deadline = ConnectDeadline::new(timeout);
+            match self
+                .connect_inner(spec, jump, session_secret, prompter, deadline)
+                .await
+            {
+                Err(err) if attempt < spec.connect_retries && err.is_transient() => {
+                    let delay = connect_retry_delay(attempt);
+                    attempt += 1;
+                    warn!(
+                        target_id = spec.target_id,
+                        attempt,
+                        ?delay,
+                        %err,
+                        "SSH connect failed, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => break result,
+            }
+        };
+        if let Err(err) = &result {
+            self.events.emit(
+                spec.target_id,
+                None,
+                ConnectionEventKind::ConnectFailed {
+                    reason: err.to_string(),
+                },
+                0,
+            );
+        }
+        result
+    }
+
+    /// Time allowed for each connection attempt to `spec`.
+    pub(super) fn connect_timeout(&self, spec: &SshConnectionSpec) -> Duration {
+        spec.connect_timeout.unwrap_or(self.connect_timeout)
+    }
+
+    pub(super) async fn open_transport(
+        &self,
+        spec: &SshConnectionSpec,
+        jump: Option<&Arc<TargetConnectionPool>>,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
+        deadline: &ConnectDeadline,
+        nodelay: bool,
+    ) -> SshPoolResult<Box<dyn SshTransport>> {
+        if let Some(jump) = jump {
+            let tunnel = deadline
+                .run(jump.open_direct_tcpip(spec.host.as_str(), spec.port, prompter))
+                .await?;
+            debug!(
+                target_id = spec.target_id,
+                jump_target_id = jump.target_id(),
+                host = spec.host,
+                port = spec.port,
+                "opened SSH jump tunnel"
+            );
+            return Ok(Box::new(tunnel));
+        }
+
+        if let Some(proxy_command) = &spec.proxy_command {
+            let command = expand_proxy_command(
+                proxy_command,
+                spec.host.as_str(),
+                spec.port,
+                spec.username.as_str(),
+            )?;
+            let stream = ProxyCommandStream::spawn(&command)?;
+            debug!(
+                target_id = spec.target_id,
+                command, "opened SSH ProxyCommand transport"
+            );
+            return Ok(Box::new(stream));
+        }
+
+        if let Some(proxy) = &spec.proxy {
+            let socket = deadline
+                .run(proxy.connect(spec.host.as_str(), spec.port))
+                .await?;
+            debug!(
+                target_id = spec.target_id,
+                ?proxy,
+                host = spec.host,
+                port = spec.port,
+                "opened SSH proxy tunnel"
+            );
+            if nodelay && let Err(err) = socket.set_nodelay(true) {
+                warn!(?err, "failed to enable TCP_NODELAY for SSH connection");
+            }
+            return Ok(Box::new(socket));
+        }
+
+        let addresses = deadline.run(resolve(spec.host.as_str(), spec.port)).await?;
+        // Not run under the deadline, so that the attempts made before it
+        // are reported.
+        let socket =
+            connect_resolved(spec.host.as_str(), spec.port, addresses, deadline.at()).await?;
+        if nodelay && let Err(err) = socket.set_nodelay(true) {
+            warn!(?err, "failed to enable TCP_NODELAY for SSH connection");
+        }
+        Ok(Box::new(socket))
     }
 
     async fn connect_inner(
         &self,
         spec: &SshConnectionSpec,
+        jump: Option<&Arc<TargetConnectionPool>>,
+        session_secret: &SessionSecret,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
         deadline: ConnectDeadline,
     ) -> SshPoolResult<ConnectedSsh> {
+        let transport = self
+            .open_transport(spec, jump, prompter, &deadline, true)
+            .await?;
+        let traffic = Arc::new(TrafficCounters::default());
+        let SshSession {
+            mut handle,
+            disconnected,
+            algorithms,
+        } = self
+            .start_session(spec, transport, prompter, &deadline, traffic.clone(), None)
+            .await?;
+
+        let (auth_result, agent_identity) = self
+            .authenticate(&mut handle, spec, session_secret, prompter, &deadline)
+            .await?;
+        if !auth_result.success() {
+            return Err(SshPoolError::AuthenticationFailed);
+        }
+        deadline.disable();
+
+        debug!(
+            target_id = spec.target_id,
+            host = spec.host,
+            port = spec.port,
+            agent_identity = agent_identity.as_deref(),
+            "SSH connection established"
+        );
+
+        Ok(ConnectedSsh {
+            handle,
+            disconnected,
+            target_id: spec.target_id,
+            events: self.events.clone(),
+            traffic,
+            agent_identity,
+            algorithms,
+        })
+    }
+
+    /// Runs the SSH handshake over `transport` up to, but not including,
+    /// authentication. With `host_key_probe` the server key is only
+    /// classified into the probe and never remembered or confirmed.
+    pub(super) async fn start_session(
+        &self,
+        spec: &SshConnectionSpec,
+        transport: Box<dyn SshTransport>,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
+        deadline: &ConnectDeadline,
+        traffic: Arc<TrafficCounters>,
+        host_key_probe: Option<HostKeyProbe>,
+    ) -> SshPoolResult<SshSession> {
         let config = russh::client::Config {
             window_size: 16 * 1024 * 1024,
             maximum_packet_size: 64 * 1024,
             nodelay: true,
-            preferred: Preferred {
-                cipher: std::borrow::Cow::Borrowed(&[
-                    cipher::AES_128_GCM,
-                    cipher::AES_256_GCM,
-                    cipher::AES_128_CTR,
-                    cipher::AES_256_CTR,
-                    cipher::CHACHA20_POLY1305,
-                ]),
-                compression: std::borrow::Cow::Borrowed(&[compression::NONE]),
-                ..Default::default()
-            },
+            keepalive_interval: self.keepalive_interval,
+            keepalive_max: self.keepalive_max,
+            preferred: preferred_algorithms(&spec.algorithms)?,
             ..Default::default()
         };
 
-        let pinned_server_public_keys = deadline
+        let known_server_keys = deadline
             .run(self.known_hosts.load(&spec.host, spec.port))
             .await?;
         let (disconnect_tx, disconnect_rx) = oneshot::channel();
+        let negotiated = Arc::new(std::sync::Mutex::new(None));
         let handler = SshClientHandler {
+            target_id: spec.target_id,
             host: spec.host.clone(),
             port: spec.port,
             known_hosts: self.known_hosts.clone(),
-            pinned_server_public_keys,
+            host_key_policy: spec
+                .host_key_policy
+                .unwrap_or_else(|| self.known_hosts.policy()),
+            prompter: prompter.cloned(),
+            known_server_keys,
             disconnect_tx: Some(disconnect_tx),
             connect_deadline: deadline.clone(),
+            negotiated: negotiated.clone(),
+            host_key_probe,
         };
 
-        let socket = match tokio::time::timeout_at(
-            deadline.at(),
-            TcpStream::connect((spec.host.as_str(), spec.port)),
-        )
-        .await
-        {
-            Ok(Ok(socket)) => socket,
-            Ok(Err(err)) => return Err(russh::Error::from(err).into()),
-            Err(_) => {
-                deadline.mark_timed_out();
-                return Err(deadline.pool_error());
-            }
-        };
-        if config.nodelay
-            && let Err(err) = socket.set_nodelay(true)
-        {
-            warn!(?err, "failed to enable TCP_NODELAY for SSH connection");
-        }
-        let stream = DeadlineStream::new(socket, deadline.clone());
+        let stream = DeadlineStream::new(CountingStream::new(transport, traffic), deadline.clone());
         let handle_result = russh::client::connect_stream(Arc::new(config), stream, handler).await;
-        let mut handle = deadline.map_result(handle_result)?;
+        let handle = deadline.map_result(handle_result)?;
+        let algorithms = negotiated
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone();
+        Ok(SshSession {
+            handle,
+            disconnected: disconnect_rx,
+            algorithms,
+        })
+    }
 
+    /// Authenticates `handle` with the credentials of `spec` and returns
+    /// the ssh-agent identity that was accepted, if any.
+    pub(super) async fn authenticate(
+        &self,
+        handle: &mut russh::client::Handle<SshClientHandler>,
+        spec: &SshConnectionSpec,
+        session_secret: &SessionSecret,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
+        deadline: &ConnectDeadline,
+    ) -> SshPoolResult<(AuthResult, Option<String>)> {
+        let mut agent_identity = None;
         let auth_result = match &spec.auth {
-            SshAuth::Password(password) => {
+            SshAuth::Password(password) => handle
+                .authenticate_password(spec.username.as_str(), password.as_str())
+                .await
+                .map_err(SshPoolError::from),
+            SshAuth::PrivateKey {
+                key_data,
+                passphrase,
+                certificate: None,
+            } => {
+                let private_key = decode_secret_key(key_data, passphrase.as_deref())?;
+                let private_key =
+                    PrivateKeyWithHashAlg::new(Arc::new(private_key), Some(HashAlg::Sha256));
                 handle
-                    .authenticate_password(spec.username.as_str(), password.as_str())
+                    .authenticate_publickey(spec.username.as_str(), private_key)
                     .await
+                    .map_err(SshPoolError::from)
             }
             SshAuth::PrivateKey {
                 key_data,
                 passphrase,
+                certificate: Some(certificate),
             } => {
                 let private_key = decode_secret_key(key_data, passphrase.as_deref())?;
+                let certificate = usable_certificate(certificate, &private_key)?;
+                handle
+                    .authenticate_openssh_cert(
+                        spec.username.as_str(),
+                        Arc::new(private_key),
+                        certificate,
+                    )
+                    .await
+                    .map_err(SshPoolError::from)
+            }
+            SshAuth::Agent => deadline
+                .run(self.authenticate_with_agent(handle, spec))
+                .await
+                .map(|(auth_result, identity)| {
+                    agent_identity = identity;
+                    auth_result
+                }),
+            SshAuth::KeyboardInteractive => {
+                deadline
+                    .run(self.authenticate_keyboard_interactive(handle, spec, prompter))
+                    .await
+            }
+            SshAuth::Prompted { key_data } => {
+                deadline
+                    .run(self.authenticate_with_session_secret(
+                        handle,
+                        spec,
+                        key_data.as_deref(),
+                        session_secret,
+                        prompter,
+                    ))
+                    .await
+            }
+        };
+        Ok((deadline.map_result(auth_result)?, agent_identity))
+    }
+
+    /// Runs keyboard-interactive rounds until the server decides, asking
+    /// `prompter` to answer each round.
+    async fn authenticate_keyboard_interactive(
+        &self,
+        handle: &mut russh::client::Handle<SshClientHandler>,
+        spec: &SshConnectionSpec,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
+    ) -> SshPoolResult<AuthResult> {
+        let mut response = handle
+            .authenticate_keyboard_interactive_start(spec.username.as_str(), None)
+            .await?;
+        loop {
+            let (name, instructions, prompts) = match response {
+                KeyboardInteractiveAuthResponse::Success => return Ok(AuthResult::Success),
+                KeyboardInteractiveAuthResponse::Failure {
+                    remaining_methods,
+                    partial_success,
+                } => {
+                    return Ok(AuthResult::Failure {
+                        remaining_methods,
+                        partial_success,
+                    });
+                }
+                KeyboardInteractiveAuthResponse::InfoRequest {
+                    name,
+                    instructions,
+                    prompts,
+                } => (name, instructions, prompts),
+            };
+
+            let answers = if prompts.is_empty() {
+                Vec::new()
+            } else {
+                debug!(
+                    target_id = spec.target_id,
+                    prompts = prompts.len(),
+                    "relaying keyboard-interactive prompts"
+                );
+                let prompts = prompts
+                    .into_iter()
+                    .map(|prompt| AuthPrompt {
+                        prompt: prompt.prompt,
+                        echo: prompt.echo,
+                    })
+                    .collect();
+                relay_prompts(prompter, spec, name, instructions, prompts).await?
+            };
+            response = handle
+                .authenticate_keyboard_interactive_respond(answers)
+                .await?;
+        }
+    }
+
+    /// Authenticates with the password or key passphrase kept in
+    /// `session_secret`, asking `prompter` when none is kept yet. Only an
+    /// accepted answer is kept for later connections.
+    async fn authenticate_with_session_secret(
+        &self,
+        handle: &mut russh::client::Handle<SshClientHandler>,
+        spec: &SshConnectionSpec,
+        key_data: Option<&str>,
+        session_secret: &SessionSecret,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
+    ) -> SshPoolResult<AuthResult> {
+        let secret = match session_secret.get() {
+            Some(secret) => secret,
+            None => {
+                let prompt = match key_data {
+                    Some(_) => "Enter passphrase for key: ".to_string(),
+                    None => format!("{}@{}'s password: ", spec.username, spec.host),
+                };
+                let prompt = AuthPrompt {
+                    prompt,
+                    echo: false,
+                };
+                let mut answers =
+                    relay_prompts(prompter, spec, String::new(), String::new(), vec![prompt])
+                        .await?;
+                Zeroizing::new(answers.remove(0))
+            }
+        };
+
+        let auth_result = match key_data {
+            Some(key_data) => {
+                let private_key = match decode_secret_key(key_data, Some(secret.as_str())) {
+                    Ok(private_key) => private_key,
+                    Err(err) => {
+                        session_secret.clear();
+                        return Err(err.into());
+                    }
+                };
                 let private_key =
                     PrivateKeyWithHashAlg::new(Arc::new(private_key), Some(HashAlg::Sha256));
                 handle
                     .authenticate_publickey(spec.username.as_str(), private_key)
-                    .await
+                    .await?
+            }
+            None => {
+                handle
+                    .authenticate_password(spec.username.as_str(), secret.as_str())
+                    .await?
             }
         };
-        let auth_result = deadline.map_result(auth_result.map_err(SshPoolError::from))?;
+        if auth_result.success() {
+            session_secret.set(secret);
+        } else {
+            session_secret.clear();
+        }
+        Ok(auth_result)
+    }
 
-        if !auth_result.success() {
-            return Err(SshPoolError::AuthenticationFailed);
+    /// Offers each ssh-agent identity until the server accepts one and
+    /// returns the accepted identity alongside the final result.
+    async fn authenticate_with_agent(
+        &self,
+        handle: &mut russh::client::Handle<SshClientHandler>,
+        spec: &SshConnectionSpec,
+    ) -> SshPoolResult<(AuthResult, Option<String>)> {
+        let socket = self
+            .agent_socket
+            .as_deref()
+            .ok_or(SshPoolError::AgentNotConfigured)?;
+        let agent_unavailable = |source| SshPoolError::AgentUnavailable {
+            socket: socket.to_path_buf(),
+            source,
+        };
+        let mut agent = connect_agent(socket).await.map_err(agent_unavailable)?;
+        let identities = agent
+            .request_identities()
+            .await
+            .map_err(agent_unavailable)?;
+        if identities.is_empty() {
+            return Err(SshPoolError::AgentNoIdentities {
+                socket: socket.to_path_buf(),
+            });
         }
-        deadline.disable();
 
-        debug!(
-            target_id = spec.target_id,
-            host = spec.host,
-            port = spec.port,
-            "SSH connection established"
-        );
+        let mut auth_result = AuthResult::Failure {
+            remaining_methods: russh::MethodSet::empty(),
+            partial_success: false,
+        };
+        for identity in identities {
+            let public_key = identity.public_key();
+            let hash_alg = if public_key.algorithm().is_rsa() {
+                handle.best_supported_rsa_hash().await?.flatten()
+            } else {
+                None
+            };
+            let description = format!(
+                "{} ({})",
+                public_key.fingerprint(HashAlg::Sha256),
+                identity.comment()
+            );
+            auth_result = match &identity {
+                AgentIdentity::PublicKey { key, .. } => {
+                    handle
+                        .authenticate_publickey_with(
+                            spec.username.as_str(),
+                            key.clone(),
+                            hash_alg,
+                            &mut agent,
+                        )
+                        .await?
+                }
+                AgentIdentity::Certificate { certificate, .. } => {
+                    handle
+                        .authenticate_certificate_with(
+                            spec.username.as_str(),
+                            certificate.clone(),
+                            hash_alg,
+                            &mut agent,
+                        )
+                        .await?
+                }
+            };
+            if auth_result.success() {
+                return Ok((auth_result, Some(description)));
+            }
+            debug!(
+                target_id = spec.target_id,
+                identity = description,
+                "ssh-agent identity rejected"
+            );
+        }
+        Ok((auth_result, None))
+    }
+}
 
-        Ok(ConnectedSsh {
-            handle,
-            disconnected: disconnect_rx,
+/// Asks `prompter` to answer `prompts`, one answer per prompt.
+async fn relay_prompts(
+    prompter: Option<&Arc<dyn AuthPrompter>>,
+    spec: &SshConnectionSpec,
+    name: String,
+    instructions: String,
+    prompts: Vec<AuthPrompt>,
+) -> SshPoolResult<Vec<String>> {
+    let prompter = prompter.ok_or(SshPoolError::AuthPromptUnavailable {
+        target_id: spec.target_id,
+    })?;
+    let expected = prompts.len();
+    let answers = prompter
+        .prompt(AuthPromptRequest {
+            target_id: spec.target_id,
+            name,
+            instructions,
+            prompts,
         })
+        .await?;
+    if answers.len() != expected {
+        return Err(SshPoolError::AuthPromptFailed {
+            target_id: spec.target_id,
+            reason: format!("expected {expected} answers, got {}", answers.len()),
+        });
     }
+    Ok(answers)
+}
+
+/// Backoff before retrying a failed connect, doubling from
+/// `CONNECT_RETRY_BASE_DELAY` up to `CONNECT_RETRY_MAX_DELAY`.
+fn connect_retry_delay(attempt: u32) -> Duration {
+    CONNECT_RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(CONNECT_RETRY_MAX_DELAY)
+}
+
+/// Parses an OpenSSH user certificate and checks that it belongs to
+/// `private_key` and has not expired yet.
+fn usable_certificate(
+    certificate: &str,
+    private_key: &ssh_key::PrivateKey,
+) -> SshPoolResult<ssh_key::Certificate> {
+    let certificate =
+        ssh_key::Certificate::from_openssh(certificate.trim()).map_err(russh::keys::Error::from)?;
+    if certificate.public_key() != private_key.public_key().key_data() {
+        return Err(SshPoolError::CertificateKeyMismatch);
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    if certificate.valid_before() <= now {
+        return Err(SshPoolError::CertificateExpired {
+            valid_before: certificate.valid_before(),
+        });
+    }
+    Ok(certificate)
 }
 
 pub(crate) struct SshClientHandler {
+    target_id: i32,
     host: String,
     port: u16,
     known_hosts: KnownHosts,
-    pinned_server_public_keys: Vec<ServerPublicKey>,
-    disconnect_tx: Option<oneshot::Sender<()>>,
+    host_key_policy: CheckServerKey,
+    prompter: Option<Arc<dyn AuthPrompter>>,
+    known_server_keys: KnownHostKeys,
+    disconnect_tx: Option<oneshot::Sender<String>>,
     connect_deadline: ConnectDeadline,
+    negotiated: Arc<std::sync::Mutex<Option<NegotiatedAlgorithms>>>,
+    host_key_probe: Option<HostKeyProbe>,
 }
 
 impl russh::client::Handler for SshClientHandler {
@@ -385,16 +1011,16 @@ impl russh::client::Handler for SshClientHandler {
         &mut self,
         server_public_key: &ssh_key::PublicKey,
     ) -> impl Future<Output = SshPoolResult<bool>> + Send {
-        let observed = ServerPublicKey {
-            key_algorithm: server_public_key.algorithm().as_str().to_string(),
-            public_key: server_public_key.public_key_base64(),
-            fingerprint: server_public_key.fingerprint(HashAlg::Sha256).to_string(),
-        };
+        let observed = ServerPublicKey::from(server_public_key);
+        let target_id = self.target_id;
         let host = self.host.clone();
         let port = self.port;
         let known_hosts = self.known_hosts.clone();
-        let pinned = self.pinned_server_public_keys.clone();
+        let policy = self.host_key_policy;
+        let prompter = self.prompter.clone();
+        let known = self.known_server_keys.clone();
         let deadline = self.connect_deadline.clone();
+        let host_key_probe = self.host_key_probe.clone();
 
         async move {
             debug!(
@@ -404,18 +1030,53 @@ impl russh::client::Handler for SshClientHandler {
                 fingerprint = observed.fingerprint,
                 "checking SSH server key"
             );
+            if let Some(probe) = host_key_probe {
+                let verdict = host_key_verdict(policy, &host, port, &known, &observed);
+                *probe.lock().unwrap_or_else(|err| err.into_inner()) = Some(HostKeyReport {
+                    algorithm: observed.key_algorithm,
+                    fingerprint: observed.fingerprint,
+                    verdict,
+                });
+                return Ok(true);
+            }
             deadline
                 .run(async move {
-                    verify_server_key(known_hosts.policy(), &host, port, &pinned, &observed)?;
-                    known_hosts
-                        .remember_accept_new(&host, port, observed)
-                        .await?;
+                    match verify_server_key(policy, &host, port, &known, &observed) {
+                        Err(SshPoolError::HostKeyUnknown { .. })
+                            if policy == CheckServerKey::Ask =>
+                        {
+                            confirm_host_key(prompter.as_ref(), target_id, &host, port, &observed)
+                                .await?;
+                        }
+                        result => result?,
+                    }
+                    known_hosts.remember(policy, &host, port, observed).await?;
                     Ok(true)
                 })
                 .await
         }
     }
 
+    fn kex_done(
+        &mut self,
+        _shared_secret: Option<&[u8]>,
+        names: &russh::Names,
+        _session: &mut russh::client::Session,
+    ) -> impl Future<Output = SshPoolResult<()>> + Send {
+        let algorithms = NegotiatedAlgorithms::from(names);
+        debug!(
+            host = self.host,
+            port = self.port,
+            ?algorithms,
+            "SSH key exchange done"
+        );
+        *self
+            .negotiated
+            .lock()
+            .unwrap_or_else(|err| err.into_inner()) = Some(algorithms);
+        async { Ok(()) }
+    }
+
     fn disconnected(
         &mut self,
         reason: DisconnectReason<Self::Error>,
@@ -424,7 +1085,10 @@ impl russh::client::Handler for SshClientHandler {
         async move {
             debug!(?reason, "SSH connection disconnected");
             if let Some(tx) = disconnect_tx {
-                let _ = tx.send(());
+                let _ = tx.send(match &reason {
+                    DisconnectReason::ReceivedDisconnect(_) => "disconnected by server".to_string(),
+                    DisconnectReason::Error(err) => err.to_string(),
+                });
             }
             match reason {
                 DisconnectReason::ReceivedDisconnect(_) => Ok(()),
@@ -476,6 +1140,7 @@ mod tests {
                 SshAuth::PrivateKey {
                     key_data: "private-key-secret".to_string(),
                     passphrase: Some("passphrase-secret".to_string()),
+                    certificate: None,
                 },
             ),
         ];
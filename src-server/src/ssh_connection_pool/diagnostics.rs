@@ -0,0 +1,440 @@
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use russh::client::AuthResult;
+use tracing::warn;
+
+use crate::sftp_client::FastSftpClient;
+
+use super::{
+    algorithms::NegotiatedAlgorithms,
+    connector::{ConnectDeadline, SshConnectionSpec, SshConnector, SshSession, SshTransport},
+    prompt::SessionSecret,
+    target_connection_pool::TargetConnectionPool,
+    tcp::{connect_resolved, resolve},
+    traffic::TrafficCounters,
+};
+
+/// Receives the server key of a diagnostic handshake instead of checking it.
+pub(super) type HostKeyProbe = Arc<std::sync::Mutex<Option<HostKeyReport>>>;
+
+/// Stages of a connection test, in the order they run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticStage {
+    Dns,
+    Connect,
+    Banner,
+    KeyExchange,
+    HostKey,
+    AuthMethods,
+    Authentication,
+    Sftp,
+}
+
+impl DiagnosticStage {
+    const ALL: [Self; 8] = [
+        Self::Dns,
+        Self::Connect,
+        Self::Banner,
+        Self::KeyExchange,
+        Self::HostKey,
+        Self::AuthMethods,
+        Self::Authentication,
+        Self::Sftp,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StageStatus {
+    Passed,
+    Failed,
+    /// Not run, either because it does not apply to the route to the target
+    /// or because an earlier stage failed.
+    Skipped,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StageReport {
+    pub stage: DiagnosticStage,
+    pub status: StageStatus,
+    pub elapsed: Option<Duration>,
+    pub detail: Option<String>,
+}
+
+/// How the key presented by the server compares to the stored keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HostKeyVerdict {
+    /// The key is stored for the host.
+    Trusted,
+    /// No key is stored yet and the policy accepts the key on first use.
+    New,
+    /// No key is stored and the policy requires one, or a confirmation.
+    Unknown,
+    /// Another key is stored for the host.
+    Changed,
+    Revoked,
+    /// Host key checking is disabled.
+    Unchecked,
+}
+
+impl HostKeyVerdict {
+    /// Whether a real connection would go on to authenticate.
+    pub fn accepted(self) -> bool {
+        matches!(self, Self::Trusted | Self::New | Self::Unchecked)
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::Trusted => "matches a stored key",
+            Self::New => "not stored yet, accepted on first use",
+            Self::Unknown => "not stored, must be stored or confirmed first",
+            Self::Changed => "differs from the stored key",
+            Self::Revoked => "revoked",
+            Self::Unchecked => "host key checking is disabled",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostKeyReport {
+    pub algorithm: String,
+    /// SHA256 fingerprint.
+    pub fingerprint: String,
+    pub verdict: HostKeyVerdict,
+}
+
+/// Outcome of a connection test, one report per `DiagnosticStage`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionDiagnostics {
+    pub stages: Vec<StageReport>,
+    /// Addresses the host name resolved to, empty when it is resolved by a
+    /// proxy or jump target.
+    pub addresses: Vec<SocketAddr>,
+    /// Identification string sent by the server, e.g. `SSH-2.0-OpenSSH_9.6`.
+    pub server_version: Option<String>,
+    pub algorithms: Option<NegotiatedAlgorithms>,
+    pub host_key: Option<HostKeyReport>,
+    /// Authentication methods the server offers, as named on the wire.
+    pub auth_methods: Vec<String>,
+}
+
+impl ConnectionDiagnostics {
+    /// Whether no stage failed.
+    pub fn passed(&self) -> bool {
+        self.stages
+            .iter()
+            .all(|stage| stage.status != StageStatus::Failed)
+    }
+
+    pub fn stage(&self, stage: DiagnosticStage) -> Option<&StageReport> {
+        self.stages.iter().find(|report| report.stage == stage)
+    }
+
+    fn record(
+        &mut self,
+        stage: DiagnosticStage,
+        status: StageStatus,
+        elapsed: Option<Duration>,
+        detail: Option<String>,
+    ) {
+        self.stages.push(StageReport {
+            stage,
+            status,
+            elapsed,
+            detail,
+        });
+    }
+
+    fn pass(&mut self, stage: DiagnosticStage, started: Instant, detail: Option<String>) {
+        self.record(stage, StageStatus::Passed, Some(started.elapsed()), detail);
+    }
+
+    fn fail(&mut self, stage: DiagnosticStage, started: Instant, detail: impl ToString) {
+        self.record(
+            stage,
+            StageStatus::Failed,
+            Some(started.elapsed()),
+            Some(detail.to_string()),
+        );
+    }
+
+    /// Marks every stage without a report as skipped.
+    fn skip_remaining(&mut self) {
+        for stage in DiagnosticStage::ALL {
+            if self.stage(stage).is_none() {
+                self.record(stage, StageStatus::Skipped, None, None);
+            }
+        }
+    }
+}
+
+impl SshConnector {
+    /// Connects to `spec` once, stage by stage, and reports how far it got.
+    /// The host key is neither remembered nor confirmed, and credentials are
+    /// only sent when a real connection would accept the key. The
+    /// connection is closed afterwards.
+    pub(crate) async fn diagnose(
+        &self,
+        spec: &SshConnectionSpec,
+        jump: Option<&Arc<TargetConnectionPool>>,
+    ) -> ConnectionDiagnostics {
+        let mut report = ConnectionDiagnostics::default();
+        self.run_diagnosis(spec, jump, &mut report).await;
+        report.skip_remaining();
+        report
+    }
+
+    async fn run_diagnosis(
+        &self,
+        spec: &SshConnectionSpec,
+        jump: Option<&Arc<TargetConnectionPool>>,
+        report: &mut ConnectionDiagnostics,
+    ) {
+        let deadline = ConnectDeadline::new(self.connect_timeout(spec));
+        let Some(transport) = self.diagnose_transport(spec, jump, &deadline, report).await else {
+            return;
+        };
+
+        let traffic = Arc::new(TrafficCounters::default());
+        let probe = HostKeyProbe::default();
+        let started = Instant::now();
+        let session = self
+            .start_session(
+                spec,
+                transport,
+                None,
+                &deadline,
+                traffic.clone(),
+                Some(probe.clone()),
+            )
+            .await;
+        report.server_version = traffic.server_version().map(str::to_string);
+        let Some(server_version) = report.server_version.clone() else {
+            let detail = match &session {
+                Err(err) => err.to_string(),
+                Ok(_) => "server sent no SSH identification".to_string(),
+            };
+            report.fail(DiagnosticStage::Banner, started, detail);
+            return;
+        };
+        report.pass(DiagnosticStage::Banner, started, Some(server_version));
+        let SshSession {
+            mut handle,
+            algorithms,
+            ..
+        } = match session {
+            Ok(session) => session,
+            Err(err) => {
+                report.fail(DiagnosticStage::KeyExchange, started, err);
+                return;
+            }
+        };
+        let detail = algorithms.as_ref().map(|algorithms| {
+            format!(
+                "{}, {}, {}",
+                algorithms.kex, algorithms.host_key, algorithms.cipher
+            )
+        });
+        report.algorithms = algorithms;
+        report.pass(DiagnosticStage::KeyExchange, started, detail);
+
+        let started = Instant::now();
+        let host_key = probe.lock().unwrap_or_else(|err| err.into_inner()).take();
+        let Some(host_key) = host_key else {
+            report.fail(
+                DiagnosticStage::HostKey,
+                started,
+                "server presented no host key",
+            );
+            return;
+        };
+        let detail = format!(
+            "{} {}: {}",
+            host_key.algorithm,
+            host_key.fingerprint,
+            host_key.verdict.description()
+        );
+        let accepted = host_key.verdict.accepted();
+        report.host_key = Some(host_key);
+        if !accepted {
+            report.fail(DiagnosticStage::HostKey, started, detail);
+            return;
+        }
+        report.pass(DiagnosticStage::HostKey, started, Some(detail));
+
+        let started = Instant::now();
+        let none_result = deadline
+            .run(async { Ok(handle.authenticate_none(spec.username.as_str()).await?) })
+            .await;
+        match none_result {
+            Ok(AuthResult::Success) => {
+                report.pass(
+                    DiagnosticStage::AuthMethods,
+                    started,
+                    Some("none".to_string()),
+                );
+                report.pass(
+                    DiagnosticStage::Authentication,
+                    started,
+                    Some("server requires no authentication".to_string()),
+                );
+            }
+            Ok(AuthResult::Failure {
+                remaining_methods, ..
+            }) => {
+                report.auth_methods = remaining_methods
+                    .iter()
+                    .map(|method| <&str>::from(method).to_string())
+                    .collect();
+                report.pass(
+                    DiagnosticStage::AuthMethods,
+                    started,
+                    Some(report.auth_methods.join(", ")),
+                );
+
+                let started = Instant::now();
+                let session_secret = SessionSecret::default();
+                match self
+                    .authenticate(&mut handle, spec, &session_secret, None, &deadline)
+                    .await
+                {
+                    Ok((AuthResult::Success, agent_identity)) => {
+                        let detail = match agent_identity {
+                            Some(identity) => format!("agent identity {identity}"),
+                            None => spec.auth.kind().to_string(),
+                        };
+                        report.pass(DiagnosticStage::Authentication, started, Some(detail));
+                    }
+                    Ok((
+                        AuthResult::Failure {
+                            remaining_methods,
+                            partial_success,
+                        },
+                        _,
+                    )) => {
+                        let detail = if partial_success {
+                            let remaining: Vec<&str> =
+                                remaining_methods.iter().map(<&str>::from).collect();
+                            format!(
+                                "{} accepted, server still requires one of: {}",
+                                spec.auth.kind(),
+                                remaining.join(", ")
+                            )
+                        } else {
+                            format!("{} rejected by the server", spec.auth.kind())
+                        };
+                        report.fail(DiagnosticStage::Authentication, started, detail);
+                        return;
+                    }
+                    Err(err) => {
+                        report.fail(DiagnosticStage::Authentication, started, err);
+                        return;
+                    }
+                }
+            }
+            Err(err) => {
+                report.fail(DiagnosticStage::AuthMethods, started, err);
+                return;
+            }
+        }
+        deadline.disable();
+
+        let started = Instant::now();
+        let timeout = self.connect_timeout(spec);
+        let sftp = tokio::time::timeout(timeout, async {
+            let channel = handle
+                .channel_open_session()
+                .await
+                .map_err(|err| err.to_string())?;
+            FastSftpClient::new_from_channel(channel)
+                .await
+                .map(drop)
+                .map_err(|err| err.to_string())
+        })
+        .await;
+        match sftp {
+            Ok(Ok(())) => report.pass(DiagnosticStage::Sftp, started, None),
+            Ok(Err(err)) => report.fail(DiagnosticStage::Sftp, started, err),
+            Err(_) => report.fail(
+                DiagnosticStage::Sftp,
+                started,
+                format!("timed out after {timeout:?}"),
+            ),
+        }
+    }
+
+    /// Resolves and connects directly where the route allows it, so that
+    /// both get a stage of their own. Proxies and jump targets resolve the
+    /// host themselves.
+    async fn diagnose_transport(
+        &self,
+        spec: &SshConnectionSpec,
+        jump: Option<&Arc<TargetConnectionPool>>,
+        deadline: &ConnectDeadline,
+        report: &mut ConnectionDiagnostics,
+    ) -> Option<Box<dyn SshTransport>> {
+        let route = match (jump, &spec.proxy_command, &spec.proxy) {
+            (Some(jump), _, _) => Some(format!("jump target {}", jump.target_id())),
+            (None, Some(_), _) => Some("ProxyCommand".to_string()),
+            (None, None, Some(proxy)) => Some(format!("proxy {proxy}")),
+            (None, None, None) => None,
+        };
+        if let Some(route) = route {
+            report.record(
+                DiagnosticStage::Dns,
+                StageStatus::Skipped,
+                None,
+                Some(format!("resolved by {route}")),
+            );
+            let started = Instant::now();
+            return match self.open_transport(spec, jump, None, deadline, true).await {
+                Ok(transport) => {
+                    report.pass(
+                        DiagnosticStage::Connect,
+                        started,
+                        Some(format!("through {route}")),
+                    );
+                    Some(transport)
+                }
+                Err(err) => {
+                    report.fail(DiagnosticStage::Connect, started, err);
+                    None
+                }
+            };
+        }
+
+        let started = Instant::now();
+        let addresses = match deadline.run(resolve(&spec.host, spec.port)).await {
+            Ok(addresses) => addresses,
+            Err(err) => {
+                report.fail(DiagnosticStage::Dns, started, err);
+                return None;
+            }
+        };
+        let detail = addresses
+            .iter()
+            .map(|address| address.ip().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        report.addresses = addresses.clone();
+        report.pass(DiagnosticStage::Dns, started, Some(detail));
+
+        let started = Instant::now();
+        let socket = match connect_resolved(&spec.host, spec.port, addresses, deadline.at()).await {
+            Ok(socket) => socket,
+            Err(err) => {
+                report.fail(DiagnosticStage::Connect, started, err);
+                return None;
+            }
+        };
+        let peer = socket.peer_addr().ok().map(|peer| peer.to_string());
+        report.pass(DiagnosticStage::Connect, started, peer);
+        if let Err(err) = socket.set_nodelay(true) {
+            warn!(?err, "failed to enable TCP_NODELAY for SSH connection");
+        }
+        Some(Box::new(socket))
+    }
+}
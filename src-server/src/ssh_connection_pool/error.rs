@@ -1,4 +1,4 @@
-use std::{error::Error, fmt, time::Duration};
+use std::{error::Error, fmt, io, net::SocketAddr, path::PathBuf, time::Duration};
 
 pub type SshPoolResult<T> = Result<T, SshPoolError>;
 
@@ -8,8 +8,38 @@ pub enum SshPoolError {
     ConnectTimeout {
         timeout: Duration,
     },
+    ResolveFailed {
+        host: String,
+        port: u16,
+        source: io::Error,
+    },
+    /// Every resolved address refused the TCP connection.
+    ConnectFailed {
+        host: String,
+        port: u16,
+        attempts: Vec<(SocketAddr, io::Error)>,
+    },
     UnsupportedAuthMethod,
     AuthenticationFailed,
+    CertificateExpired {
+        valid_before: u64,
+    },
+    CertificateKeyMismatch,
+    AgentNotConfigured,
+    AgentUnavailable {
+        socket: PathBuf,
+        source: russh::keys::Error,
+    },
+    AgentNoIdentities {
+        socket: PathBuf,
+    },
+    AuthPromptUnavailable {
+        target_id: i32,
+    },
+    AuthPromptFailed {
+        target_id: i32,
+        reason: String,
+    },
     ConnectionExpired {
         connection_id: String,
     },
@@ -17,6 +47,40 @@ pub enum SshPoolError {
         resource: &'static str,
         limit: usize,
     },
+    JumpTargetNotFound {
+        target_id: i32,
+        jump_target_id: i32,
+    },
+    JumpTargetCycle {
+        target_id: i32,
+    },
+    StoredKeyNotFound {
+        target_id: i32,
+        key_id: i32,
+    },
+    InvalidProxy {
+        reason: String,
+    },
+    Proxy {
+        proxy: String,
+        reason: String,
+    },
+    ProxyCommand {
+        command: String,
+        reason: String,
+    },
+    /// The target uses a `ProxyCommand` but the server does not allow them.
+    ProxyCommandDisabled,
+    /// A `%h` or `%r` value that is not safe to substitute into the
+    /// `ProxyCommand` shell line.
+    UnsafeProxyCommandToken {
+        token: char,
+        value: String,
+    },
+    UnsupportedAlgorithm {
+        kind: &'static str,
+        name: String,
+    },
     HostKeyUnknown {
         host: String,
         port: u16,
@@ -28,25 +92,160 @@ pub enum SshPoolError {
         expected_fingerprints: Vec<String>,
         actual_fingerprint: String,
     },
+    HostKeyRejected {
+        host: String,
+        port: u16,
+        fingerprint: String,
+    },
+    HostKeyRevoked {
+        host: String,
+        port: u16,
+        fingerprint: String,
+    },
     Key(russh::keys::Error),
     Ssh(russh::Error),
     Database(sea_orm::DbErr),
 }
 
+impl SshPoolError {
+    /// Whether the same request may succeed on a fresh connection, because
+    /// the failure came from the connection rather than from the target's
+    /// configuration.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::ConnectTimeout { .. } | Self::ConnectionExpired { .. } => true,
+            Self::ResolveFailed { source, .. } => is_transient_resolve_error(source),
+            Self::ConnectFailed { attempts, .. } => {
+                attempts.iter().any(|(_, err)| is_transient_io_error(err))
+            }
+            Self::Ssh(err) => is_transient_ssh_error(err),
+            _ => false,
+        }
+    }
+}
+
+/// Connection-level russh failures that reconnecting can recover from.
+pub(crate) fn is_transient_ssh_error(err: &russh::Error) -> bool {
+    match err {
+        russh::Error::Disconnect
+        | russh::Error::HUP
+        | russh::Error::ConnectionTimeout
+        | russh::Error::KeepaliveTimeout
+        | russh::Error::InactivityTimeout
+        | russh::Error::SendError
+        | russh::Error::RecvError
+        | russh::Error::Elapsed(_) => true,
+        russh::Error::IO(err) => is_transient_io_error(err),
+        _ => false,
+    }
+}
+
+pub(crate) fn is_transient_io_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::HostUnreachable
+            | io::ErrorKind::NetworkUnreachable
+    )
+}
+
+/// A lookup that found no addresses will not find any on retry, while a
+/// resolver that could not be reached may.
+fn is_transient_resolve_error(err: &io::Error) -> bool {
+    err.kind() != io::ErrorKind::NotFound
+}
+
 impl fmt::Display for SshPoolError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::ConnectTimeout { timeout } => {
                 write!(f, "SSH connection timed out after {timeout:?}")
             }
+            Self::ResolveFailed { host, port, source } => {
+                write!(f, "failed to resolve {host}:{port}: {source}")
+            }
+            Self::ConnectFailed {
+                host,
+                port,
+                attempts,
+            } => {
+                write!(f, "failed to connect to {host}:{port}")?;
+                for (index, (address, err)) in attempts.iter().enumerate() {
+                    let separator = if index == 0 { ": " } else { "; " };
+                    write!(f, "{separator}{address} ({err})")?;
+                }
+                Ok(())
+            }
             Self::UnsupportedAuthMethod => f.write_str("unsupported SSH authentication method"),
             Self::AuthenticationFailed => f.write_str("SSH authentication failed"),
+            Self::CertificateExpired { valid_before } => {
+                write!(f, "SSH user certificate expired at {valid_before}")
+            }
+            Self::CertificateKeyMismatch => {
+                f.write_str("SSH user certificate does not match the private key")
+            }
+            Self::AgentNotConfigured => f.write_str("no ssh-agent socket configured"),
+            Self::AgentUnavailable { socket, source } => {
+                write!(
+                    f,
+                    "ssh-agent at {} is unavailable: {source}",
+                    socket.display()
+                )
+            }
+            Self::AgentNoIdentities { socket } => {
+                write!(f, "ssh-agent at {} has no identities", socket.display())
+            }
+            Self::AuthPromptUnavailable { target_id } => write!(
+                f,
+                "SSH target {target_id} requires keyboard-interactive input but no client is attached"
+            ),
+            Self::AuthPromptFailed { target_id, reason } => write!(
+                f,
+                "keyboard-interactive prompt for SSH target {target_id} failed: {reason}"
+            ),
             Self::ConnectionExpired { connection_id } => {
                 write!(f, "SSH connection {connection_id} is no longer active")
             }
             Self::CapacityExceeded { resource, limit } => {
                 write!(f, "maximum {resource} capacity of {limit} reached")
             }
+            Self::JumpTargetNotFound {
+                target_id,
+                jump_target_id,
+            } => write!(
+                f,
+                "jump target {jump_target_id} of SSH target {target_id} not found"
+            ),
+            Self::StoredKeyNotFound { target_id, key_id } => {
+                write!(f, "stored key {key_id} of SSH target {target_id} not found")
+            }
+            Self::JumpTargetCycle { target_id } => {
+                write!(
+                    f,
+                    "jump target chain of SSH target {target_id} contains a cycle"
+                )
+            }
+            Self::InvalidProxy { reason } => write!(f, "invalid proxy: {reason}"),
+            Self::Proxy { proxy, reason } => write!(f, "proxy {proxy} failed: {reason}"),
+            Self::ProxyCommand { command, reason } => {
+                write!(f, "ProxyCommand `{command}` failed: {reason}")
+            }
+            Self::ProxyCommandDisabled => f.write_str(
+                "ProxyCommand is disabled on this server; set WEBSSH_RS_ALLOW_PROXY_COMMAND=true to allow it",
+            ),
+            Self::UnsafeProxyCommandToken { token, value } => write!(
+                f,
+                "%{token} value `{value}` contains characters that are not allowed in a ProxyCommand"
+            ),
+            Self::UnsupportedAlgorithm { kind, name } => {
+                write!(f, "unsupported SSH {kind} algorithm {name}")
+            }
             Self::HostKeyUnknown {
                 host,
                 port,
@@ -65,6 +264,22 @@ impl fmt::Display for SshPoolError {
                 "SSH host key mismatch for {host}:{port}: expected one of {}, got {actual_fingerprint}",
                 expected_fingerprints.join(", ")
             ),
+            Self::HostKeyRejected {
+                host,
+                port,
+                fingerprint,
+            } => write!(
+                f,
+                "SSH host key for {host}:{port} was rejected by the client ({fingerprint})"
+            ),
+            Self::HostKeyRevoked {
+                host,
+                port,
+                fingerprint,
+            } => write!(
+                f,
+                "SSH host key for {host}:{port} is revoked ({fingerprint})"
+            ),
             Self::Key(err) => err.fmt(f),
             Self::Ssh(err) => err.fmt(f),
             Self::Database(err) => err.fmt(f),
@@ -77,6 +292,8 @@ impl Error for SshPoolError {
         match self {
             Self::Ssh(err) => Some(err),
             Self::Key(err) => Some(err),
+            Self::AgentUnavailable { source, .. } => Some(source),
+            Self::ResolveFailed { source, .. } => Some(source),
             Self::Database(err) => Some(err),
             _ => None,
         }
@@ -89,6 +306,15 @@ impl From<russh::Error> for SshPoolError {
     }
 }
 
+impl From<russh::AgentAuthError> for SshPoolError {
+    fn from(err: russh::AgentAuthError) -> Self {
+        match err {
+            russh::AgentAuthError::Send(_) => Self::Ssh(russh::Error::SendError),
+            russh::AgentAuthError::Key(err) => Self::Key(err),
+        }
+    }
+}
+
 impl From<russh::keys::Error> for SshPoolError {
     fn from(err: russh::keys::Error) -> Self {
         Self::Key(err)
@@ -0,0 +1,78 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::sync::broadcast;
+
+const EVENT_CAPACITY: usize = 256;
+
+/// Lifecycle step of a pooled SSH connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionEventKind {
+    /// A new connection is being opened for the target.
+    Connecting,
+    /// Opening a new connection failed.
+    ConnectFailed {
+        reason: String,
+    },
+    /// The connection finished authentication and joined the pool.
+    Authenticated,
+    ChannelOpened,
+    ChannelClosed,
+    /// The connection takes no new channels and closes once idle.
+    Expiring,
+    Closed {
+        reason: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionEvent {
+    pub target_id: i32,
+    /// `None` until the connection is authenticated.
+    pub connection_id: Option<String>,
+    pub kind: ConnectionEventKind,
+    pub active_channels: usize,
+    /// Unix timestamp in milliseconds.
+    pub at: u64,
+}
+
+/// Broadcasts connection events to every subscriber. Events are dropped
+/// when nobody listens, and slow subscribers miss the oldest ones.
+#[derive(Clone)]
+pub(crate) struct ConnectionEvents {
+    tx: broadcast::Sender<ConnectionEvent>,
+}
+
+impl ConnectionEvents {
+    pub(crate) fn new() -> Self {
+        Self {
+            tx: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.tx.subscribe()
+    }
+
+    pub(crate) fn emit(
+        &self,
+        target_id: i32,
+        connection_id: Option<&str>,
+        kind: ConnectionEventKind,
+        active_channels: usize,
+    ) {
+        let _ = self.tx.send(ConnectionEvent {
+            target_id,
+            connection_id: connection_id.map(str::to_string),
+            kind,
+            active_channels,
+            at: now_ms(),
+        });
+    }
+}
+
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
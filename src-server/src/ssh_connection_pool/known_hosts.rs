@@ -1,13 +1,28 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use hmac::{Hmac, Mac};
+use russh::keys::{HashAlg, PublicKeyBase64, ssh_key};
 use sea_orm::{
-    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
-    sea_query::OnConflict,
+    ActiveValue::Set, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, sea_query::OnConflict,
 };
+use sha1::Sha1;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crate::{config::CheckServerKey, entities::ssh_known_host};
+use crate::{
+    config::CheckServerKey,
+    entities::ssh_known_host::{self, KnownHostMarker},
+    repositories::ssh_known_host::host_eq,
+};
+
+use super::{
+    diagnostics::HostKeyVerdict,
+    error::{SshPoolError, SshPoolResult},
+    prompt::{AuthPrompter, HostKeyPromptRequest},
+};
 
-use super::error::{SshPoolError, SshPoolResult};
+/// Port stored with host pattern entries, whose port is part of the pattern.
+pub(crate) const PATTERN_PORT: u16 = 0;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct ServerPublicKey {
@@ -16,11 +31,54 @@ pub(crate) struct ServerPublicKey {
     pub(crate) fingerprint: String,
 }
 
+impl ServerPublicKey {
+    fn same_key(&self, other: &ServerPublicKey) -> bool {
+        self.key_algorithm == other.key_algorithm && self.public_key == other.public_key
+    }
+}
+
+impl From<&ssh_key::PublicKey> for ServerPublicKey {
+    fn from(public_key: &ssh_key::PublicKey) -> Self {
+        Self {
+            key_algorithm: public_key.algorithm().as_str().to_string(),
+            public_key: public_key.public_key_base64(),
+            fingerprint: public_key.fingerprint(HashAlg::Sha256).to_string(),
+        }
+    }
+}
+
+impl From<ssh_known_host::Model> for ServerPublicKey {
+    fn from(known_host: ssh_known_host::Model) -> Self {
+        Self {
+            key_algorithm: known_host.key_algorithm,
+            public_key: known_host.public_key,
+            fingerprint: known_host.fingerprint,
+        }
+    }
+}
+
+/// Keys stored for one endpoint, split by their known_hosts marker.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct KnownHostKeys {
+    pub(crate) trusted: Vec<ServerPublicKey>,
+    pub(crate) revoked: Vec<ServerPublicKey>,
+}
+
+impl KnownHostKeys {
+    fn trusts(&self, key: &ServerPublicKey) -> bool {
+        self.trusted.iter().any(|trusted| trusted.same_key(key))
+    }
+
+    fn revokes(&self, key: &ServerPublicKey) -> bool {
+        self.revoked.iter().any(|revoked| revoked.same_key(key))
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct KnownHosts {
     db: DatabaseConnection,
     policy: CheckServerKey,
-    accept_new_lock: Arc<Mutex<()>>,
+    remember_lock: Arc<Mutex<()>>,
 }
 
 impl KnownHosts {
@@ -28,114 +86,272 @@ impl KnownHosts {
         Self {
             db,
             policy,
-            accept_new_lock: Arc::new(Mutex::new(())),
+            remember_lock: Arc::new(Mutex::new(())),
         }
     }
 
+    /// Policy of targets that do not set their own.
     pub(crate) fn policy(&self) -> CheckServerKey {
         self.policy
     }
 
-    pub(crate) async fn load(&self, host: &str, port: u16) -> SshPoolResult<Vec<ServerPublicKey>> {
+    /// Loads the keys stored for `host:port`, including host pattern entries
+    /// that match it.
+    pub(crate) async fn load(&self, host: &str, port: u16) -> SshPoolResult<KnownHostKeys> {
         let known_hosts = ssh_known_host::Entity::find()
-            .filter(ssh_known_host::Column::Host.eq(host))
-            .filter(ssh_known_host::Column::Port.eq(port))
+            .filter(
+                Condition::any()
+                    .add(
+                        Condition::all()
+                            .add(host_eq(host))
+                            .add(ssh_known_host::Column::Port.eq(port)),
+                    )
+                    .add(ssh_known_host::Column::Port.eq(PATTERN_PORT)),
+            )
+            .order_by_asc(ssh_known_host::Column::Id)
             .all(&self.db)
             .await?;
 
-        Ok(known_hosts
-            .into_iter()
-            .map(|known_host| ServerPublicKey {
-                key_algorithm: known_host.key_algorithm,
-                public_key: known_host.public_key,
-                fingerprint: known_host.fingerprint,
-            })
-            .collect())
+        let mut keys = KnownHostKeys::default();
+        for known_host in known_hosts {
+            if known_host.port == PATTERN_PORT && !host_patterns_match(&known_host.host, host, port)
+            {
+                continue;
+            }
+            match known_host.marker {
+                None => keys.trusted.push(known_host.into()),
+                Some(KnownHostMarker::Revoked) => keys.revoked.push(known_host.into()),
+            }
+        }
+        Ok(keys)
     }
 
-    pub(crate) async fn remember_accept_new(
+    /// Stores a verified first key of `host:port` under the policies that
+    /// learn keys. Fails when another connection stored a different key
+    /// first.
+    pub(crate) async fn remember(
         &self,
+        policy: CheckServerKey,
         host: &str,
         port: u16,
         observed: ServerPublicKey,
     ) -> SshPoolResult<()> {
-        if self.policy != CheckServerKey::AcceptNew {
+        if !matches!(policy, CheckServerKey::AcceptNew | CheckServerKey::Ask) {
             return Ok(());
         }
 
-        let _guard = self.accept_new_lock.lock().await;
+        let _guard = self.remember_lock.lock().await;
         let stored_keys = self.load(host, port).await?;
-        if stored_keys.iter().any(|key| {
-            key.key_algorithm == observed.key_algorithm && key.public_key == observed.public_key
-        }) {
+        if stored_keys.trusts(&observed) {
             return Ok(());
         }
-        if !stored_keys.is_empty() {
-            return Err(SshPoolError::HostKeyMismatch {
-                host: host.to_string(),
-                port,
-                expected_fingerprints: stored_keys.into_iter().map(|key| key.fingerprint).collect(),
-                actual_fingerprint: observed.fingerprint,
-            });
+        if !stored_keys.trusted.is_empty() {
+            return Err(mismatch(host, port, &stored_keys.trusted, &observed));
         }
 
         let active_model = ssh_known_host::ActiveModel {
             id: sea_orm::ActiveValue::NotSet,
-            host: Set(host.to_string()),
+            host: Set(host.to_lowercase()),
             port: Set(port),
             key_algorithm: Set(observed.key_algorithm.clone()),
             public_key: Set(observed.public_key.clone()),
             fingerprint: Set(observed.fingerprint.clone()),
+            marker: Set(None),
         };
 
         ssh_known_host::Entity::insert(active_model)
             .on_conflict(
-                OnConflict::columns([ssh_known_host::Column::Host, ssh_known_host::Column::Port])
-                    .do_nothing()
-                    .to_owned(),
+                OnConflict::columns([
+                    ssh_known_host::Column::Host,
+                    ssh_known_host::Column::Port,
+                    ssh_known_host::Column::KeyAlgorithm,
+                    ssh_known_host::Column::PublicKey,
+                ])
+                .do_nothing()
+                .to_owned(),
             )
             .do_nothing()
             .exec_without_returning(&self.db)
             .await?;
 
+        // Another connection may have stored a different first key at the
+        // same time. The key stored first wins and the others are removed.
         let stored_keys = self.load(host, port).await?;
-        if !stored_keys.iter().any(|key| {
-            key.key_algorithm == observed.key_algorithm && key.public_key == observed.public_key
-        }) {
-            return Err(SshPoolError::HostKeyMismatch {
-                host: host.to_string(),
-                port,
-                expected_fingerprints: stored_keys.into_iter().map(|key| key.fingerprint).collect(),
-                actual_fingerprint: observed.fingerprint,
-            });
+        if stored_keys
+            .trusted
+            .first()
+            .is_some_and(|first| first.same_key(&observed))
+        {
+            return Ok(());
+        }
+        ssh_known_host::Entity::delete_many()
+            .filter(ssh_known_host::Column::Host.eq(host.to_lowercase()))
+            .filter(ssh_known_host::Column::Port.eq(port))
+            .filter(ssh_known_host::Column::KeyAlgorithm.eq(observed.key_algorithm.as_str()))
+            .filter(ssh_known_host::Column::PublicKey.eq(observed.public_key.as_str()))
+            .filter(ssh_known_host::Column::Marker.is_null())
+            .exec(&self.db)
+            .await?;
+        let expected: Vec<_> = stored_keys
+            .trusted
+            .into_iter()
+            .filter(|key| !key.same_key(&observed))
+            .collect();
+        Err(mismatch(host, port, &expected, &observed))
+    }
+}
+
+/// Whether an OpenSSH host pattern list such as `*.example.com,!bad.example.com`
+/// or a hashed `|1|salt|hash` entry matches `host:port`. Like OpenSSH, a
+/// non-default port is matched as `[host]:port`.
+pub(crate) fn host_patterns_match(patterns: &str, host: &str, port: u16) -> bool {
+    let name = if port == 22 {
+        host.to_lowercase()
+    } else {
+        format!("[{}]:{port}", host.to_lowercase())
+    };
+    let mut matched = false;
+    for pattern in patterns.split(',') {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let hit = if pattern.starts_with("|1|") {
+            hashed_host_matches(pattern, &name)
+        } else {
+            wildcard_match(&pattern.to_lowercase(), &name)
+        };
+        if hit && negated {
+            return false;
+        }
+        matched |= hit;
+    }
+    matched
+}
+
+/// Checks a `|1|salt|hash` entry, where hash is HMAC-SHA1 of the host name
+/// keyed with salt.
+fn hashed_host_matches(entry: &str, name: &str) -> bool {
+    let Some((salt, hash)) = entry
+        .strip_prefix("|1|")
+        .and_then(|rest| rest.split_once('|'))
+    else {
+        return false;
+    };
+    let (Ok(salt), Ok(hash)) = (STANDARD.decode(salt), STANDARD.decode(hash)) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(name.as_bytes());
+    mac.verify_slice(&hash).is_ok()
+}
+
+/// Matches `*` (any run) and `?` (any single character) in linear time,
+/// backtracking only to the last `*`.
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
         }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn mismatch(
+    host: &str,
+    port: u16,
+    expected: &[ServerPublicKey],
+    observed: &ServerPublicKey,
+) -> SshPoolError {
+    SshPoolError::HostKeyMismatch {
+        host: host.to_string(),
+        port,
+        expected_fingerprints: expected.iter().map(|key| key.fingerprint.clone()).collect(),
+        actual_fingerprint: observed.fingerprint.clone(),
+    }
+}
 
-        Ok(())
+/// Asks the client behind the connect whether to trust an unknown host key.
+/// Without such a client the key stays unknown.
+pub(crate) async fn confirm_host_key(
+    prompter: Option<&Arc<dyn AuthPrompter>>,
+    target_id: i32,
+    host: &str,
+    port: u16,
+    observed: &ServerPublicKey,
+) -> SshPoolResult<()> {
+    let Some(prompter) = prompter else {
+        return Err(SshPoolError::HostKeyUnknown {
+            host: host.to_string(),
+            port,
+            fingerprint: observed.fingerprint.clone(),
+        });
+    };
+    let trusted = prompter
+        .confirm_host_key(HostKeyPromptRequest {
+            target_id,
+            host: host.to_string(),
+            port,
+            key_algorithm: observed.key_algorithm.clone(),
+            fingerprint: observed.fingerprint.clone(),
+        })
+        .await?;
+    if !trusted {
+        return Err(SshPoolError::HostKeyRejected {
+            host: host.to_string(),
+            port,
+            fingerprint: observed.fingerprint.clone(),
+        });
     }
+    Ok(())
 }
 
+/// Checks the key presented by `host:port` against its stored keys.
+/// Revoked keys are rejected under every policy that checks keys.
 pub(crate) fn verify_server_key(
     policy: CheckServerKey,
     host: &str,
     port: u16,
-    pinned: &[ServerPublicKey],
+    known: &KnownHostKeys,
     observed: &ServerPublicKey,
 ) -> SshPoolResult<()> {
     if policy == CheckServerKey::Disabled {
         return Ok(());
     }
 
-    if pinned.iter().any(|key| {
-        key.key_algorithm == observed.key_algorithm && key.public_key == observed.public_key
-    }) {
+    if known.revokes(observed) {
+        return Err(SshPoolError::HostKeyRevoked {
+            host: host.to_string(),
+            port,
+            fingerprint: observed.fingerprint.clone(),
+        });
+    }
+
+    if known.trusts(observed) {
         return Ok(());
     }
 
-    if policy == CheckServerKey::AcceptNew && pinned.is_empty() {
+    if policy == CheckServerKey::AcceptNew && known.trusted.is_empty() {
         return Ok(());
     }
 
-    if pinned.is_empty() {
+    if known.trusted.is_empty() {
         return Err(SshPoolError::HostKeyUnknown {
             host: host.to_string(),
             port,
@@ -143,19 +359,33 @@ pub(crate) fn verify_server_key(
         });
     }
 
-    Err(SshPoolError::HostKeyMismatch {
-        host: host.to_string(),
-        port,
-        expected_fingerprints: pinned.iter().map(|key| key.fingerprint.clone()).collect(),
-        actual_fingerprint: observed.fingerprint.clone(),
-    })
+    Err(mismatch(host, port, &known.trusted, observed))
+}
+
+/// Classifies `observed` the way `verify_server_key` would, without asking
+/// the client or remembering the key.
+pub(crate) fn host_key_verdict(
+    policy: CheckServerKey,
+    host: &str,
+    port: u16,
+    known: &KnownHostKeys,
+    observed: &ServerPublicKey,
+) -> HostKeyVerdict {
+    match verify_server_key(policy, host, port, known, observed) {
+        Ok(()) if policy == CheckServerKey::Disabled => HostKeyVerdict::Unchecked,
+        Ok(()) if known.trusts(observed) => HostKeyVerdict::Trusted,
+        Ok(()) => HostKeyVerdict::New,
+        Err(SshPoolError::HostKeyUnknown { .. }) => HostKeyVerdict::Unknown,
+        Err(SshPoolError::HostKeyRevoked { .. }) => HostKeyVerdict::Revoked,
+        Err(_) => HostKeyVerdict::Changed,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::migrations::Migrator;
-    use sea_orm::{Database, EntityTrait};
+    use sea_orm::{ActiveModelTrait, Database, EntityTrait};
     use sea_orm_migration::MigratorTrait;
 
     fn key(public_key: &str) -> ServerPublicKey {
@@ -170,32 +400,180 @@ mod tests {
         }
     }
 
+    fn trusted(keys: &[ServerPublicKey]) -> KnownHostKeys {
+        KnownHostKeys {
+            trusted: keys.to_vec(),
+            ..KnownHostKeys::default()
+        }
+    }
+
+    fn verify(
+        policy: CheckServerKey,
+        known: &KnownHostKeys,
+        observed: &ServerPublicKey,
+    ) -> SshPoolResult<()> {
+        verify_server_key(policy, "host", 22, known, observed)
+    }
+
     #[test]
     fn server_key_policy_is_enforced() {
         let observed = key("observed");
-        assert!(verify_server_key(CheckServerKey::Disabled, "host", 22, &[], &observed).is_ok());
-        assert!(verify_server_key(CheckServerKey::AcceptNew, "host", 22, &[], &observed).is_ok());
-        assert!(verify_server_key(CheckServerKey::Strict, "host", 22, &[], &observed).is_err());
+        let empty = KnownHostKeys::default();
+        assert!(verify(CheckServerKey::Disabled, &empty, &observed).is_ok());
+        assert!(verify(CheckServerKey::AcceptNew, &empty, &observed).is_ok());
+        assert!(verify(CheckServerKey::Strict, &empty, &observed).is_err());
+        assert!(matches!(
+            verify(CheckServerKey::Ask, &empty, &observed),
+            Err(SshPoolError::HostKeyUnknown { .. })
+        ));
         assert!(
-            verify_server_key(
+            verify(
                 CheckServerKey::Strict,
-                "host",
-                22,
-                &[observed.clone()],
-                &observed,
+                &trusted(std::slice::from_ref(&observed)),
+                &observed
             )
             .is_ok()
         );
         assert!(matches!(
-            verify_server_key(
+            verify(
                 CheckServerKey::AcceptNew,
-                "host",
-                22,
-                &[key("changed")],
-                &observed,
+                &trusted(&[key("changed")]),
+                &observed
             ),
             Err(SshPoolError::HostKeyMismatch { .. })
         ));
+        assert!(
+            verify(
+                CheckServerKey::Strict,
+                &trusted(&[key("other"), observed.clone()]),
+                &observed,
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn revoked_keys_are_rejected_unless_checking_is_disabled() {
+        let observed = key("observed");
+        let known = KnownHostKeys {
+            trusted: vec![observed.clone()],
+            revoked: vec![observed.clone()],
+        };
+        assert!(matches!(
+            verify(CheckServerKey::AcceptNew, &known, &observed),
+            Err(SshPoolError::HostKeyRevoked { .. })
+        ));
+        assert!(verify(CheckServerKey::Disabled, &known, &observed).is_ok());
+    }
+
+    #[test]
+    fn host_patterns_follow_openssh_matching() {
+        assert!(host_patterns_match("*.example.com", "a.example.com", 22));
+        assert!(!host_patterns_match("*.example.com", "a.example.com", 2222));
+        assert!(host_patterns_match(
+            "[*.example.com]:2222",
+            "a.example.com",
+            2222
+        ));
+        assert!(host_patterns_match("web?,db", "WEB1", 22));
+        assert!(!host_patterns_match(
+            "*.example.com,!bad.example.com",
+            "bad.example.com",
+            22
+        ));
+
+        let salt = [7u8; 20];
+        let mut mac = Hmac::<Sha1>::new_from_slice(&salt).unwrap();
+        mac.update(b"[hashed.example.com]:2222");
+        let hashed = format!(
+            "|1|{}|{}",
+            STANDARD.encode(salt),
+            STANDARD.encode(mac.finalize().into_bytes())
+        );
+        assert!(host_patterns_match(&hashed, "hashed.example.com", 2222));
+        assert!(!host_patterns_match(&hashed, "hashed.example.com", 22));
+    }
+
+    #[test]
+    fn wildcard_match_backtracks_in_linear_time() {
+        let text = "a".repeat(4096);
+        let pattern = format!("{}b", "a*".repeat(64));
+        assert!(!wildcard_match(&pattern, &text));
+        assert!(wildcard_match(&format!("{}a", "a*".repeat(64)), &text));
+        assert!(wildcard_match("*.example.c?m", "db.example.com"));
+    }
+
+    #[tokio::test]
+    async fn load_includes_matching_pattern_entries() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let known_host =
+            |host: &str, port: u16, public_key: &str, marker| ssh_known_host::ActiveModel {
+                id: sea_orm::ActiveValue::NotSet,
+                host: Set(host.to_string()),
+                port: Set(port),
+                key_algorithm: Set("ssh-ed25519".to_string()),
+                public_key: Set(public_key.to_string()),
+                fingerprint: Set(format!("SHA256:{public_key}")),
+                marker: Set(marker),
+            };
+        ssh_known_host::Entity::insert_many([
+            known_host("a.example.com", 22, "exact", None),
+            known_host("a.example.com", 2222, "other-port", None),
+            known_host("*.example.com", PATTERN_PORT, "pattern", None),
+            known_host(
+                "*.example.com,!a.example.com",
+                PATTERN_PORT,
+                "excluded",
+                None,
+            ),
+            known_host("*", PATTERN_PORT, "revoked", Some(KnownHostMarker::Revoked)),
+        ])
+        .exec(&db)
+        .await
+        .unwrap();
+
+        let keys = KnownHosts::new(db, CheckServerKey::Strict)
+            .load("a.example.com", 22)
+            .await
+            .unwrap();
+        assert_eq!(keys.trusted, vec![key("exact"), key("pattern")]);
+        assert_eq!(keys.revoked, vec![key("revoked")]);
+    }
+
+    #[tokio::test]
+    async fn hosts_are_matched_in_any_letter_case() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        ssh_known_host::ActiveModel {
+            id: sea_orm::ActiveValue::NotSet,
+            host: Set("Legacy.Example.com".to_string()),
+            port: Set(22),
+            key_algorithm: Set("ssh-ed25519".to_string()),
+            public_key: Set("legacy".to_string()),
+            fingerprint: Set("SHA256:legacy".to_string()),
+            marker: Set(None),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        let known_hosts = KnownHosts::new(db.clone(), CheckServerKey::AcceptNew);
+
+        let keys = known_hosts.load("legacy.EXAMPLE.com", 22).await.unwrap();
+        assert_eq!(keys.trusted, vec![key("legacy")]);
+
+        known_hosts
+            .remember(CheckServerKey::AcceptNew, "New.Example.com", 22, key("new"))
+            .await
+            .unwrap();
+        let keys = known_hosts.load("new.example.COM", 22).await.unwrap();
+        assert_eq!(keys.trusted, vec![key("new")]);
+        let stored = ssh_known_host::Entity::find()
+            .filter(ssh_known_host::Column::Host.eq("new.example.com"))
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 1);
     }
 
     #[tokio::test]
@@ -206,12 +584,14 @@ mod tests {
         let second_known_hosts = KnownHosts::new(db.clone(), CheckServerKey::AcceptNew);
 
         assert!(!Arc::ptr_eq(
-            &first_known_hosts.accept_new_lock,
-            &second_known_hosts.accept_new_lock,
+            &first_known_hosts.remember_lock,
+            &second_known_hosts.remember_lock,
         ));
 
-        let first = first_known_hosts.remember_accept_new("concurrent", 22, key("first"));
-        let second = second_known_hosts.remember_accept_new(
+        let first =
+            first_known_hosts.remember(CheckServerKey::AcceptNew, "concurrent", 22, key("first"));
+        let second = second_known_hosts.remember(
+            CheckServerKey::AcceptNew,
             "concurrent",
             22,
             key_with_algorithm("ecdsa-sha2-nistp256", "second"),
@@ -230,8 +610,8 @@ mod tests {
         let first_known_hosts = KnownHosts::new(db.clone(), CheckServerKey::AcceptNew);
         let second_known_hosts = KnownHosts::new(db.clone(), CheckServerKey::AcceptNew);
 
-        let first = first_known_hosts.remember_accept_new("same", 22, key("key"));
-        let second = second_known_hosts.remember_accept_new("same", 22, key("key"));
+        let first = first_known_hosts.remember(CheckServerKey::AcceptNew, "same", 22, key("key"));
+        let second = second_known_hosts.remember(CheckServerKey::AcceptNew, "same", 22, key("key"));
         let (first, second) = tokio::join!(first, second);
 
         assert!(first.is_ok());
@@ -1,26 +1,49 @@
+mod algorithms;
 mod connection;
 mod connector;
+mod diagnostics;
 mod error;
+mod events;
 mod known_hosts;
 mod lease;
+mod priority;
+mod prompt;
+mod proxy;
+mod proxy_command;
 mod target;
 mod target_connection_pool;
+mod tcp;
 #[cfg(test)]
 mod tests;
+mod traffic;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
 
 use sea_orm::DatabaseConnection;
 use tokio::sync::{Mutex, RwLock};
 
-use crate::config::CheckServerKey;
+use crate::{config::CheckServerKey, secrets::Secrets};
 
+pub use algorithms::{NegotiatedAlgorithms, validate_algorithms};
 pub use connection::ConnectionState;
+pub use diagnostics::{
+    ConnectionDiagnostics, DiagnosticStage, HostKeyReport, HostKeyVerdict, StageReport, StageStatus,
+};
 pub use error::{SshPoolError, SshPoolResult};
+pub(crate) use error::{is_transient_io_error, is_transient_ssh_error};
+pub use events::{ConnectionEvent, ConnectionEventKind};
+pub(crate) use known_hosts::{PATTERN_PORT, ServerPublicKey, wildcard_match};
 pub use lease::{SshChannelGuard, SshChannelStreamGuard, SshChannelTransferGuard};
+pub use priority::ChannelPriority;
+pub use prompt::{
+    AuthPrompt, AuthPromptRequest, AuthPrompter, AuthPrompterRegistration, HostKeyPromptRequest,
+};
+pub use proxy::{SshProxy, TargetProxy};
+pub(crate) use proxy_command::expand_proxy_command;
 
 use connector::{SshConnectionSpec, SshConnector};
 use known_hosts::KnownHosts;
+use prompt::AuthPrompters;
 use target_connection_pool::TargetConnectionPool;
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
@@ -36,16 +59,32 @@ pub struct ConnectionSnapshot {
     pub state: ConnectionState,
     pub target_id: i32,
     pub active_channels: usize,
+    pub agent_identity: Option<String>,
+    pub algorithms: Option<NegotiatedAlgorithms>,
+    /// Unix timestamp in milliseconds.
+    pub created_at: u64,
+    /// Unix timestamp in milliseconds of the last transport traffic.
+    pub last_activity: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Identification string sent by the server, e.g. `SSH-2.0-OpenSSH_9.6`.
+    pub server_version: Option<String>,
 }
 
 pub(crate) struct SshConnectionPool {
     db: DatabaseConnection,
+    secrets: Secrets,
     target_connection_pools: Mutex<HashMap<i32, Arc<TargetConnectionPool>>>,
     expired_connection_pools: Arc<Mutex<Vec<Arc<TargetConnectionPool>>>>,
     lifecycle_locks: Mutex<HashMap<i32, Arc<RwLock<()>>>>,
     connector: Arc<SshConnector>,
+    auth_prompters: AuthPrompters,
     max_connections_per_target: usize,
     max_channels_per_connection: usize,
+    reserve_interactive_channel: bool,
+    idle_timeout: Option<Duration>,
+    default_proxy: Option<SshProxy>,
+    allow_proxy_command: bool,
 }
 
 impl SshConnectionPool {
@@ -58,29 +97,130 @@ impl SshConnectionPool {
         let known_hosts = KnownHosts::new(db.clone(), check_server_key);
         Self {
             db,
+            secrets: Secrets::default(),
             target_connection_pools: Mutex::new(HashMap::new()),
             expired_connection_pools: Arc::new(Mutex::new(Vec::new())),
             lifecycle_locks: Mutex::new(HashMap::new()),
             connector: Arc::new(SshConnector::new(known_hosts)),
+            auth_prompters: AuthPrompters::default(),
             max_connections_per_target,
             max_channels_per_connection,
+            reserve_interactive_channel: false,
+            idle_timeout: None,
+            default_proxy: None,
+            allow_proxy_command: false,
         }
     }
 
-    async fn connection_pool_for(&self, spec: SshConnectionSpec) -> Arc<TargetConnectionPool> {
+    /// Opens the target passwords and keys read from `db`.
+    pub(crate) fn with_secrets(mut self, secrets: Secrets) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    pub(crate) fn with_agent_socket(mut self, agent_socket: Option<PathBuf>) -> Self {
+        let connector = self
+            .connector
+            .as_ref()
+            .clone()
+            .with_agent_socket(agent_socket);
+        self.connector = Arc::new(connector);
+        self
+    }
+
+    pub(crate) fn with_keepalive(mut self, interval: Option<Duration>, max: usize) -> Self {
+        let connector = self
+            .connector
+            .as_ref()
+            .clone()
+            .with_keepalive(interval, max);
+        self.connector = Arc::new(connector);
+        self
+    }
+
+    /// Proxy used by targets that do not choose their own.
+    pub(crate) fn with_default_proxy(mut self, default_proxy: Option<SshProxy>) -> Self {
+        self.default_proxy = default_proxy;
+        self
+    }
+
+    /// Lets targets reach their host through a `ProxyCommand`, which runs a
+    /// shell command on this server. Targets that use one fail to connect
+    /// while this is off.
+    pub(crate) fn with_proxy_command_allowed(mut self, allow: bool) -> Self {
+        self.allow_proxy_command = allow;
+        self
+    }
+
+    pub(crate) fn allows_proxy_command(&self) -> bool {
+        self.allow_proxy_command
+    }
+
+    pub(crate) fn max_connections_per_target(&self) -> usize {
+        self.max_connections_per_target
+    }
+
+    /// Keeps the last free channel of every target for interactive sessions,
+    /// so that bulk work can never starve a terminal.
+    pub(crate) fn with_reserved_interactive_channel(mut self, reserve: bool) -> Self {
+        self.reserve_interactive_channel = reserve;
+        self
+    }
+
+    /// Closes pooled connections that have had no channel for `idle_timeout`.
+    pub(crate) fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Streams lifecycle events of every pooled connection.
+    pub(crate) fn subscribe_connection_events(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<ConnectionEvent> {
+        self.connector.events().subscribe()
+    }
+
+    /// Registers `prompter` for `target_id` until the registration is
+    /// dropped, so that requests quoting the registration token can hand it
+    /// their authentication prompts.
+    pub(crate) fn register_auth_prompter(
+        &self,
+        target_id: i32,
+        prompter: Arc<dyn AuthPrompter>,
+    ) -> AuthPrompterRegistration {
+        self.auth_prompters.register(target_id, prompter)
+    }
+
+    /// Prompter registered under `token` for `target_id`, if any.
+    pub(crate) fn auth_prompter(
+        &self,
+        target_id: i32,
+        token: &str,
+    ) -> Option<Arc<dyn AuthPrompter>> {
+        self.auth_prompters.find(target_id, token)
+    }
+
+    async fn connection_pool_for(
+        &self,
+        spec: SshConnectionSpec,
+        jump: Option<Arc<TargetConnectionPool>>,
+    ) -> Arc<TargetConnectionPool> {
         let target_id = spec.target_id();
         let (target_connection_pool, expired_connection_pool) = {
             let mut target_connection_pools = self.target_connection_pools.lock().await;
             if let Some(existing) = target_connection_pools.get(&target_id) {
-                if existing.matches(&spec) {
+                if existing.matches(&spec, jump.as_ref()) {
                     (Arc::clone(existing), None)
                 } else {
                     let expired = target_connection_pools.remove(&target_id);
                     let pool = Arc::new(TargetConnectionPool::new(
                         spec,
+                        jump,
                         Arc::clone(&self.connector),
                         self.max_connections_per_target,
                         self.max_channels_per_connection,
+                        self.reserve_interactive_channel,
+                        self.idle_timeout,
                     ));
                     target_connection_pools.insert(target_id, Arc::clone(&pool));
                     (pool, expired)
@@ -88,9 +228,12 @@ impl SshConnectionPool {
             } else {
                 let pool = Arc::new(TargetConnectionPool::new(
                     spec,
+                    jump,
                     Arc::clone(&self.connector),
                     self.max_connections_per_target,
                     self.max_channels_per_connection,
+                    self.reserve_interactive_channel,
+                    self.idle_timeout,
                 ));
                 target_connection_pools.insert(target_id, Arc::clone(&pool));
                 (pool, None)
@@ -0,0 +1,95 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::Notify;
+
+/// What a channel is used for. Requests waiting for capacity are served
+/// highest priority first, and in arrival order within a priority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChannelPriority {
+    /// Bulk upload and download.
+    Transfer,
+    /// SFTP directory listing and file operations.
+    Browse,
+    /// One-off commands.
+    Exec,
+    /// Terminal sessions.
+    Interactive,
+}
+
+type WaitKey = (Reverse<ChannelPriority>, u64);
+
+/// Orders the channel requests of a target pool. Only the first request in
+/// the queue may take capacity; the others wait on the pool's `notify`.
+pub(crate) struct WaitQueue {
+    waiting: Mutex<(u64, BTreeSet<WaitKey>)>,
+    notify: Arc<Notify>,
+}
+
+impl WaitQueue {
+    pub(crate) fn new(notify: Arc<Notify>) -> Self {
+        Self {
+            waiting: Mutex::new((0, BTreeSet::new())),
+            notify,
+        }
+    }
+
+    pub(crate) fn enqueue(&self, priority: ChannelPriority) -> WaitTicket<'_> {
+        let mut waiting = self.lock();
+        let (next_seq, keys) = &mut *waiting;
+        let key = (Reverse(priority), *next_seq);
+        *next_seq += 1;
+        keys.insert(key);
+        drop(waiting);
+        // A request that overtakes the current head must get the chance to
+        // take capacity the head is waiting for.
+        self.notify.notify_waiters();
+        WaitTicket { queue: self, key }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, (u64, BTreeSet<WaitKey>)> {
+        self.waiting.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Place of a request in the `WaitQueue`, given up when dropped.
+pub(crate) struct WaitTicket<'a> {
+    queue: &'a WaitQueue,
+    key: WaitKey,
+}
+
+impl WaitTicket<'_> {
+    pub(crate) fn is_first(&self) -> bool {
+        self.queue.lock().1.first() == Some(&self.key)
+    }
+}
+
+impl Drop for WaitTicket<'_> {
+    fn drop(&mut self) {
+        self.queue.lock().1.remove(&self.key);
+        self.queue.notify.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn higher_priority_goes_first_then_arrival_order() {
+        let queue = WaitQueue::new(Arc::new(Notify::new()));
+        let transfer = queue.enqueue(ChannelPriority::Transfer);
+        assert!(transfer.is_first());
+        let first_exec = queue.enqueue(ChannelPriority::Exec);
+        let second_exec = queue.enqueue(ChannelPriority::Exec);
+        assert!(!transfer.is_first());
+        assert!(first_exec.is_first());
+        drop(first_exec);
+        assert!(second_exec.is_first());
+        drop(second_exec);
+        assert!(transfer.is_first());
+    }
+}
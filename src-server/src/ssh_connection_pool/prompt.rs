@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+use super::error::SshPoolResult;
+
+/// A single keyboard-interactive prompt sent by the SSH server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AuthPrompt {
+    pub prompt: String,
+    pub echo: bool,
+}
+
+/// One round of keyboard-interactive prompts for a target.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AuthPromptRequest {
+    pub target_id: i32,
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<AuthPrompt>,
+}
+
+/// Unknown host key presented by a target under `CheckServerKey::Ask`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct HostKeyPromptRequest {
+    pub target_id: i32,
+    pub host: String,
+    pub port: u16,
+    pub key_algorithm: String,
+    pub fingerprint: String,
+}
+
+/// Answers keyboard-interactive prompts on behalf of the client that
+/// triggered a connection. Implementations must return one answer per
+/// prompt, in order.
+pub trait AuthPrompter: Send + Sync {
+    fn prompt(&self, request: AuthPromptRequest) -> BoxFuture<'_, SshPoolResult<Vec<String>>>;
+
+    /// Asks whether the host key in `request` should be trusted. Prompters
+    /// that cannot show a host key reject it.
+    fn confirm_host_key(
+        &self,
+        request: HostKeyPromptRequest,
+    ) -> BoxFuture<'_, SshPoolResult<bool>> {
+        let _ = request;
+        Box::pin(async { Ok(false) })
+    }
+}
+
+type PrompterEntry = (i32, Arc<dyn AuthPrompter>);
+
+/// Length of the random token a client quotes to have its prompter answer
+/// for a request.
+const PROMPT_TOKEN_LEN: usize = 32;
+
+/// Prompters of the attached clients, keyed by an unguessable token issued
+/// on registration. Only the client the token was handed to can quote it on
+/// the requests whose connects it answers for. A prompter only answers for
+/// the target it was registered for.
+#[derive(Clone, Default)]
+pub(crate) struct AuthPrompters {
+    inner: Arc<Mutex<HashMap<String, PrompterEntry>>>,
+}
+
+impl AuthPrompters {
+    pub(crate) fn register(
+        &self,
+        target_id: i32,
+        prompter: Arc<dyn AuthPrompter>,
+    ) -> AuthPrompterRegistration {
+        let token = nanoid::nanoid!(PROMPT_TOKEN_LEN);
+        self.inner
+            .lock()
+            .unwrap()
+            .insert(token.clone(), (target_id, prompter));
+        AuthPrompterRegistration {
+            prompters: self.clone(),
+            token,
+        }
+    }
+
+    /// Prompter registered under `token` for `target_id`, if any.
+    pub(crate) fn find(&self, target_id: i32, token: &str) -> Option<Arc<dyn AuthPrompter>> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .get(token)
+            .filter(|(registered_target_id, _)| *registered_target_id == target_id)
+            .map(|(_, prompter)| Arc::clone(prompter))
+    }
+
+    fn unregister(&self, token: &str) {
+        self.inner.lock().unwrap().remove(token);
+    }
+}
+
+/// Keeps a prompter registered for its target until dropped.
+pub struct AuthPrompterRegistration {
+    prompters: AuthPrompters,
+    token: String,
+}
+
+impl AuthPrompterRegistration {
+    /// Token to hand to the registering client only.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl Drop for AuthPrompterRegistration {
+    fn drop(&mut self) {
+        self.prompters.unregister(&self.token);
+    }
+}
+
+/// Password or key passphrase answered by the attached client for a target
+/// that does not store one. It lives only as long as the target connection
+/// pool that owns it and is zeroized when cleared or dropped.
+#[derive(Default)]
+pub(crate) struct SessionSecret {
+    secret: Mutex<Option<Zeroizing<String>>>,
+}
+
+impl SessionSecret {
+    pub(crate) fn get(&self) -> Option<Zeroizing<String>> {
+        self.secret.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set(&self, secret: Zeroizing<String>) {
+        *self.secret.lock().unwrap() = Some(secret);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.secret.lock().unwrap().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedAnswer(&'static str);
+
+    impl AuthPrompter for FixedAnswer {
+        fn prompt(&self, request: AuthPromptRequest) -> BoxFuture<'_, SshPoolResult<Vec<String>>> {
+            let answers = vec![self.0.to_string(); request.prompts.len()];
+            Box::pin(async move { Ok(answers) })
+        }
+    }
+
+    async fn answer(prompters: &AuthPrompters, target_id: i32, token: &str) -> Option<String> {
+        let prompter = prompters.find(target_id, token)?;
+        let request = AuthPromptRequest {
+            target_id,
+            name: String::new(),
+            instructions: String::new(),
+            prompts: vec![AuthPrompt {
+                prompt: "code: ".to_string(),
+                echo: false,
+            }],
+        };
+        prompter.prompt(request).await.unwrap().pop()
+    }
+
+    #[tokio::test]
+    async fn session_answers_only_for_its_target_until_dropped() {
+        let prompters = AuthPrompters::default();
+        let first = prompters.register(1, Arc::new(FixedAnswer("first")));
+        let second = prompters.register(1, Arc::new(FixedAnswer("second")));
+        let (a, b) = (first.token().to_string(), second.token().to_string());
+        assert_ne!(a, b);
+        assert_eq!(a.len(), PROMPT_TOKEN_LEN);
+
+        assert_eq!(answer(&prompters, 1, &a).await.as_deref(), Some("first"));
+        assert_eq!(answer(&prompters, 1, &b).await.as_deref(), Some("second"));
+        assert_eq!(answer(&prompters, 2, &a).await, None);
+        assert_eq!(answer(&prompters, 1, "c").await, None);
+
+        drop(first);
+        assert_eq!(answer(&prompters, 1, &a).await, None);
+        assert_eq!(answer(&prompters, 1, &b).await.as_deref(), Some("second"));
+    }
+}
@@ -0,0 +1,399 @@
+use std::{fmt, net::IpAddr};
+
+use base64::Engine;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use super::error::{SshPoolError, SshPoolResult};
+
+const HTTP_RESPONSE_HEAD_LIMIT: usize = 8 * 1024;
+const SOCKS5_DEFAULT_PORT: u16 = 1080;
+
+/// Outbound proxy that SSH connections are tunnelled through, written as
+/// `http://[user:password@]host[:port]` for HTTP CONNECT or
+/// `socks5://[user:password@]host[:port]` for SOCKS5.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SshProxy {
+    kind: ProxyKind,
+    host: String,
+    port: u16,
+    credentials: Option<ProxyCredentials>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProxyKind {
+    HttpConnect,
+    Socks5,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct ProxyCredentials {
+    username: String,
+    password: String,
+}
+
+impl SshProxy {
+    pub fn parse(value: &str) -> SshPoolResult<Self> {
+        let invalid = |reason: String| SshPoolError::InvalidProxy { reason };
+        let url = url::Url::parse(value.trim()).map_err(|err| invalid(err.to_string()))?;
+        let kind = match url.scheme() {
+            "http" => ProxyKind::HttpConnect,
+            // The target host name is always resolved by the proxy.
+            "socks5" | "socks5h" => ProxyKind::Socks5,
+            scheme => return Err(invalid(format!("unsupported proxy scheme {scheme}"))),
+        };
+        let host = match url.host() {
+            Some(url::Host::Ipv6(ip)) => ip.to_string(),
+            Some(host) => host.to_string(),
+            None => return Err(invalid("missing proxy host".to_string())),
+        };
+        let port = match kind {
+            ProxyKind::HttpConnect => url.port_or_known_default().unwrap_or(80),
+            ProxyKind::Socks5 => url.port().unwrap_or(SOCKS5_DEFAULT_PORT),
+        };
+        let credentials = if url.username().is_empty() {
+            None
+        } else {
+            let decode = |value: &str| {
+                percent_decode(value).ok_or_else(|| invalid("invalid proxy credentials".into()))
+            };
+            Some(ProxyCredentials {
+                username: decode(url.username())?,
+                password: decode(url.password().unwrap_or_default())?,
+            })
+        };
+        Ok(Self {
+            kind,
+            host,
+            port,
+            credentials,
+        })
+    }
+
+    /// Connects to the proxy and asks it to open a tunnel to `host:port`.
+    /// The returned stream carries the tunnelled bytes only.
+    pub(crate) async fn connect(&self, host: &str, port: u16) -> SshPoolResult<TcpStream> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .map_err(|err| self.error(err.to_string()))?;
+        match self.kind {
+            ProxyKind::HttpConnect => self.http_connect(&mut stream, host, port).await?,
+            ProxyKind::Socks5 => self.socks5_connect(&mut stream, host, port).await?,
+        }
+        Ok(stream)
+    }
+
+    async fn http_connect(
+        &self,
+        stream: &mut TcpStream,
+        host: &str,
+        port: u16,
+    ) -> SshPoolResult<()> {
+        let authority = match host.parse::<IpAddr>() {
+            Ok(IpAddr::V6(ip)) => format!("[{ip}]:{port}"),
+            _ => format!("{host}:{port}"),
+        };
+        let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+        if let Some(credentials) = &self.credentials {
+            let token = base64::engine::general_purpose::STANDARD
+                .encode(format!("{}:{}", credentials.username, credentials.password));
+            request.push_str(&format!("Proxy-Authorization: Basic {token}\r\n"));
+        }
+        request.push_str("\r\n");
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|err| self.error(err.to_string()))?;
+
+        // Read byte by byte so no tunnelled data is consumed with the head.
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            if head.len() >= HTTP_RESPONSE_HEAD_LIMIT {
+                return Err(self.error("proxy response head is too large".to_string()));
+            }
+            let byte = stream
+                .read_u8()
+                .await
+                .map_err(|err| self.error(err.to_string()))?;
+            head.push(byte);
+        }
+        let head = String::from_utf8_lossy(&head);
+        let status_line = head.lines().next().unwrap_or_default();
+        let status = status_line.split_whitespace().nth(1);
+        if status != Some("200") {
+            return Err(self.error(format!("CONNECT rejected: {status_line}")));
+        }
+        Ok(())
+    }
+
+    async fn socks5_connect(
+        &self,
+        stream: &mut TcpStream,
+        host: &str,
+        port: u16,
+    ) -> SshPoolResult<()> {
+        self.socks5_handshake(stream, host, port)
+            .await
+            .map_err(|err| match err {
+                Socks5Error::Io(err) => self.error(err.to_string()),
+                Socks5Error::Rejected(reason) => self.error(reason),
+            })
+    }
+
+    async fn socks5_handshake(
+        &self,
+        stream: &mut TcpStream,
+        host: &str,
+        port: u16,
+    ) -> Result<(), Socks5Error> {
+        let method = if self.credentials.is_some() {
+            SOCKS5_AUTH_USERNAME_PASSWORD
+        } else {
+            SOCKS5_AUTH_NONE
+        };
+        stream.write_all(&[SOCKS5_VERSION, 1, method]).await?;
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply[0] != SOCKS5_VERSION {
+            return Err(Socks5Error::Rejected(format!(
+                "unexpected SOCKS version {}",
+                reply[0]
+            )));
+        }
+        if reply[1] != method {
+            return Err(Socks5Error::Rejected(
+                "no acceptable SOCKS5 authentication method".to_string(),
+            ));
+        }
+
+        if let Some(credentials) = &self.credentials {
+            let username = credentials.username.as_bytes();
+            let password = credentials.password.as_bytes();
+            if username.len() > 255 || password.len() > 255 {
+                return Err(Socks5Error::Rejected(
+                    "SOCKS5 credentials are longer than 255 bytes".to_string(),
+                ));
+            }
+            let mut request = vec![1, username.len() as u8];
+            request.extend_from_slice(username);
+            request.push(password.len() as u8);
+            request.extend_from_slice(password);
+            stream.write_all(&request).await?;
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0 {
+                return Err(Socks5Error::Rejected(
+                    "SOCKS5 authentication failed".to_string(),
+                ));
+            }
+        }
+
+        let mut request = vec![SOCKS5_VERSION, SOCKS5_CMD_CONNECT, 0];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(SOCKS5_ATYP_IPV4);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(SOCKS5_ATYP_IPV6);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                if host.len() > 255 {
+                    return Err(Socks5Error::Rejected(
+                        "target host name is longer than 255 bytes".to_string(),
+                    ));
+                }
+                request.push(SOCKS5_ATYP_DOMAIN);
+                request.push(host.len() as u8);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0 {
+            return Err(Socks5Error::Rejected(format!(
+                "SOCKS5 CONNECT failed: {}",
+                socks5_reply_message(reply[1])
+            )));
+        }
+        let bound_address_len = match reply[3] {
+            SOCKS5_ATYP_IPV4 => 4,
+            SOCKS5_ATYP_IPV6 => 16,
+            SOCKS5_ATYP_DOMAIN => usize::from(stream.read_u8().await?),
+            atyp => {
+                return Err(Socks5Error::Rejected(format!(
+                    "unexpected SOCKS5 address type {atyp}"
+                )));
+            }
+        };
+        let mut bound_address = vec![0u8; bound_address_len + 2];
+        stream.read_exact(&mut bound_address).await?;
+        Ok(())
+    }
+
+    fn error(&self, reason: String) -> SshPoolError {
+        SshPoolError::Proxy {
+            proxy: format!("{}:{}", self.host, self.port),
+            reason,
+        }
+    }
+}
+
+/// Proxy setting stored on a target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TargetProxy {
+    /// Use the global default proxy, if one is configured.
+    Default,
+    /// Connect directly even when a global default proxy is configured.
+    Direct,
+    Proxy(SshProxy),
+}
+
+impl TargetProxy {
+    /// Parses the `proxy` column of a target: empty for the default,
+    /// `direct` to bypass it, or a proxy URL.
+    pub fn parse(value: Option<&str>) -> SshPoolResult<Self> {
+        match value.map(str::trim) {
+            None | Some("") => Ok(Self::Default),
+            Some(value) if value.eq_ignore_ascii_case("direct") => Ok(Self::Direct),
+            Some(value) => SshProxy::parse(value).map(Self::Proxy),
+        }
+    }
+
+    pub(crate) fn resolve(self, default_proxy: Option<&SshProxy>) -> Option<SshProxy> {
+        match self {
+            Self::Default => default_proxy.cloned(),
+            Self::Direct => None,
+            Self::Proxy(proxy) => Some(proxy),
+        }
+    }
+}
+
+impl fmt::Debug for SshProxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SshProxy")
+            .field("kind", &self.kind)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field(
+                "username",
+                &self
+                    .credentials
+                    .as_ref()
+                    .map(|credentials| credentials.username.as_str()),
+            )
+            .finish()
+    }
+}
+
+/// Writes the proxy URL without its credentials.
+impl fmt::Display for SshProxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = match self.kind {
+            ProxyKind::HttpConnect => "http",
+            ProxyKind::Socks5 => "socks5",
+        };
+        if self.host.contains(':') {
+            write!(f, "{scheme}://[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{scheme}://{}:{}", self.host, self.port)
+        }
+    }
+}
+
+const SOCKS5_VERSION: u8 = 5;
+const SOCKS5_AUTH_NONE: u8 = 0;
+const SOCKS5_AUTH_USERNAME_PASSWORD: u8 = 2;
+const SOCKS5_CMD_CONNECT: u8 = 1;
+const SOCKS5_ATYP_IPV4: u8 = 1;
+const SOCKS5_ATYP_DOMAIN: u8 = 3;
+const SOCKS5_ATYP_IPV6: u8 = 4;
+
+enum Socks5Error {
+    Io(std::io::Error),
+    Rejected(String),
+}
+
+impl From<std::io::Error> for Socks5Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+fn socks5_reply_message(code: u8) -> &'static str {
+    match code {
+        1 => "general SOCKS server failure",
+        2 => "connection not allowed by ruleset",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        7 => "command not supported",
+        8 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let high = char::from(input.next()?).to_digit(16)?;
+            let low = char::from(input.next()?).to_digit(16)?;
+            bytes.push((high * 16 + low) as u8);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_proxy_urls() {
+        let proxy = SshProxy::parse("http://proxy.example.com:3128").unwrap();
+        assert_eq!(proxy.kind, ProxyKind::HttpConnect);
+        assert_eq!(
+            (proxy.host.as_str(), proxy.port),
+            ("proxy.example.com", 3128)
+        );
+        assert!(proxy.credentials.is_none());
+
+        let proxy = SshProxy::parse("socks5://alice:p%40ss@[::1]").unwrap();
+        assert_eq!(proxy.kind, ProxyKind::Socks5);
+        assert_eq!((proxy.host.as_str(), proxy.port), ("::1", 1080));
+        let credentials = proxy.credentials.unwrap();
+        assert_eq!(credentials.username, "alice");
+        assert_eq!(credentials.password, "p@ss");
+
+        assert_eq!(SshProxy::parse("http://proxy").unwrap().port, 80);
+        assert!(SshProxy::parse("https://proxy:443").is_err());
+        assert!(SshProxy::parse("proxy:8080").is_err());
+    }
+
+    #[test]
+    fn target_proxy_falls_back_to_the_default() {
+        let default_proxy = SshProxy::parse("socks5://127.0.0.1:1080").unwrap();
+        let resolve = |value| {
+            TargetProxy::parse(value)
+                .unwrap()
+                .resolve(Some(&default_proxy))
+        };
+        assert_eq!(resolve(None), Some(default_proxy.clone()));
+        assert_eq!(resolve(Some(" ")), Some(default_proxy.clone()));
+        assert_eq!(resolve(Some("DIRECT")), None);
+        assert_eq!(
+            resolve(Some("http://127.0.0.1:3128")),
+            Some(SshProxy::parse("http://127.0.0.1:3128").unwrap())
+        );
+    }
+}
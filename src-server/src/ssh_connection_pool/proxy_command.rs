@@ -0,0 +1,211 @@
+use std::{
+    io,
+    pin::Pin,
+    process::Stdio,
+    task::{Context, Poll},
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, ReadBuf},
+    process::{Child, ChildStdin, ChildStdout, Command},
+};
+use tracing::{debug, warn};
+
+use super::error::{SshPoolError, SshPoolResult};
+
+/// Expands the OpenSSH `ProxyCommand` tokens `%h`, `%p`, `%r` and `%%`.
+/// Unknown tokens are kept as written.
+///
+/// The command runs through a shell, so a host or user that could be read
+/// as anything but a single word is rejected rather than substituted.
+pub(crate) fn expand_proxy_command(
+    template: &str,
+    host: &str,
+    port: u16,
+    user: &str,
+) -> SshPoolResult<String> {
+    check_token('h', host)?;
+    check_token('r', user)?;
+    let mut command = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            command.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => command.push_str(host),
+            Some('p') => command.push_str(&port.to_string()),
+            Some('r') => command.push_str(user),
+            Some('%') => command.push('%'),
+            Some(other) => {
+                command.push('%');
+                command.push(other);
+            }
+            None => command.push('%'),
+        }
+    }
+    Ok(command)
+}
+
+/// Accepts host names, IP addresses and user names, which need no quoting
+/// and cannot be mistaken for an option.
+fn check_token(token: char, value: &str) -> SshPoolResult<()> {
+    let safe = !value.starts_with('-')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '@' | '+'));
+    if safe {
+        Ok(())
+    } else {
+        Err(SshPoolError::UnsafeProxyCommandToken {
+            token,
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Transport backed by the stdin/stdout of a `ProxyCommand` child process.
+///
+/// The child is killed and reaped when the stream is dropped, which happens
+/// when the SSH session that owns it closes.
+pub(crate) struct ProxyCommandStream {
+    child: Option<Child>,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+}
+
+impl ProxyCommandStream {
+    pub(crate) fn spawn(command: &str) -> SshPoolResult<Self> {
+        let mut child = shell_command(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| SshPoolError::ProxyCommand {
+                command: command.to_string(),
+                reason: err.to_string(),
+            })?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(SshPoolError::ProxyCommand {
+                command: command.to_string(),
+                reason: "missing stdio pipes".to_string(),
+            });
+        };
+        if let Some(stderr) = child.stderr.take() {
+            let pid = child.id();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    warn!(?pid, line, "ProxyCommand stderr");
+                }
+            });
+        }
+        debug!(pid = child.id(), command, "spawned SSH ProxyCommand");
+        Ok(Self {
+            child: Some(child),
+            stdin,
+            stdout,
+        })
+    }
+}
+
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("/bin/sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+impl Drop for ProxyCommandStream {
+    fn drop(&mut self) {
+        let Some(mut child) = self.child.take() else {
+            return;
+        };
+        if let Err(err) = child.start_kill() {
+            debug!(?err, "ProxyCommand already exited");
+        }
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(async move {
+                    match child.wait().await {
+                        Ok(status) => debug!(%status, "reaped SSH ProxyCommand"),
+                        Err(err) => warn!(?err, "failed to reap SSH ProxyCommand"),
+                    }
+                });
+            }
+            // Without a runtime `kill_on_drop` still kills the child.
+            Err(_) => drop(child),
+        }
+    }
+}
+
+impl AsyncRead for ProxyCommandStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdout).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ProxyCommandStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stdin).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdin).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdin).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand_proxy_command;
+
+    #[test]
+    fn expands_openssh_tokens() {
+        assert_eq!(
+            expand_proxy_command("nc %h %p # %r 100%% %x%", "example.com", 2222, "root").unwrap(),
+            "nc example.com 2222 # root 100% %x%"
+        );
+        assert_eq!(
+            expand_proxy_command("ssh -W [%h]:%p %r@gw", "fe80::1", 22, "deploy.user").unwrap(),
+            "ssh -W [fe80::1]:22 deploy.user@gw"
+        );
+    }
+
+    #[test]
+    fn rejects_hosts_and_users_the_shell_would_interpret() {
+        for (host, user) in [
+            ("example.com; touch /tmp/pwned", "root"),
+            ("$(id)", "root"),
+            ("-oProxyCommand=id", "root"),
+            ("example.com", "root`id`"),
+            ("example.com", "a b"),
+            ("example.com", "'root'"),
+        ] {
+            assert!(
+                expand_proxy_command("nc %h %p", host, 22, user).is_err(),
+                "{host} / {user}"
+            );
+        }
+    }
+}
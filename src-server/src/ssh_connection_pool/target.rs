@@ -1,23 +1,27 @@
-use std::{future::Future, sync::Arc};
+use std::{collections::HashSet, future::Future, sync::Arc, time::Duration};
 
 use anyhow::{Context, Result};
 
 use crate::{
+    config::CheckServerKey,
     entities::target::{self, TargetAuthMethod},
-    repositories::target as target_repository,
+    repositories::{key_store as key_store_repository, target as target_repository},
     sftp_client::{FastSftpClient, SftpClientGuard},
 };
 
 use super::{
-    ChannelMode, SshChannelGuard, SshConnectionPool,
+    AuthPrompter, ChannelMode, ChannelPriority, SshChannelGuard, SshConnectionPool,
     connector::{SshAuth, SshConnectionSpec},
+    diagnostics::ConnectionDiagnostics,
     error::{SshPoolError, SshPoolResult},
+    proxy::{SshProxy, TargetProxy},
     target_connection_pool::TargetConnectionPool,
 };
 
 pub(crate) struct TargetSshContext {
     target: target::Model,
     connection_pool: SshPoolResult<Arc<TargetConnectionPool>>,
+    prompter: Option<Arc<dyn AuthPrompter>>,
 }
 
 impl SshConnectionPool {
@@ -32,31 +36,208 @@ impl SshConnectionPool {
 
     pub(crate) async fn context(&self, target_id: i32) -> Result<TargetSshContext> {
         let lifecycle_guard = self.lifecycle_lock(target_id).await.read_owned().await;
-        let target = target_repository::find_by_id(&self.db, target_id)
+        let target = target_repository::find_by_id(&self.db, &self.secrets, target_id)
             .await
             .with_context(|| format!("failed to query SSH target {target_id}"))?
             .ok_or_else(|| anyhow::anyhow!("SSH target {target_id} not found"))?;
-        let connection_pool = match connection_spec(&target) {
-            Ok(spec) => Ok(self.connection_pool_for(spec).await),
+        let connection_pool = match self.connection_specs(&target).await {
+            Ok(specs) => Ok(self.connection_pool_chain(specs).await),
             Err(err) => Err(err),
         };
         drop(lifecycle_guard);
         Ok(TargetSshContext {
             target,
             connection_pool,
+            prompter: None,
         })
     }
 
+    /// Resolves the jump target chain of `target`, outermost jump target
+    /// first and `target` itself last.
+    async fn connection_specs(
+        &self,
+        target: &target::Model,
+    ) -> SshPoolResult<Vec<SshConnectionSpec>> {
+        let default_proxy = self.default_proxy.as_ref();
+        let mut specs = vec![connection_spec(
+            target,
+            self.stored_key(target).await?,
+            default_proxy,
+            self.allow_proxy_command,
+        )?];
+        let mut visited = HashSet::from([target.id]);
+        let mut current = target.clone();
+        while let Some(jump_target_id) = current.jump_target_id {
+            if !visited.insert(jump_target_id) {
+                return Err(SshPoolError::JumpTargetCycle {
+                    target_id: target.id,
+                });
+            }
+            let jump_target =
+                target_repository::find_by_id(&self.db, &self.secrets, jump_target_id)
+                    .await?
+                    .ok_or(SshPoolError::JumpTargetNotFound {
+                        target_id: current.id,
+                        jump_target_id,
+                    })?;
+            specs.push(connection_spec(
+                &jump_target,
+                self.stored_key(&jump_target).await?,
+                default_proxy,
+                self.allow_proxy_command,
+            )?);
+            current = jump_target;
+        }
+        specs.reverse();
+        Ok(specs)
+    }
+
+    /// Private key of the key store entry `target` authenticates with.
+    async fn stored_key(&self, target: &target::Model) -> SshPoolResult<Option<String>> {
+        let Some(key_id) = target.key_id else {
+            return Ok(None);
+        };
+        let stored_key = key_store_repository::find_by_id(&self.db, &self.secrets, key_id)
+            .await?
+            .ok_or(SshPoolError::StoredKeyNotFound {
+                target_id: target.id,
+                key_id,
+            })?;
+        Ok(Some(stored_key.private_key))
+    }
+
+    async fn connection_pool_chain(
+        &self,
+        specs: Vec<SshConnectionSpec>,
+    ) -> Arc<TargetConnectionPool> {
+        let mut jump = None;
+        for spec in specs {
+            jump = Some(self.connection_pool_for(spec, jump).await);
+        }
+        jump.expect("connection spec chain always contains the target itself")
+    }
+
     pub(crate) async fn channel(
         &self,
         target_id: i32,
         mode: ChannelMode,
+        priority: ChannelPriority,
     ) -> Result<SshChannelGuard> {
-        self.context(target_id).await?.channel(mode).await
+        self.prompted_channel(target_id, mode, priority, None).await
     }
 
-    pub(crate) async fn sftp(&self, target_id: i32, mode: ChannelMode) -> Result<SftpClientGuard> {
-        let channel = self.channel(target_id, mode).await?;
+    /// Like `channel`, with `prompter` answering the authentication prompts
+    /// of a connect this request triggers.
+    pub(crate) async fn prompted_channel(
+        &self,
+        target_id: i32,
+        mode: ChannelMode,
+        priority: ChannelPriority,
+        prompter: Option<Arc<dyn AuthPrompter>>,
+    ) -> Result<SshChannelGuard> {
+        self.context(target_id)
+            .await?
+            .with_prompter(prompter)
+            .channel(mode, priority)
+            .await
+    }
+
+    /// Opens the idle connections `target_id` keeps ready, or one when it
+    /// keeps none, so that the next channel does not wait for the handshake.
+    pub(crate) async fn warm(&self, target_id: i32) -> Result<()> {
+        let connection_pool = self.context(target_id).await?.connection_pool?;
+        let min_idle = connection_pool.min_idle_connections().max(1);
+        Ok(connection_pool.fill_idle(min_idle).await?)
+    }
+
+    /// Opens a channel to `target_id` over a connection of its own that
+    /// authenticates with `password` instead of the target's credentials,
+    /// e.g. to install a public key before key authentication works. The
+    /// connection is closed with the channel.
+    pub(crate) async fn password_channel(
+        &self,
+        target_id: i32,
+        password: String,
+    ) -> Result<SshChannelGuard> {
+        let lifecycle_guard = self.lifecycle_lock(target_id).await.read_owned().await;
+        let target = target_repository::find_by_id(&self.db, &self.secrets, target_id)
+            .await
+            .with_context(|| format!("failed to query SSH target {target_id}"))?
+            .ok_or_else(|| anyhow::anyhow!("SSH target {target_id} not found"))?;
+        let mut password_target = target.clone();
+        password_target.method = TargetAuthMethod::Password;
+        password_target.password = Some(password);
+        password_target.key_id = None;
+        let mut specs = self.connection_specs(&password_target).await?;
+        let spec = specs
+            .pop()
+            .expect("connection spec chain always contains the target itself");
+        let jump = if specs.is_empty() {
+            None
+        } else {
+            Some(self.connection_pool_chain(specs).await)
+        };
+        drop(lifecycle_guard);
+
+        let connection_pool =
+            TargetConnectionPool::new(spec, jump, Arc::clone(&self.connector), 1, 1, false, None);
+        let channel = Arc::new(connection_pool)
+            .acquire(ChannelMode::Dedicated, ChannelPriority::Exec, None)
+            .await?;
+        Ok(channel)
+    }
+
+    /// Tests `target`, saved or not, stage by stage over a connection of
+    /// its own. Jump targets are reached over one-off connections too, so
+    /// pooled connections are neither used nor disturbed.
+    pub(crate) async fn diagnose(
+        &self,
+        target: &target::Model,
+    ) -> SshPoolResult<ConnectionDiagnostics> {
+        let mut specs = self.connection_specs(target).await?;
+        let spec = specs
+            .pop()
+            .expect("connection spec chain always contains the target itself");
+        let mut jumps: Vec<Arc<TargetConnectionPool>> = Vec::new();
+        for jump_spec in specs {
+            let jump = TargetConnectionPool::new(
+                jump_spec,
+                jumps.last().cloned(),
+                Arc::clone(&self.connector),
+                1,
+                1,
+                false,
+                None,
+            );
+            jumps.push(Arc::new(jump));
+        }
+
+        let diagnostics = self.connector.diagnose(&spec, jumps.last()).await;
+        for jump in jumps.iter().rev() {
+            jump.expire().await;
+        }
+        Ok(diagnostics)
+    }
+
+    pub(crate) async fn sftp(
+        &self,
+        target_id: i32,
+        mode: ChannelMode,
+        priority: ChannelPriority,
+    ) -> Result<SftpClientGuard> {
+        self.prompted_sftp(target_id, mode, priority, None).await
+    }
+
+    pub(crate) async fn prompted_sftp(
+        &self,
+        target_id: i32,
+        mode: ChannelMode,
+        priority: ChannelPriority,
+        prompter: Option<Arc<dyn AuthPrompter>>,
+    ) -> Result<SftpClientGuard> {
+        let channel = self
+            .prompted_channel(target_id, mode, priority, prompter)
+            .await?;
         let client = FastSftpClient::new(channel).await?;
         Ok(SftpClientGuard::new(client))
     }
@@ -82,21 +263,51 @@ impl TargetSshContext {
         &self.target
     }
 
-    pub(crate) async fn channel(self, mode: ChannelMode) -> Result<SshChannelGuard> {
+    /// Lets `prompter` answer the authentication prompts of a connect
+    /// that `channel` triggers.
+    pub(crate) fn with_prompter(mut self, prompter: Option<Arc<dyn AuthPrompter>>) -> Self {
+        self.prompter = prompter;
+        self
+    }
+
+    pub(crate) async fn channel(
+        self,
+        mode: ChannelMode,
+        priority: ChannelPriority,
+    ) -> Result<SshChannelGuard> {
         let connection_pool = self.connection_pool?;
-        Ok(connection_pool.acquire(mode).await?)
+        Ok(connection_pool
+            .acquire(mode, priority, self.prompter.as_ref())
+            .await?)
     }
 }
 
-fn connection_spec(target: &target::Model) -> SshPoolResult<SshConnectionSpec> {
+fn connection_spec(
+    target: &target::Model,
+    stored_key: Option<String>,
+    default_proxy: Option<&SshProxy>,
+    allow_proxy_command: bool,
+) -> SshPoolResult<SshConnectionSpec> {
+    let proxy_command = target
+        .proxy_command
+        .clone()
+        .filter(|command| !command.trim().is_empty());
+    if proxy_command.is_some() && !allow_proxy_command {
+        return Err(SshPoolError::ProxyCommandDisabled);
+    }
+    let key_data = stored_key.or_else(|| target.key.clone());
     let auth = match &target.method {
         TargetAuthMethod::Password => {
             SshAuth::Password(target.password.clone().unwrap_or_default())
         }
         TargetAuthMethod::PrivateKey => SshAuth::PrivateKey {
-            key_data: target.key.clone().unwrap_or_default(),
+            key_data: key_data.unwrap_or_default(),
             passphrase: target.password.clone(),
+            certificate: target.certificate.clone(),
         },
+        TargetAuthMethod::Agent => SshAuth::Agent,
+        TargetAuthMethod::KeyboardInteractive => SshAuth::KeyboardInteractive,
+        TargetAuthMethod::Prompt => SshAuth::Prompted { key_data },
         TargetAuthMethod::None => return Err(SshPoolError::UnsupportedAuthMethod),
     };
     Ok(SshConnectionSpec::new(
@@ -105,5 +316,18 @@ fn connection_spec(target: &target::Model) -> SshPoolResult<SshConnectionSpec> {
         target.host.clone(),
         target.port.unwrap_or(22),
         auth,
-    ))
+    )
+    .with_jump_target_id(target.jump_target_id)
+    .with_proxy(TargetProxy::parse(target.proxy.as_deref())?.resolve(default_proxy))
+    .with_algorithms(target.algorithms.clone().unwrap_or_default())
+    .with_connect_timeout(
+        target
+            .connect_timeout
+            .filter(|timeout| *timeout > 0)
+            .map(|timeout| Duration::from_secs(timeout.into())),
+    )
+    .with_connect_retries(target.connect_retries.unwrap_or_default())
+    .with_min_idle_connections(target.min_idle_connections.unwrap_or_default() as usize)
+    .with_host_key_policy(target.host_key_policy.map(CheckServerKey::from))
+    .with_proxy_command(proxy_command))
 }
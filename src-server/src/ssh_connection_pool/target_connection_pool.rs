@@ -1,60 +1,141 @@
-use std::sync::{
-    Arc,
-    atomic::{AtomicBool, Ordering},
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
 };
 
+use futures_util::future::BoxFuture;
 use tokio::sync::{Mutex, Notify};
-use tracing::debug;
+use tracing::{debug, warn};
 
 use super::{
     ChannelMode, ConnectionSnapshot,
-    connection::{ChannelPermit, ConnectionState, SshConnection},
+    connection::{ChannelKind, ChannelPermit, ConnectionState, SshConnection},
     connector::{SshConnectionSpec, SshConnector},
     error::{SshPoolError, SshPoolResult},
-    lease::SshChannelGuard,
+    lease::{SshChannelGuard, SshChannelStreamGuard},
+    priority::{ChannelPriority, WaitQueue},
+    prompt::{AuthPrompter, SessionSecret},
 };
 
 pub(crate) struct TargetConnectionPool {
     spec: SshConnectionSpec,
+    jump: Option<Arc<TargetConnectionPool>>,
     connector: Arc<SshConnector>,
+    session_secret: SessionSecret,
     connections: Mutex<Vec<Arc<SshConnection>>>,
     connect_lock: Mutex<()>,
     notify: Arc<Notify>,
+    waiters: WaitQueue,
     max_connections: usize,
     max_channels_per_connection: usize,
+    /// Keeps the last free channel for `ChannelPriority::Interactive`.
+    reserve_interactive: bool,
+    idle_timeout: Option<Duration>,
     expired: AtomicBool,
 }
 
 impl TargetConnectionPool {
     pub(crate) fn new(
         spec: SshConnectionSpec,
+        jump: Option<Arc<TargetConnectionPool>>,
         connector: Arc<SshConnector>,
         max_connections: usize,
         max_channels_per_connection: usize,
+        reserve_interactive: bool,
+        idle_timeout: Option<Duration>,
     ) -> Self {
+        let notify = Arc::new(Notify::new());
         Self {
             spec,
+            jump,
             connector,
+            session_secret: SessionSecret::default(),
             connections: Mutex::new(Vec::new()),
             connect_lock: Mutex::new(()),
-            notify: Arc::new(Notify::new()),
+            waiters: WaitQueue::new(Arc::clone(&notify)),
+            notify,
             max_connections,
             max_channels_per_connection,
+            reserve_interactive,
+            idle_timeout,
             expired: AtomicBool::new(false),
         }
     }
 
-    pub(crate) fn matches(&self, spec: &SshConnectionSpec) -> bool {
-        self.spec == *spec && !self.expired.load(Ordering::Acquire)
+    pub(crate) fn matches(
+        &self,
+        spec: &SshConnectionSpec,
+        jump: Option<&Arc<TargetConnectionPool>>,
+    ) -> bool {
+        let same_jump = match (&self.jump, jump) {
+            (Some(current), Some(jump)) => Arc::ptr_eq(current, jump),
+            (None, None) => true,
+            _ => false,
+        };
+        self.spec == *spec && same_jump && !self.expired.load(Ordering::Acquire)
     }
 
     pub(crate) fn target_id(&self) -> i32 {
         self.spec.target_id()
     }
 
+    pub(crate) fn min_idle_connections(&self) -> usize {
+        self.spec.min_idle_connections()
+    }
+
+    /// Acquires a session channel. A connect it triggers asks `prompter`,
+    /// the client behind the request, to answer authentication prompts.
     pub(crate) async fn acquire(
         self: &Arc<Self>,
         mode: ChannelMode,
+        priority: ChannelPriority,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
+    ) -> SshPoolResult<SshChannelGuard> {
+        self.acquire_channel(mode, priority, ChannelKind::Session, prompter)
+            .await
+    }
+
+    /// Opens a `direct-tcpip` channel to `host:port` for a target that uses
+    /// this pool as its jump target. The returned stream holds a channel
+    /// permit until the tunnelled session ends.
+    pub(crate) fn open_direct_tcpip<'a>(
+        self: &'a Arc<Self>,
+        host: &'a str,
+        port: u16,
+        prompter: Option<&'a Arc<dyn AuthPrompter>>,
+    ) -> BoxFuture<'a, SshPoolResult<SshChannelStreamGuard>> {
+        Box::pin(async move {
+            let kind = ChannelKind::DirectTcpip {
+                host: host.to_string(),
+                port,
+            };
+            // A tunnel carries every channel of the tunnelled target, so it
+            // must not be held back by the interactive reservation.
+            let channel = self
+                .acquire_channel(
+                    ChannelMode::Shared,
+                    ChannelPriority::Interactive,
+                    kind,
+                    prompter,
+                )
+                .await?;
+            channel
+                .into_stream()
+                .ok_or_else(|| SshPoolError::ConnectionExpired {
+                    connection_id: format!("target:{}", self.spec.target_id()),
+                })
+        })
+    }
+
+    async fn acquire_channel(
+        self: &Arc<Self>,
+        mode: ChannelMode,
+        priority: ChannelPriority,
+        kind: ChannelKind,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
     ) -> SshPoolResult<SshChannelGuard> {
         if self.max_connections == 0 {
             return Err(SshPoolError::CapacityExceeded {
@@ -69,47 +150,47 @@ impl TargetConnectionPool {
             });
         }
 
+        let ticket = self.waiters.enqueue(priority);
         loop {
             self.ensure_active()?;
-            if let Some(reservation) = self.try_existing(mode).await {
-                return self.open_reserved(reservation).await;
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if !ticket.is_first() {
+                notified.await;
+                continue;
+            }
+            if let Some(reservation) = self.try_existing(mode, priority).await {
+                drop(ticket);
+                return self.open_replenished(reservation, &kind).await;
             }
 
             let connect_guard = self.connect_lock.lock().await;
             self.ensure_active()?;
-            let notified = self.notify.notified();
-            tokio::pin!(notified);
-            notified.as_mut().enable();
 
-            if let Some(reservation) = self.try_existing(mode).await {
-                return self.open_reserved(reservation).await;
+            if let Some(reservation) = self.try_existing(mode, priority).await {
+                drop(connect_guard);
+                drop(ticket);
+                return self.open_replenished(reservation, &kind).await;
             }
 
-            let connection_count = {
+            let may_connect = {
                 let mut connections = self.connections.lock().await;
                 connections.retain(|connection| connection.state() != ConnectionState::Closed);
-                connections.len()
+                connections.len() < self.max_connections
+                    && self.admits(&connections, mode, priority)
             };
 
-            if connection_count >= self.max_connections {
+            if !may_connect {
                 drop(connect_guard);
                 notified.await;
                 continue;
             }
 
-            let connected = self.connector.connect(&self.spec).await?;
-            let connection = SshConnection::new(
-                connected,
-                self.max_channels_per_connection,
-                Arc::clone(&self.notify),
-                Arc::downgrade(self),
-            );
-
-            if let Err(err) = self.ensure_active() {
-                connection.expire();
-                return Err(err);
-            }
-
+            // The next request may queue for the connection being opened.
+            drop(ticket);
+            let connection = self.connect(prompter).await?;
             let reservation =
                 connection
                     .try_reserve()
@@ -126,13 +207,136 @@ impl TargetConnectionPool {
                 ?mode,
                 "registered SSH connection"
             );
-            return self.open_reserved((connection, reservation)).await;
+            drop(connect_guard);
+            return self
+                .open_replenished((connection, reservation), &kind)
+                .await;
+        }
+    }
+
+    /// Opens and authenticates a connection that is not registered yet.
+    /// Callers hold `connect_lock`.
+    async fn connect(
+        self: &Arc<Self>,
+        prompter: Option<&Arc<dyn AuthPrompter>>,
+    ) -> SshPoolResult<Arc<SshConnection>> {
+        let connected = self
+            .connector
+            .connect(
+                &self.spec,
+                self.jump.as_ref(),
+                &self.session_secret,
+                prompter,
+            )
+            .await?;
+        let connection = SshConnection::new(
+            connected,
+            self.max_channels_per_connection,
+            Arc::clone(&self.notify),
+            Arc::downgrade(self),
+        );
+
+        if let Err(err) = self.ensure_active() {
+            self.session_secret.clear();
+            connection.expire();
+            return Err(err);
+        }
+        Ok(connection)
+    }
+
+    /// Opens connections until `min_idle` of them are authenticated and
+    /// without channels, or the pool is full. Nobody answers prompts for
+    /// these connections.
+    pub(crate) async fn fill_idle(self: &Arc<Self>, min_idle: usize) -> SshPoolResult<()> {
+        loop {
+            self.ensure_active()?;
+            let _connect_guard = self.connect_lock.lock().await;
+            self.ensure_active()?;
+            let (idle, total) = {
+                let mut connections = self.connections.lock().await;
+                connections.retain(|connection| connection.state() != ConnectionState::Closed);
+                (idle_count(&connections), connections.len())
+            };
+            if idle >= min_idle || total >= self.max_connections {
+                return Ok(());
+            }
+
+            let connection = self.connect(None).await?;
+            self.connections.lock().await.push(Arc::clone(&connection));
+            debug!(
+                target_id = self.spec.target_id(),
+                connection_id = connection.id(),
+                "registered idle SSH connection"
+            );
+            self.notify.notify_waiters();
+            self.schedule_idle_close(&connection);
         }
     }
 
-    async fn try_existing(&self, mode: ChannelMode) -> Option<(Arc<SshConnection>, ChannelPermit)> {
+    /// Refills the idle connections kept by `min_idle_connections` in the
+    /// background. Targets that authenticate through prompts are skipped,
+    /// as nobody would be there to answer them.
+    fn replenish(self: &Arc<Self>) {
+        let min_idle = self.spec.min_idle_connections();
+        if min_idle == 0 || self.spec.auth.is_interactive() || self.ensure_active().is_err() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let pool = Arc::clone(self);
+        runtime.spawn(async move {
+            if let Err(err) = pool.fill_idle(min_idle).await
+                && pool.ensure_active().is_ok()
+            {
+                warn!(
+                    target_id = pool.spec.target_id(),
+                    %err,
+                    "failed to open idle SSH connection"
+                );
+            }
+        });
+    }
+
+    /// Whether a `priority` request may take capacity for `mode` without
+    /// using up the channel kept for interactive sessions.
+    fn admits(
+        &self,
+        connections: &[Arc<SshConnection>],
+        mode: ChannelMode,
+        priority: ChannelPriority,
+    ) -> bool {
+        if !self.reserve_interactive || priority == ChannelPriority::Interactive {
+            return true;
+        }
+        let cost = match mode {
+            ChannelMode::Shared => 1,
+            ChannelMode::Dedicated => self.max_channels_per_connection,
+        };
+        // Without room for both, the reservation would block `mode` forever.
+        if self.max_connections * self.max_channels_per_connection <= cost {
+            return true;
+        }
+        let free_channels: usize = connections
+            .iter()
+            .filter(|connection| connection.state() == ConnectionState::Active)
+            .map(|connection| self.max_channels_per_connection - connection.active_channels())
+            .sum::<usize>()
+            + self.max_connections.saturating_sub(connections.len())
+                * self.max_channels_per_connection;
+        free_channels > cost
+    }
+
+    async fn try_existing(
+        &self,
+        mode: ChannelMode,
+        priority: ChannelPriority,
+    ) -> Option<(Arc<SshConnection>, ChannelPermit)> {
         let mut connections = self.connections.lock().await;
         connections.retain(|connection| connection.state() != ConnectionState::Closed);
+        if !self.admits(&connections, mode, priority) {
+            return None;
+        }
 
         match mode {
             ChannelMode::Shared => connections.iter().find_map(|connection| {
@@ -151,21 +355,35 @@ impl TargetConnectionPool {
         }
     }
 
+    /// Opens the reserved channel and refills the idle connections the
+    /// reservation may have used up.
+    async fn open_replenished(
+        self: &Arc<Self>,
+        reservation: (Arc<SshConnection>, ChannelPermit),
+        kind: &ChannelKind,
+    ) -> SshPoolResult<SshChannelGuard> {
+        self.replenish();
+        self.open_reserved(reservation, kind).await
+    }
+
     async fn open_reserved(
         self: &Arc<Self>,
-        (connection, permit): (Arc<SshConnection>, ChannelPermit),
+        (connection, mut permit): (Arc<SshConnection>, ChannelPermit),
+        kind: &ChannelKind,
     ) -> SshPoolResult<SshChannelGuard> {
         let connection_id = connection.id().to_string();
         let pool = Arc::clone(self);
+        let kind = kind.clone();
         let (result_tx, result_rx) = tokio::sync::oneshot::channel();
         tokio::spawn(async move {
-            let result = match connection.open_channel().await {
+            let result = match connection.open_channel(&kind).await {
                 Ok(channel) => {
                     debug!(
                         connection_id = connection.id(),
                         channel_id = ?channel.id(),
                         "opened SSH channel"
                     );
+                    permit.mark_opened();
                     let guard = SshChannelGuard::new(channel, permit);
                     match pool.ensure_active() {
                         Ok(()) => Ok(guard),
@@ -203,6 +421,7 @@ impl TargetConnectionPool {
         if self.expired.swap(true, Ordering::AcqRel) {
             return;
         }
+        self.session_secret.clear();
         let connections = self.connections.lock().await.clone();
         for connection in connections {
             connection.expire();
@@ -210,6 +429,40 @@ impl TargetConnectionPool {
         self.notify.notify_waiters();
     }
 
+    /// Closes `connection` once it has stayed without channels for the idle
+    /// timeout. Any reservation in between keeps it open, and so does
+    /// `min_idle_connections`.
+    pub(crate) fn schedule_idle_close(self: &Arc<Self>, connection: &Arc<SshConnection>) {
+        let Some(idle_timeout) = self.idle_timeout else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let reservations = connection.reservations();
+        let connection = Arc::downgrade(connection);
+        let pool = Arc::downgrade(self);
+        runtime.spawn(async move {
+            tokio::time::sleep(idle_timeout).await;
+            let Some(connection) = connection.upgrade() else {
+                return;
+            };
+            if let Some(pool) = pool.upgrade()
+                && idle_count(&pool.connections.lock().await) <= pool.spec.min_idle_connections()
+            {
+                return;
+            }
+            if connection.is_idle() && connection.reservations() == reservations {
+                debug!(
+                    connection_id = connection.id(),
+                    ?idle_timeout,
+                    "closing idle SSH connection"
+                );
+                connection.expire();
+            }
+        });
+    }
+
     pub(crate) async fn expire_connection(&self, connection_id: &str) -> bool {
         let connection = self
             .connections
@@ -235,16 +488,24 @@ impl TargetConnectionPool {
                 state: connection.state(),
                 target_id: self.spec.target_id(),
                 active_channels: connection.active_channels(),
+                agent_identity: connection.agent_identity().map(str::to_string),
+                algorithms: connection.algorithms().cloned(),
+                created_at: connection.created_at(),
+                last_activity: connection.traffic().last_activity(),
+                bytes_in: connection.traffic().bytes_in(),
+                bytes_out: connection.traffic().bytes_out(),
+                server_version: connection.traffic().server_version().map(str::to_string),
             })
             .collect()
     }
 
-    pub(crate) async fn remove_closed(&self, connection_id: &str) {
+    pub(crate) async fn remove_closed(self: &Arc<Self>, connection_id: &str) {
         self.connections
             .lock()
             .await
             .retain(|connection| connection.id() != connection_id);
         self.notify.notify_waiters();
+        self.replenish();
     }
 
     pub(crate) async fn wait_until_empty(&self) {
@@ -259,3 +520,10 @@ impl TargetConnectionPool {
         }
     }
 }
+
+fn idle_count(connections: &[Arc<SshConnection>]) -> usize {
+    connections
+        .iter()
+        .filter(|connection| connection.state() == ConnectionState::Active && connection.is_idle())
+        .count()
+}
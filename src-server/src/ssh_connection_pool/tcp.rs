@@ -0,0 +1,211 @@
+use std::{io, net::SocketAddr, pin::pin, time::Duration};
+
+use futures_util::{StreamExt, stream::FuturesUnordered};
+use tokio::{net::TcpStream, time::Instant};
+use tracing::debug;
+
+use super::error::{SshPoolError, SshPoolResult};
+
+/// Delay before racing the next address while earlier attempts are still
+/// pending (RFC 8305 "Connection Attempt Delay").
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Resolves `host` to the addresses `connect_resolved` tries.
+pub(crate) async fn resolve(host: &str, port: u16) -> SshPoolResult<Vec<SocketAddr>> {
+    let resolve_failed = |source| SshPoolError::ResolveFailed {
+        host: host.to_string(),
+        port,
+        source,
+    };
+    let addresses = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(resolve_failed)?
+        .collect::<Vec<_>>();
+    if addresses.is_empty() {
+        return Err(resolve_failed(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no addresses found",
+        )));
+    }
+    Ok(addresses)
+}
+
+/// Connects to the first of `addresses` that accepts, racing them Happy
+/// Eyeballs style: address families are interleaved and a new attempt
+/// starts every `CONNECTION_ATTEMPT_DELAY` until one succeeds.
+///
+/// At `deadline` the attempts still pending fail as timed out, and the
+/// error lists them along with the ones that failed before.
+pub(crate) async fn connect_resolved(
+    host: &str,
+    port: u16,
+    addresses: Vec<SocketAddr>,
+    deadline: Instant,
+) -> SshPoolResult<TcpStream> {
+    connect_addresses(host, port, interleave_families(addresses), deadline).await
+}
+
+async fn connect_addresses(
+    host: &str,
+    port: u16,
+    addresses: Vec<SocketAddr>,
+    deadline: Instant,
+) -> SshPoolResult<TcpStream> {
+    let mut remaining = addresses.into_iter();
+    let mut pending = FuturesUnordered::new();
+    let mut in_flight = Vec::new();
+    let mut failures = Vec::new();
+    let mut next_attempt = pin!(tokio::time::sleep(Duration::ZERO));
+    let mut expired = pin!(tokio::time::sleep_until(deadline));
+
+    loop {
+        tokio::select! {
+            Some((address, result)) = pending.next(), if !pending.is_empty() => {
+                in_flight.retain(|pending| *pending != address);
+                match result {
+                    Ok(socket) => return Ok(socket),
+                    Err(err) => {
+                        debug!(host, %address, ?err, "TCP connect attempt failed");
+                        failures.push((address, err));
+                        // A failure starts the next attempt right away.
+                        next_attempt.as_mut().reset(Instant::now());
+                    }
+                }
+            }
+            () = &mut expired, if !pending.is_empty() || remaining.len() > 0 => {
+                failures.extend(in_flight.drain(..).map(|address| {
+                    let err = io::Error::new(io::ErrorKind::TimedOut, "connect timed out");
+                    (address, err)
+                }));
+                return Err(SshPoolError::ConnectFailed {
+                    host: host.to_string(),
+                    port,
+                    attempts: failures,
+                });
+            }
+            () = &mut next_attempt, if remaining.len() > 0 => {
+                let address = remaining.next().expect("remaining address");
+                in_flight.push(address);
+                pending.push(async move { (address, TcpStream::connect(address).await) });
+                next_attempt
+                    .as_mut()
+                    .reset(Instant::now() + CONNECTION_ATTEMPT_DELAY);
+            }
+            else => {
+                return Err(SshPoolError::ConnectFailed {
+                    host: host.to_string(),
+                    port,
+                    attempts: failures,
+                });
+            }
+        }
+    }
+}
+
+/// Orders addresses so that families alternate, starting with the family
+/// of the first resolved address.
+fn interleave_families(addresses: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let Some(first) = addresses.first() else {
+        return addresses;
+    };
+    let first_is_ipv6 = first.is_ipv6();
+    let (preferred, other): (Vec<_>, Vec<_>) = addresses
+        .into_iter()
+        .partition(|address| address.is_ipv6() == first_is_ipv6);
+    let mut preferred = preferred.into_iter();
+    let mut other = other.into_iter();
+    let mut interleaved = Vec::new();
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => return interleaved,
+            (first, second) => interleaved.extend(first.into_iter().chain(second)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_families_alternate() {
+        let addresses = [
+            "[::1]:22",
+            "[::2]:22",
+            "[::3]:22",
+            "10.0.0.1:22",
+            "10.0.0.2:22",
+        ]
+        .map(|address| address.parse::<SocketAddr>().unwrap());
+        let ordered = interleave_families(addresses.to_vec());
+        assert_eq!(
+            ordered,
+            [
+                addresses[0],
+                addresses[3],
+                addresses[1],
+                addresses[4],
+                addresses[2]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_next_address_and_reports_every_failure() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap();
+        let closed = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap()
+        };
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let socket = connect_addresses("test", 22, vec![closed, open], deadline)
+            .await
+            .unwrap();
+        assert_eq!(socket.peer_addr().unwrap(), open);
+
+        let err = connect_addresses("test", 22, vec![closed, closed], deadline)
+            .await
+            .unwrap_err();
+        let SshPoolError::ConnectFailed { attempts, .. } = &err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(attempts.len(), 2);
+        assert!(err.to_string().contains(&closed.to_string()));
+        assert!(err.is_transient());
+    }
+
+    #[tokio::test]
+    async fn reports_the_attempts_made_before_the_deadline() {
+        let closed = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap()
+        };
+        // Once its accept queue is full, the listener drops further SYNs and
+        // the next attempt stays pending.
+        let backlogged = tokio::net::TcpSocket::new_v4().unwrap();
+        backlogged.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let backlogged = backlogged.listen(0).unwrap();
+        let unanswered = backlogged.local_addr().unwrap();
+        let mut queued = Vec::new();
+        while let Ok(Ok(stream)) =
+            tokio::time::timeout(Duration::from_millis(200), TcpStream::connect(unanswered)).await
+        {
+            queued.push(stream);
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(500);
+        let err = connect_addresses("test", 22, vec![closed, unanswered], deadline)
+            .await
+            .unwrap_err();
+        let SshPoolError::ConnectFailed { attempts, .. } = &err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].0, closed);
+        assert_eq!(attempts[1].0, unanswered);
+        assert_eq!(attempts[1].1.kind(), io::ErrorKind::TimedOut);
+        assert!(err.is_transient());
+    }
+}
@@ -5,24 +5,38 @@ use axum::{
     http::HeaderMap,
 };
 use futures_util::StreamExt;
-use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database, EntityTrait};
 use sea_orm_migration::MigratorTrait;
 
 use crate::{
     AppBaseState, AppState,
     apis::{
         sftp::{download, dto::SftpFileUriPayload},
-        target::{TargetUpdatePayload, remove_for_test, update_for_test},
-        transfer::TransferService,
+        target::{
+            TargetUpdatePayload, detect_capabilities_for_test, is_windows, remove_for_test,
+            spawn_capability_detection, update_for_test,
+        },
+        transfer::{CreateDownloadTaskPayload, TransferService},
     },
     config::{CheckServerKey, Config},
-    entities::target::{self, TargetAuthMethod},
+    entities::{
+        key_store, ssh_known_host,
+        target::{self, TargetAlgorithms, TargetAuthMethod, TargetHostKeyPolicy},
+        transfer_task::TransferTaskStatus,
+    },
     migrations::Migrator,
-    repositories::target as target_repository,
-    tests::sftp_server,
+    repositories::{
+        target as target_repository, target_capabilities as target_capabilities_repository,
+    },
+    secrets::Secrets,
+    tests::{proxy_server, sftp_server},
 };
 
-use super::{ChannelMode, ConnectionState, SshConnectionPool, SshPoolError};
+use super::{
+    AuthPromptRequest, AuthPrompter, ChannelMode, ChannelPriority, ConnectionEventKind,
+    ConnectionState, DiagnosticStage, HostKeyPromptRequest, HostKeyVerdict, SshConnectionPool,
+    SshPoolError, SshPoolResult, SshProxy, StageStatus,
+};
 
 struct TestContext {
     db: sea_orm::DatabaseConnection,
@@ -55,8 +69,45 @@ fn test_target() -> target::Model {
         method: TargetAuthMethod::Password,
         user: "root".to_string(),
         key: None,
+        key_id: None,
+        certificate: None,
         password: Some("123456".to_string()),
         system: Some("linux".to_string()),
+        jump_target_id: None,
+        proxy: None,
+        proxy_command: None,
+        algorithms: None,
+        connect_timeout: None,
+        connect_retries: None,
+        min_idle_connections: None,
+        host_key_policy: None,
+        group_id: None,
+        tags: None,
+    }
+}
+
+fn update_payload(current: target::Model) -> TargetUpdatePayload {
+    TargetUpdatePayload {
+        id: current.id,
+        host: current.host,
+        port: current.port,
+        method: current.method,
+        user: current.user,
+        key: current.key,
+        key_id: current.key_id,
+        certificate: current.certificate,
+        password: current.password,
+        system: current.system,
+        jump_target_id: current.jump_target_id,
+        proxy: current.proxy,
+        proxy_command: current.proxy_command,
+        algorithms: current.algorithms,
+        connect_timeout: current.connect_timeout,
+        connect_retries: current.connect_retries,
+        min_idle_connections: current.min_idle_connections,
+        host_key_policy: current.host_key_policy,
+        group_id: current.group_id,
+        tags: current.tags,
     }
 }
 
@@ -125,12 +176,128 @@ async fn connection_pool_regressions() {
     )
     .await
     .expect("download body lifecycle scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        download_task_resumes_after_a_lost_connection(&context),
+    )
+    .await
+    .expect("transfer resume scenario timed out");
     tokio::time::timeout(
         Duration::from_secs(10),
         target_expiry_rejects_a_channel_opened_after_expiry(&context),
     )
     .await
     .expect("post-open target expiry scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        idle_connection_is_closed_after_the_idle_timeout(&context),
+    )
+    .await
+    .expect("idle timeout scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        unanswered_keepalives_close_the_connection(&context),
+    )
+    .await
+    .expect("keepalive scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        jump_target_tunnels_through_its_pooled_connection(&context),
+    )
+    .await
+    .expect("jump target scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        jump_target_cycle_is_rejected(&context),
+    )
+    .await
+    .expect("jump target cycle scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        proxies_tunnel_connections_and_check_credentials(&context),
+    )
+    .await
+    .expect("proxy scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        target_algorithms_are_negotiated(&context),
+    )
+    .await
+    .expect("algorithm preference scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        connect_retries_and_timeouts_follow_the_target(&context),
+    )
+    .await
+    .expect("connect retry scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        min_idle_connections_are_refilled(&context),
+    )
+    .await
+    .expect("minimum idle connection scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        waiters_are_served_in_priority_order(&context),
+    )
+    .await
+    .expect("channel priority scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        connection_lifecycle_is_reported(&context),
+    )
+    .await
+    .expect("connection event scenario timed out");
+    #[cfg(target_os = "linux")]
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        proxy_command_is_reaped_with_its_connection(&context),
+    )
+    .await
+    .expect("ProxyCommand scenario timed out");
+    #[cfg(unix)]
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        agent_auth_falls_back_to_the_next_identity(&context),
+    )
+    .await
+    .expect("ssh-agent authentication scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        keyboard_interactive_prompts_are_relayed_to_the_attached_client(&context),
+    )
+    .await
+    .expect("keyboard-interactive scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        prompted_password_is_kept_until_the_target_pool_expires(&context),
+    )
+    .await
+    .expect("prompted password scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        unknown_host_key_is_confirmed_by_the_attached_client(&context),
+    )
+    .await
+    .expect("host key prompt scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        target_host_key_policy_overrides_the_pool_policy(&context),
+    )
+    .await
+    .expect("target host key policy scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        certificate_auth_uses_a_valid_user_certificate(&context),
+    )
+    .await
+    .expect("certificate authentication scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        stored_key_and_password_channel_authenticate_the_target(&context),
+    )
+    .await
+    .expect("stored key scenario timed out");
     tokio::time::timeout(
         Duration::from_secs(10),
         target_reads_wait_for_mutation(&context),
@@ -149,6 +316,18 @@ async fn connection_pool_regressions() {
     )
     .await
     .expect("target delete scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        connection_test_reports_each_stage(&context),
+    )
+    .await
+    .expect("connection test scenario timed out");
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        capabilities_are_detected_after_the_first_connection(&context),
+    )
+    .await
+    .expect("capability detection scenario timed out");
 }
 
 async fn unsupported_auth_is_rejected_before_pool_creation(context: &TestContext) {
@@ -157,7 +336,10 @@ async fn unsupported_auth_is_rejected_before_pool_creation(context: &TestContext
     unsupported.update(&context.db).await.unwrap();
 
     let pool = connection_pool(context, 1, 1);
-    let err = match pool.channel(1, ChannelMode::Shared).await {
+    let err = match pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
         Ok(_) => panic!("unsupported authentication should be rejected"),
         Err(err) => err,
     };
@@ -174,28 +356,37 @@ async fn unsupported_auth_is_rejected_before_pool_creation(context: &TestContext
 
 async fn released_channel_capacity_can_be_acquired_again(context: &TestContext) {
     let pool = connection_pool(context, 1, 1);
-    let first = pool.channel(1, ChannelMode::Shared).await.unwrap();
+    let first = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
 
     assert!(
         tokio::time::timeout(
             Duration::from_millis(100),
-            pool.channel(1, ChannelMode::Shared),
+            pool.channel(1, ChannelMode::Shared, ChannelPriority::Interactive),
         )
         .await
         .is_err()
     );
 
     drop(first);
-    let second = tokio::time::timeout(Duration::from_secs(2), pool.channel(1, ChannelMode::Shared))
-        .await
-        .expect("released permit should wake a waiter")
-        .unwrap();
+    let second = tokio::time::timeout(
+        Duration::from_secs(2),
+        pool.channel(1, ChannelMode::Shared, ChannelPriority::Interactive),
+    )
+    .await
+    .expect("released permit should wake a waiter")
+    .unwrap();
     drop(second);
 }
 
 async fn split_keeps_capacity_until_transfer_guard_is_dropped(context: &TestContext) {
     let pool = connection_pool(context, 1, 1);
-    let channel = pool.channel(1, ChannelMode::Shared).await.unwrap();
+    let channel = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
     let (reader, writer, lease) = channel.split().unwrap();
     writer.close().await.unwrap();
     drop(reader);
@@ -204,25 +395,33 @@ async fn split_keeps_capacity_until_transfer_guard_is_dropped(context: &TestCont
     assert!(
         tokio::time::timeout(
             Duration::from_millis(100),
-            pool.channel(1, ChannelMode::Shared),
+            pool.channel(1, ChannelMode::Shared, ChannelPriority::Interactive),
         )
         .await
         .is_err()
     );
 
     drop(lease);
-    let channel =
-        tokio::time::timeout(Duration::from_secs(2), pool.channel(1, ChannelMode::Shared))
-            .await
-            .expect("dropping split lease should release capacity")
-            .unwrap();
+    let channel = tokio::time::timeout(
+        Duration::from_secs(2),
+        pool.channel(1, ChannelMode::Shared, ChannelPriority::Interactive),
+    )
+    .await
+    .expect("dropping split lease should release capacity")
+    .unwrap();
     drop(channel);
 }
 
 async fn dedicated_channel_does_not_expire_an_active_shared_connection(context: &TestContext) {
     let pool = connection_pool(context, 2, 1);
-    let mut shared = pool.channel(1, ChannelMode::Shared).await.unwrap();
-    let dedicated = pool.channel(1, ChannelMode::Dedicated).await.unwrap();
+    let mut shared = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    let dedicated = pool
+        .channel(1, ChannelMode::Dedicated, ChannelPriority::Interactive)
+        .await
+        .unwrap();
 
     let snapshots = pool.connection_snapshots(Some(1)).await;
     assert_eq!(snapshots.len(), 2);
@@ -252,17 +451,22 @@ async fn dedicated_channel_does_not_expire_an_active_shared_connection(context:
 
 async fn changed_connection_spec_replaces_the_target_connection_pool(context: &TestContext) {
     let pool = connection_pool(context, 1, 1);
-    let old_channel = pool.channel(1, ChannelMode::Shared).await.unwrap();
+    let old_channel = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
     let old_connection_id = pool.connection_snapshots(Some(1)).await[0].id.clone();
 
     let mut changed = target::ActiveModel::from(test_target());
     changed.password = Set(Some("changed".to_string()));
     changed.update(&context.db).await.unwrap();
-    let replacement =
-        tokio::time::timeout(Duration::from_secs(2), pool.channel(1, ChannelMode::Shared))
-            .await
-            .expect("replacement channel acquisition timed out")
-            .unwrap();
+    let replacement = tokio::time::timeout(
+        Duration::from_secs(2),
+        pool.channel(1, ChannelMode::Shared, ChannelPriority::Interactive),
+    )
+    .await
+    .expect("replacement channel acquisition timed out")
+    .unwrap();
     let snapshots = pool.connection_snapshots(Some(1)).await;
     assert_eq!(snapshots.len(), 2);
     assert!(snapshots.iter().any(|snapshot| {
@@ -279,7 +483,10 @@ async fn changed_connection_spec_replaces_the_target_connection_pool(context: &T
 
 async fn expiring_an_idle_connection_closes_it(context: &TestContext) {
     let pool = connection_pool(context, 1, 1);
-    let channel = pool.channel(1, ChannelMode::Shared).await.unwrap();
+    let channel = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
     drop(channel);
     let connection_id = pool.connection_snapshots(Some(1)).await[0].id.clone();
 
@@ -289,13 +496,19 @@ async fn expiring_an_idle_connection_closes_it(context: &TestContext) {
 
 async fn dropping_dedicated_sftp_releases_its_connection(context: &TestContext) {
     let pool = connection_pool(context, 1, 1);
-    let sftp = pool.sftp(1, ChannelMode::Dedicated).await.unwrap();
-    drop(sftp);
-
-    let shared = tokio::time::timeout(Duration::from_secs(2), pool.channel(1, ChannelMode::Shared))
+    let sftp = pool
+        .sftp(1, ChannelMode::Dedicated, ChannelPriority::Interactive)
         .await
-        .expect("dropping the SFTP guard should close its dedicated connection")
         .unwrap();
+    drop(sftp);
+
+    let shared = tokio::time::timeout(
+        Duration::from_secs(2),
+        pool.channel(1, ChannelMode::Shared, ChannelPriority::Interactive),
+    )
+    .await
+    .expect("dropping the SFTP guard should close its dedicated connection")
+    .unwrap();
     drop(shared);
 }
 
@@ -304,7 +517,10 @@ async fn hard_aborting_dedicated_sftp_releases_its_connection(context: &TestCont
     let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
     let task_pool = Arc::clone(&pool);
     let task = tokio::spawn(async move {
-        let sftp = task_pool.sftp(1, ChannelMode::Dedicated).await.unwrap();
+        let sftp = task_pool
+            .sftp(1, ChannelMode::Dedicated, ChannelPriority::Interactive)
+            .await
+            .unwrap();
         let _ = ready_tx.send(());
         std::future::pending::<()>().await;
         drop(sftp);
@@ -318,10 +534,13 @@ async fn hard_aborting_dedicated_sftp_releases_its_connection(context: &TestCont
     task.abort();
     assert!(task.await.unwrap_err().is_cancelled());
 
-    let shared = tokio::time::timeout(Duration::from_secs(2), pool.channel(1, ChannelMode::Shared))
-        .await
-        .expect("hard-aborted SFTP task should release its dedicated connection")
-        .unwrap();
+    let shared = tokio::time::timeout(
+        Duration::from_secs(2),
+        pool.channel(1, ChannelMode::Shared, ChannelPriority::Interactive),
+    )
+    .await
+    .expect("hard-aborted SFTP task should release its dedicated connection")
+    .unwrap();
     drop(shared);
 }
 
@@ -353,11 +572,78 @@ async fn download_body_releases_its_sftp_lease(context: &TestContext) {
     wait_until_no_active_channels(&pool).await;
 }
 
+async fn download_task_resumes_after_a_lost_connection(context: &TestContext) {
+    let pool = connection_pool(context, 1, 1);
+    let chunk_size = crate::sftp_client::transfer::DEFAULT_PIPELINE_CHUNK_SIZE as u64;
+    let base_state = Arc::new(AppBaseState {
+        db: context.db.clone(),
+        config: Config {
+            transfer_chunk_size: chunk_size as usize,
+            ..Config::default()
+        },
+        secrets: Secrets::default(),
+    });
+    let transfer_service = TransferService::new(base_state, pool);
+    let local_path =
+        std::env::temp_dir().join(format!("webssh-rs-resume-{}.bin", nanoid::nanoid!()));
+    sftp_server::lose_connection_at(2 * chunk_size);
+
+    let task = transfer_service
+        .create_download(CreateDownloadTaskPayload {
+            source_uri: format!("sftp:1:{}", sftp_server::RESUMABLE_FILE_PATH),
+            local_path: Some(local_path.to_string_lossy().to_string()),
+            local_dir: None,
+        })
+        .await
+        .unwrap();
+    let task = loop {
+        let task = transfer_service.get_task_model(&task.id).await.unwrap();
+        if !matches!(
+            task.status,
+            TransferTaskStatus::Wait | TransferTaskStatus::Run
+        ) {
+            break task;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    };
+    let downloaded = std::fs::read(&local_path).unwrap();
+    let _ = std::fs::remove_file(&local_path);
+
+    assert_eq!(
+        task.status,
+        TransferTaskStatus::Success,
+        "{:?}",
+        task.fail_reason
+    );
+    assert_eq!(task.attempts, 2);
+    let reads = sftp_server::resumable_reads();
+    assert_eq!(
+        reads.iter().filter(|offset| **offset == 0).count(),
+        1,
+        "the retry should resume after the chunks already written: {reads:?}"
+    );
+    assert_eq!(
+        reads
+            .iter()
+            .filter(|offset| **offset == 2 * chunk_size)
+            .count(),
+        2
+    );
+    assert_eq!(downloaded.len(), sftp_server::RESUMABLE_FILE_SIZE);
+    assert!(
+        downloaded
+            .iter()
+            .enumerate()
+            .all(|(index, byte)| *byte == (index % 251) as u8)
+    );
+}
+
 fn download_app_state(context: &TestContext) -> (Arc<SshConnectionPool>, Arc<AppState>) {
     let pool = connection_pool(context, 1, 1);
     let base_state = Arc::new(AppBaseState {
         db: context.db.clone(),
         config: Config::default(),
+        secrets: Secrets::default(),
     });
     let transfer_service = TransferService::new(Arc::clone(&base_state), Arc::clone(&pool));
     let state = Arc::new(AppState {
@@ -405,7 +691,11 @@ async fn target_expiry_rejects_a_channel_opened_after_expiry(context: &TestConte
     let pool = connection_pool(context, 1, 1);
     context.channel_open_control.block_next();
     let acquire_pool = Arc::clone(&pool);
-    let acquire = tokio::spawn(async move { acquire_pool.channel(1, ChannelMode::Shared).await });
+    let acquire = tokio::spawn(async move {
+        acquire_pool
+            .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+            .await
+    });
 
     context.channel_open_control.wait_until_blocked().await;
     pool.expire_target(1).await;
@@ -439,92 +729,1223 @@ async fn target_expiry_rejects_a_channel_opened_after_expiry(context: &TestConte
     .expect("the rejected channel and expired connection should be cleaned up");
 }
 
-async fn target_reads_wait_for_mutation(context: &TestContext) {
-    let pool = connection_pool(context, 1, 1);
-    let current = target_repository::find_by_id(&context.db, 1)
+async fn idle_connection_is_closed_after_the_idle_timeout(context: &TestContext) {
+    let pool = Arc::new(
+        SshConnectionPool::new(context.db.clone(), CheckServerKey::AcceptNew, 1, 1)
+            .with_idle_timeout(Some(Duration::from_millis(200))),
+    );
+    let channel = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
         .await
-        .unwrap()
         .unwrap();
-    let updated_system = "mutation-serialized-linux".to_string();
-    let mut active_model = target::ActiveModel::from(current);
-    active_model.system = Set(Some(updated_system.clone()));
+    let connection_id = pool.connection_snapshots(Some(1)).await[0].id.clone();
 
-    let (mutation_started_tx, mutation_started_rx) = tokio::sync::oneshot::channel();
-    let (mutation_release_tx, mutation_release_rx) = tokio::sync::oneshot::channel();
-    let mutation_pool = Arc::clone(&pool);
-    let mutation_db = context.db.clone();
-    let mutation = tokio::spawn(async move {
-        mutation_pool
-            .with_target_mutation(1, move || async move {
-                let _ = mutation_started_tx.send(());
-                mutation_release_rx.await.unwrap();
-                target_repository::update(&mutation_db, active_model).await
-            })
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    let snapshots = pool.connection_snapshots(Some(1)).await;
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].state, ConnectionState::Active);
+
+    drop(channel);
+    wait_until_connection_is_removed(&pool, &connection_id).await;
+}
+
+async fn unanswered_keepalives_close_the_connection(context: &TestContext) {
+    let pool = Arc::new(
+        SshConnectionPool::new(context.db.clone(), CheckServerKey::AcceptNew, 1, 2)
+            .with_keepalive(Some(Duration::from_millis(100)), 2),
+    );
+    let channel = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    let connection_id = pool.connection_snapshots(Some(1)).await[0].id.clone();
+
+    // The server stops reading, and answering keepalives, while a channel
+    // open is held at the gate.
+    context.channel_open_control.block_next();
+    let acquire_pool = Arc::clone(&pool);
+    let acquire = tokio::spawn(async move {
+        acquire_pool
+            .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
             .await
     });
+    context.channel_open_control.wait_until_blocked().await;
 
-    mutation_started_rx.await.unwrap();
-    let reader_pool = Arc::clone(&pool);
-    let reader = tokio::spawn(async move { reader_pool.context(1).await });
-    tokio::time::sleep(Duration::from_millis(50)).await;
-    assert!(
-        !reader.is_finished(),
-        "target reads must wait until the mutation finishes"
-    );
+    wait_until_connection_is_removed(&pool, &connection_id).await;
+    context.channel_open_control.release();
+    assert!(acquire.await.unwrap().is_err());
+    drop(channel);
+}
 
-    mutation_release_tx.send(()).unwrap();
-    mutation.await.unwrap().unwrap();
-    let refreshed = tokio::time::timeout(Duration::from_secs(2), reader)
+async fn insert_jump_target(context: &TestContext, id: i32, jump_target_id: Option<i32>) {
+    let mut jumped = test_target();
+    jumped.id = id;
+    jumped.jump_target_id = jump_target_id;
+    target::ActiveModel::from(jumped)
+        .insert(&context.db)
         .await
-        .expect("target read should resume after the mutation")
-        .unwrap()
         .unwrap();
-    assert_eq!(
-        refreshed.target().system.as_deref(),
-        Some(updated_system.as_str())
-    );
 }
 
-async fn target_update_is_not_blocked_by_a_capacity_waiter(context: &TestContext) {
-    let pool = connection_pool(context, 1, 1);
-    let active = pool.channel(1, ChannelMode::Shared).await.unwrap();
-    let waiter = spawn_capacity_waiter(&pool).await;
+async fn jump_target_tunnels_through_its_pooled_connection(context: &TestContext) {
+    insert_jump_target(context, 2, Some(1)).await;
+    let pool = connection_pool(context, 1, 2);
 
-    let current = target_repository::find_by_id(&context.db, 1)
+    let mut channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
         .await
-        .unwrap()
         .unwrap();
-    let updated_system = "updated-linux".to_string();
-    let payload = TargetUpdatePayload {
-        id: current.id,
-        host: current.host,
-        port: current.port,
-        method: current.method,
-        user: current.user,
-        key: current.key,
-        password: current.password,
-        system: Some(updated_system.clone()),
-    };
-
-    let updated = tokio::time::timeout(
-        Duration::from_secs(2),
-        update_for_test(&context.db, &pool, payload),
-    )
-    .await
-    .expect("target update must not wait for channel capacity")
-    .unwrap();
-    assert_eq!(updated.system.as_deref(), Some(updated_system.as_str()));
-    assert_capacity_waiter_expired(waiter).await;
+    let jump_snapshots = pool.connection_snapshots(Some(1)).await;
+    assert_eq!(jump_snapshots.len(), 1);
+    assert_eq!(jump_snapshots[0].active_channels, 1);
+    assert_eq!(pool.connection_snapshots(Some(2)).await.len(), 1);
 
-    let persisted = target_repository::find_by_id(&context.db, 1)
+    channel.exec(true, "hello").await.unwrap();
+    let message = tokio::time::timeout(Duration::from_secs(2), channel.wait())
         .await
-        .unwrap()
         .unwrap();
-    assert_eq!(persisted.system.as_deref(), Some(updated_system.as_str()));
-    {
-        let refreshed = pool.context(1).await.unwrap();
-        assert_eq!(
-            refreshed.target().system.as_deref(),
+    assert!(matches!(message, Some(russh::ChannelMsg::Data { .. })));
+    drop(channel);
+
+    let tunnelled_connection_id = pool.connection_snapshots(Some(2)).await[0].id.clone();
+    assert!(pool.expire_connection(2, &tunnelled_connection_id).await);
+    tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            if active_channel_count(&pool).await == 0 {
+                return;
+            }
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("closing the tunnelled connection should release its jump channel");
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+}
+
+async fn jump_target_cycle_is_rejected(context: &TestContext) {
+    insert_jump_target(context, 2, Some(3)).await;
+    insert_jump_target(context, 3, Some(2)).await;
+    let pool = connection_pool(context, 1, 1);
+
+    let err = match pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("a jump target cycle should be rejected"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::JumpTargetCycle { target_id: 2 })
+    ));
+    assert!(pool.connection_snapshots(None).await.is_empty());
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+    remove_for_test(&context.db, &pool, 3).await.unwrap();
+}
+
+async fn connection_lifecycle_is_reported(context: &TestContext) {
+    let pool = connection_pool(context, 1, 1);
+    let mut events = pool.subscribe_connection_events();
+
+    let mut channel = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    channel.exec(true, "hello").await.unwrap();
+    let message = tokio::time::timeout(Duration::from_secs(2), channel.wait())
+        .await
+        .unwrap();
+    assert!(matches!(message, Some(russh::ChannelMsg::Data { .. })));
+    let snapshot = pool.connection_snapshots(Some(1)).await.remove(0);
+    assert_eq!(snapshot.active_channels, 1);
+    assert!(snapshot.created_at > 0);
+    assert!(snapshot.last_activity >= snapshot.created_at);
+    assert!(snapshot.bytes_in > 0 && snapshot.bytes_out > 0);
+    assert!(
+        snapshot
+            .server_version
+            .as_deref()
+            .is_some_and(|version| version.starts_with("SSH-2.0-"))
+    );
+    drop(channel);
+    wait_until_no_active_channels(&pool).await;
+    assert!(pool.expire_connection(1, &snapshot.id).await);
+
+    let mut kinds = Vec::new();
+    loop {
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.target_id, 1);
+        if event.kind != ConnectionEventKind::Connecting {
+            assert_eq!(event.connection_id.as_deref(), Some(snapshot.id.as_str()));
+        }
+        let closed = matches!(event.kind, ConnectionEventKind::Closed { .. });
+        kinds.push(event.kind);
+        if closed {
+            break;
+        }
+    }
+    assert_eq!(
+        kinds,
+        vec![
+            ConnectionEventKind::Connecting,
+            ConnectionEventKind::Authenticated,
+            ConnectionEventKind::ChannelOpened,
+            ConnectionEventKind::ChannelClosed,
+            ConnectionEventKind::Expiring,
+            ConnectionEventKind::Closed {
+                reason: "expired".to_string()
+            },
+        ]
+    );
+
+    let mut rejected = target::ActiveModel::from(test_target());
+    rejected.password = Set(Some(sftp_server::REJECTED_PASSWORD.to_string()));
+    rejected.clone().update(&context.db).await.unwrap();
+    assert!(
+        pool.channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        events.recv().await.unwrap().kind,
+        ConnectionEventKind::Connecting
+    );
+    assert_eq!(
+        events.recv().await.unwrap().kind,
+        ConnectionEventKind::ConnectFailed {
+            reason: SshPoolError::AuthenticationFailed.to_string()
+        }
+    );
+    rejected.password = Set(test_target().password);
+    rejected.update(&context.db).await.unwrap();
+}
+
+async fn target_algorithms_are_negotiated(context: &TestContext) {
+    let mut configured = test_target();
+    configured.id = 2;
+    configured.algorithms = Some(TargetAlgorithms {
+        cipher: vec!["aes256-ctr".to_string()],
+        mac: vec!["hmac-sha2-256".to_string()],
+        compression: vec!["zlib@openssh.com".to_string()],
+        ..Default::default()
+    });
+    target::ActiveModel::from(configured)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 1);
+
+    let channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    let algorithms = pool.connection_snapshots(Some(2)).await[0]
+        .algorithms
+        .clone()
+        .expect("negotiated algorithms should be recorded");
+    assert_eq!(algorithms.cipher, "aes256-ctr");
+    assert_eq!(algorithms.client_mac, "hmac-sha2-256");
+    assert_eq!(algorithms.client_compression, "zlib@openssh.com");
+    assert_eq!(algorithms.server_compression, "zlib@openssh.com");
+    drop(channel);
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+}
+
+async fn connect_retries_and_timeouts_follow_the_target(context: &TestContext) {
+    let unused_addr = || async {
+        tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    };
+
+    // The target only starts listening after the first attempt failed.
+    let late_addr = unused_addr().await;
+    let mut retried = test_target();
+    retried.id = 2;
+    retried.port = Some(late_addr.port());
+    retried.connect_retries = Some(3);
+    target::ActiveModel::from(retried)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let forwarder = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let listener = tokio::net::TcpListener::bind(late_addr).await.unwrap();
+        let (mut inbound, _) = listener.accept().await.unwrap();
+        let mut outbound =
+            tokio::net::TcpStream::connect(("127.0.0.1", test_target().port.unwrap()))
+                .await
+                .unwrap();
+        let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+    });
+    let pool = connection_pool(context, 1, 1);
+    let channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    drop(channel);
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+    forwarder.abort();
+
+    let refused_addr = unused_addr().await;
+    let mut refused = test_target();
+    refused.id = 2;
+    refused.port = Some(refused_addr.port());
+    target::ActiveModel::from(refused)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 1);
+    let err = match pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("a closed port should be reported"),
+        Err(err) => err,
+    };
+    match err.downcast_ref::<SshPoolError>() {
+        Some(SshPoolError::ConnectFailed { attempts, .. }) => {
+            assert_eq!(attempts.len(), 1);
+            assert_eq!(attempts[0].0, refused_addr);
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+
+    // A server that never sends its identification runs into the target's
+    // own timeout.
+    let silent = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut stalled = test_target();
+    stalled.id = 2;
+    stalled.port = Some(silent.local_addr().unwrap().port());
+    stalled.connect_timeout = Some(1);
+    target::ActiveModel::from(stalled)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 1);
+    let err = match pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("a silent server should time out"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::ConnectTimeout { timeout }) if *timeout == Duration::from_secs(1)
+    ));
+    drop(silent);
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+}
+
+async fn waiters_are_served_in_priority_order(context: &TestContext) {
+    let spawn_waiter = |pool: &Arc<SshConnectionPool>, priority| {
+        let pool = Arc::clone(pool);
+        tokio::spawn(async move { pool.channel(1, ChannelMode::Shared, priority).await })
+    };
+
+    let pool = connection_pool(context, 1, 1);
+    let active = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Transfer)
+        .await
+        .unwrap();
+    let transfer = spawn_waiter(&pool, ChannelPriority::Transfer);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let interactive = spawn_waiter(&pool, ChannelPriority::Interactive);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!transfer.is_finished() && !interactive.is_finished());
+
+    drop(active);
+    let interactive = interactive.await.unwrap().unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(
+        !transfer.is_finished(),
+        "the earlier transfer must wait for the interactive session"
+    );
+    drop(interactive);
+    drop(transfer.await.unwrap().unwrap());
+    wait_until_no_active_channels(&pool).await;
+
+    // With the reservation, bulk work leaves the last channel free.
+    let pool = Arc::new(
+        SshConnectionPool::new(context.db.clone(), CheckServerKey::AcceptNew, 2, 1)
+            .with_reserved_interactive_channel(true),
+    );
+    let transfer = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Transfer)
+        .await
+        .unwrap();
+    let blocked = spawn_waiter(&pool, ChannelPriority::Exec);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(
+        !blocked.is_finished(),
+        "the reserved channel must not go to an exec request"
+    );
+    let interactive = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    drop(transfer);
+    drop(interactive);
+    drop(blocked.await.unwrap().unwrap());
+    wait_until_no_active_channels(&pool).await;
+}
+
+async fn idle_connection_ids(pool: &SshConnectionPool, target_id: i32) -> Vec<String> {
+    pool.connection_snapshots(Some(target_id))
+        .await
+        .into_iter()
+        .filter(|snapshot| {
+            snapshot.state == ConnectionState::Active && snapshot.active_channels == 0
+        })
+        .map(|snapshot| snapshot.id)
+        .collect()
+}
+
+async fn min_idle_connections_are_refilled(context: &TestContext) {
+    let mut warmed = test_target();
+    warmed.id = 2;
+    warmed.min_idle_connections = Some(1);
+    target::ActiveModel::from(warmed)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 2, 1);
+    pool.warm(2).await.unwrap();
+    let warmed = idle_connection_ids(&pool, 2).await;
+    assert_eq!(warmed.len(), 1);
+
+    // The dedicated channel takes the warmed connection and a new one is
+    // opened in the background.
+    let channel = pool
+        .channel(2, ChannelMode::Dedicated, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    let refilled = loop {
+        let idle = idle_connection_ids(&pool, 2).await;
+        if !idle.is_empty() && idle != warmed {
+            break idle;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    };
+    assert_eq!(refilled.len(), 1);
+    assert_eq!(pool.connection_snapshots(Some(2)).await.len(), 2);
+    drop(channel);
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+
+    // Nobody answers the prompts of a background refill, so targets that
+    // prompt are not refilled even with the answer cached.
+    let mut prompted = test_target();
+    prompted.id = 2;
+    prompted.method = TargetAuthMethod::Prompt;
+    prompted.password = None;
+    prompted.min_idle_connections = Some(1);
+    target::ActiveModel::from(prompted)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 2, 1);
+    let prompter = Arc::new(RecordingPrompter {
+        answer: "password",
+        requests: std::sync::Mutex::new(Vec::new()),
+    });
+    let channel = pool
+        .prompted_channel(
+            2,
+            ChannelMode::Dedicated,
+            ChannelPriority::Interactive,
+            Some(prompter.clone()),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(pool.connection_snapshots(Some(2)).await.len(), 1);
+    drop(channel);
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+}
+
+async fn proxies_tunnel_connections_and_check_credentials(context: &TestContext) {
+    let http_proxy = proxy_server::start_http_connect_proxy().await;
+    let socks5_proxy = proxy_server::start_socks5_proxy().await;
+    let proxy_url = |scheme: &str, addr: std::net::SocketAddr, password: &str| {
+        format!(
+            "{scheme}://{}:{password}@{addr}",
+            proxy_server::PROXY_USERNAME
+        )
+    };
+
+    let mut proxied = test_target();
+    proxied.id = 2;
+    proxied.proxy = Some(proxy_url(
+        "http",
+        http_proxy.addr,
+        proxy_server::PROXY_PASSWORD,
+    ));
+    target::ActiveModel::from(proxied)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = Arc::new(
+        SshConnectionPool::new(context.db.clone(), CheckServerKey::AcceptNew, 1, 1)
+            .with_default_proxy(Some(
+                SshProxy::parse(&proxy_url(
+                    "socks5",
+                    socks5_proxy.addr,
+                    proxy_server::PROXY_PASSWORD,
+                ))
+                .unwrap(),
+            )),
+    );
+
+    let channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    assert_eq!(http_proxy.tunnels(), 1);
+    drop(channel);
+    let channel = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    assert_eq!(socks5_proxy.tunnels(), 1);
+    drop(channel);
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+
+    for proxy in [
+        proxy_url("http", http_proxy.addr, "wrong"),
+        proxy_url("socks5", socks5_proxy.addr, "wrong"),
+    ] {
+        let mut rejected = test_target();
+        rejected.id = 2;
+        rejected.proxy = Some(proxy);
+        target::ActiveModel::from(rejected)
+            .insert(&context.db)
+            .await
+            .unwrap();
+        let pool = connection_pool(context, 1, 1);
+        let err = match pool
+            .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+            .await
+        {
+            Ok(_) => panic!("wrong proxy credentials should be rejected"),
+            Err(err) => err,
+        };
+        assert!(matches!(
+            err.downcast_ref::<SshPoolError>(),
+            Some(SshPoolError::Proxy { .. })
+        ));
+        remove_for_test(&context.db, &pool, 2).await.unwrap();
+    }
+    assert_eq!(http_proxy.tunnels(), 1);
+    assert_eq!(socks5_proxy.tunnels(), 1);
+}
+
+#[cfg(target_os = "linux")]
+async fn proxy_command_is_reaped_with_its_connection(context: &TestContext) {
+    let pid_file = std::env::temp_dir().join(format!("webssh-rs-proxy-{}.pid", nanoid::nanoid!()));
+    let mut proxied = test_target();
+    proxied.id = 2;
+    proxied.proxy_command = Some(format!(
+        "echo $$ > {}; exec bash -c 'exec 3<>/dev/tcp/%h/%p; cat <&3 & exec cat >&3'",
+        pid_file.display()
+    ));
+    target::ActiveModel::from(proxied)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let disabled = connection_pool(context, 1, 1);
+    let err = match disabled
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("a ProxyCommand should not run while they are disabled"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::ProxyCommandDisabled)
+    ));
+    assert!(!std::fs::exists(&pid_file).unwrap());
+    let pool = Arc::new(
+        SshConnectionPool::new(context.db.clone(), CheckServerKey::AcceptNew, 1, 1)
+            .with_proxy_command_allowed(true),
+    );
+
+    let mut channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    channel.exec(true, "hello").await.unwrap();
+    let message = tokio::time::timeout(Duration::from_secs(2), channel.wait())
+        .await
+        .unwrap();
+    assert!(matches!(message, Some(russh::ChannelMsg::Data { .. })));
+    drop(channel);
+
+    let pid = std::fs::read_to_string(&pid_file).unwrap();
+    let _ = std::fs::remove_file(&pid_file);
+    let process = std::path::PathBuf::from(format!("/proc/{}", pid.trim()));
+    assert!(process.exists());
+    let connection_id = pool.connection_snapshots(Some(2)).await[0].id.clone();
+    assert!(pool.expire_connection(2, &connection_id).await);
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while process.exists() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the ProxyCommand child should be reaped once its connection closes");
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+}
+
+#[cfg(unix)]
+async fn spawn_test_agent(keys: &[russh::keys::PrivateKey]) -> std::path::PathBuf {
+    let socket = std::env::temp_dir().join(format!("webssh-rs-agent-{}.sock", nanoid::nanoid!()));
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    let connections = Box::pin(async_stream::stream! {
+        loop {
+            yield listener.accept().await.map(|(stream, _)| stream);
+        }
+    });
+    tokio::spawn(russh::keys::agent::server::serve(connections, ()));
+
+    let mut agent = russh::keys::agent::client::AgentClient::connect_uds(&socket)
+        .await
+        .unwrap();
+    for key in keys {
+        agent.add_identity(key, &[]).await.unwrap();
+    }
+    socket
+}
+
+#[cfg(unix)]
+async fn agent_auth_falls_back_to_the_next_identity(context: &TestContext) {
+    let keys = [
+        russh::keys::PrivateKey::random(&mut rand::rng(), russh::keys::Algorithm::Ed25519).unwrap(),
+        russh::keys::PrivateKey::random(&mut rand::rng(), russh::keys::Algorithm::Ed25519).unwrap(),
+    ];
+    let socket = spawn_test_agent(&keys).await;
+    let identities = russh::keys::agent::client::AgentClient::connect_uds(&socket)
+        .await
+        .unwrap()
+        .request_identities()
+        .await
+        .unwrap();
+    assert_eq!(identities.len(), 2);
+    sftp_server::reject_public_key(identities[0].public_key().into_owned());
+    let accepted = identities[1]
+        .public_key()
+        .fingerprint(russh::keys::HashAlg::Sha256)
+        .to_string();
+
+    let mut agent_target = test_target();
+    agent_target.id = 2;
+    agent_target.method = TargetAuthMethod::Agent;
+    agent_target.password = None;
+    target::ActiveModel::from(agent_target)
+        .insert(&context.db)
+        .await
+        .unwrap();
+
+    let unconfigured = connection_pool(context, 1, 1);
+    let err = match unconfigured
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("agent authentication should require an agent socket"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::AgentNotConfigured)
+    ));
+
+    let pool = Arc::new(
+        SshConnectionPool::new(context.db.clone(), CheckServerKey::AcceptNew, 1, 1)
+            .with_agent_socket(Some(socket.clone())),
+    );
+    let channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    let snapshots = pool.connection_snapshots(Some(2)).await;
+    assert_eq!(snapshots.len(), 1);
+    let agent_identity = snapshots[0].agent_identity.as_deref().unwrap();
+    assert!(agent_identity.starts_with(accepted.as_str()));
+    drop(channel);
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+    let _ = std::fs::remove_file(socket);
+}
+
+struct RecordingPrompter {
+    answer: &'static str,
+    requests: std::sync::Mutex<Vec<AuthPromptRequest>>,
+}
+
+impl AuthPrompter for RecordingPrompter {
+    fn prompt(
+        &self,
+        request: AuthPromptRequest,
+    ) -> futures_util::future::BoxFuture<'_, SshPoolResult<Vec<String>>> {
+        let answers = vec![self.answer.to_string(); request.prompts.len()];
+        self.requests.lock().unwrap().push(request);
+        Box::pin(async move { Ok(answers) })
+    }
+}
+
+async fn keyboard_interactive_prompts_are_relayed_to_the_attached_client(context: &TestContext) {
+    let mut interactive_target = test_target();
+    interactive_target.id = 2;
+    interactive_target.method = TargetAuthMethod::KeyboardInteractive;
+    interactive_target.password = None;
+    target::ActiveModel::from(interactive_target)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 1);
+
+    let err = match pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("keyboard-interactive auth needs an attached client"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::AuthPromptUnavailable { target_id: 2 })
+    ));
+
+    let wrong = Arc::new(RecordingPrompter {
+        answer: "000000",
+        requests: std::sync::Mutex::new(Vec::new()),
+    });
+    let err = match pool
+        .prompted_channel(
+            2,
+            ChannelMode::Shared,
+            ChannelPriority::Interactive,
+            Some(wrong.clone()),
+        )
+        .await
+    {
+        Ok(_) => panic!("a wrong verification code should be rejected"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::AuthenticationFailed)
+    ));
+
+    let prompter = Arc::new(RecordingPrompter {
+        answer: sftp_server::KEYBOARD_INTERACTIVE_CODE,
+        requests: std::sync::Mutex::new(Vec::new()),
+    });
+    // Another client attached to the same target never sees the prompts.
+    let bystander = Arc::new(RecordingPrompter {
+        answer: sftp_server::KEYBOARD_INTERACTIVE_CODE,
+        requests: std::sync::Mutex::new(Vec::new()),
+    });
+    let registration = pool.register_auth_prompter(2, bystander.clone());
+    assert!(pool.auth_prompter(2, registration.token()).is_some());
+    assert!(pool.auth_prompter(1, registration.token()).is_none());
+    let channel = pool
+        .prompted_channel(
+            2,
+            ChannelMode::Shared,
+            ChannelPriority::Interactive,
+            Some(prompter.clone()),
+        )
+        .await
+        .unwrap();
+    {
+        let requests = prompter.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].target_id, 2);
+        assert_eq!(
+            requests[0].prompts[0].prompt,
+            sftp_server::KEYBOARD_INTERACTIVE_PROMPT
+        );
+        assert!(!requests[0].prompts[0].echo);
+    }
+    assert!(bystander.requests.lock().unwrap().is_empty());
+    drop(channel);
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+}
+
+async fn prompted_password_is_kept_until_the_target_pool_expires(context: &TestContext) {
+    let mut prompted_target = test_target();
+    prompted_target.id = 2;
+    prompted_target.method = TargetAuthMethod::Prompt;
+    prompted_target.password = None;
+    target::ActiveModel::from(prompted_target)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 1);
+
+    let wrong = Arc::new(RecordingPrompter {
+        answer: sftp_server::REJECTED_PASSWORD,
+        requests: std::sync::Mutex::new(Vec::new()),
+    });
+    let err = match pool
+        .prompted_channel(
+            2,
+            ChannelMode::Shared,
+            ChannelPriority::Interactive,
+            Some(wrong.clone()),
+        )
+        .await
+    {
+        Ok(_) => panic!("a wrong password should be rejected"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::AuthenticationFailed)
+    ));
+
+    let prompter = Arc::new(RecordingPrompter {
+        answer: "123456",
+        requests: std::sync::Mutex::new(Vec::new()),
+    });
+    let channel = pool
+        .prompted_channel(
+            2,
+            ChannelMode::Shared,
+            ChannelPriority::Interactive,
+            Some(prompter.clone()),
+        )
+        .await
+        .unwrap();
+    {
+        let requests = prompter.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].prompts[0].prompt, "root@127.0.0.1's password: ");
+        assert!(!requests[0].prompts[0].echo);
+    }
+    drop(channel);
+
+    // A new connection of the same pool reuses the accepted password.
+    let connection_id = pool.connection_snapshots(Some(2)).await[0].id.clone();
+    assert!(pool.expire_connection(2, &connection_id).await);
+    wait_until_connection_is_removed(&pool, &connection_id).await;
+    let channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    drop(channel);
+    assert_eq!(prompter.requests.lock().unwrap().len(), 1);
+
+    // Expiring the target pool forgets it.
+    pool.expire_target(2).await;
+    let err = match pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("the password should be forgotten with its pool"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::AuthPromptUnavailable { target_id: 2 })
+    ));
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+}
+
+struct HostKeyPrompter {
+    trust: bool,
+    requests: std::sync::Mutex<Vec<HostKeyPromptRequest>>,
+}
+
+impl AuthPrompter for HostKeyPrompter {
+    fn prompt(
+        &self,
+        _request: AuthPromptRequest,
+    ) -> futures_util::future::BoxFuture<'_, SshPoolResult<Vec<String>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn confirm_host_key(
+        &self,
+        request: HostKeyPromptRequest,
+    ) -> futures_util::future::BoxFuture<'_, SshPoolResult<bool>> {
+        self.requests.lock().unwrap().push(request);
+        let trust = self.trust;
+        Box::pin(async move { Ok(trust) })
+    }
+}
+
+async fn unknown_host_key_is_confirmed_by_the_attached_client(context: &TestContext) {
+    ssh_known_host::Entity::delete_many()
+        .exec(&context.db)
+        .await
+        .unwrap();
+    let pool = Arc::new(SshConnectionPool::new(
+        context.db.clone(),
+        CheckServerKey::Ask,
+        1,
+        1,
+    ));
+
+    let err = match pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("an unknown host key needs an attached client"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::HostKeyUnknown { .. })
+    ));
+
+    let rejecting = Arc::new(HostKeyPrompter {
+        trust: false,
+        requests: std::sync::Mutex::new(Vec::new()),
+    });
+    let err = match pool
+        .prompted_channel(
+            1,
+            ChannelMode::Shared,
+            ChannelPriority::Interactive,
+            Some(rejecting.clone()),
+        )
+        .await
+    {
+        Ok(_) => panic!("a rejected host key should fail the connection"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::HostKeyRejected { port: 2222, .. })
+    ));
+    assert_eq!(rejecting.requests.lock().unwrap().len(), 1);
+    assert!(
+        ssh_known_host::Entity::find()
+            .all(&context.db)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let trusting = Arc::new(HostKeyPrompter {
+        trust: true,
+        requests: std::sync::Mutex::new(Vec::new()),
+    });
+    let channel = pool
+        .prompted_channel(
+            1,
+            ChannelMode::Shared,
+            ChannelPriority::Interactive,
+            Some(trusting.clone()),
+        )
+        .await
+        .unwrap();
+    drop(channel);
+    let stored = ssh_known_host::Entity::find()
+        .all(&context.db)
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 1);
+    {
+        let requests = trusting.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].target_id, 1);
+        assert_eq!(requests[0].host, "127.0.0.1");
+        assert_eq!(requests[0].fingerprint, stored[0].fingerprint);
+    }
+
+    // The confirmed key is trusted without asking again.
+    pool.expire_target(1).await;
+    let channel = pool
+        .prompted_channel(
+            1,
+            ChannelMode::Shared,
+            ChannelPriority::Interactive,
+            Some(trusting.clone()),
+        )
+        .await
+        .unwrap();
+    drop(channel);
+    assert_eq!(trusting.requests.lock().unwrap().len(), 1);
+    pool.expire_target(1).await;
+}
+
+async fn target_host_key_policy_overrides_the_pool_policy(context: &TestContext) {
+    ssh_known_host::Entity::delete_many()
+        .exec(&context.db)
+        .await
+        .unwrap();
+    let mut strict = test_target();
+    strict.id = 2;
+    strict.host_key_policy = Some(TargetHostKeyPolicy::Strict);
+    target::ActiveModel::from(strict)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 1);
+
+    let err = match pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("a strict target should reject an unknown host key"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::HostKeyUnknown { .. })
+    ));
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+
+    let pool = Arc::new(SshConnectionPool::new(
+        context.db.clone(),
+        CheckServerKey::Strict,
+        1,
+        1,
+    ));
+    let mut disabled = test_target();
+    disabled.id = 2;
+    disabled.host_key_policy = Some(TargetHostKeyPolicy::Disabled);
+    target::ActiveModel::from(disabled)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    drop(channel);
+    assert!(
+        ssh_known_host::Entity::find()
+            .all(&context.db)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+}
+
+async fn certificate_auth_uses_a_valid_user_certificate(context: &TestContext) {
+    let private_key =
+        russh::keys::PrivateKey::random(&mut rand::rng(), russh::keys::Algorithm::Ed25519).unwrap();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut certificate_target = test_target();
+    certificate_target.id = 2;
+    certificate_target.method = TargetAuthMethod::PrivateKey;
+    certificate_target.key = Some(
+        private_key
+            .to_openssh(russh::keys::ssh_key::LineEnding::LF)
+            .unwrap()
+            .to_string(),
+    );
+    certificate_target.password = None;
+    certificate_target.certificate = Some(sftp_server::sign_user_certificate(
+        &private_key,
+        &["root"],
+        now - 60,
+        now - 1,
+    ));
+    target::ActiveModel::from(certificate_target.clone())
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 1);
+
+    let err = match pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+    {
+        Ok(_) => panic!("an expired certificate should not be offered"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::CertificateExpired { valid_before }) if *valid_before == now - 1
+    ));
+
+    let mut renewed = target::ActiveModel::from(certificate_target);
+    renewed.certificate = Set(Some(sftp_server::sign_user_certificate(
+        &private_key,
+        &["root"],
+        now - 60,
+        now + 3600,
+    )));
+    renewed.update(&context.db).await.unwrap();
+    let channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    drop(channel);
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+}
+
+async fn stored_key_and_password_channel_authenticate_the_target(context: &TestContext) {
+    let private_key =
+        russh::keys::PrivateKey::random(&mut rand::rng(), russh::keys::Algorithm::Ed25519).unwrap();
+    let stored_key = key_store::ActiveModel {
+        id: sea_orm::ActiveValue::NotSet,
+        name: Set("deploy".to_string()),
+        algorithm: Set("ssh-ed25519".to_string()),
+        public_key: Set(private_key.public_key().to_openssh().unwrap()),
+        private_key: Set(private_key
+            .to_openssh(russh::keys::ssh_key::LineEnding::LF)
+            .unwrap()
+            .to_string()),
+        fingerprint: Set("SHA256:fingerprint".to_string()),
+        comment: Set(String::new()),
+        created_at: Set(1),
+    }
+    .insert(&context.db)
+    .await
+    .unwrap();
+    let mut keyed = test_target();
+    keyed.id = 2;
+    keyed.method = TargetAuthMethod::PrivateKey;
+    keyed.password = None;
+    keyed.key_id = Some(stored_key.id);
+    target::ActiveModel::from(keyed)
+        .insert(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 1);
+
+    let channel = pool
+        .channel(2, ChannelMode::Shared, ChannelPriority::Exec)
+        .await
+        .unwrap();
+    channel.exec(true, "hello").await.unwrap();
+    drop(channel);
+
+    let err = match pool
+        .password_channel(2, sftp_server::REJECTED_PASSWORD.to_string())
+        .await
+    {
+        Ok(_) => panic!("a rejected password should fail the connection"),
+        Err(err) => err,
+    };
+    assert!(matches!(
+        err.downcast_ref::<SshPoolError>(),
+        Some(SshPoolError::AuthenticationFailed)
+    ));
+    let channel = pool
+        .password_channel(2, "123456".to_string())
+        .await
+        .unwrap();
+    channel.exec(true, "hello").await.unwrap();
+    drop(channel);
+    // The one-off connection is not part of the target's pool.
+    assert_eq!(pool.connection_snapshots(Some(2)).await.len(), 1);
+
+    remove_for_test(&context.db, &pool, 2).await.unwrap();
+    key_store::Entity::delete_by_id(stored_key.id)
+        .exec(&context.db)
+        .await
+        .unwrap();
+}
+
+async fn target_reads_wait_for_mutation(context: &TestContext) {
+    let pool = connection_pool(context, 1, 1);
+    let current = target_repository::find_by_id(&context.db, &Secrets::default(), 1)
+        .await
+        .unwrap()
+        .unwrap();
+    let updated_system = "mutation-serialized-linux".to_string();
+    let mut active_model = target::ActiveModel::from(current);
+    active_model.system = Set(Some(updated_system.clone()));
+
+    let (mutation_started_tx, mutation_started_rx) = tokio::sync::oneshot::channel();
+    let (mutation_release_tx, mutation_release_rx) = tokio::sync::oneshot::channel();
+    let mutation_pool = Arc::clone(&pool);
+    let mutation_db = context.db.clone();
+    let mutation = tokio::spawn(async move {
+        mutation_pool
+            .with_target_mutation(1, move || async move {
+                let _ = mutation_started_tx.send(());
+                mutation_release_rx.await.unwrap();
+                target_repository::update(&mutation_db, &Secrets::default(), active_model).await
+            })
+            .await
+    });
+
+    mutation_started_rx.await.unwrap();
+    let reader_pool = Arc::clone(&pool);
+    let reader = tokio::spawn(async move { reader_pool.context(1).await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(
+        !reader.is_finished(),
+        "target reads must wait until the mutation finishes"
+    );
+
+    mutation_release_tx.send(()).unwrap();
+    mutation.await.unwrap().unwrap();
+    let refreshed = tokio::time::timeout(Duration::from_secs(2), reader)
+        .await
+        .expect("target read should resume after the mutation")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        refreshed.target().system.as_deref(),
+        Some(updated_system.as_str())
+    );
+}
+
+async fn target_update_is_not_blocked_by_a_capacity_waiter(context: &TestContext) {
+    let pool = connection_pool(context, 1, 1);
+    let active = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
+    let waiter = spawn_capacity_waiter(&pool).await;
+
+    let current = target_repository::find_by_id(&context.db, &Secrets::default(), 1)
+        .await
+        .unwrap()
+        .unwrap();
+    let updated_system = "updated-linux".to_string();
+    let payload = TargetUpdatePayload {
+        system: Some(updated_system.clone()),
+        ..update_payload(current)
+    };
+
+    let updated = tokio::time::timeout(
+        Duration::from_secs(2),
+        update_for_test(&context.db, &Secrets::default(), &pool, payload),
+    )
+    .await
+    .expect("target update must not wait for channel capacity")
+    .unwrap();
+    assert_eq!(
+        updated.target.system.as_deref(),
+        Some(updated_system.as_str())
+    );
+    assert_capacity_waiter_expired(waiter).await;
+
+    let persisted = target_repository::find_by_id(&context.db, &Secrets::default(), 1)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(persisted.system.as_deref(), Some(updated_system.as_str()));
+    {
+        let refreshed = pool.context(1).await.unwrap();
+        assert_eq!(
+            refreshed.target().system.as_deref(),
             Some(updated_system.as_str())
         );
     }
@@ -534,7 +1955,10 @@ async fn target_update_is_not_blocked_by_a_capacity_waiter(context: &TestContext
 
 async fn target_delete_is_not_blocked_by_a_capacity_waiter(context: &TestContext) {
     let pool = connection_pool(context, 1, 1);
-    let active = pool.channel(1, ChannelMode::Shared).await.unwrap();
+    let active = pool
+        .channel(1, ChannelMode::Shared, ChannelPriority::Interactive)
+        .await
+        .unwrap();
     let waiter = spawn_capacity_waiter(&pool).await;
 
     tokio::time::timeout(
@@ -547,7 +1971,7 @@ async fn target_delete_is_not_blocked_by_a_capacity_waiter(context: &TestContext
     assert_capacity_waiter_expired(waiter).await;
 
     assert!(
-        target_repository::find_by_id(&context.db, 1)
+        target_repository::find_by_id(&context.db, &Secrets::default(), 1)
             .await
             .unwrap()
             .is_none()
@@ -561,7 +1985,11 @@ async fn spawn_capacity_waiter(
     pool: &SshConnectionPool,
 ) -> tokio::task::JoinHandle<anyhow::Result<super::SshChannelGuard>> {
     let waiting_context = pool.context(1).await.unwrap();
-    let waiter = tokio::spawn(async move { waiting_context.channel(ChannelMode::Shared).await });
+    let waiter = tokio::spawn(async move {
+        waiting_context
+            .channel(ChannelMode::Shared, ChannelPriority::Interactive)
+            .await
+    });
     tokio::time::sleep(Duration::from_millis(50)).await;
     assert!(
         !waiter.is_finished(),
@@ -593,7 +2021,7 @@ async fn assert_capacity_waiter_expired(
 async fn wait_until_connection_is_removed(pool: &SshConnectionPool, connection_id: &str) {
     tokio::time::timeout(Duration::from_secs(2), async {
         loop {
-            let snapshots = pool.connection_snapshots(Some(1)).await;
+            let snapshots = pool.connection_snapshots(None).await;
             if snapshots
                 .iter()
                 .all(|snapshot| snapshot.id != connection_id)
@@ -606,3 +2034,159 @@ async fn wait_until_connection_is_removed(pool: &SshConnectionPool, connection_i
     .await
     .expect("closed connection should be removed from the pool");
 }
+
+async fn connection_test_reports_each_stage(context: &TestContext) {
+    ssh_known_host::Entity::delete_many()
+        .exec(&context.db)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 1);
+    let mut unsaved = test_target();
+    unsaved.id = 0;
+
+    let report = pool.diagnose(&unsaved).await.unwrap();
+    assert!(report.passed(), "{report:?}");
+    assert!(
+        report
+            .stages
+            .iter()
+            .all(|stage| stage.status == StageStatus::Passed)
+    );
+    assert!(!report.addresses.is_empty());
+    assert!(
+        report
+            .server_version
+            .as_deref()
+            .is_some_and(|version| version.starts_with("SSH-2.0-"))
+    );
+    assert!(report.algorithms.is_some());
+    assert_eq!(
+        report.host_key.as_ref().map(|host_key| host_key.verdict),
+        Some(HostKeyVerdict::New)
+    );
+    assert!(
+        report
+            .auth_methods
+            .iter()
+            .any(|method| method == "password")
+    );
+    // Nothing is remembered or pooled by a test.
+    assert!(
+        ssh_known_host::Entity::find()
+            .all(&context.db)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert!(pool.connection_snapshots(None).await.is_empty());
+
+    let mut rejected = unsaved.clone();
+    rejected.password = Some(sftp_server::REJECTED_PASSWORD.to_string());
+    let report = pool.diagnose(&rejected).await.unwrap();
+    assert!(!report.passed());
+    let status = |stage| report.stage(stage).map(|report| report.status);
+    assert_eq!(
+        status(DiagnosticStage::AuthMethods),
+        Some(StageStatus::Passed)
+    );
+    assert_eq!(
+        status(DiagnosticStage::Authentication),
+        Some(StageStatus::Failed)
+    );
+    assert_eq!(status(DiagnosticStage::Sftp), Some(StageStatus::Skipped));
+
+    let mut strict = unsaved.clone();
+    strict.host_key_policy = Some(TargetHostKeyPolicy::Strict);
+    let report = pool.diagnose(&strict).await.unwrap();
+    let status = |stage| report.stage(stage).map(|report| report.status);
+    assert_eq!(
+        report.host_key.as_ref().map(|host_key| host_key.verdict),
+        Some(HostKeyVerdict::Unknown)
+    );
+    assert_eq!(status(DiagnosticStage::HostKey), Some(StageStatus::Failed));
+    assert_eq!(
+        status(DiagnosticStage::AuthMethods),
+        Some(StageStatus::Skipped)
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut closed = unsaved;
+    closed.port = Some(listener.local_addr().unwrap().port());
+    drop(listener);
+    let report = pool.diagnose(&closed).await.unwrap();
+    let status = |stage| report.stage(stage).map(|report| report.status);
+    assert_eq!(status(DiagnosticStage::Dns), Some(StageStatus::Passed));
+    assert_eq!(status(DiagnosticStage::Connect), Some(StageStatus::Failed));
+    assert_eq!(status(DiagnosticStage::Banner), Some(StageStatus::Skipped));
+    assert_eq!(report.stages.len(), 8);
+}
+
+async fn capabilities_are_detected_after_the_first_connection(context: &TestContext) {
+    if target_repository::find_by_id(&context.db, &Secrets::default(), 1)
+        .await
+        .unwrap()
+        .is_none()
+    {
+        target::ActiveModel::from(test_target())
+            .insert(&context.db)
+            .await
+            .unwrap();
+    }
+    target_capabilities_repository::delete(&context.db, 1)
+        .await
+        .unwrap();
+    let pool = connection_pool(context, 1, 2);
+    spawn_capability_detection(context.db.clone(), &pool);
+
+    drop(
+        pool.channel(1, ChannelMode::Shared, ChannelPriority::Exec)
+            .await
+            .unwrap(),
+    );
+    let capabilities = loop {
+        match target_capabilities_repository::find_by_target_id(&context.db, 1)
+            .await
+            .unwrap()
+        {
+            Some(capabilities) => break capabilities,
+            None => tokio::time::sleep(Duration::from_millis(20)).await,
+        }
+    };
+    assert_eq!(capabilities.os, "linux");
+    assert_eq!(capabilities.os_version.as_deref(), Some("6.8.0-test"));
+    assert_eq!(capabilities.shell.as_deref(), Some("/bin/bash"));
+    assert_eq!(capabilities.sftp_version, Some(3));
+    assert!(capabilities.home_dir.is_some());
+    assert!(capabilities.has_tool("sha256sum"));
+
+    // Detection wins over the manually entered system.
+    let mut target = test_target();
+    target.system = Some("windows".to_string());
+    assert!(!is_windows(&context.db, &target).await.unwrap());
+
+    let redetected = detect_capabilities_for_test(&context.db, &pool, 1)
+        .await
+        .unwrap();
+    assert!(redetected.detected_at >= capabilities.detected_at);
+
+    let mut moved = update_payload(test_target());
+    moved.host = "localhost".to_string();
+    update_for_test(&context.db, &Secrets::default(), &pool, moved)
+        .await
+        .unwrap();
+    assert!(
+        target_capabilities_repository::find_by_target_id(&context.db, 1)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(is_windows(&context.db, &target).await.unwrap());
+    update_for_test(
+        &context.db,
+        &Secrets::default(),
+        &pool,
+        update_payload(test_target()),
+    )
+    .await
+    .unwrap();
+}
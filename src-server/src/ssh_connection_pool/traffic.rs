@@ -0,0 +1,151 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::events::now_ms;
+
+/// RFC 4253 limits the identification line to 255 bytes.
+const MAX_VERSION_LINE: usize = 255;
+
+/// Byte counters of an SSH transport, plus the identification string the
+/// server sent before the key exchange.
+#[derive(Default)]
+pub(crate) struct TrafficCounters {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    last_activity: AtomicU64,
+    server_version: OnceLock<String>,
+    version_line: Mutex<Vec<u8>>,
+}
+
+impl TrafficCounters {
+    pub(crate) fn bytes_in(&self) -> u64 {
+        self.bytes_in.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn bytes_out(&self) -> u64 {
+        self.bytes_out.load(Ordering::Relaxed)
+    }
+
+    /// Unix timestamp in milliseconds of the last read or write, `0` before
+    /// any traffic.
+    pub(crate) fn last_activity(&self) -> u64 {
+        self.last_activity.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn server_version(&self) -> Option<&str> {
+        self.server_version.get().map(String::as_str)
+    }
+
+    fn record_read(&self, bytes: &[u8]) {
+        self.bytes_in
+            .fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        self.last_activity.store(now_ms(), Ordering::Relaxed);
+        if self.server_version.get().is_none() {
+            self.scan_version(bytes);
+        }
+    }
+
+    fn record_write(&self, len: usize) {
+        self.bytes_out.fetch_add(len as u64, Ordering::Relaxed);
+        self.last_activity.store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// Servers may send other lines before `SSH-`; only the first line that
+    /// starts with it is kept.
+    fn scan_version(&self, bytes: &[u8]) {
+        let mut line = self
+            .version_line
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        for &byte in bytes {
+            if byte != b'\n' {
+                if line.len() < MAX_VERSION_LINE {
+                    line.push(byte);
+                }
+                continue;
+            }
+            if line.starts_with(b"SSH-") {
+                let version = String::from_utf8_lossy(&line).trim_end().to_string();
+                let _ = self.server_version.set(version);
+                line.clear();
+                line.shrink_to_fit();
+                return;
+            }
+            line.clear();
+        }
+    }
+}
+
+pub(crate) struct CountingStream<R> {
+    inner: R,
+    counters: Arc<TrafficCounters>,
+}
+
+impl<R> CountingStream<R> {
+    pub(crate) fn new(inner: R, counters: Arc<TrafficCounters>) -> Self {
+        Self { inner, counters }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CountingStream<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll
+            && buf.filled().len() > filled
+        {
+            self.counters.record_read(&buf.filled()[filled..]);
+        }
+        poll
+    }
+}
+
+impl<R: AsyncWrite + Unpin> AsyncWrite for CountingStream<R> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(len)) = poll {
+            self.counters.record_write(len);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_version_skips_banner_lines() {
+        let counters = TrafficCounters::default();
+        counters.record_read(b"welcome\r\nSSH-2.0-Open");
+        assert_eq!(counters.server_version(), None);
+        counters.record_read(b"SSH_9.6\r\n\x00\x00");
+        assert_eq!(counters.server_version(), Some("SSH-2.0-OpenSSH_9.6"));
+        assert_eq!(counters.bytes_in(), 32);
+    }
+}
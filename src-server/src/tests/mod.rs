@@ -1,2 +1,4 @@
 #[cfg(test)]
+pub mod proxy_server;
+#[cfg(test)]
 pub mod sftp_server;
@@ -0,0 +1,155 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use base64::Engine;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tracing::debug;
+
+pub(crate) const PROXY_USERNAME: &str = "proxy-user";
+pub(crate) const PROXY_PASSWORD: &str = "proxy-pass";
+
+/// Minimal local proxy that accepts `PROXY_USERNAME`/`PROXY_PASSWORD` and
+/// forwards tunnels to whatever address the client asks for.
+pub(crate) struct StandInProxy {
+    pub addr: SocketAddr,
+    tunnels: Arc<AtomicUsize>,
+}
+
+impl StandInProxy {
+    /// Number of tunnels opened after successful authentication.
+    pub fn tunnels(&self) -> usize {
+        self.tunnels.load(Ordering::SeqCst)
+    }
+}
+
+pub(crate) async fn start_http_connect_proxy() -> StandInProxy {
+    start(|stream| Box::pin(http_connect_handshake(stream))).await
+}
+
+pub(crate) async fn start_socks5_proxy() -> StandInProxy {
+    start(|stream| Box::pin(socks5_handshake(stream))).await
+}
+
+type Handshake =
+    fn(
+        &mut TcpStream,
+    ) -> std::pin::Pin<Box<dyn Future<Output = std::io::Result<Option<String>>> + Send + '_>>;
+
+async fn start(handshake: Handshake) -> StandInProxy {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let tunnels = Arc::new(AtomicUsize::new(0));
+    let counter = tunnels.clone();
+    tokio::spawn(async move {
+        while let Ok((mut client, _)) = listener.accept().await {
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                let destination = match handshake(&mut client).await {
+                    Ok(Some(destination)) => destination,
+                    Ok(None) => return,
+                    Err(err) => {
+                        debug!(?err, "stand-in proxy handshake failed");
+                        return;
+                    }
+                };
+                let Ok(mut upstream) = TcpStream::connect(destination).await else {
+                    return;
+                };
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+            });
+        }
+    });
+    StandInProxy { addr, tunnels }
+}
+
+async fn http_connect_handshake(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        head.push(stream.read_u8().await?);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let destination = lines
+        .next()
+        .and_then(|line| line.strip_prefix("CONNECT "))
+        .and_then(|line| line.split_whitespace().next())
+        .unwrap_or_default()
+        .to_string();
+    let expected = format!(
+        "Basic {}",
+        base64::engine::general_purpose::STANDARD
+            .encode(format!("{PROXY_USERNAME}:{PROXY_PASSWORD}"))
+    );
+    let authorized = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.eq_ignore_ascii_case("proxy-authorization") && value.trim() == expected
+        })
+    });
+    if !authorized {
+        stream
+            .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+            .await?;
+        return Ok(None);
+    }
+    stream
+        .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+        .await?;
+    Ok(Some(destination))
+}
+
+async fn socks5_handshake(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await?;
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&2) {
+        stream.write_all(&[5, 0xff]).await?;
+        return Ok(None);
+    }
+    stream.write_all(&[5, 2]).await?;
+
+    let mut version = [0u8; 2];
+    stream.read_exact(&mut version).await?;
+    let mut username = vec![0u8; version[1] as usize];
+    stream.read_exact(&mut username).await?;
+    let mut password = vec![0u8; stream.read_u8().await? as usize];
+    stream.read_exact(&mut password).await?;
+    if username != PROXY_USERNAME.as_bytes() || password != PROXY_PASSWORD.as_bytes() {
+        stream.write_all(&[1, 1]).await?;
+        return Ok(None);
+    }
+    stream.write_all(&[1, 0]).await?;
+
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await?;
+    let host = match request[3] {
+        1 => {
+            let mut octets = [0u8; 4];
+            stream.read_exact(&mut octets).await?;
+            std::net::Ipv4Addr::from(octets).to_string()
+        }
+        3 => {
+            let mut name = vec![0u8; stream.read_u8().await? as usize];
+            stream.read_exact(&mut name).await?;
+            String::from_utf8_lossy(&name).into_owned()
+        }
+        4 => {
+            let mut octets = [0u8; 16];
+            stream.read_exact(&mut octets).await?;
+            format!("[{}]", std::net::Ipv6Addr::from(octets))
+        }
+        _ => return Ok(None),
+    };
+    let port = stream.read_u16().await?;
+    stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+    Ok(Some(format!("{host}:{port}")))
+}
@@ -16,6 +16,82 @@ use tracing::{debug, error, info};
 
 pub(crate) const DOWNLOAD_FILE_SIZE: usize = 20_000;
 pub(crate) const DOWNLOAD_FILE_PATH: &str = "/download.bin";
+/// File large enough to take several pipelined reads, so that a transfer can
+/// fail between two of them.
+pub(crate) const RESUMABLE_FILE_SIZE: usize = 1024 * 1024;
+pub(crate) const RESUMABLE_FILE_PATH: &str = "/resumable.bin";
+pub(crate) const KEYBOARD_INTERACTIVE_PROMPT: &str = "Verification code: ";
+pub(crate) const KEYBOARD_INTERACTIVE_CODE: &str = "424242";
+/// Password that `auth_password` rejects; every other password is accepted.
+pub(crate) const REJECTED_PASSWORD: &str = "wrong-password";
+/// Answer to the POSIX environment probe of target capability detection.
+pub(crate) const POSIX_PROBE_OUTPUT: &str =
+    "os=Linux\nos_version=6.8.0-test\nshell=/bin/bash\ntool=tar\ntool=sha256sum\n";
+
+/// Public keys that `auth_publickey` rejects, so a client can be made to fall
+/// back to its next identity.
+static REJECTED_PUBLIC_KEYS: std::sync::Mutex<Vec<ssh_key::PublicKey>> =
+    std::sync::Mutex::new(Vec::new());
+
+pub(crate) fn reject_public_key(public_key: ssh_key::PublicKey) {
+    REJECTED_PUBLIC_KEYS.lock().unwrap().push(public_key);
+}
+
+/// Signs an OpenSSH user certificate for `private_key` with a throwaway CA
+/// and returns it in `*-cert.pub` format.
+pub(crate) fn sign_user_certificate(
+    private_key: &russh::keys::PrivateKey,
+    principals: &[&str],
+    valid_after: u64,
+    valid_before: u64,
+) -> String {
+    let ca =
+        russh::keys::PrivateKey::random(&mut rand::rng(), ssh_key::Algorithm::Ed25519).unwrap();
+    let mut builder = ssh_key::certificate::Builder::new(
+        [0u8; 16],
+        private_key.public_key().key_data().clone(),
+        valid_after,
+        valid_before,
+    )
+    .unwrap();
+    builder.key_id("webssh-rs-test").unwrap();
+    for principal in principals {
+        builder.valid_principal(*principal).unwrap();
+    }
+    builder.sign(&ca).unwrap().to_openssh().unwrap()
+}
+
+/// Offset at which the next read of `RESUMABLE_FILE_PATH` answers
+/// `SSH_FX_CONNECTION_LOST`, once.
+static LOSE_CONNECTION_AT: std::sync::Mutex<Option<u64>> = std::sync::Mutex::new(None);
+/// Offsets of every read of `RESUMABLE_FILE_PATH`, in the order served.
+static RESUMABLE_READS: std::sync::Mutex<Vec<u64>> = std::sync::Mutex::new(Vec::new());
+
+pub(crate) fn lose_connection_at(offset: u64) {
+    *LOSE_CONNECTION_AT.lock().unwrap() = Some(offset);
+    RESUMABLE_READS.lock().unwrap().clear();
+}
+
+pub(crate) fn resumable_reads() -> Vec<u64> {
+    RESUMABLE_READS.lock().unwrap().clone()
+}
+
+/// Size of the file served at `path`, whose byte at `index` is `index % 251`.
+fn file_size(path: &str) -> Option<usize> {
+    match path {
+        DOWNLOAD_FILE_PATH => Some(DOWNLOAD_FILE_SIZE),
+        RESUMABLE_FILE_PATH => Some(RESUMABLE_FILE_SIZE),
+        _ => None,
+    }
+}
+
+fn is_public_key_rejected(public_key: &ssh_key::PublicKey) -> bool {
+    REJECTED_PUBLIC_KEYS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|rejected| rejected.key_data() == public_key.key_data())
+}
 
 #[derive(Clone)]
 pub(crate) struct ChannelOpenControl {
@@ -144,6 +220,46 @@ impl russh::server::Handler for SshServerSession {
 
     async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
         info!("SshServerSession: @auth_password {}, {}", user, password);
+        if password == REJECTED_PASSWORD {
+            return Ok(Auth::reject());
+        }
+        Ok(Auth::Accept)
+    }
+
+    async fn auth_keyboard_interactive<'a>(
+        &'a mut self,
+        user: &str,
+        _submethods: &str,
+        response: Option<russh::server::Response<'a>>,
+    ) -> Result<Auth, Self::Error> {
+        info!("SshServerSession: @auth_keyboard_interactive {}", user);
+        let Some(mut response) = response else {
+            return Ok(Auth::Partial {
+                name: "".into(),
+                instructions: "".into(),
+                prompts: vec![(KEYBOARD_INTERACTIVE_PROMPT.into(), false)].into(),
+            });
+        };
+        if response
+            .next()
+            .is_some_and(|answer| answer.as_ref() == KEYBOARD_INTERACTIVE_CODE.as_bytes())
+        {
+            Ok(Auth::Accept)
+        } else {
+            Ok(Auth::reject())
+        }
+    }
+
+    async fn auth_openssh_certificate(
+        &mut self,
+        user: &str,
+        certificate: &ssh_key::Certificate,
+    ) -> Result<Auth, Self::Error> {
+        info!(
+            "SshServerSession: @auth_openssh_certificate {}, {:?}",
+            user,
+            certificate.valid_principals()
+        );
         Ok(Auth::Accept)
     }
 
@@ -156,6 +272,9 @@ impl russh::server::Handler for SshServerSession {
             "SshServerSession: @auth_publickey {}, {:?}",
             user, public_key
         );
+        if is_public_key_rejected(public_key) {
+            return Ok(Auth::reject());
+        }
         Ok(Auth::Accept)
     }
 
@@ -184,6 +303,34 @@ impl russh::server::Handler for SshServerSession {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
+    async fn channel_open_direct_tcpip(
+        &mut self,
+        channel: Channel<Msg>,
+        host_to_connect: &str,
+        port_to_connect: u32,
+        _originator_address: &str,
+        _originator_port: u32,
+        reply: ChannelOpenHandle,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        info!(
+            "SshServerSession: channel_open_direct_tcpip {}:{}",
+            host_to_connect, port_to_connect
+        );
+        let port = u16::try_from(port_to_connect)?;
+        let Ok(mut socket) = tokio::net::TcpStream::connect((host_to_connect, port)).await else {
+            reply.reject(russh::ChannelOpenFailure::ConnectFailed).await;
+            return Ok(());
+        };
+        reply.accept().await;
+        tokio::spawn(async move {
+            let mut stream = channel.into_stream();
+            let _ = tokio::io::copy_bidirectional(&mut stream, &mut socket).await;
+        });
+        Ok(())
+    }
+
     async fn channel_eof(
         &mut self,
         channel: ChannelId,
@@ -205,13 +352,16 @@ impl russh::server::Handler for SshServerSession {
             let channels = self.channels.lock().await;
             if let Some(channel) = channels.get(&channel) {
                 let cmd_str = String::from_utf8_lossy(cmd);
-                let exec = "exec ".as_bytes();
-                let done = " done".as_bytes();
-                let combined = [exec, cmd, done].concat();
+                let probe = cmd_str.starts_with("printf 'os=");
+                let combined = if probe {
+                    POSIX_PROBE_OUTPUT.as_bytes().to_vec()
+                } else {
+                    [b"exec ".as_slice(), cmd, b" done"].concat()
+                };
                 let _ = channel.data(combined.as_slice()).await;
                 channel.exit_status(0).await?;
                 channel.eof().await?;
-                if cmd_str == "close_channel" {
+                if probe || cmd_str == "close_channel" {
                     channel.close().await?;
                 }
                 Ok(())
@@ -293,7 +443,7 @@ impl russh_sftp::server::Handler for SftpServerSession {
         _pflags: OpenFlags,
         _attrs: FileAttributes,
     ) -> Result<Handle, Self::Error> {
-        if filename != DOWNLOAD_FILE_PATH {
+        if file_size(&filename).is_none() {
             return Err(StatusCode::NoSuchFile);
         }
         Ok(Handle {
@@ -309,27 +459,31 @@ impl russh_sftp::server::Handler for SftpServerSession {
         offset: u64,
         len: u32,
     ) -> Result<Data, Self::Error> {
-        if handle != DOWNLOAD_FILE_PATH {
-            return Err(StatusCode::Failure);
+        let size = file_size(&handle).ok_or(StatusCode::Failure)?;
+        if handle == RESUMABLE_FILE_PATH {
+            RESUMABLE_READS.lock().unwrap().push(offset);
+            let mut lose_connection_at = LOSE_CONNECTION_AT.lock().unwrap();
+            if lose_connection_at.is_some_and(|lose_at| offset >= lose_at) {
+                *lose_connection_at = None;
+                return Err(StatusCode::ConnectionLost);
+            }
         }
         let offset = usize::try_from(offset).map_err(|_| StatusCode::Failure)?;
-        if offset >= DOWNLOAD_FILE_SIZE {
+        if offset >= size {
             return Err(StatusCode::Eof);
         }
         let len = usize::try_from(len).map_err(|_| StatusCode::Failure)?;
-        let end = offset.saturating_add(len).min(DOWNLOAD_FILE_SIZE);
+        let end = offset.saturating_add(len).min(size);
         let data = (offset..end).map(|index| (index % 251) as u8).collect();
         Ok(Data { id, data })
     }
 
     async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
-        if path != DOWNLOAD_FILE_PATH {
-            return Err(StatusCode::NoSuchFile);
-        }
+        let size = file_size(&path).ok_or(StatusCode::NoSuchFile)?;
         Ok(Attrs {
             id,
             attrs: FileAttributes {
-                size: Some(DOWNLOAD_FILE_SIZE as u64),
+                size: Some(size as u64),
                 permissions: Some(0o100644),
                 ..FileAttributes::default()
             },